@@ -1,6 +1,8 @@
 mod maybe_send;
 mod sleep;
+mod unix_time;
 
 pub use maybe_send::MaybeSend;
 pub use sleep::sleep;
+pub use unix_time::unix_time;
 pub use web_time::{Duration, Instant};
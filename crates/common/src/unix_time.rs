@@ -0,0 +1,9 @@
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// Returns the current unix time in seconds.
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
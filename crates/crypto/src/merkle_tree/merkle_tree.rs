@@ -90,6 +90,11 @@ impl<C: MerkleConfig> MerkleTree<C> {
         self.tree[0].len()
     }
 
+    /// Returns the leaves of the tree in order
+    pub fn leaves(&self) -> Vec<U256> {
+        self.tree[0].clone()
+    }
+
     pub fn state(&self) -> MerkleTreeState<C> {
         self.clone().into_state()
     }
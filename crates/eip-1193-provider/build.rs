@@ -4,5 +4,6 @@ fn main() {
         wasm: { all(target_arch = "wasm32") },
         js: { all(target_arch = "wasm32", feature = "js") },
         alloy: { all(feature = "alloy") },
+        signer: { all(feature = "signer") },
     }
 }
@@ -1,16 +1,14 @@
 use std::sync::Arc;
 
 use alloy::{
-    eips::BlockId,
-    network::TransactionBuilder,
-    primitives::{Address, Bytes, FixedBytes},
     providers::{DynProvider, Provider},
-    rpc::types::{Filter, TransactionRequest},
+    rpc::types::TransactionRequest,
     transports::{RpcError, TransportErrorKind},
 };
+use serde_json::Value;
 
 use crate::{
-    provider::{Eip1193Error, Eip1193Provider, IntoEip1193Provider, RawLog},
+    provider::{Eip1193Error, Eip1193Provider, IntoEip1193Provider},
     tx_data::TxData,
 };
 
@@ -29,85 +27,14 @@ impl Alloy {
 #[cfg_attr(native, async_trait::async_trait)]
 #[cfg_attr(wasm, async_trait::async_trait(?Send))]
 impl Eip1193Provider for Alloy {
-    async fn get_chain_id(&self) -> Result<u64, Eip1193Error> {
-        Ok(self.inner.get_chain_id().await?)
-    }
-
-    async fn get_block_number(&self) -> Result<u64, Eip1193Error> {
-        Ok(self.inner.get_block_number().await?)
-    }
-
-    async fn logs(
-        &self,
-        address: Address,
-        event_signature: Option<FixedBytes<32>>,
-        from_block: Option<u64>,
-        to_block: Option<u64>,
-    ) -> Result<Vec<RawLog>, Eip1193Error> {
-        let mut filter = Filter::new().address(address);
-        if let Some(event_signature) = event_signature {
-            filter = filter.event_signature(event_signature);
-        }
-        if let Some(from_block) = from_block {
-            filter = filter.from_block(from_block);
-        }
-        if let Some(to_block) = to_block {
-            filter = filter.to_block(to_block);
-        }
-
-        let logs = self.inner.get_logs(&filter).await?;
-        let logs = logs
-            .into_iter()
-            .map(|log| RawLog {
-                topics: log.topics().to_vec(),
-                block_number: log.block_number,
-                block_timestamp: log.block_timestamp,
-                transaction_hash: log.transaction_hash,
-                address: log.address(),
-                data: log.data().data.clone(),
-            })
-            .collect();
-
-        Ok(logs)
-    }
-
-    async fn eth_call(&self, to: Address, data: Bytes) -> Result<Bytes, Eip1193Error> {
-        let request = TransactionRequest::default().to(to).with_input(data);
-        Ok(self.inner.call(request).await?)
-    }
-
-    async fn estimate_gas(
-        &self,
-        to: Address,
-        data: Bytes,
-        from: Option<Address>,
-    ) -> Result<u64, Eip1193Error> {
-        let mut request = TransactionRequest::default().to(to).with_input(data);
-        if let Some(f) = from {
-            request = request.from(f);
-        }
-        Ok(self.inner.estimate_gas(request).await?)
-    }
-
-    async fn gas_price(&self) -> Result<u128, Eip1193Error> {
-        Ok(self.inner.get_gas_price().await?)
-    }
-
-    async fn transaction_count(
-        &self,
-        address: Address,
-        block: Option<u64>,
-    ) -> Result<u64, Eip1193Error> {
-        let block_id = match block {
-            Some(b) => BlockId::number(b),
-            None => BlockId::latest(),
-        };
-
-        Ok(self
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+        let params = serde_json::value::to_raw_value(&params)
+            .map_err(|e| Eip1193Error::Decode(e.to_string()))?;
+        let result = self
             .inner
-            .get_transaction_count(address)
-            .block_id(block_id)
-            .await?)
+            .raw_request_dyn(method.to_string().into(), &params)
+            .await?;
+        serde_json::from_str(result.get()).map_err(|e| Eip1193Error::Decode(e.to_string()))
     }
 }
 
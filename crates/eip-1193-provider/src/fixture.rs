@@ -0,0 +1,231 @@
+//! Request/response fixtures for deterministic, offline replays of network clients.
+//!
+//! A [`Recorder`] captures every request a client makes along with its response. The resulting
+//! [`Fixture`] can be saved and later served by a [`Replayer`], which answers identical requests
+//! with the recorded responses in the order they were recorded. A single fixture can hold the
+//! traffic of several clients (EIP-1193, Subsquid, POI) since entries are keyed by method name.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use thiserror::Error;
+
+/// A recorded sequence of requests and responses.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Fixture {
+    pub entries: Vec<FixtureEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureEntry {
+    /// Client-defined method name, e.g. `eth_getLogs` or `ppoi_validated_txid`.
+    pub method: String,
+    pub params: Value,
+    pub response: FixtureResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FixtureResponse {
+    Ok(Value),
+    Err(Value),
+}
+
+/// How a client handles its network traffic.
+#[derive(Clone, Default)]
+pub enum FixtureMode {
+    /// Talk to the network without recording.
+    #[default]
+    Live,
+    /// Talk to the network and record every request to the recorder.
+    Record(Arc<Recorder>),
+    /// Never touch the network. Serve every request from the replayer.
+    Replay(Arc<Replayer>),
+}
+
+#[derive(Debug, Error)]
+pub enum FixtureError {
+    #[error("No recorded response for {method} {params}")]
+    Missing { method: String, params: String },
+    #[error("Serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Records requests and responses into a [`Fixture`].
+#[derive(Default)]
+pub struct Recorder {
+    fixture: Mutex<Fixture>,
+}
+
+/// Serves recorded responses from a [`Fixture`].
+///
+/// Identical requests are answered with their recorded responses in order. Once a request's
+/// responses are exhausted, the last one is repeated so that polling calls such as
+/// `eth_blockNumber` keep working past the end of the recording.
+pub struct Replayer {
+    responses: Mutex<HashMap<(String, String), Responses>>,
+}
+
+struct Responses {
+    queue: VecDeque<FixtureResponse>,
+    last: FixtureResponse,
+}
+
+impl Fixture {
+    pub fn from_json(json: &str) -> Result<Self, FixtureError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, FixtureError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    #[cfg(native)]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, FixtureError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    #[cfg(native)]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), FixtureError> {
+        Ok(std::fs::write(path, self.to_json()?)?)
+    }
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a request and its response. Requests whose params or response cannot be
+    /// serialized are not recorded, since recording must never change the behavior of the client.
+    pub fn record<P, R, E>(&self, method: &str, params: &P, response: Result<R, E>)
+    where
+        P: Serialize + ?Sized,
+        R: Serialize,
+        E: Serialize,
+    {
+        let Ok(params) = serde_json::to_value(params) else {
+            return;
+        };
+        let response = match response {
+            Ok(r) => serde_json::to_value(r).map(FixtureResponse::Ok),
+            Err(e) => serde_json::to_value(e).map(FixtureResponse::Err),
+        };
+        let Ok(response) = response else {
+            return;
+        };
+
+        self.lock().entries.push(FixtureEntry {
+            method: method.to_string(),
+            params,
+            response,
+        });
+    }
+
+    /// Returns a copy of everything recorded so far.
+    pub fn fixture(&self) -> Fixture {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Fixture> {
+        self.fixture.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Replayer {
+    pub fn new(fixture: Fixture) -> Self {
+        let mut responses: HashMap<(String, String), Responses> = HashMap::new();
+        for entry in fixture.entries {
+            let key = (entry.method, entry.params.to_string());
+            match responses.get_mut(&key) {
+                Some(r) => r.queue.push_back(entry.response),
+                None => {
+                    responses.insert(
+                        key,
+                        Responses {
+                            queue: VecDeque::from([entry.response.clone()]),
+                            last: entry.response,
+                        },
+                    );
+                }
+            }
+        }
+
+        Self {
+            responses: Mutex::new(responses),
+        }
+    }
+
+    /// Returns the next recorded response for `method` called with `params`.
+    pub fn replay<P>(&self, method: &str, params: &P) -> Result<FixtureResponse, FixtureError>
+    where
+        P: Serialize + ?Sized,
+    {
+        let params = serde_json::to_value(params)?.to_string();
+        let mut responses = self.responses.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(r) = responses.get_mut(&(method.to_string(), params.clone())) else {
+            return Err(FixtureError::Missing {
+                method: method.to_string(),
+                params,
+            });
+        };
+
+        if let Some(next) = r.queue.pop_front() {
+            r.last = next;
+        }
+        Ok(r.last.clone())
+    }
+
+    /// Like [`Replayer::replay`], but deserializes the recorded response.
+    pub fn replay_as<P, R, E>(&self, method: &str, params: &P) -> Result<Result<R, E>, FixtureError>
+    where
+        P: Serialize + ?Sized,
+        R: DeserializeOwned,
+        E: DeserializeOwned,
+    {
+        Ok(match self.replay(method, params)? {
+            FixtureResponse::Ok(value) => Ok(serde_json::from_value(value)?),
+            FixtureResponse::Err(value) => Err(serde_json::from_value(value)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replays_in_order() {
+        let recorder = Recorder::new();
+        recorder.record("eth_blockNumber", &(), Ok::<_, String>(1));
+        recorder.record("eth_blockNumber", &(), Err::<u64, _>("timeout"));
+        recorder.record("eth_blockNumber", &(), Ok::<_, String>(2));
+        recorder.record("eth_chainId", &[1, 2], Ok::<_, String>(10));
+
+        let fixture = Fixture::from_json(&recorder.fixture().to_json().unwrap()).unwrap();
+        let replayer = Replayer::new(fixture);
+        let block = || {
+            replayer
+                .replay_as::<_, u64, String>("eth_blockNumber", &())
+                .unwrap()
+        };
+
+        assert_eq!(block(), Ok(1));
+        assert_eq!(block(), Err("timeout".to_string()));
+        assert_eq!(block(), Ok(2));
+        assert_eq!(block(), Ok(2), "last response repeats");
+
+        let chain_id = replayer.replay_as::<_, u64, String>("eth_chainId", &[1, 2]);
+        assert_eq!(chain_id.unwrap(), Ok(10));
+        assert!(matches!(
+            replayer.replay("eth_chainId", &[2, 1]),
+            Err(FixtureError::Missing { .. })
+        ));
+    }
+}
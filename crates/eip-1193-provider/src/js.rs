@@ -1,24 +1,15 @@
-use alloy::primitives::{Address, Bytes, FixedBytes};
-use js_sys::BigInt;
+use serde::Serialize;
+use serde_json::{Value, json};
 use wasm_bindgen::prelude::*;
 
-use crate::provider::{Eip1193Error, Eip1193Provider, RawLog};
+use crate::provider::{Eip1193Error, Eip1193Provider};
 
 #[wasm_bindgen(typescript_custom_section)]
 const TS_INTERFACE: &str = r#"
+/** An EIP-1193 provider, e.g. `window.ethereum` or a viem client. */
 export interface Eip1193Provider {
-    getChainId(): Promise<bigint>;
-    getBlockNumber(): Promise<bigint>;
-    getLogs(
-        address: `0x${string}`, 
-        eventSignature: `0x${string}` | undefined, 
-        fromBlock: number | undefined, 
-        toBlock: number | undefined,
-    ): Promise<RawLog[]>;
-    ethCall(to: `0x${string}`, data: `0x${string}`): Promise<`0x${string}`>;
-    estimateGas(to: `0x${string}`, from: `0x${string}` | undefined, data: `0x${string}`): Promise<bigint>;
-    getGasPrice(): Promise<bigint>;
-    getTransactionCount(address: `0x${string}`, block: number | undefined): Promise<bigint>;
+    /** Sends a JSON-RPC request and resolves to its raw result. */
+    request(args: { method: string; params: unknown[] }): Promise<unknown>;
 }
 "#;
 
@@ -27,162 +18,26 @@ extern "C" {
     #[wasm_bindgen(typescript_type = "Eip1193Provider")]
     pub type JsEip1193Provider;
 
-    #[wasm_bindgen(method, catch, js_name = "getChainId")]
-    pub async fn get_chain_id(this: &JsEip1193Provider) -> Result<JsValue, JsValue>;
-
-    #[wasm_bindgen(method, catch, js_name = "getBlockNumber")]
-    pub async fn get_block_number(this: &JsEip1193Provider) -> Result<JsValue, JsValue>;
-
-    #[wasm_bindgen(method, catch, js_name = "getLogs")]
-    pub async fn get_logs(
-        this: &JsEip1193Provider,
-        address: &str,
-        event_signature: Option<String>,
-        from_block: Option<u64>,
-        to_block: Option<u64>,
-    ) -> Result<JsValue, JsValue>;
-
-    #[wasm_bindgen(method, catch, js_name = "ethCall")]
-    pub async fn eth_call(
-        this: &JsEip1193Provider,
-        to: &str,
-        data: &str,
-    ) -> Result<JsValue, JsValue>;
-
-    #[wasm_bindgen(method, catch, js_name = "estimateGas")]
-    pub async fn estimate_gas(
-        this: &JsEip1193Provider,
-        to: &str,
-        from: Option<String>,
-        data: &str,
-    ) -> Result<JsValue, JsValue>;
-
-    #[wasm_bindgen(method, catch, js_name = "getGasPrice")]
-    pub async fn get_gas_price(this: &JsEip1193Provider) -> Result<JsValue, JsValue>;
-
-    #[wasm_bindgen(method, catch, js_name = "getTransactionCount")]
-    pub async fn get_transaction_count(
-        this: &JsEip1193Provider,
-        address: &str,
-        block: Option<u64>,
-    ) -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = "request")]
+    pub async fn request(this: &JsEip1193Provider, args: JsValue) -> Result<JsValue, JsValue>;
 }
 
 #[async_trait::async_trait(?Send)]
 impl Eip1193Provider for JsEip1193Provider {
-    async fn get_chain_id(&self) -> Result<u64, Eip1193Error> {
-        let result = self
-            .get_chain_id()
-            .await
-            .map_err(|e| Eip1193Error::Rpc(format!("{:?}", e)))?;
-        js_bigint_to_u64(result)
-    }
-
-    async fn get_block_number(&self) -> Result<u64, Eip1193Error> {
-        let result = self
-            .get_block_number()
-            .await
-            .map_err(|e| Eip1193Error::Rpc(format!("{:?}", e)))?;
-        js_bigint_to_u64(result)
-    }
-
-    async fn logs(
-        &self,
-        address: Address,
-        event_signature: Option<FixedBytes<32>>,
-        from_block: Option<u64>,
-        to_block: Option<u64>,
-    ) -> Result<Vec<RawLog>, Eip1193Error> {
-        let addr_str = format!("{:#x}", address);
-        let sig_str = event_signature.map(|s| format!("{:#x}", s));
-
-        let result = self
-            .get_logs(&addr_str, sig_str, from_block, to_block)
-            .await
-            .map_err(|e| Eip1193Error::Rpc(format!("{:?}", e)))?;
-
-        let logs: Vec<RawLog> = serde_wasm_bindgen::from_value(result)
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+        //? JSON-compatible so params are passed as plain objects rather than `Map`s.
+        let args = json!({ "method": method, "params": params })
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
             .map_err(|e| Eip1193Error::Decode(e.to_string()))?;
-        Ok(logs)
-    }
-
-    async fn eth_call(&self, to: Address, data: Bytes) -> Result<Bytes, Eip1193Error> {
-        let to_str = format!("{:#x}", to);
-        let data_str = format!("0x{}", hex::encode(data));
 
         let result = self
-            .eth_call(&to_str, &data_str)
+            .request(args)
             .await
             .map_err(|e| Eip1193Error::Rpc(format!("{:?}", e)))?;
+        if result.is_undefined() {
+            return Ok(Value::Null);
+        }
 
-        let hex_str: String = serde_wasm_bindgen::from_value(result)
-            .map_err(|e| Eip1193Error::Decode(e.to_string()))?;
-        let bytes = parse_hex_bytes(&hex_str)?;
-        Ok(bytes.into())
-    }
-
-    async fn estimate_gas(
-        &self,
-        to: Address,
-        data: Bytes,
-        from: Option<Address>,
-    ) -> Result<u64, Eip1193Error> {
-        let to_str = format!("{:#x}", to);
-        let from_str = from.map(|f| format!("{:#x}", f));
-        let data_str = format!("0x{}", hex::encode(data));
-        let result = self
-            .estimate_gas(&to_str, from_str, &data_str)
-            .await
-            .map_err(|e| Eip1193Error::Rpc(format!("{:?}", e)))?;
-        js_bigint_to_u64(result)
-    }
-
-    async fn gas_price(&self) -> Result<u128, Eip1193Error> {
-        let result = self
-            .get_gas_price()
-            .await
-            .map_err(|e| Eip1193Error::Rpc(format!("{:?}", e)))?;
-        js_bigint_to_u128(result)
+        serde_wasm_bindgen::from_value(result).map_err(|e| Eip1193Error::Decode(e.to_string()))
     }
-
-    async fn transaction_count(
-        &self,
-        address: Address,
-        block: Option<u64>,
-    ) -> Result<u64, Eip1193Error> {
-        let address_str = format!("{:#x}", address);
-        let block_num = block;
-        let result = self
-            .get_transaction_count(&address_str, block_num)
-            .await
-            .map_err(|e| Eip1193Error::Rpc(format!("{:?}", e)))?;
-        js_bigint_to_u64(result)
-    }
-}
-
-fn js_bigint_to_u64(val: JsValue) -> Result<u64, Eip1193Error> {
-    let bigint = BigInt::from(val);
-    let s = bigint
-        .to_string(10)
-        .map_err(|e| Eip1193Error::Decode(format!("{:?}", e)))?
-        .as_string()
-        .ok_or_else(|| Eip1193Error::Decode("BigInt.toString returned non-string".into()))?;
-    s.parse::<u64>()
-        .map_err(|e| Eip1193Error::Decode(e.to_string()))
-}
-
-fn js_bigint_to_u128(val: JsValue) -> Result<u128, Eip1193Error> {
-    let bigint = BigInt::from(val);
-    let s = bigint
-        .to_string(10)
-        .map_err(|e| Eip1193Error::Decode(format!("{:?}", e)))?
-        .as_string()
-        .ok_or_else(|| Eip1193Error::Decode("BigInt.toString returned non-string".into()))?;
-    s.parse::<u128>()
-        .map_err(|e| Eip1193Error::Decode(e.to_string()))
-}
-
-fn parse_hex_bytes(s: &str) -> Result<Vec<u8>, Eip1193Error> {
-    let s = s.strip_prefix("0x").unwrap_or(s);
-    hex::decode(s).map_err(|e| Eip1193Error::Decode(e.to_string()))
 }
@@ -1,6 +1,10 @@
 #[cfg(alloy)]
 pub mod alloy;
+pub mod fixture;
 #[cfg(js)]
 pub mod js;
+pub mod middleware;
 pub mod provider;
+pub mod recording;
+pub mod transaction;
 pub mod tx_data;
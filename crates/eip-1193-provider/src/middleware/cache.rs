@@ -0,0 +1,229 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use alloy::primitives::U64;
+use serde_json::{Value, json};
+
+use crate::provider::{Eip1193Error, Eip1193Provider, IntoEip1193Provider};
+
+/// Caches responses that can never change: the chain ID, and logs, headers and proofs of blocks
+/// deep enough that they won't be reorged.
+///
+/// Blocks count as final once they're `finality_depth` blocks behind the latest block number
+/// seen through this provider. If none has been seen yet, it's fetched once.
+pub struct CachedProvider {
+    inner: Arc<dyn Eip1193Provider>,
+    finality_depth: u64,
+    capacity: usize,
+    /// Latest block number seen, or 0 if unknown.
+    head: AtomicU64,
+    cache: Mutex<Cache>,
+}
+
+#[derive(Default)]
+struct Cache {
+    responses: HashMap<(String, String), Value>,
+    /// Keys in insertion order, for evicting the oldest first.
+    order: VecDeque<(String, String)>,
+}
+
+impl CachedProvider {
+    pub fn new(inner: impl IntoEip1193Provider) -> Self {
+        Self {
+            inner: inner.into_eip1193(),
+            finality_depth: 64,
+            capacity: 1024,
+            head: AtomicU64::new(0),
+            cache: Mutex::new(Cache::default()),
+        }
+    }
+
+    /// Sets how many blocks behind the latest block a block must be before responses about it
+    /// are cached.
+    pub fn with_finality_depth(mut self, finality_depth: u64) -> Self {
+        self.finality_depth = finality_depth;
+        self
+    }
+
+    /// Sets the maximum number of cached responses. The oldest are evicted first.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Returns the block number a cacheable request is pinned to, `Some(None)` if the request is
+    /// cacheable regardless of block, or `None` if it isn't cacheable.
+    fn cacheable_block(method: &str, params: &Value) -> Option<Option<&Value>> {
+        match method {
+            "eth_chainId" => Some(None),
+            "eth_getLogs" if params[0]["blockHash"].is_null() => Some(Some(&params[0]["toBlock"])),
+            "eth_getBlockByNumber" => Some(Some(&params[0])),
+            "eth_getProof" => Some(Some(&params[2])),
+            _ => None,
+        }
+    }
+
+    /// Returns whether `block` is a block number at least `finality_depth` behind the head.
+    /// Tags like `latest` are never final.
+    async fn is_final(&self, block: &Value) -> Result<bool, Eip1193Error> {
+        let Ok(block) = serde_json::from_value::<U64>(block.clone()) else {
+            return Ok(false);
+        };
+
+        let mut head = self.head.load(Ordering::Relaxed);
+        if head == 0 {
+            let result = self.inner.request("eth_blockNumber", json!([])).await?;
+            head = self.observe_head(&result);
+        }
+        Ok(block.to::<u64>().saturating_add(self.finality_depth) <= head)
+    }
+
+    /// Records `result` of an `eth_blockNumber` call as the head, returning the new head.
+    fn observe_head(&self, result: &Value) -> u64 {
+        let Ok(number) = serde_json::from_value::<U64>(result.clone()) else {
+            return self.head.load(Ordering::Relaxed);
+        };
+        let number = number.to::<u64>();
+        self.head.fetch_max(number, Ordering::Relaxed).max(number)
+    }
+
+    fn insert(&self, key: (String, String), value: Value) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if cache.responses.insert(key.clone(), value).is_none() {
+            cache.order.push_back(key);
+        }
+        while cache.order.len() > self.capacity {
+            if let Some(oldest) = cache.order.pop_front() {
+                cache.responses.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl Eip1193Provider for CachedProvider {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+        let Some(block) = Self::cacheable_block(method, &params) else {
+            let result = self.inner.request(method, params).await;
+            if let (Ok(value), "eth_blockNumber") = (&result, method) {
+                self.observe_head(value);
+            }
+            return result;
+        };
+
+        let key = (method.to_string(), params.to_string());
+        let cached = {
+            let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            cache.responses.get(&key).cloned()
+        };
+        if let Some(value) = cached {
+            return Ok(value);
+        }
+
+        let cacheable = match block {
+            Some(block) => self.is_final(block).await?,
+            None => true,
+        };
+        let value = self.inner.request(method, params).await?;
+        //? A missing block might just not have been seen by the node yet.
+        if cacheable && !value.is_null() {
+            self.insert(key, value.clone());
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts requests and answers each with the request count so far.
+    struct CountingProvider {
+        head: u64,
+        requests: AtomicU64,
+    }
+
+    #[async_trait::async_trait]
+    impl Eip1193Provider for CountingProvider {
+        async fn request(&self, method: &str, _params: Value) -> Result<Value, Eip1193Error> {
+            if method == "eth_blockNumber" {
+                return Ok(json!(U64::from(self.head)));
+            }
+            Ok(json!(self.requests.fetch_add(1, Ordering::Relaxed)))
+        }
+    }
+
+    fn logs_params(to_block: u64) -> Value {
+        json!([{
+            "address": "0x0000000000000000000000000000000000000001",
+            "toBlock": U64::from(to_block),
+        }])
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        let inner = Arc::new(CountingProvider {
+            head: 1000,
+            requests: AtomicU64::new(0),
+        });
+        let provider = CachedProvider::new(inner.clone()).with_capacity(2);
+
+        let chain_id = provider.request("eth_chainId", json!([])).await.unwrap();
+        assert_eq!(
+            provider.request("eth_chainId", json!([])).await.unwrap(),
+            chain_id
+        );
+
+        // Logs of final blocks are cached, recent ones aren't.
+        let old = provider
+            .request("eth_getLogs", logs_params(100))
+            .await
+            .unwrap();
+        assert_eq!(
+            provider
+                .request("eth_getLogs", logs_params(100))
+                .await
+                .unwrap(),
+            old
+        );
+        let recent = provider
+            .request("eth_getLogs", logs_params(990))
+            .await
+            .unwrap();
+        assert_ne!(
+            provider
+                .request("eth_getLogs", logs_params(990))
+                .await
+                .unwrap(),
+            recent
+        );
+
+        // Uncacheable methods always reach the node.
+        let price = provider.request("eth_gasPrice", json!([])).await.unwrap();
+        assert_ne!(
+            provider.request("eth_gasPrice", json!([])).await.unwrap(),
+            price
+        );
+
+        // The oldest response is evicted once over capacity.
+        provider
+            .request("eth_getLogs", logs_params(200))
+            .await
+            .unwrap();
+        assert_ne!(
+            provider.request("eth_chainId", json!([])).await.unwrap(),
+            chain_id
+        );
+        assert_eq!(inner.requests.load(Ordering::Relaxed), 8);
+    }
+}
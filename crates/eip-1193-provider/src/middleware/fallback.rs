@@ -0,0 +1,188 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use futures::future::join_all;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::{
+    middleware::{is_transient, retry::NON_IDEMPOTENT},
+    provider::{Eip1193Error, Eip1193Provider},
+};
+
+/// Spreads requests over several endpoints round-robin, falling back to the next endpoint when
+/// one fails with a transient error. Transaction submissions are only sent to one endpoint.
+///
+/// With [`FallbackProvider::with_call_quorum`], `eth_call` results are only trusted once enough
+/// endpoints agree on them.
+pub struct FallbackProvider {
+    providers: Vec<Arc<dyn Eip1193Provider>>,
+    next: AtomicUsize,
+    call_quorum: usize,
+}
+
+impl FallbackProvider {
+    pub fn new(providers: Vec<Arc<dyn Eip1193Provider>>) -> Self {
+        Self {
+            providers,
+            next: AtomicUsize::new(0),
+            call_quorum: 1,
+        }
+    }
+
+    /// Requires `quorum` endpoints to return the same `eth_call` result. Quorum calls are sent
+    /// to every endpoint at once.
+    pub fn with_call_quorum(mut self, quorum: usize) -> Self {
+        self.call_quorum = quorum.max(1);
+        self
+    }
+
+    /// Sends the request to each endpoint in turn, starting with the next in the rotation, until
+    /// one succeeds or fails with a non-transient error.
+    async fn request_any(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut last_error = Eip1193Error::Rpc("No endpoints".into());
+
+        for i in 0..self.providers.len() {
+            let index = (start + i) % self.providers.len();
+            match self.providers[index].request(method, params.clone()).await {
+                Err(e) if is_transient(&e) && !NON_IDEMPOTENT.contains(&method) => {
+                    warn!(
+                        "{} failed on endpoint {} ({}), falling back",
+                        method, index, e
+                    );
+                    last_error = e;
+                }
+                result => return result,
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Sends the request to every endpoint and returns the first result at least
+    /// `call_quorum` of them agree on.
+    async fn request_quorum(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+        let results = join_all(
+            self.providers
+                .iter()
+                .map(|provider| provider.request(method, params.clone())),
+        )
+        .await;
+
+        let mut tallies: Vec<(&Result<Value, Eip1193Error>, usize)> = Vec::new();
+        for result in &results {
+            match tallies.iter_mut().find(|(r, _)| same_result(r, result)) {
+                Some((_, count)) => *count += 1,
+                None => tallies.push((result, 1)),
+            }
+        }
+
+        match tallies
+            .into_iter()
+            .find(|(_, count)| *count >= self.call_quorum)
+        {
+            Some((Ok(value), _)) => Ok(value.clone()),
+            //? Agreed-on errors, e.g. reverts, are as trustworthy as agreed-on results.
+            Some((Err(e), _)) => Err(e.clone()),
+            None => Err(Eip1193Error::Rpc(format!(
+                "Fewer than {} of {} endpoints agreed on {}",
+                self.call_quorum,
+                self.providers.len(),
+                method
+            ))),
+        }
+    }
+}
+
+fn same_result(a: &Result<Value, Eip1193Error>, b: &Result<Value, Eip1193Error>) -> bool {
+    match (a, b) {
+        (Ok(a), Ok(b)) => a == b,
+        (Err(a), Err(b)) => a.to_string() == b.to_string(),
+        _ => false,
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl Eip1193Provider for FallbackProvider {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+        if method == "eth_call" && self.call_quorum > 1 {
+            self.request_quorum(method, params).await
+        } else {
+            self.request_any(method, params).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Answers every request with `response`.
+    struct FixedProvider {
+        response: Result<Value, &'static str>,
+    }
+
+    #[async_trait::async_trait]
+    impl Eip1193Provider for FixedProvider {
+        async fn request(&self, _method: &str, _params: Value) -> Result<Value, Eip1193Error> {
+            self.response
+                .clone()
+                .map_err(|e| Eip1193Error::Rpc(e.to_string()))
+        }
+    }
+
+    fn fallback(responses: Vec<Result<Value, &'static str>>) -> FallbackProvider {
+        FallbackProvider::new(
+            responses
+                .into_iter()
+                .map(|response| Arc::new(FixedProvider { response }) as Arc<dyn Eip1193Provider>)
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_fallback() {
+        let provider = fallback(vec![Err("503 Service Unavailable"), Ok(json!("0x1"))]);
+        for _ in 0..2 {
+            let result = provider.request("eth_chainId", json!([])).await;
+            assert_eq!(result.unwrap(), "0x1");
+        }
+
+        // Non-transient errors aren't retried on other endpoints.
+        let provider = fallback(vec![Err("execution reverted"), Ok(json!("0x"))]);
+        assert!(provider.request("eth_call", json!([])).await.is_err());
+
+        // A submission that timed out may have gone through, so it isn't sent again.
+        let provider = fallback(vec![Err("timeout"), Ok(json!("0x1234"))]);
+        assert!(
+            provider
+                .request("eth_sendRawTransaction", json!(["0x"]))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_call_quorum() {
+        let provider = fallback(vec![
+            Ok(json!("0x01")),
+            Ok(json!("0x02")),
+            Ok(json!("0x01")),
+        ])
+        .with_call_quorum(2);
+        assert_eq!(
+            provider.request("eth_call", json!([])).await.unwrap(),
+            "0x01"
+        );
+
+        let provider = fallback(vec![Ok(json!("0x01")), Ok(json!("0x02")), Err("timeout")])
+            .with_call_quorum(2);
+        assert!(provider.request("eth_call", json!([])).await.is_err());
+    }
+}
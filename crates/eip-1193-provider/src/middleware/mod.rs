@@ -0,0 +1,19 @@
+//! [`Eip1193Provider`](crate::provider::Eip1193Provider) wrappers that make unreliable RPC
+//! endpoints usable. Each wraps any provider, so they compose:
+//!
+//! ```ignore
+//! let provider = RetryProvider::new(Arc::new(RateLimitedProvider::new(
+//!     Arc::new(FallbackProvider::new(vec![primary, backup])),
+//!     10,
+//! )));
+//! ```
+
+mod cache;
+mod fallback;
+mod rate_limit;
+mod retry;
+
+pub use cache::CachedProvider;
+pub use fallback::FallbackProvider;
+pub use rate_limit::RateLimitedProvider;
+pub use retry::{RetryProvider, is_transient};
@@ -0,0 +1,77 @@
+use std::sync::{Arc, Mutex};
+
+use common::{Duration, Instant};
+use serde_json::Value;
+
+use crate::provider::{Eip1193Error, Eip1193Provider, IntoEip1193Provider};
+
+/// Limits the request rate with a token bucket: requests are let through immediately while
+/// tokens are left, and otherwise wait for the bucket to refill.
+pub struct RateLimitedProvider {
+    inner: Arc<dyn Eip1193Provider>,
+    requests_per_second: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimitedProvider {
+    /// Allows `requests_per_second` requests per second on average, in bursts of up to
+    /// `requests_per_second` requests.
+    pub fn new(inner: impl IntoEip1193Provider, requests_per_second: u32) -> Self {
+        let requests_per_second = f64::from(requests_per_second.max(1));
+        Self {
+            inner: inner.into_eip1193(),
+            requests_per_second,
+            burst: requests_per_second,
+            bucket: Mutex::new(Bucket {
+                tokens: requests_per_second,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    /// Sets how many requests can be sent at once after a quiet period.
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = f64::from(burst.max(1));
+        self.bucket
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .tokens = self.burst;
+        self
+    }
+
+    /// Waits until a token is available and takes it.
+    async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+                let now = Instant::now();
+                let refill = now.duration_since(bucket.refilled).as_secs_f64();
+                bucket.tokens = (bucket.tokens + refill * self.requests_per_second).min(self.burst);
+                bucket.refilled = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.requests_per_second)
+            };
+
+            common::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl Eip1193Provider for RateLimitedProvider {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+        self.acquire().await;
+        self.inner.request(method, params).await
+    }
+}
@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use common::Duration;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::provider::{Eip1193Error, Eip1193Provider, IntoEip1193Provider};
+
+/// Methods that must not be repeated. A submission that timed out may still have reached the
+/// node, so retrying it, on the same endpoint or another, could broadcast it twice.
+pub(crate) const NON_IDEMPOTENT: &[&str] = &["eth_sendRawTransaction", "eth_sendTransaction"];
+
+/// Retries requests that fail with a transient error, backing off exponentially between
+/// attempts. Transaction submissions are never retried.
+pub struct RetryProvider {
+    inner: Arc<dyn Eip1193Provider>,
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryProvider {
+    pub fn new(inner: impl IntoEip1193Provider) -> Self {
+        Self {
+            inner: inner.into_eip1193(),
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+
+    /// Sets how many times a request is retried before its error is returned.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before the first retry. The delay doubles after each retry, up to `max`.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl Eip1193Provider for RetryProvider {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+        if NON_IDEMPOTENT.contains(&method) {
+            return self.inner.request(method, params).await;
+        }
+
+        let mut backoff = self.initial_backoff;
+        let mut retries = 0;
+        loop {
+            match self.inner.request(method, params.clone()).await {
+                Err(e) if retries < self.max_retries && is_transient(&e) => {
+                    retries += 1;
+                    warn!(
+                        "{} failed ({}), retry {}/{} in {:?}",
+                        method, e, retries, self.max_retries, backoff
+                    );
+                    common::sleep(backoff).await;
+                    backoff = (backoff * 2).min(self.max_backoff);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Returns whether `e` is likely to go away if the request is repeated, e.g. rate limiting,
+/// timeouts, or a load-balanced node lagging behind its peers.
+pub fn is_transient(e: &Eip1193Error) -> bool {
+    const TRANSIENT: &[&str] = &[
+        "429",
+        "rate limit",
+        "rate-limit",
+        "too many requests",
+        "timeout",
+        "timed out",
+        "connection",
+        "502",
+        "503",
+        "504",
+        "bad gateway",
+        "service unavailable",
+        "temporarily unavailable",
+        "header not found",
+        "try again",
+    ];
+
+    let Eip1193Error::Rpc(message) = e else {
+        return false;
+    };
+
+    let message = message.to_lowercase();
+    TRANSIENT.iter().any(|m| message.contains(m))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serde_json::json;
+
+    use super::*;
+
+    /// Fails with each of `errors` in turn, then succeeds.
+    struct FlakyProvider {
+        errors: Mutex<Vec<&'static str>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Eip1193Provider for FlakyProvider {
+        async fn request(&self, _method: &str, _params: Value) -> Result<Value, Eip1193Error> {
+            let mut errors = self.errors.lock().unwrap();
+            if errors.is_empty() {
+                return Ok(json!("0x1"));
+            }
+            Err(Eip1193Error::Rpc(errors.remove(0).into()))
+        }
+    }
+
+    fn retrying(errors: Vec<&'static str>) -> RetryProvider {
+        let flaky = Arc::new(FlakyProvider {
+            errors: Mutex::new(errors),
+        });
+        RetryProvider::new(flaky)
+            .with_max_retries(2)
+            .with_backoff(Duration::ZERO, Duration::ZERO)
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        let provider = retrying(vec!["503 Service Unavailable", "request timed out"]);
+        assert_eq!(
+            provider.request("eth_chainId", json!([])).await.unwrap(),
+            "0x1"
+        );
+
+        let provider = retrying(vec!["429", "429", "429"]);
+        assert!(provider.request("eth_chainId", json!([])).await.is_err());
+
+        // Errors that would repeat are returned immediately.
+        let provider = retrying(vec!["execution reverted"]);
+        assert!(matches!(
+            provider.request("eth_call", json!([])).await,
+            Err(Eip1193Error::Rpc(m)) if m == "execution reverted"
+        ));
+
+        // Submissions may have reached the node, so they are never repeated.
+        let provider = retrying(vec!["503 Service Unavailable"]);
+        assert!(
+            provider
+                .request("eth_sendRawTransaction", json!(["0x00"]))
+                .await
+                .is_err()
+        );
+    }
+}
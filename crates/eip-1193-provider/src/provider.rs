@@ -1,61 +1,239 @@
 use std::sync::Arc;
 
 use alloy::{
-    primitives::{Address, Bytes, FixedBytes, Log},
+    consensus::Header,
+    eips::BlockNumberOrTag,
+    primitives::{Address, B256, Bytes, FixedBytes, Log, U64, U128},
+    rpc::types::{EIP1186AccountProofResponse, FeeHistory, TransactionReceipt},
     sol_types::SolCall,
 };
 use common::MaybeSend;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
 use thiserror::Error;
 
-// TODO: Split me up into multiple per-domain traits (logs, receipts, gas,
-// transactions, caller, etc)
-//
-// TODO: Ensure I'm a fully compliant EIP-1193 provider
+use crate::tx_data::TxData;
+
+/// An EIP-1193 provider: anything that can answer JSON-RPC requests.
+///
+/// Typed access to the methods this crate uses is provided by the extension traits below
+/// ([`Eip1193Blocks`], [`Eip1193Logs`], [`Eip1193Caller`], [`Eip1193Gas`],
+/// [`Eip1193Transactions`] and [`Eip1193Proofs`]), which are implemented for every provider.
 #[cfg_attr(native, async_trait::async_trait)]
 #[cfg_attr(wasm, async_trait::async_trait(?Send))]
 pub trait Eip1193Provider: MaybeSend {
-    async fn get_chain_id(&self) -> Result<u64, Eip1193Error>;
+    /// Sends the JSON-RPC request `method` with positional `params` and returns its result.
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Eip1193Error>;
+}
+
+/// Typed [`Eip1193Provider::request`].
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+pub trait Eip1193Request: Eip1193Provider {
+    async fn request_as<R: DeserializeOwned + MaybeSend>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<R, Eip1193Error> {
+        let result = self.request(method, params).await?;
+        serde_json::from_value(result).map_err(|e| Eip1193Error::Decode(e.to_string()))
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+pub trait Eip1193Blocks: Eip1193Request {
+    async fn get_chain_id(&self) -> Result<u64, Eip1193Error> {
+        let chain_id: U64 = self.request_as("eth_chainId", json!([])).await?;
+        Ok(chain_id.to())
+    }
+
+    async fn get_block_number(&self) -> Result<u64, Eip1193Error> {
+        let number: U64 = self.request_as("eth_blockNumber", json!([])).await?;
+        Ok(number.to())
+    }
 
-    async fn get_block_number(&self) -> Result<u64, Eip1193Error>;
+    /// Returns the header of block `block`.
+    async fn get_block(&self, block: u64) -> Result<Header, Eip1193Error> {
+        let params = json!([BlockNumberOrTag::Number(block), false]);
+        let header: Option<Header> = self.request_as("eth_getBlockByNumber", params).await?;
+        header.ok_or_else(|| Eip1193Error::Rpc(format!("Block {block} not found")))
+    }
+}
 
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+pub trait Eip1193Logs: Eip1193Request {
+    /// Returns logs emitted by `address`. If `event_signatures` is non-empty, only logs whose
+    /// topic0 matches one of them are returned.
     async fn logs(
         &self,
         address: Address,
-        event_signature: Option<FixedBytes<32>>,
+        event_signatures: &[FixedBytes<32>],
         from_block: Option<u64>,
         to_block: Option<u64>,
-    ) -> Result<Vec<RawLog>, Eip1193Error>;
+    ) -> Result<Vec<RawLog>, Eip1193Error> {
+        //? Built by hand rather than with `Filter` so topics keep their order, keeping params
+        //? stable for fixtures and caches.
+        let mut filter = json!({ "address": address });
+        if !event_signatures.is_empty() {
+            filter["topics"] = json!([event_signatures]);
+        }
+        if let Some(from_block) = from_block {
+            filter["fromBlock"] = json!(BlockNumberOrTag::Number(from_block));
+        }
+        if let Some(to_block) = to_block {
+            filter["toBlock"] = json!(BlockNumberOrTag::Number(to_block));
+        }
+
+        let logs: Vec<alloy::rpc::types::Log> =
+            self.request_as("eth_getLogs", json!([filter])).await?;
+        let logs = logs
+            .into_iter()
+            .map(|log| RawLog {
+                topics: log.topics().to_vec(),
+                block_number: log.block_number,
+                block_timestamp: log.block_timestamp,
+                transaction_hash: log.transaction_hash,
+                address: log.address(),
+                data: log.data().data.clone(),
+            })
+            .collect();
+
+        Ok(logs)
+    }
+}
 
-    async fn eth_call(&self, to: Address, data: Bytes) -> Result<Bytes, Eip1193Error>;
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+pub trait Eip1193Caller: Eip1193Request {
+    async fn eth_call(&self, to: Address, data: Bytes) -> Result<Bytes, Eip1193Error> {
+        let params = json!([{ "to": to, "data": data }, BlockNumberOrTag::Latest]);
+        self.request_as("eth_call", params).await
+    }
+
+    async fn sol_call<C: SolCall + common::MaybeSend>(
+        &self,
+        to: Address,
+        call: C,
+    ) -> Result<C::Return, Eip1193Error> {
+        let data = call.abi_encode().into();
+        let ret = self.eth_call(to, data).await?;
+        C::abi_decode_returns(&ret).map_err(|e| Eip1193Error::Decode(e.to_string()))
+    }
+}
 
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+pub trait Eip1193Gas: Eip1193Request {
     async fn estimate_gas(
         &self,
         to: Address,
         data: Bytes,
         from: Option<Address>,
-    ) -> Result<u64, Eip1193Error>;
+    ) -> Result<u64, Eip1193Error> {
+        let mut call = json!({ "to": to, "data": data });
+        if let Some(from) = from {
+            call["from"] = json!(from);
+        }
+        let gas: U64 = self.request_as("eth_estimateGas", json!([call])).await?;
+        Ok(gas.to())
+    }
 
-    async fn gas_price(&self) -> Result<u128, Eip1193Error>;
+    /// Estimates the gas of sending `tx` from `from`, including its value.
+    async fn estimate_tx_gas(&self, tx: &TxData, from: Address) -> Result<u64, Eip1193Error> {
+        let call = json!({ "to": tx.to, "data": tx.data, "value": tx.value, "from": from });
+        let gas: U64 = self.request_as("eth_estimateGas", json!([call])).await?;
+        Ok(gas.to())
+    }
+
+    async fn gas_price(&self) -> Result<u128, Eip1193Error> {
+        let price: U128 = self.request_as("eth_gasPrice", json!([])).await?;
+        Ok(price.to())
+    }
+
+    /// Returns base fees and gas usage of the latest `block_count` blocks, along with the
+    /// priority fees paid at each of `reward_percentiles`.
+    async fn fee_history(
+        &self,
+        block_count: u64,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory, Eip1193Error> {
+        let params = json!([
+            U64::from(block_count),
+            BlockNumberOrTag::Latest,
+            reward_percentiles
+        ]);
+        self.request_as("eth_feeHistory", params).await
+    }
+
+    /// Returns the node's suggested priority fee per gas.
+    async fn max_priority_fee(&self) -> Result<u128, Eip1193Error> {
+        let fee: U128 = self
+            .request_as("eth_maxPriorityFeePerGas", json!([]))
+            .await?;
+        Ok(fee.to())
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+pub trait Eip1193Transactions: Eip1193Request {
+    /// Returns the transaction with hash `hash`.
+    async fn get_transaction(&self, hash: B256) -> Result<RawTransaction, Eip1193Error> {
+        let tx: Option<RpcTransaction> = self
+            .request_as("eth_getTransactionByHash", json!([hash]))
+            .await?;
+        let tx = tx.ok_or_else(|| Eip1193Error::Rpc(format!("Transaction {hash} not found")))?;
+        Ok(RawTransaction {
+            hash: tx.hash,
+            block_number: tx.block_number.map(|n| n.to()),
+            transaction_index: tx.transaction_index.map(|i| i.to()),
+            from: tx.from,
+            to: tx.to,
+            input: tx.input,
+        })
+    }
 
     async fn transaction_count(
         &self,
         address: Address,
-        block: Option<u64>,
-    ) -> Result<u64, Eip1193Error>;
+        block: BlockNumberOrTag,
+    ) -> Result<u64, Eip1193Error> {
+        let count: U64 = self
+            .request_as("eth_getTransactionCount", json!([address, block]))
+            .await?;
+        Ok(count.to())
+    }
+
+    /// Submits a signed, EIP-2718 encoded transaction and returns its hash.
+    async fn send_raw_transaction(&self, tx: Bytes) -> Result<B256, Eip1193Error> {
+        self.request_as("eth_sendRawTransaction", json!([tx])).await
+    }
+
+    /// Returns the receipt of the transaction with hash `hash`, or `None` if it hasn't been
+    /// included yet.
+    async fn get_transaction_receipt(
+        &self,
+        hash: B256,
+    ) -> Result<Option<TransactionReceipt>, Eip1193Error> {
+        self.request_as("eth_getTransactionReceipt", json!([hash]))
+            .await
+    }
 }
 
 #[cfg_attr(native, async_trait::async_trait)]
 #[cfg_attr(wasm, async_trait::async_trait(?Send))]
-pub trait Eip1193Caller: Eip1193Provider {
-    async fn sol_call<C: SolCall + common::MaybeSend>(
+pub trait Eip1193Proofs: Eip1193Request {
+    /// Returns the EIP-1186 account and storage proofs for `address` at block `block`.
+    async fn get_proof(
         &self,
-        to: Address,
-        call: C,
-    ) -> Result<C::Return, Eip1193Error> {
-        let data = call.abi_encode().into();
-        let ret = self.eth_call(to, data).await?;
-        C::abi_decode_returns(&ret).map_err(|e| Eip1193Error::Decode(e.to_string()))
+        address: Address,
+        storage_keys: Vec<B256>,
+        block: u64,
+    ) -> Result<EIP1186AccountProofResponse, Eip1193Error> {
+        let params = json!([address, storage_keys, BlockNumberOrTag::Number(block)]);
+        self.request_as("eth_getProof", params).await
     }
 }
 
@@ -63,7 +241,13 @@ pub trait IntoEip1193Provider {
     fn into_eip1193(self) -> Arc<dyn Eip1193Provider>;
 }
 
+impl<T> Eip1193Request for T where T: Eip1193Provider + ?Sized {}
+impl<T> Eip1193Blocks for T where T: Eip1193Provider + ?Sized {}
+impl<T> Eip1193Logs for T where T: Eip1193Provider + ?Sized {}
 impl<T> Eip1193Caller for T where T: Eip1193Provider + ?Sized {}
+impl<T> Eip1193Gas for T where T: Eip1193Provider + ?Sized {}
+impl<T> Eip1193Transactions for T where T: Eip1193Provider + ?Sized {}
+impl<T> Eip1193Proofs for T where T: Eip1193Provider + ?Sized {}
 
 impl<T> IntoEip1193Provider for Arc<T>
 where
@@ -80,7 +264,8 @@ impl IntoEip1193Provider for Arc<dyn Eip1193Provider> {
     }
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum Eip1193Error {
     #[error("RPC error: {0}")]
     Rpc(String),
@@ -111,6 +296,43 @@ pub struct RawLog {
     pub data: Bytes,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(target_arch = "wasm32", derive(tsify::Tsify))]
+pub struct RawTransaction {
+    #[cfg_attr(target_arch = "wasm32", tsify(type = "`0x${string}`"))]
+    pub hash: B256,
+
+    #[cfg_attr(target_arch = "wasm32", tsify(type = "number | null"))]
+    pub block_number: Option<u64>,
+
+    #[cfg_attr(target_arch = "wasm32", tsify(type = "number | null"))]
+    pub transaction_index: Option<u64>,
+
+    #[cfg_attr(target_arch = "wasm32", tsify(type = "`0x${string}`"))]
+    pub from: Address,
+
+    /// `None` for contract creations.
+    #[cfg_attr(target_arch = "wasm32", tsify(type = "`0x${string}` | null"))]
+    pub to: Option<Address>,
+
+    #[cfg_attr(target_arch = "wasm32", tsify(type = "`0x${string}`"))]
+    pub input: Bytes,
+}
+
+/// The fields of an `eth_getTransactionByHash` result that [`RawTransaction`] keeps. Decoding
+/// only these accepts every transaction type, including chain-specific ones.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTransaction {
+    hash: B256,
+    block_number: Option<U64>,
+    transaction_index: Option<U64>,
+    from: Address,
+    to: Option<Address>,
+    input: Bytes,
+}
+
 impl RawLog {
     pub fn inner(&self) -> Log {
         Log::new_unchecked(self.address, self.topics.clone(), self.data.clone())
@@ -0,0 +1,127 @@
+//! Providers that record and replay EIP-1193 traffic using [`crate::fixture`].
+
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::{
+    fixture::{Recorder, Replayer},
+    provider::{Eip1193Error, Eip1193Provider, IntoEip1193Provider},
+};
+
+/// Wraps a provider and records every request and its response.
+pub struct RecordingProvider {
+    inner: Arc<dyn Eip1193Provider>,
+    recorder: Arc<Recorder>,
+}
+
+/// Serves requests from a recorded fixture without touching the network.
+///
+/// Requests that were never recorded fail with [`Eip1193Error::Rpc`].
+pub struct ReplayProvider {
+    replayer: Arc<Replayer>,
+}
+
+impl RecordingProvider {
+    pub fn new(inner: impl IntoEip1193Provider, recorder: Arc<Recorder>) -> Self {
+        Self {
+            inner: inner.into_eip1193(),
+            recorder,
+        }
+    }
+}
+
+impl ReplayProvider {
+    pub fn new(replayer: Arc<Replayer>) -> Self {
+        Self { replayer }
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl Eip1193Provider for RecordingProvider {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+        let result = self.inner.request(method, params.clone()).await;
+        self.recorder.record(method, &params, result.as_ref());
+        result
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl Eip1193Provider for ReplayProvider {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+        self.replayer
+            .replay_as(method, &params)
+            .map_err(|e| Eip1193Error::Rpc(e.to_string()))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, B256, Bytes, U128};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        fixture::Fixture,
+        provider::{Eip1193Blocks, Eip1193Caller, Eip1193Gas, Eip1193Logs},
+    };
+
+    /// Provider with canned responses. `eth_call` always reverts.
+    struct StaticProvider;
+
+    #[async_trait::async_trait]
+    impl Eip1193Provider for StaticProvider {
+        async fn request(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+            match method {
+                "eth_chainId" => Ok(json!("0x1")),
+                "eth_blockNumber" => Ok(json!("0x64")),
+                "eth_getLogs" => Ok(json!([{
+                    "address": params[0]["address"],
+                    "topics": [B256::ZERO],
+                    "data": "0x010203",
+                    "blockNumber": params[0]["fromBlock"],
+                }])),
+                "eth_call" => Err(Eip1193Error::Rpc("execution reverted".into())),
+                "eth_gasPrice" | "eth_maxPriorityFeePerGas" => Ok(json!(U128::MAX)),
+                _ => Err(Eip1193Error::Rpc(format!("Unsupported method {method}"))),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_replay() {
+        let recorder = Arc::new(Recorder::new());
+        let recording = RecordingProvider::new(Arc::new(StaticProvider), recorder.clone());
+
+        let address = Address::repeat_byte(1);
+        let logs = recording
+            .logs(address, &[], Some(5), Some(10))
+            .await
+            .unwrap();
+        recording.get_block_number().await.unwrap();
+        recording.gas_price().await.unwrap();
+        recording.max_priority_fee().await.unwrap();
+        recording.eth_call(address, Bytes::new()).await.unwrap_err();
+
+        let fixture = Fixture::from_json(&recorder.fixture().to_json().unwrap()).unwrap();
+        let replay = ReplayProvider::new(Arc::new(Replayer::new(fixture)));
+
+        let replayed = replay.logs(address, &[], Some(5), Some(10)).await.unwrap();
+        assert_eq!(replayed.len(), logs.len());
+        assert_eq!(replayed[0].data, logs[0].data);
+        assert_eq!(replayed[0].block_number, Some(5));
+        assert_eq!(replay.get_block_number().await.unwrap(), 100);
+        assert_eq!(replay.gas_price().await.unwrap(), u128::MAX);
+        assert_eq!(replay.max_priority_fee().await.unwrap(), u128::MAX);
+        assert!(matches!(
+            replay.eth_call(address, Bytes::new()).await,
+            Err(Eip1193Error::Rpc(m)) if m == "execution reverted"
+        ));
+
+        // Calls that were never recorded fail instead of reaching the network.
+        assert!(replay.logs(address, &[], Some(6), Some(10)).await.is_err());
+        assert!(replay.get_chain_id().await.is_err());
+    }
+}
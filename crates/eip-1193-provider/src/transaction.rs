@@ -0,0 +1,305 @@
+//! Self-broadcasting: building, signing and sending transactions from a local EVM account, and
+//! waiting for them to confirm.
+
+use alloy::{
+    consensus::TxEip1559,
+    eips::BlockNumberOrTag,
+    primitives::{Address, B256, TxKind},
+    rpc::types::TransactionReceipt,
+};
+#[cfg(signer)]
+use alloy::{
+    consensus::{SignableTransaction, TxEnvelope},
+    eips::eip2718::Encodable2718,
+    primitives::Bytes,
+    signers::{SignerSync, local::PrivateKeySigner},
+};
+use common::Duration;
+use thiserror::Error;
+use tracing::info;
+
+use crate::{
+    provider::{Eip1193Blocks, Eip1193Error, Eip1193Gas, Eip1193Provider, Eip1193Transactions},
+    tx_data::TxData,
+};
+
+#[derive(Debug, Error)]
+pub enum TransactionError {
+    #[error("Provider error: {0}")]
+    Provider(#[from] Eip1193Error),
+    #[error("Signing error: {0}")]
+    Signer(String),
+    #[error("Fee history returned no base fee")]
+    MissingBaseFee,
+    #[error("Transaction {0} reverted")]
+    Reverted(B256),
+    #[error("Timed out waiting for transaction {0}")]
+    Timeout(B256),
+}
+
+/// Fee caps for an EIP-1559 transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Eip1559Fees {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+/// Suggests fees using the node's priority fee and the next block's base fee. The base fee is
+/// doubled so the transaction stays includable through several full blocks.
+pub async fn estimate_fees(
+    provider: &dyn Eip1193Provider,
+) -> Result<Eip1559Fees, TransactionError> {
+    let history = provider.fee_history(1, &[]).await?;
+    let base_fee = history
+        .base_fee_per_gas
+        .last()
+        .copied()
+        .ok_or(TransactionError::MissingBaseFee)?;
+    let priority_fee = provider.max_priority_fee().await?;
+
+    Ok(Eip1559Fees {
+        max_fee_per_gas: base_fee.saturating_mul(2).saturating_add(priority_fee),
+        max_priority_fee_per_gas: priority_fee,
+    })
+}
+
+/// Builds an unsigned EIP-1559 transaction sending `tx` from `from`, filling in the chain ID,
+/// nonce and fees from `provider`.
+///
+/// If `gas_limit` is None it's estimated, including `tx.value`. The nonce counts pending
+/// transactions, so transactions sent back to back don't reuse it.
+pub async fn prepare_transaction(
+    provider: &dyn Eip1193Provider,
+    from: Address,
+    tx: TxData,
+    gas_limit: Option<u64>,
+) -> Result<TxEip1559, TransactionError> {
+    let chain_id = provider.get_chain_id().await?;
+    let nonce = provider
+        .transaction_count(from, BlockNumberOrTag::Pending)
+        .await?;
+    let gas_limit = match gas_limit {
+        Some(gas_limit) => gas_limit,
+        None => provider.estimate_tx_gas(&tx, from).await?,
+    };
+    let fees = estimate_fees(provider).await?;
+
+    Ok(TxEip1559 {
+        chain_id,
+        nonce,
+        gas_limit,
+        max_fee_per_gas: fees.max_fee_per_gas,
+        max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+        to: TxKind::Call(tx.to),
+        value: tx.value,
+        access_list: Default::default(),
+        input: tx.data,
+    })
+}
+
+/// Signs `tx` and returns it EIP-2718 encoded, ready for
+/// [`Eip1193Transactions::send_raw_transaction`].
+#[cfg(signer)]
+pub fn sign_transaction(
+    signer: &PrivateKeySigner,
+    tx: TxEip1559,
+) -> Result<Bytes, TransactionError> {
+    let signature = signer
+        .sign_hash_sync(&tx.signature_hash())
+        .map_err(|e| TransactionError::Signer(e.to_string()))?;
+    let envelope = TxEnvelope::from(tx.into_signed(signature));
+    Ok(envelope.encoded_2718().into())
+}
+
+/// Prepares, signs and submits `tx` from `signer`'s account, returning its hash.
+#[cfg(signer)]
+pub async fn send_transaction(
+    provider: &dyn Eip1193Provider,
+    signer: &PrivateKeySigner,
+    tx: TxData,
+) -> Result<B256, TransactionError> {
+    let tx = prepare_transaction(provider, signer.address(), tx, None).await?;
+    let raw = sign_transaction(signer, tx)?;
+    let hash = provider.send_raw_transaction(raw).await?;
+    info!("Sent transaction {}", hash);
+    Ok(hash)
+}
+
+/// Waits until the transaction `hash` is included and `confirmations` blocks deep, counting its
+/// own block as the first. Fails if it reverted or `timeout` passes first.
+///
+/// The receipt is re-fetched on every poll, so a transaction that's reorged out is waited on
+/// again until it's re-included.
+pub async fn wait_for_confirmations(
+    provider: &dyn Eip1193Provider,
+    hash: B256,
+    confirmations: u64,
+    poll_interval: Duration,
+    timeout: Duration,
+) -> Result<TransactionReceipt, TransactionError> {
+    let start = common::Instant::now();
+    while start.elapsed() < timeout {
+        let receipt = provider.get_transaction_receipt(hash).await?;
+        if let Some((receipt, block_number)) =
+            receipt.and_then(|r| r.block_number.map(|block| (r, block)))
+        {
+            if !receipt.status() {
+                return Err(TransactionError::Reverted(hash));
+            }
+
+            let head = provider.get_block_number().await?;
+            if head.saturating_sub(block_number) + 1 >= confirmations {
+                return Ok(receipt);
+            }
+        }
+
+        info!("Transaction {} not yet confirmed, retrying...", hash);
+        common::sleep(poll_interval).await;
+    }
+
+    Err(TransactionError::Timeout(hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy::primitives::U256;
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::{
+        fixture::{Recorder, Replayer},
+        recording::ReplayProvider,
+    };
+
+    /// Replays `hash` as pending, then included in block 5 with `status`, and a head of block 6.
+    fn provider(hash: B256, status: &str) -> ReplayProvider {
+        let receipt = json!({
+            "transactionHash": hash,
+            "transactionIndex": "0x0",
+            "blockHash": B256::repeat_byte(2),
+            "blockNumber": "0x5",
+            "from": Address::repeat_byte(3),
+            "to": Address::repeat_byte(4),
+            "cumulativeGasUsed": "0x5208",
+            "gasUsed": "0x5208",
+            "effectiveGasPrice": "0x1",
+            "contractAddress": null,
+            "logs": [],
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "type": "0x2",
+            "status": status,
+        });
+
+        let recorder = Recorder::new();
+        recorder.record::<_, _, Eip1193Error>(
+            "eth_getTransactionReceipt",
+            &json!([hash]),
+            Ok(Value::Null),
+        );
+        recorder.record::<_, _, Eip1193Error>(
+            "eth_getTransactionReceipt",
+            &json!([hash]),
+            Ok(receipt),
+        );
+        recorder.record::<_, _, Eip1193Error>("eth_blockNumber", &json!([]), Ok(json!("0x6")));
+        ReplayProvider::new(Arc::new(Replayer::new(recorder.fixture())))
+    }
+
+    #[tokio::test]
+    async fn test_prepare_transaction() {
+        let from = Address::repeat_byte(3);
+        let tx = TxData::new(
+            Address::repeat_byte(4),
+            vec![1, 2, 3].into(),
+            U256::from(1000),
+        );
+
+        let recorder = Recorder::new();
+        recorder.record::<_, _, Eip1193Error>("eth_chainId", &json!([]), Ok(json!("0x1")));
+        recorder.record::<_, _, Eip1193Error>(
+            "eth_getTransactionCount",
+            &json!([from, "pending"]),
+            Ok(json!("0x7")),
+        );
+        recorder.record::<_, _, Eip1193Error>(
+            "eth_estimateGas",
+            &json!([{ "to": tx.to, "data": tx.data, "value": tx.value, "from": from }]),
+            Ok(json!("0x5208")),
+        );
+        recorder.record::<_, _, Eip1193Error>(
+            "eth_feeHistory",
+            &json!(["0x1", "latest", []]),
+            Ok(json!({
+                "oldestBlock": "0x1",
+                "baseFeePerGas": ["0x64", "0x6e"],
+                "gasUsedRatio": [0.5],
+            })),
+        );
+        recorder.record::<_, _, Eip1193Error>(
+            "eth_maxPriorityFeePerGas",
+            &json!([]),
+            Ok(json!("0x2")),
+        );
+        let provider = ReplayProvider::new(Arc::new(Replayer::new(recorder.fixture())));
+
+        let prepared = prepare_transaction(&provider, from, tx, None)
+            .await
+            .unwrap();
+        assert_eq!(prepared.chain_id, 1);
+        assert_eq!(prepared.nonce, 7);
+        assert_eq!(prepared.gas_limit, 21000);
+        assert_eq!(prepared.max_fee_per_gas, 222);
+        assert_eq!(prepared.max_priority_fee_per_gas, 2);
+        assert_eq!(prepared.value, U256::from(1000));
+    }
+
+    #[cfg(signer)]
+    #[test]
+    fn test_sign_transaction() {
+        use alloy::{consensus::Transaction, eips::eip2718::Decodable2718};
+
+        let signer = PrivateKeySigner::from_bytes(&B256::repeat_byte(1)).unwrap();
+        let tx = TxEip1559 {
+            chain_id: 1,
+            nonce: 7,
+            gas_limit: 21000,
+            max_fee_per_gas: 222,
+            max_priority_fee_per_gas: 2,
+            to: TxKind::Call(Address::repeat_byte(4)),
+            value: U256::from(1000),
+            access_list: Default::default(),
+            input: vec![1, 2, 3].into(),
+        };
+
+        let raw = sign_transaction(&signer, tx.clone()).unwrap();
+        let envelope = TxEnvelope::decode_2718(&mut raw.as_ref()).unwrap();
+        let TxEnvelope::Eip1559(signed) = &envelope else {
+            panic!("Expected an EIP-1559 transaction");
+        };
+        assert_eq!(signed.tx(), &tx);
+        assert_eq!(envelope.nonce(), 7);
+        assert_eq!(signed.recover_signer().unwrap(), signer.address(),);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_confirmations() {
+        let hash = B256::repeat_byte(1);
+        let interval = Duration::from_millis(1);
+        let timeout = Duration::from_millis(20);
+
+        let confirmed = provider(hash, "0x1");
+        let receipt = wait_for_confirmations(&confirmed, hash, 2, interval, timeout)
+            .await
+            .unwrap();
+        assert_eq!(receipt.block_number, Some(5));
+
+        let result = wait_for_confirmations(&confirmed, hash, 3, interval, timeout).await;
+        assert!(matches!(result, Err(TransactionError::Timeout(h)) if h == hash));
+
+        let reverted = provider(hash, "0x0");
+        let result = wait_for_confirmations(&reverted, hash, 1, interval, timeout).await;
+        assert!(matches!(result, Err(TransactionError::Reverted(h)) if h == hash));
+    }
+}
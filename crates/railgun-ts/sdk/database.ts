@@ -1,26 +1,100 @@
 import type { Storage } from "@kohaku-eth/plugins";
 import type { Database } from "../pkg";
 
+/**
+ * Value written in place of deleted keys, since host storage can't delete. Stored
+ * values are hex-encoded, so this never collides with a real value.
+ */
+const TOMBSTONE = "deleted";
+
+/** Keys per index segment. Adding a key only rewrites the last segment. */
+const INDEX_SEGMENT_SIZE = 256;
+
+type KeyIndex = {
+    keys: Set<string>;
+    /** Number of persisted segments. */
+    segments: number;
+    /** Keys in the last segment, which is rewritten as keys are added. */
+    tail: string[];
+};
+
 /**
  * Adapter that wraps a kohaku database and exposes the railgun Database interface.
+ *
+ * Host storage has no way to list or delete keys, so the adapter keeps its own
+ * persisted index of keys to support prefix scans. The index is split into
+ * fixed-size segments so adding a key doesn't rewrite the whole index. Deleted
+ * keys are overwritten with a tombstone and stay in the index.
  */
 export class DatabaseAdapter implements Database {
+    private index?: Promise<KeyIndex>;
+
     constructor(private prefix: string, private storage: Storage) { }
 
     async get(key: string): Promise<string | null> {
         const value = await this.storage.get(this.key(key));
-        return value || value === "" ? value : null;
+        if (value === undefined || value === null || value === TOMBSTONE) return null;
+        return value;
     }
 
     async set(key: string, value: string): Promise<void> {
         await this.storage.set(this.key(key), value);
+        await this.addKey(key);
     }
 
     async delete(key: string): Promise<void> {
-        await this.storage.set(this.key(key), "");
+        await this.storage.set(this.key(key), TOMBSTONE);
+    }
+
+    async entries(prefix: string): Promise<[string, string][]> {
+        const { keys } = await this.loadIndex();
+        const entries: [string, string][] = [];
+        for (const key of keys) {
+            if (!key.startsWith(prefix)) continue;
+
+            const value = await this.get(key);
+            if (value !== null) entries.push([key, value]);
+        }
+        return entries;
     }
 
     private key(key: string): string {
         return `${this.prefix}:${key}`;
     }
+
+    private segmentKey(segment: number): string {
+        return `${this.prefix}#keys:${segment}`;
+    }
+
+    private async addKey(key: string): Promise<void> {
+        const index = await this.loadIndex();
+        if (index.keys.has(key)) return;
+
+        index.keys.add(key);
+        const newSegment = index.segments === 0 || index.tail.length >= INDEX_SEGMENT_SIZE;
+        if (newSegment) {
+            index.segments += 1;
+            index.tail = [];
+        }
+        index.tail.push(key);
+
+        await this.storage.set(this.segmentKey(index.segments - 1), JSON.stringify(index.tail));
+        if (newSegment) {
+            await this.storage.set(`${this.prefix}#keys`, String(index.segments));
+        }
+    }
+
+    private loadIndex(): Promise<KeyIndex> {
+        this.index ??= (async () => {
+            const segments = Number(await this.storage.get(`${this.prefix}#keys`) ?? 0);
+            const index: KeyIndex = { keys: new Set(), segments, tail: [] };
+            for (let i = 0; i < segments; i++) {
+                const raw = await this.storage.get(this.segmentKey(i));
+                index.tail = raw ? JSON.parse(raw) : [];
+                index.tail.forEach((key) => index.keys.add(key));
+            }
+            return index;
+        })();
+        return this.index;
+    }
 }
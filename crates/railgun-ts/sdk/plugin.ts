@@ -103,8 +103,12 @@ export type RGBroadcaster = Broadcaster<RGPrivateOperation>;
 export type RailgunPluginConfig = {
     /** Optional log level for debugging.  Defaults to `Off`  */
     logLevel?: LogLevel,
-    /** Optional RPC call batch size when syncing (default: 10) */
+    /** Optional initial block range per `eth_getLogs` call when syncing. Adapts to the node's limits (default: 1000) */
     rpcBatchSize?: number,
+    /** Optional maximum number of concurrent `eth_getLogs` calls when syncing (default: 4) */
+    rpcConcurrency?: number,
+    /** Optional maximum `eth_getLogs` calls per second when syncing (default: 10) */
+    rpcRequestsPerSecond?: number,
     /** Optional index for key derivation (default: 0) */
     keyIndex?: number,
     /** Optional POI toggle (default: true) */
@@ -151,7 +155,13 @@ export async function createRailgunPlugin(host: Host, config?: RailgunPluginConf
         .withUtxoSyncer(
             UtxoSyncer.chained([
                 UtxoSyncer.subsquid(chain),
-                UtxoSyncer.rpc(chain, eip1193Provider, BigInt(config?.rpcBatchSize ?? 10))
+                UtxoSyncer.rpc(
+                    chain,
+                    eip1193Provider,
+                    config?.rpcBatchSize !== undefined ? BigInt(config.rpcBatchSize) : undefined,
+                    config?.rpcConcurrency,
+                    config?.rpcRequestsPerSecond,
+                )
             ])
         );
     if (config?.poi !== false) {
@@ -1,7 +1,11 @@
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc};
 
+use alloy::primitives::B256;
 use eip_1193_provider::js::JsEip1193Provider;
-use railgun::{builder::RailgunBuilder, chain_config::ChainConfig};
+use railgun::{
+    ArtifactManifest, HttpArtifactSource, RemoteProver, TrustedCheckpoint, builder::RailgunBuilder,
+    chain_config::ChainConfig, poi::PoiPolicy,
+};
 use wasm_bindgen::{JsError, prelude::wasm_bindgen};
 
 use crate::{database::JsDatabase, provider::JsRailgunProvider, utxo_syncer::JsUtxoSyncer};
@@ -41,6 +45,44 @@ impl JsRailgunBuilder {
         self
     }
 
+    /// Offloads transaction and POI proving to the proving service at `url` instead of proving
+    /// in the browser.
+    ///
+    /// The service sees every private circuit input, so it must be trusted with them.
+    #[wasm_bindgen(js_name = "withRemoteProver")]
+    pub fn with_remote_prover(mut self, url: String) -> Self {
+        self.inner = self.inner.with_prover(Arc::new(RemoteProver::new(url)));
+        self
+    }
+
+    /// Downloads circuit artifacts from `{baseUrl}/{circuit}/{file}` instead of the default
+    /// artifact host. Artifacts are still checked against the pinned manifest.
+    #[wasm_bindgen(js_name = "withArtifactBaseUrl")]
+    pub fn with_artifact_base_url(mut self, base_url: String) -> Self {
+        self.inner = self
+            .inner
+            .with_artifact_source(Arc::new(HttpArtifactSource::new(&base_url)));
+        self
+    }
+
+    /// Checks circuit artifacts against the JSON `manifest` of pinned SHA-256 hashes instead of
+    /// the manifest pinned in this build. Artifacts that `manifest` doesn't pin are rejected.
+    #[wasm_bindgen(js_name = "withArtifactManifest")]
+    pub fn with_artifact_manifest(mut self, manifest: String) -> Result<Self, JsError> {
+        let manifest =
+            ArtifactManifest::from_json(&manifest).map_err(|e| JsError::new(&e.to_string()))?;
+        self.inner = self.inner.with_artifact_manifest(manifest);
+        Ok(self)
+    }
+
+    /// Generates witnesses from each circuit's witness graph instead of running its WASM. The
+    /// artifact host must serve each circuit's witness graph (`graph.bin.br`).
+    #[wasm_bindgen(js_name = "withWitnessGraphs")]
+    pub fn with_witness_graphs(mut self) -> Self {
+        self.inner = self.inner.with_witness_graphs();
+        self
+    }
+
     /// Enables POI (Proof of innocence) support for the provider.
     ///
     /// Uses the default chain-specific POI endpoints and list keys from the chain config. Enabling
@@ -52,6 +94,48 @@ impl JsRailgunBuilder {
         self
     }
 
+    /// Sets how the statuses of a note on each POI list decide whether it can be spent. Defaults
+    /// to requiring every list to be `Valid`.
+    #[wasm_bindgen(js_name = "withPoiPolicy")]
+    pub fn with_poi_policy(mut self, policy: PoiPolicy) -> Self {
+        self.inner = self.inner.with_poi_policy(policy);
+        self
+    }
+
+    /// Reconstructs TXIDs from on-chain calldata and cross-checks them against the Subsquid
+    /// operations and the POI node's TXID roots. Only applies when POI support is enabled.
+    #[wasm_bindgen(js_name = "withTxidVerification")]
+    pub fn with_txid_verification(mut self) -> Self {
+        self.inner = self.inner.with_txid_verification();
+        self
+    }
+
+    /// Verifies UTXO Merkle roots with storage proofs against a trusted block instead of
+    /// trusting the RPC's `eth_call` results. Roots are only verifiable up to that block.
+    #[wasm_bindgen(js_name = "withTrustedCheckpoint")]
+    pub fn with_trusted_checkpoint(
+        mut self,
+        block_number: u64,
+        block_hash: String,
+    ) -> Result<Self, JsError> {
+        let hash = B256::from_str(&block_hash).map_err(|e| JsError::new(&e.to_string()))?;
+        self.inner = self
+            .inner
+            .with_trusted_checkpoint(TrustedCheckpoint::BlockHash {
+                number: block_number,
+                hash,
+            });
+        Ok(self)
+    }
+
+    /// Sets how many times an RPC request failing with a transient error, such as rate limiting
+    /// or a timeout, is retried before the error is returned. Defaults to 3.
+    #[wasm_bindgen(js_name = "withRpcRetries")]
+    pub fn with_rpc_retries(mut self, retries: u32) -> Self {
+        self.inner = self.inner.with_rpc_retries(retries);
+        self
+    }
+
     /// Builds the `RailgunProvider` with the specified configuration.
     pub async fn build(self) -> Result<JsRailgunProvider, JsError> {
         let inner = self
@@ -1,4 +1,5 @@
 use alloy::hex;
+use js_sys::{Array, Reflect};
 use railgun::database::{Database, DatabaseError};
 use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
 
@@ -11,6 +12,13 @@ export interface Database {
     get(key: string): Promise<string | null>;
     set(key: string, value: string): Promise<void>;
     delete(key: string): Promise<void>;
+    /**
+     * Returns all `[key, value]` pairs whose key starts with `prefix`. Keys are
+     * hex-encoded, so a hex prefix match is a byte prefix match.
+     *
+     * Optional. Without it, prefix scans fail.
+     */
+    entries?(prefix: string): Promise<[string, string][]>;
 }
 "#;
 
@@ -27,6 +35,9 @@ extern "C" {
 
     #[wasm_bindgen(method, catch, js_name = "delete")]
     pub async fn delete(this: &JsDatabase, key: &str) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "entries")]
+    pub async fn entries(this: &JsDatabase, prefix: &str) -> Result<JsValue, JsValue>;
 }
 
 #[async_trait::async_trait(?Send)]
@@ -65,4 +76,52 @@ impl Database for JsDatabase {
             .await
             .map_err(|e| DatabaseError::StorageError(format!("JS delete error: {:?}", e)))
     }
+
+    async fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DatabaseError> {
+        let has_entries = Reflect::get(self, &JsValue::from_str("entries"))
+            .is_ok_and(|entries| entries.is_function());
+        if !has_entries {
+            return Err(DatabaseError::StorageError(
+                "JS entries error: Database does not implement entries".to_string(),
+            ));
+        }
+
+        let prefix = hex::encode(prefix);
+        let result = self
+            .entries(&prefix)
+            .await
+            .map_err(|e| DatabaseError::StorageError(format!("JS entries error: {:?}", e)))?;
+
+        let mut entries = Vec::new();
+        for entry in Array::from(&result).iter() {
+            let entry = Array::from(&entry);
+            let (Some(key), Some(value)) = (entry.get(0).as_string(), entry.get(1).as_string())
+            else {
+                return Err(DatabaseError::StorageError(
+                    "JS entries error: entry is not a [string, string] pair".to_string(),
+                ));
+            };
+
+            let key = hex::decode(key).map_err(|e| {
+                DatabaseError::StorageError(format!("JS entries error: invalid hex key: {:?}", e))
+            })?;
+            let value = hex::decode(value).map_err(|e| {
+                DatabaseError::StorageError(format!("JS entries error: invalid hex value: {:?}", e))
+            })?;
+            entries.push((key, value));
+        }
+
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+}
+
+/// Runs the shared `Database` conformance suite against a JS database
+/// implementation. Rejects if the implementation violates the expected semantics.
+///
+/// The database must be empty.
+#[cfg(feature = "testing")]
+#[wasm_bindgen(js_name = "runDatabaseConformance")]
+pub async fn run_database_conformance(database: JsDatabase) {
+    railgun::database::conformance::run(&database).await;
 }
@@ -1,10 +1,14 @@
 use std::str::FromStr;
 
-use alloy::primitives::Address;
+use alloy::primitives::{Address, B256};
 use eip_1193_provider::tx_data::TxData;
 use railgun::{
+    TrustedCheckpoint,
     account::address::RailgunAddress,
-    provider::{BalanceEntry, NoteEntry, RailgunProvider},
+    crypto::railgun_txid::Txid,
+    poi::provider::{PoiEvent, PoiSubmission},
+    provider::{BalanceEntry, BlockedNote, NoteEntry, RailgunProvider},
+    snapshot::Snapshot,
 };
 use serde::Serialize;
 use tsify::Tsify;
@@ -36,6 +40,21 @@ pub struct Balances(Vec<BalanceEntry>);
 #[serde(transparent)]
 pub struct Notes(Vec<NoteEntry>);
 
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi)]
+#[serde(transparent)]
+pub struct BlockedNotes(Vec<BlockedNote>);
+
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi)]
+#[serde(transparent)]
+pub struct PoiSubmissions(Vec<PoiSubmission>);
+
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi)]
+#[serde(transparent)]
+pub struct PoiEvents(Vec<PoiEvent>);
+
 impl JsRailgunProvider {
     pub fn new(inner: RailgunProvider) -> Self {
         Self { inner }
@@ -53,6 +72,23 @@ impl JsRailgunProvider {
             .map_err(|e| JsError::new(&e.to_string()))
     }
 
+    /// Moves the trusted checkpoint UTXO Merkle roots are verified against to a later block.
+    /// Only available when built with `withTrustedCheckpoint`.
+    #[wasm_bindgen(js_name = "setTrustedCheckpoint")]
+    pub fn set_trusted_checkpoint(
+        &self,
+        block_number: u64,
+        block_hash: String,
+    ) -> Result<(), JsError> {
+        let hash = B256::from_str(&block_hash).map_err(|e| JsError::new(&e.to_string()))?;
+        self.inner
+            .set_trusted_checkpoint(TrustedCheckpoint::BlockHash {
+                number: block_number,
+                hash,
+            })
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
     /// Syncs the provider to the latest block.
     pub async fn sync(&mut self) -> Result<(), JsError> {
         self.inner
@@ -61,6 +97,86 @@ impl JsRailgunProvider {
             .map_err(|e| JsError::new(&e.to_string()))
     }
 
+    /// Returns the POI proofs waiting to be submitted for this wallet's transactions, and those
+    /// submitted recently, with their state and last error.
+    #[wasm_bindgen(js_name = "poiSubmissions")]
+    pub fn poi_submissions(&self) -> PoiSubmissions {
+        PoiSubmissions(self.inner.poi_submissions())
+    }
+
+    /// Takes the POI status changes of this wallet's notes observed since the last call, such
+    /// as a shield becoming spendable. Statuses are re-checked on every sync.
+    #[wasm_bindgen(js_name = "poiEvents")]
+    pub async fn poi_events(&mut self) -> Result<PoiEvents, JsError> {
+        self.inner
+            .poi_events()
+            .await
+            .map(PoiEvents)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Retries a failed POI submission on the next sync instead of waiting out its backoff.
+    #[wasm_bindgen(js_name = "retryPoiSubmission")]
+    pub async fn retry_poi_submission(&mut self, txid: String) -> Result<(), JsError> {
+        let txid = Txid::from_str(&txid).map_err(|e| JsError::new(&e.to_string()))?;
+        self.inner
+            .retry_poi_submission(txid)
+            .await
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Stops submitting the POI proof for a transaction.
+    #[wasm_bindgen(js_name = "dropPoiSubmission")]
+    pub async fn drop_poi_submission(&mut self, txid: String) -> Result<(), JsError> {
+        let txid = Txid::from_str(&txid).map_err(|e| JsError::new(&e.to_string()))?;
+        self.inner
+            .drop_poi_submission(txid)
+            .await
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Exports the synced state as a snapshot file.
+    ///
+    /// If `includeAccounts` is set, the decrypted notes of every registered account are
+    /// included. Such snapshots contain private data and must never be published.
+    #[wasm_bindgen(js_name = "exportSnapshot")]
+    pub fn export_snapshot(
+        &self,
+        #[wasm_bindgen(js_name = "includeAccounts")] include_accounts: bool,
+    ) -> Result<Vec<u8>, JsError> {
+        self.inner
+            .export_snapshot(include_accounts)
+            .and_then(|s| Ok(s.to_bytes()?))
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Replaces the synced state with a snapshot file. Tree roots are verified before any state
+    /// is replaced. If `trustedSigner` is set the snapshot must be signed by that address.
+    ///
+    /// The snapshot must carry the notes of every registered account, so import published
+    /// snapshots before registering accounts.
+    #[wasm_bindgen(js_name = "importSnapshot")]
+    pub async fn import_snapshot(
+        &mut self,
+        snapshot: &[u8],
+        #[wasm_bindgen(
+            js_name = "trustedSigner",
+            unchecked_param_type = "`0x${string}` | undefined"
+        )]
+        trusted_signer: Option<String>,
+    ) -> Result<(), JsError> {
+        let snapshot = Snapshot::from_bytes(snapshot).map_err(|e| JsError::new(&e.to_string()))?;
+        let trusted_signer = trusted_signer
+            .map(|s| Address::from_str(&s))
+            .transpose()
+            .map_err(|e| JsError::new(&e.to_string()))?;
+
+        self.inner
+            .import_snapshot(&snapshot, trusted_signer)
+            .await
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
     /// Returns the balance for the given address.
     ///
     /// If POI is enabled, only returns the spendable balance according to the POI provider.
@@ -73,6 +189,13 @@ impl JsRailgunProvider {
         Notes(self.inner.notes(address.clone()).await)
     }
 
+    /// Returns the unspent notes for the given address that can't be spent under the POI
+    /// policy, along with the lists blocking each of them.
+    #[wasm_bindgen(js_name = "blockedNotes")]
+    pub async fn blocked_notes(&mut self, address: RailgunAddress) -> BlockedNotes {
+        BlockedNotes(self.inner.blocked_notes(address.clone()).await)
+    }
+
     /// Helper to create a shield builder.
     pub fn shield(&self) -> JsShieldBuilder {
         JsShieldBuilder {
@@ -1,6 +1,10 @@
 use std::sync::Arc;
 
-use eip_1193_provider::js::JsEip1193Provider;
+use eip_1193_provider::{
+    js::JsEip1193Provider,
+    middleware::{RateLimitedProvider, RetryProvider},
+    provider::Eip1193Provider,
+};
 use railgun::{
     chain_config::ChainConfig,
     indexer::syncer::{ChainedSyncer, RpcSyncer, SubsquidSyncer, UtxoSyncer},
@@ -21,16 +25,32 @@ impl JsUtxoSyncer {
         }
     }
 
+    /// Creates an RPC syncer. `batchSize` is the initial block range per `eth_getLogs` call and
+    /// adapts to the node's limits while syncing. Transient RPC errors are retried, and
+    /// `requestsPerSecond` limits the request rate.
     #[wasm_bindgen(js_name = "rpc")]
     pub fn new_rpc(
         chain: &ChainConfig,
         provider: JsEip1193Provider,
-        #[wasm_bindgen(js_name = "batchSize")] batch_size: u64,
+        #[wasm_bindgen(js_name = "batchSize")] batch_size: Option<u64>,
+        concurrency: Option<usize>,
+        #[wasm_bindgen(js_name = "requestsPerSecond")] requests_per_second: Option<u32>,
     ) -> Self {
+        let mut provider: Arc<dyn Eip1193Provider> = Arc::new(provider);
+        if let Some(requests_per_second) = requests_per_second {
+            provider = Arc::new(RateLimitedProvider::new(provider, requests_per_second));
+        }
+
+        let mut syncer = RpcSyncer::new(chain.clone(), Arc::new(RetryProvider::new(provider)));
+        if let Some(batch_size) = batch_size {
+            syncer = syncer.with_batch_size(batch_size);
+        }
+        if let Some(concurrency) = concurrency {
+            syncer = syncer.with_concurrency(concurrency);
+        }
+
         Self {
-            inner: Arc::new(
-                RpcSyncer::new(chain.clone(), Arc::new(provider)).with_batch_size(batch_size),
-            ),
+            inner: Arc::new(syncer),
         }
     }
 
@@ -0,0 +1,27 @@
+import { expect, test } from "vitest";
+import { MemoryStorage } from "@kohaku-eth/plugins";
+import * as pkg from "../pkg/index.js";
+import { DatabaseAdapter } from "../sdk/database.js";
+import { ensureInitialized } from "../sdk/lib.js";
+
+type Conformance = { runDatabaseConformance?: (database: DatabaseAdapter) => Promise<void> };
+
+/**
+ * Runs the shared database conformance suite against the adapter over host storage.
+ *
+ * Needs the wasm built with the `testing` feature: `pnpm run test:database-conformance`.
+ */
+test("database-conformance", async () => {
+    await ensureInitialized();
+
+    const { runDatabaseConformance } = pkg as Conformance;
+    expect(runDatabaseConformance, "wasm was built without the `testing` feature").toBeDefined();
+
+    await runDatabaseConformance!(new DatabaseAdapter("conformance", new MemoryStorage()));
+
+    // The adapter's key index is persisted, so a fresh adapter over the same storage must see
+    // the same state.
+    const storage = new MemoryStorage();
+    await new DatabaseAdapter("reload", storage).set("aa01", "ff");
+    expect(await new DatabaseAdapter("reload", storage).entries("aa")).toEqual([["aa01", "ff"]]);
+}, 60 * 1000);
@@ -40,7 +40,7 @@ pub mod bench {
         c.bench_function("handle_shield_event", |b| {
             b.iter_batched(
                 || IndexedAccount::from_state(signer.clone(), Default::default()),
-                |mut account| account.handle_shield_event(&event).unwrap(),
+                |mut account| account.handle_shield_event(&event, 0).unwrap(),
                 criterion::BatchSize::SmallInput,
             );
         });
@@ -106,7 +106,7 @@ pub mod bench {
 
         c.bench_function("handle_shield_event_nomatch", |b| {
             b.iter(|| {
-                account.handle_shield_event(&event).unwrap();
+                account.handle_shield_event(&event, 0).unwrap();
             });
         });
     }
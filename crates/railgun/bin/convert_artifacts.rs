@@ -3,110 +3,674 @@
 //!
 //! Conversion step takes upward of 3 seconds in release mode, so we want to do this
 //! once.
+//!
+//! Usage:
+//! - `main [OUT]` downloads the published transact circuits from IPFS and converts them into `OUT`,
+//!   default the current directory.
+//! - `main convert SRC OUT` converts every `railgun/NNxMM` and `railgun/poi/NNxMM` circuit under
+//!   `SRC`. Each circuit directory holds `zkey` and `wasm`, either of which may be
+//!   brotli-compressed as `zkey.br` and `wasm.br`, and optionally a witness graph `graph.bin`,
+//!   either as written by circom-witnesscalc's `build-circuit` or already in [`WitnessGraph`]'s
+//!   format.
+//! - `main verify DIR` re-checks a converted artifact tree against its `manifest.json`.
+//!
+//! Converted artifacts are written to `OUT/<circuit>/`, alongside `OUT/manifest.json`,
+//! which records every file's hash and size and every circuit's shape, and `OUT/pins.json`,
+//! an [`ArtifactManifest`] for pinning the artifacts with `ArtifactLoader::with_manifest`. When
+//! publishing artifacts to the default host, copy `pins.json` to `src/circuit/artifact_pins.json`
+//! so that builds check them by default.
+//! Output only depends on the inputs, so re-running a conversion reproduces the same tree.
+//!
+//! If `WITNESS_GRAPH_DIR` is set, downloaded circuits' witness graphs are also packaged from
+//! `<WITNESS_GRAPH_DIR>/<circuit>.bin`. Graphs are compiled from the circuit sources, which
+//! aren't part of the published artifacts, with circom-witnesscalc's `build-circuit`:
+//! `build-circuit <circuit>.circom <circuit>.bin`.
 
-use std::io::Cursor;
+use std::{
+    collections::BTreeMap,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
 
+use anyhow::{Context, bail, ensure};
 use ark_bn254::{Bn254, Fr};
 use ark_circom::read_zkey;
 use ark_groth16::ProvingKey;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use railgun::crypto::serializable_np_index::SerializableNpIndex;
-use tracing::info;
+use railgun::{ArtifactManifest, WitnessGraph, crypto::serializable_np_index::SerializableNpIndex};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
 
 const IPFS_BASE: &str = "https://ipfs-lb.com/ipfs/QmUsmnK4PFc7zDp2cmC4wBZxYLjNyRgWfs5GNcJJ2uLcpU";
 
+const WASM_FILE: &str = "wasm.br";
+const PROVING_KEY_FILE: &str = "proving_key.bin.br";
+const MATRICES_FILE: &str = "matrices.bin.br";
+const GRAPH_FILE: &str = "graph.bin.br";
+
+/// Hashes, sizes and shapes of a converted artifact tree, keyed by circuit name.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    circuits: BTreeMap<String, CircuitEntry>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CircuitEntry {
+    nullifiers: usize,
+    commitments: usize,
+    shape: CircuitShape,
+    /// Converted files, keyed by file name.
+    files: BTreeMap<String, FileEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CircuitShape {
+    constraints: usize,
+    public_inputs: usize,
+    witness_size: usize,
+}
+
+/// Hash and size of a file as written, i.e. still compressed.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileEntry {
+    sha256: String,
+    size: usize,
+}
+
+/// A circuit's original artifacts.
+struct SourceArtifacts {
+    zkey: Vec<u8>,
+    wasm: Vec<u8>,
+    graph: Option<Vec<u8>>,
+}
+
 pub async fn main() {
     tracing_subscriber::fmt::init();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["convert", src, out] => convert_dir(Path::new(src), Path::new(out)),
+        ["verify", dir] => verify_dir(Path::new(dir)),
+        [] => download_all(Path::new(".")).await,
+        [out] => download_all(Path::new(out)).await,
+        _ => Err(anyhow::anyhow!(
+            "Usage: main [OUT] | main convert SRC OUT | main verify DIR"
+        )),
+    };
+
+    if let Err(e) = result {
+        error!("{:#}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Downloads and converts the published transact circuits.
+async fn download_all(out: &Path) -> anyhow::Result<()> {
+    let graph_dir = std::env::var("WITNESS_GRAPH_DIR").ok().map(PathBuf::from);
+
+    let mut manifest = Manifest::default();
     for i in 1..6 {
         for j in 1..6 {
-            let circuit_name = format!("0{}x0{}", i, j);
-            convert_artifacts(&circuit_name).await;
+            let shape = format!("{:02}x{:02}", i, j);
+            let zkey = download(&format!("{}/circuits/{}/zkey.br", IPFS_BASE, shape)).await?;
+            let wasm = download(&format!("{}/prover/snarkjs/{}.wasm.br", IPFS_BASE, shape)).await?;
+            let graph = match &graph_dir {
+                Some(dir) => Some(read(&dir.join(format!("{}.bin", shape)))?),
+                None => None,
+            };
+
+            let source = SourceArtifacts {
+                zkey: decompress(&zkey)?,
+                wasm: decompress(&wasm)?,
+                graph,
+            };
+            let circuit_name = format!("railgun/{}", shape);
+            let entry = convert_circuit(&circuit_name, source, out)
+                .with_context(|| format!("Failed to convert {}", circuit_name))?;
+            manifest.circuits.insert(circuit_name, entry);
         }
     }
+
+    write_manifest(out, &manifest)
 }
 
-async fn convert_artifacts(circuit_name: &str) {
-    info!("Converting artifacts for circuit: {}", circuit_name);
+/// Converts every circuit found under `src` into `out`.
+fn convert_dir(src: &Path, out: &Path) -> anyhow::Result<()> {
+    let circuits = find_circuits(src)?;
+    ensure!(
+        !circuits.is_empty(),
+        "No circuits found in {}",
+        src.display()
+    );
 
-    let url = format!("{}/circuits/{}/zkey.br", IPFS_BASE, circuit_name);
-    info!("Downloading {}", url);
-    let compressed = reqwest::get(&url).await.unwrap().bytes().await.unwrap();
+    let mut manifest = Manifest::default();
+    for circuit_name in circuits {
+        let dir = src.join(&circuit_name);
+        let graph_path = dir.join("graph.bin");
+        let source = SourceArtifacts {
+            zkey: read_maybe_compressed(&dir, "zkey")?,
+            wasm: read_maybe_compressed(&dir, "wasm")?,
+            graph: graph_path.exists().then(|| read(&graph_path)).transpose()?,
+        };
+
+        let entry = convert_circuit(&circuit_name, source, out)
+            .with_context(|| format!("Failed to convert {}", circuit_name))?;
+        manifest.circuits.insert(circuit_name, entry);
+    }
 
-    let mut zkey = Vec::new();
-    brotli::BrotliDecompress(&mut compressed.as_ref(), &mut zkey).unwrap();
+    write_manifest(out, &manifest)
+}
+
+/// Returns the names of the `railgun/NNxMM` and `railgun/poi/NNxMM` circuits under `src`,
+/// sorted.
+fn find_circuits(src: &Path) -> anyhow::Result<Vec<String>> {
+    let mut circuits = Vec::new();
+    for prefix in ["railgun", "railgun/poi"] {
+        let dir = src.join(prefix);
+        if !dir.is_dir() {
+            continue;
+        }
+
+        for entry in
+            std::fs::read_dir(&dir).with_context(|| format!("Reading {}", dir.display()))?
+        {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type()?.is_dir() && parse_shape(&name).is_some() {
+                circuits.push(format!("{}/{}", prefix, name));
+            }
+        }
+    }
+
+    circuits.sort();
+    Ok(circuits)
+}
+
+/// Parses a circuit shape like `01x02` into its nullifier and commitment counts.
+fn parse_shape(shape: &str) -> Option<(usize, usize)> {
+    let (nullifiers, commitments) = shape.split_once('x')?;
+    if nullifiers.len() != 2 || commitments.len() != 2 {
+        return None;
+    }
+    Some((nullifiers.parse().ok()?, commitments.parse().ok()?))
+}
+
+fn circuit_counts(circuit_name: &str) -> anyhow::Result<(usize, usize)> {
+    circuit_name
+        .rsplit('/')
+        .next()
+        .and_then(parse_shape)
+        .with_context(|| format!("Invalid circuit name {}", circuit_name))
+}
+
+/// Converts a single circuit into `out/<circuit_name>/`, reading every artifact back to check
+/// it was written correctly.
+fn convert_circuit(
+    circuit_name: &str,
+    source: SourceArtifacts,
+    out: &Path,
+) -> anyhow::Result<CircuitEntry> {
+    info!("Converting artifacts for circuit: {}", circuit_name);
+    let (nullifiers, commitments) = circuit_counts(circuit_name)?;
 
     info!("Parsing .zkey file");
-    let mut cursor = Cursor::new(zkey);
-    let (proving_key, matrices) = read_zkey(&mut cursor).unwrap();
-    let matrices: SerializableNpIndex<_> = matrices.into();
-
-    std::fs::create_dir_all(&circuit_name).unwrap();
-    let wasm_path = format!("{}/wasm.br", circuit_name);
-    let proving_key_path = format!("{}/proving_key.bin.br", circuit_name);
-    let matrices_path = format!("{}/matrices.bin.br", circuit_name);
-
-    let wasm_url = format!("{}/prover/snarkjs/{}.wasm.br", IPFS_BASE, circuit_name);
-    info!("Downloading {}", wasm_url);
-    let wasm_bytes = reqwest::get(&wasm_url)
-        .await
-        .unwrap()
-        .bytes()
-        .await
-        .unwrap();
-    std::fs::write(&wasm_path, &wasm_bytes).unwrap();
-
-    let params = brotli::enc::BrotliEncoderParams::default();
-
-    info!("Serializing proving key and matrices to disk");
-    let mut proving_key_bytes = Vec::new();
-    proving_key
-        .serialize_uncompressed(&mut proving_key_bytes)
-        .unwrap();
-    let mut proving_key_file = std::fs::File::create(&proving_key_path).unwrap();
-    brotli::BrotliCompress(
-        &mut proving_key_bytes.as_slice(),
-        &mut proving_key_file,
-        &params,
-    )
-    .unwrap();
-    proving_key_file.sync_all().unwrap();
+    let (proving_key, matrices) =
+        read_zkey(&mut Cursor::new(source.zkey)).context("Failed to parse zkey")?;
+    let matrices: SerializableNpIndex<Fr> = matrices.into();
+    let shape = circuit_shape(&proving_key, &matrices)?;
 
+    //? Transact circuits take the merkle root and bound params hash, then one signal per
+    //? nullifier and per commitment.
+    if !circuit_name.starts_with("railgun/poi/") {
+        ensure!(
+            shape.public_inputs == 2 + nullifiers + commitments,
+            "Expected {} public inputs, zkey has {}",
+            2 + nullifiers + commitments,
+            shape.public_inputs
+        );
+    }
+    ensure!(
+        source.wasm.starts_with(b"\0asm"),
+        "WASM artifact is not a WASM module"
+    );
+
+    let dir = out.join(circuit_name);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Creating {}", dir.display()))?;
+    let mut files = BTreeMap::new();
+
+    let mut proving_key_bytes = Vec::new();
+    proving_key.serialize_uncompressed(&mut proving_key_bytes)?;
     let mut matrices_bytes = Vec::new();
-    matrices
-        .serialize_uncompressed(&mut matrices_bytes)
-        .unwrap();
-    let mut matrices_file = std::fs::File::create(&matrices_path).unwrap();
-    brotli::BrotliCompress(&mut matrices_bytes.as_slice(), &mut matrices_file, &params).unwrap();
-    matrices_file.sync_all().unwrap();
+    matrices.serialize_uncompressed(&mut matrices_bytes)?;
+
+    info!("Serializing artifacts to disk");
+    write_compressed(&dir, WASM_FILE, &source.wasm, &mut files)?;
+    write_compressed(&dir, PROVING_KEY_FILE, &proving_key_bytes, &mut files)?;
+    write_compressed(&dir, MATRICES_FILE, &matrices_bytes, &mut files)?;
+    if let Some(graph) = &source.graph {
+        let graph = if WitnessGraph::is_witnesscalc(graph) {
+            let converted = WitnessGraph::from_witnesscalc(graph)
+                .context("Failed to convert witnesscalc graph")?;
+            let mut bytes = Vec::new();
+            converted.serialize_uncompressed(&mut bytes)?;
+            bytes
+        } else {
+            WitnessGraph::deserialize_uncompressed(&mut Cursor::new(graph))
+                .context("Failed to deserialize witness graph")?;
+            graph.clone()
+        };
+        write_compressed(&dir, GRAPH_FILE, &graph, &mut files)?;
+    }
 
     info!("Artifacts converted and saved to disk. Verifying...");
+    let (proving_key_read_back, matrices_read_back) = read_circuit(&dir)?;
+    ensure!(
+        proving_key == proving_key_read_back,
+        "Proving key read back from disk differs"
+    );
+    ensure!(
+        matrices == matrices_read_back,
+        "Matrices read back from disk differ"
+    );
+    let wasm_read_back = decompress(&read(&dir.join(WASM_FILE))?)?;
+    ensure!(
+        wasm_read_back == source.wasm,
+        "WASM read back from disk differs"
+    );
+
+    info!("Conversion complete. Artifacts saved to {}", dir.display());
+    Ok(CircuitEntry {
+        nullifiers,
+        commitments,
+        shape,
+        files,
+    })
+}
+
+fn circuit_shape(
+    proving_key: &ProvingKey<Bn254>,
+    matrices: &SerializableNpIndex<Fr>,
+) -> anyhow::Result<CircuitShape> {
+    //? The first instance variable is the constant `1`, not a public input.
+    let public_inputs = matrices.num_instance_variables - 1;
+    ensure!(
+        proving_key.vk.gamma_abc_g1.len() == matrices.num_instance_variables,
+        "Proving key has {} public inputs, matrices have {}",
+        proving_key.vk.gamma_abc_g1.len().saturating_sub(1),
+        public_inputs
+    );
+
+    Ok(CircuitShape {
+        constraints: matrices.num_constraints,
+        public_inputs,
+        witness_size: matrices.num_instance_variables + matrices.num_witness_variables,
+    })
+}
+
+fn read_circuit(dir: &Path) -> anyhow::Result<(ProvingKey<Bn254>, SerializableNpIndex<Fr>)> {
+    let proving_key_bytes = decompress(&read(&dir.join(PROVING_KEY_FILE))?)?;
+    let proving_key = ProvingKey::<Bn254>::deserialize_uncompressed_unchecked(&mut Cursor::new(
+        proving_key_bytes,
+    ))
+    .context("Failed to deserialize proving key")?;
+
+    let matrices_bytes = decompress(&read(&dir.join(MATRICES_FILE))?)?;
+    let matrices = SerializableNpIndex::<Fr>::deserialize_uncompressed_unchecked(&mut Cursor::new(
+        matrices_bytes,
+    ))
+    .context("Failed to deserialize matrices")?;
+
+    Ok((proving_key, matrices))
+}
+
+/// Re-checks every file and circuit shape in `dir` against `dir/manifest.json`, reporting
+/// every mismatch, every listed circuit that's missing and every circuit or file that isn't
+/// listed.
+fn verify_dir(dir: &Path) -> anyhow::Result<()> {
+    let manifest_path = dir.join("manifest.json");
+    let manifest: Manifest = serde_json::from_slice(&read(&manifest_path)?)
+        .with_context(|| format!("Failed to parse {}", manifest_path.display()))?;
+
+    let mut failures = 0;
+    for (circuit_name, entry) in &manifest.circuits {
+        if let Err(e) = verify_circuit(dir, circuit_name, entry) {
+            error!("{}: {:#}", circuit_name, e);
+            failures += 1;
+        }
+    }
+    for circuit_name in find_circuits(dir)? {
+        if !manifest.circuits.contains_key(&circuit_name) {
+            error!("{}: Circuit is not listed in the manifest", circuit_name);
+            failures += 1;
+        }
+    }
 
-    let mut proving_key_disk = Vec::new();
-    brotli::BrotliDecompress(
-        &mut std::fs::read(&proving_key_path).unwrap().as_slice(),
-        &mut proving_key_disk,
-    )
-    .unwrap();
-    let proving_key_read_back =
-        ProvingKey::<Bn254>::deserialize_uncompressed_unchecked(&mut Cursor::new(proving_key_disk))
-            .expect("Failed to deserialize proving key");
-    assert_eq!(proving_key, proving_key_read_back);
-
-    let mut matrices_disk = Vec::new();
-    brotli::BrotliDecompress(
-        &mut std::fs::read(&matrices_path).unwrap().as_slice(),
-        &mut matrices_disk,
-    )
-    .unwrap();
-    let matrices_read_back = SerializableNpIndex::<Fr>::deserialize_uncompressed_unchecked(
-        &mut Cursor::new(matrices_disk),
-    )
-    .expect("Failed to deserialize matrices");
-    assert_eq!(matrices, matrices_read_back);
+    if failures > 0 {
+        bail!(
+            "{} problems found verifying {} circuits",
+            failures,
+            manifest.circuits.len()
+        );
+    }
+    info!("All {} circuits verified", manifest.circuits.len());
+    Ok(())
+}
+
+fn verify_circuit(dir: &Path, circuit_name: &str, entry: &CircuitEntry) -> anyhow::Result<()> {
+    let circuit_dir = dir.join(circuit_name);
+    ensure!(circuit_dir.is_dir(), "Circuit is missing");
+    ensure!(
+        circuit_counts(circuit_name)? == (entry.nullifiers, entry.commitments),
+        "Nullifier and commitment counts don't match the circuit name"
+    );
+
+    //? Every file is checked before failing, so one run reports all of a circuit's problems.
+    let mut problems = Vec::new();
+    for (file, expected) in &entry.files {
+        let path = circuit_dir.join(file);
+        if !path.exists() {
+            problems.push(format!("{} is missing", file));
+            continue;
+        }
+
+        let actual = file_entry(&read(&path)?);
+        if actual != *expected {
+            problems.push(format!(
+                "{} has hash {} and size {}, expected {} and {}",
+                file, actual.sha256, actual.size, expected.sha256, expected.size
+            ));
+        }
+    }
+    for dir_entry in std::fs::read_dir(&circuit_dir)
+        .with_context(|| format!("Reading {}", circuit_dir.display()))?
+    {
+        let dir_entry = dir_entry?;
+        let file = dir_entry.file_name().to_string_lossy().into_owned();
+        if dir_entry.file_type()?.is_file() && !entry.files.contains_key(&file) {
+            problems.push(format!("{} is not listed in the manifest", file));
+        }
+    }
+    if !problems.is_empty() {
+        bail!("{}", problems.join("; "));
+    }
+
+    let (proving_key, matrices) = read_circuit(&circuit_dir)?;
+    let shape = circuit_shape(&proving_key, &matrices)?;
+    ensure!(
+        shape == entry.shape,
+        "Circuit shape {:?} doesn't match the manifest's {:?}",
+        shape,
+        entry.shape
+    );
 
+    info!("Verified {}", circuit_name);
+    Ok(())
+}
+
+fn write_manifest(out: &Path, manifest: &Manifest) -> anyhow::Result<()> {
+    let mut pins = ArtifactManifest::new();
+    for (circuit_name, entry) in &manifest.circuits {
+        for file in entry.files.keys() {
+            let bytes = read(&out.join(circuit_name).join(file))?;
+            pins.insert(circuit_name, file, &bytes);
+        }
+    }
+
+    std::fs::write(
+        out.join("manifest.json"),
+        serde_json::to_vec_pretty(manifest)?,
+    )?;
+    std::fs::write(out.join("pins.json"), serde_json::to_vec_pretty(&pins)?)?;
     info!(
-        "Conversion complete. WASM saved to {}, proving key saved to {}, matrices saved to {}",
-        wasm_path, proving_key_path, matrices_path
+        "Wrote manifest for {} circuits to {}",
+        manifest.circuits.len(),
+        out.display()
     );
+    Ok(())
+}
+
+/// Compresses `bytes` into `dir/file`, recording the written file in `files`.
+fn write_compressed(
+    dir: &Path,
+    file: &str,
+    bytes: &[u8],
+    files: &mut BTreeMap<String, FileEntry>,
+) -> anyhow::Result<()> {
+    let mut compressed = Vec::new();
+    brotli::BrotliCompress(
+        &mut &bytes[..],
+        &mut compressed,
+        &brotli::enc::BrotliEncoderParams::default(),
+    )?;
+
+    let path = dir.join(file);
+    std::fs::write(&path, &compressed).with_context(|| format!("Writing {}", path.display()))?;
+    files.insert(file.to_string(), file_entry(&compressed));
+    Ok(())
+}
+
+fn file_entry(bytes: &[u8]) -> FileEntry {
+    FileEntry {
+        sha256: hex::encode(Sha256::digest(bytes)),
+        size: bytes.len(),
+    }
+}
+
+/// Reads `dir/name`, or decompresses `dir/name.br` if that exists instead.
+fn read_maybe_compressed(dir: &Path, name: &str) -> anyhow::Result<Vec<u8>> {
+    let compressed = dir.join(format!("{}.br", name));
+    if compressed.exists() {
+        return decompress(&read(&compressed)?);
+    }
+    read(&dir.join(name))
+}
+
+fn read(path: &Path) -> anyhow::Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("Reading {}", path.display()))
+}
+
+fn decompress(bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    brotli::BrotliDecompress(&mut &bytes[..], &mut out).context("Failed to decompress")?;
+    Ok(out)
+}
+
+async fn download(url: &str) -> anyhow::Result<Vec<u8>> {
+    info!("Downloading {}", url);
+    let response = reqwest::get(url).await?.error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::{G1Affine, G2Affine};
+    use ark_ec::AffineRepr;
+    use ark_groth16::VerifyingKey;
+
+    use super::*;
+
+    /// WASM of the toy circuit from the witness graph fixture.
+    const TOY_WASM: &[u8] = include_bytes!("../tests/fixtures/witness_graph/toy/wasm.br");
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "railgun-convert-artifacts-{}",
+            rand::random::<u64>()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes a converted `railgun/01x01` circuit with one public input into `out`, standing in
+    /// for a converted zkey.
+    fn write_circuit(out: &Path) -> (String, CircuitEntry) {
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        let proving_key = ProvingKey::<Bn254> {
+            vk: VerifyingKey {
+                alpha_g1: g1,
+                beta_g2: g2,
+                gamma_g2: g2,
+                delta_g2: g2,
+                gamma_abc_g1: vec![g1, g1],
+            },
+            beta_g1: g1,
+            delta_g1: g1,
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+        let matrices = SerializableNpIndex::<Fr> {
+            num_instance_variables: 2,
+            num_witness_variables: 3,
+            num_constraints: 1,
+            a_num_non_zero: 1,
+            b_num_non_zero: 1,
+            c_num_non_zero: 1,
+            a: vec![vec![(Fr::from(1u64), 2)]],
+            b: vec![vec![(Fr::from(1u64), 3)]],
+            c: vec![vec![(Fr::from(1u64), 1)]],
+        };
+
+        let circuit_name = "railgun/01x01".to_string();
+        let dir = out.join(&circuit_name);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut proving_key_bytes = Vec::new();
+        proving_key
+            .serialize_uncompressed(&mut proving_key_bytes)
+            .unwrap();
+        let mut matrices_bytes = Vec::new();
+        matrices
+            .serialize_uncompressed(&mut matrices_bytes)
+            .unwrap();
+
+        let mut files = BTreeMap::new();
+        let wasm = decompress(TOY_WASM).unwrap();
+        write_compressed(&dir, WASM_FILE, &wasm, &mut files).unwrap();
+        write_compressed(&dir, PROVING_KEY_FILE, &proving_key_bytes, &mut files).unwrap();
+        write_compressed(&dir, MATRICES_FILE, &matrices_bytes, &mut files).unwrap();
+
+        let entry = CircuitEntry {
+            nullifiers: 1,
+            commitments: 1,
+            shape: circuit_shape(&proving_key, &matrices).unwrap(),
+            files,
+        };
+        (circuit_name, entry)
+    }
+
+    fn write_tree(out: &Path) -> Manifest {
+        let (circuit_name, entry) = write_circuit(out);
+        let mut manifest = Manifest::default();
+        manifest.circuits.insert(circuit_name, entry);
+        write_manifest(out, &manifest).unwrap();
+        manifest
+    }
+
+    #[test]
+    fn test_parse_shape() {
+        assert_eq!(parse_shape("01x02"), Some((1, 2)));
+        assert_eq!(parse_shape("13x13"), Some((13, 13)));
+        assert_eq!(parse_shape("1x2"), None);
+        assert_eq!(parse_shape("001x02"), None);
+        assert_eq!(parse_shape("0102"), None);
+        assert_eq!(parse_shape("abxcd"), None);
+        assert_eq!(parse_shape("poi"), None);
+    }
+
+    #[test]
+    fn test_find_circuits() {
+        let src = temp_dir();
+        for dir in [
+            "railgun/02x01",
+            "railgun/01x02",
+            "railgun/poi/03x03",
+            "railgun/other",
+            "unrelated/01x01",
+        ] {
+            std::fs::create_dir_all(src.join(dir)).unwrap();
+        }
+        std::fs::write(src.join("railgun/05x05"), b"not a directory").unwrap();
+
+        assert_eq!(
+            find_circuits(&src).unwrap(),
+            vec!["railgun/01x02", "railgun/02x01", "railgun/poi/03x03"]
+        );
+        assert!(find_circuits(&src.join("missing")).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&src).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let out = temp_dir();
+        let manifest = write_tree(&out);
+
+        let read_back: Manifest =
+            serde_json::from_slice(&read(&out.join("manifest.json")).unwrap()).unwrap();
+        assert_eq!(read_back, manifest);
+
+        let pins =
+            ArtifactManifest::from_json(&std::fs::read_to_string(out.join("pins.json")).unwrap())
+                .unwrap();
+        for (circuit_name, entry) in &manifest.circuits {
+            for file in entry.files.keys() {
+                let bytes = read(&out.join(circuit_name).join(file)).unwrap();
+                pins.verify(circuit_name, file, &bytes).unwrap();
+                pins.verify(circuit_name, file, &bytes[1..]).unwrap_err();
+            }
+        }
+
+        std::fs::remove_dir_all(&out).unwrap();
+    }
+
+    #[test]
+    fn test_verify_dir_reports_mismatches() {
+        let out = temp_dir();
+        let manifest = write_tree(&out);
+        let (circuit_name, entry) = manifest.circuits.iter().next().unwrap();
+        let circuit_dir = out.join(circuit_name);
+        verify_dir(&out).unwrap();
+
+        let wasm = read(&circuit_dir.join(WASM_FILE)).unwrap();
+        std::fs::write(circuit_dir.join(WASM_FILE), &wasm[1..]).unwrap();
+        std::fs::remove_file(circuit_dir.join(MATRICES_FILE)).unwrap();
+        std::fs::write(circuit_dir.join("extra.bin"), b"extra").unwrap();
+        let err = verify_circuit(&out, circuit_name, entry)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(&format!("{} has hash", WASM_FILE)), "{}", err);
+        assert!(
+            err.contains(&format!("{} is missing", MATRICES_FILE)),
+            "{}",
+            err
+        );
+        assert!(err.contains("extra.bin is not listed"), "{}", err);
+        verify_dir(&out).unwrap_err();
+
+        //? An intact circuit that the manifest doesn't list is also reported.
+        std::fs::remove_dir_all(&out).unwrap();
+        write_tree(&out);
+        std::fs::create_dir_all(out.join("railgun/02x02")).unwrap();
+        verify_dir(&out).unwrap_err();
+
+        std::fs::remove_dir_all(out.join("railgun/02x02")).unwrap();
+        std::fs::remove_dir_all(out.join(circuit_name)).unwrap();
+        let err = verify_circuit(&out, circuit_name, entry)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Circuit is missing"), "{}", err);
+        verify_dir(&out).unwrap_err();
+
+        std::fs::remove_dir_all(&out).unwrap();
+    }
 }
@@ -0,0 +1,82 @@
+//! Self-hosted POI node.
+//!
+//! Serves the `ppoi_*` JSON-RPC methods on `POST /` and syncs new shields and operations every
+//! `POI_NODE_SYNC_INTERVAL` seconds. Every shield is admitted to every list. Requests are served
+//! from the last synced state while a sync is running.
+//!
+//! Configured through environment variables:
+//! - `POI_NODE_CHAIN_ID`: chain to serve, default `1`
+//! - `POI_NODE_LIST_KEYS`: comma-separated list keys, default the chain's list keys
+//! - `POI_NODE_ADDR`: address to listen on, default `127.0.0.1:8080`
+//! - `POI_NODE_DB`: database directory, default `poi_node_db`
+//! - `POI_NODE_SYNC_INTERVAL`: seconds between syncs, default `60`
+
+use std::{env, sync::Arc, time::Duration};
+
+use axum::{Json, Router, extract::State, routing::post};
+use railgun::{
+    chain_config::ChainConfig,
+    database::fs::FilesystemDatabase,
+    poi::{
+        ListKey,
+        node::{AllowAll, PoiNode},
+    },
+};
+use serde_json::Value;
+use tracing::{error, info};
+
+type SharedNode = Arc<PoiNode>;
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let chain_id: u64 = env_or("POI_NODE_CHAIN_ID", "1")
+        .parse()
+        .expect("Invalid chain ID");
+    let chain = ChainConfig::from_chain_id(chain_id).expect("Unsupported chain");
+    let list_keys: Vec<ListKey> = match env::var("POI_NODE_LIST_KEYS") {
+        Ok(keys) => keys.split(',').map(|k| k.trim().into()).collect(),
+        Err(_) => chain.list_keys.clone(),
+    };
+    let addr = env_or("POI_NODE_ADDR", "127.0.0.1:8080");
+    let sync_interval: u64 = env_or("POI_NODE_SYNC_INTERVAL", "60")
+        .parse()
+        .expect("Invalid sync interval");
+
+    let db = FilesystemDatabase::new(env_or("POI_NODE_DB", "poi_node_db"))
+        .await
+        .expect("Failed to open database");
+    let node = PoiNode::new(chain, Arc::new(db), list_keys, Arc::new(AllowAll))
+        .await
+        .expect("Failed to create POI node");
+    let node: SharedNode = Arc::new(node);
+
+    tokio::spawn(sync_loop(node.clone(), Duration::from_secs(sync_interval)));
+
+    let app = Router::new().route("/", post(handle)).with_state(node);
+    let listener = tokio::net::TcpListener::bind(&addr)
+        .await
+        .expect("Failed to bind");
+    info!("POI node listening on {}", addr);
+    axum::serve(listener, app).await.expect("Server error");
+}
+
+async fn handle(State(node): State<SharedNode>, Json(request): Json<Value>) -> Json<Value> {
+    Json(node.handle(request).await)
+}
+
+async fn sync_loop(node: SharedNode, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        match node.sync_to(u64::MAX).await {
+            Ok(()) => info!("Synced POI node to block {}", node.synced_block().await),
+            Err(e) => error!("POI node sync failed: {}", e),
+        }
+    }
+}
+
+fn env_or(key: &str, default: &str) -> String {
+    env::var(key).unwrap_or_else(|_| default.to_string())
+}
@@ -0,0 +1,839 @@
+//! `railgun` command-line wallet.
+//!
+//! Keeps a single wallet in a data directory: its keys in `keys.json`, the synced provider state
+//! in `db/`, and the wallet's history in `history.json`. Every command prints JSON to stdout so it
+//! can be used from scripts; logs go to stderr.
+//!
+//! Configured through flags or environment variables:
+//! - `RAILGUN_HOME`: data directory, default `.railgun`
+//! - `RAILGUN_RPC_URL`: JSON-RPC endpoint, whose chain selects the chain config
+//! - `RAILGUN_PRIVATE_KEY`: EVM private key used to send transactions. Without it, transactions are
+//!   printed as unsigned `TxData` instead.
+//! - `RAILGUN_MNEMONIC`, `RAILGUN_SPENDING_KEY`, `RAILGUN_VIEWING_KEY`: secrets for `keys import`,
+//!   so they don't end up in the shell history
+//!
+//! `balance`, `notes` and `history` only read the local state. Run `sync` first to bring them up
+//! to date. `poi status` syncs before reporting, and `transfer` and `unshield` sync before
+//! building.
+
+use std::{
+    collections::HashSet,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use alloy::{
+    primitives::{Address, B256},
+    providers::{DynProvider, Provider, ProviderBuilder},
+    signers::local::{MnemonicBuilder, PrivateKeySigner as EvmSigner, coins_bip39::English},
+};
+use anyhow::{Context, bail};
+use clap::{Args, Parser, Subcommand};
+use eip_1193_provider::tx_data::TxData;
+use railgun::{
+    account::{
+        address::RailgunAddress,
+        chain::ChainId,
+        signer::{PrivateKeySigner, RailgunSigner, spending_key_path, viewing_key_path},
+    },
+    builder::RailgunBuilder,
+    caip::AssetId,
+    chain_config::ChainConfig,
+    crypto::{
+        keys::{HexKey, SpendingKey, ViewingKey},
+        railgun_txid::Txid,
+    },
+    database::fs::FilesystemDatabase,
+    provider::{NoteEntry, RailgunProvider},
+    snapshot::Snapshot,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Parser)]
+#[command(name = "railgun", about = "Railgun command-line wallet")]
+struct Cli {
+    /// Directory holding the wallet's keys, state and history.
+    #[arg(long, env = "RAILGUN_HOME", default_value = ".railgun", global = true)]
+    data_dir: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage the wallet's railgun keys.
+    #[command(subcommand)]
+    Key(KeyCommand),
+    /// Sync the wallet to the latest block.
+    Sync(Network),
+    /// Print the spendable balance of each asset.
+    Balance(Network),
+    /// Print the wallet's unspent notes.
+    Notes {
+        #[command(flatten)]
+        network: Network,
+        /// Only print notes that can't be spent under the POI policy.
+        #[arg(long)]
+        blocked: bool,
+    },
+    /// Print the notes received and spent, and the transactions sent by this wallet.
+    History,
+    /// Shield tokens into a railgun address, by default this wallet's.
+    Shield {
+        #[command(flatten)]
+        network: Network,
+        /// Asset to shield, e.g. `erc20:0x...`. Shields the chain's base token if omitted.
+        #[arg(long)]
+        asset: Option<AssetId>,
+        #[arg(long)]
+        value: u128,
+        #[arg(long)]
+        to: Option<RailgunAddress>,
+    },
+    /// Transfer shielded tokens to another railgun address.
+    Transfer {
+        #[command(flatten)]
+        network: Network,
+        #[arg(long)]
+        asset: AssetId,
+        #[arg(long)]
+        value: u128,
+        #[arg(long)]
+        to: RailgunAddress,
+        #[arg(long, default_value = "")]
+        memo: String,
+    },
+    /// Unshield tokens to an EVM address.
+    Unshield {
+        #[command(flatten)]
+        network: Network,
+        #[arg(long)]
+        asset: AssetId,
+        #[arg(long)]
+        value: u128,
+        #[arg(long)]
+        to: Address,
+    },
+    /// Inspect and manage POI submissions.
+    #[command(subcommand)]
+    Poi(PoiCommand),
+    /// Export the synced state to a snapshot file.
+    Export {
+        #[command(flatten)]
+        network: Network,
+        path: PathBuf,
+        /// Include this wallet's decrypted notes. Such snapshots must be kept private.
+        #[arg(long)]
+        include_accounts: bool,
+    },
+    /// Replace the synced state with a snapshot file. The snapshot must carry this wallet's notes,
+    /// i.e. be exported with `--include-accounts`.
+    Import {
+        #[command(flatten)]
+        network: Network,
+        path: PathBuf,
+        /// Require the snapshot to be signed by this address.
+        #[arg(long)]
+        trusted_signer: Option<Address>,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeyCommand {
+    /// Generate new random keys.
+    Generate {
+        /// Overwrite existing keys.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Import keys as hex, or derive them from a mnemonic.
+    Import {
+        #[arg(
+            long,
+            env = "RAILGUN_SPENDING_KEY",
+            hide_env_values = true,
+            requires = "viewing_key",
+            conflicts_with = "mnemonic"
+        )]
+        spending_key: Option<String>,
+        #[arg(
+            long,
+            env = "RAILGUN_VIEWING_KEY",
+            hide_env_values = true,
+            requires = "spending_key"
+        )]
+        viewing_key: Option<String>,
+        #[arg(
+            long,
+            env = "RAILGUN_MNEMONIC",
+            hide_env_values = true,
+            required_unless_present = "spending_key"
+        )]
+        mnemonic: Option<String>,
+        /// Account index to derive from the mnemonic.
+        #[arg(long, default_value_t = 0)]
+        index: u32,
+        /// Overwrite existing keys.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the wallet's 0zk address on a chain.
+    Address {
+        #[arg(long, default_value_t = 1)]
+        chain_id: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum PoiCommand {
+    /// Print pending POI submissions, blocked notes and recent POI status changes.
+    Status(Network),
+    /// Retry a failed submission on the next sync.
+    Retry {
+        #[command(flatten)]
+        network: Network,
+        txid: Txid,
+    },
+    /// Stop submitting the POI proof for a transaction.
+    Drop {
+        #[command(flatten)]
+        network: Network,
+        txid: Txid,
+    },
+}
+
+#[derive(Args)]
+struct Network {
+    #[arg(long, env = "RAILGUN_RPC_URL")]
+    rpc_url: String,
+    /// EVM private key used to send transactions.
+    #[arg(long, env = "RAILGUN_PRIVATE_KEY", hide_env_values = true)]
+    private_key: Option<String>,
+    /// Enable POI support.
+    #[arg(long)]
+    poi: bool,
+}
+
+/// The wallet's railgun keys, stored unencrypted in `keys.json`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Keys {
+    spending_key: SpendingKey,
+    viewing_key: ViewingKey,
+}
+
+/// Wallet history, stored in `history.json`.
+///
+/// The indexer only tracks unspent notes, so history is recorded by the CLI itself: notes that
+/// appear or disappear between syncs, and the transactions this wallet sent.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct History {
+    /// Unspent notes as of the last sync.
+    known_notes: Vec<HistoryNote>,
+    entries: Vec<HistoryEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryNote {
+    blinded_commitment: String,
+    asset: AssetId,
+    amount: u128,
+    memo: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum HistoryEntry {
+    #[serde(rename_all = "camelCase")]
+    Received { note: HistoryNote, timestamp: u64 },
+    #[serde(rename_all = "camelCase")]
+    Spent { note: HistoryNote, timestamp: u64 },
+    #[serde(rename_all = "camelCase")]
+    Sent {
+        action: String,
+        asset: Option<AssetId>,
+        value: u128,
+        recipient: String,
+        tx_hashes: Vec<B256>,
+        timestamp: u64,
+    },
+}
+
+/// An opened wallet: the provider with this wallet's signer registered.
+struct Wallet {
+    railgun: RailgunProvider,
+    signer: Arc<dyn RailgunSigner>,
+    provider: DynProvider,
+    can_send: bool,
+    data_dir: PathBuf,
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
+
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(output) => println!("{}", serde_json::to_string_pretty(&output).unwrap()),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn run(cli: Cli) -> anyhow::Result<serde_json::Value> {
+    let data_dir = cli.data_dir;
+    match cli.command {
+        Command::Key(command) => key(&data_dir, command),
+        Command::Sync(network) => {
+            let mut wallet = Wallet::open(&data_dir, &network).await?;
+            Ok(json!({ "newEntries": wallet.sync().await? }))
+        }
+        Command::Balance(network) => {
+            let mut wallet = Wallet::open(&data_dir, &network).await?;
+            let address = wallet.signer.address();
+            Ok(json!(wallet.railgun.balance(address).await))
+        }
+        Command::Notes { network, blocked } => {
+            let mut wallet = Wallet::open(&data_dir, &network).await?;
+            let address = wallet.signer.address();
+            if blocked {
+                Ok(json!(wallet.railgun.blocked_notes(address).await))
+            } else {
+                Ok(json!(wallet.railgun.notes(address).await))
+            }
+        }
+        Command::History => Ok(json!(load_history(&data_dir)?.entries)),
+        Command::Shield {
+            network,
+            asset,
+            value,
+            to,
+        } => {
+            let wallet = Wallet::open(&data_dir, &network).await?;
+            let recipient = to.unwrap_or_else(|| wallet.signer.address());
+            let builder = wallet.railgun.shield();
+            let builder = match asset {
+                Some(asset) => builder.shield(recipient, asset, value),
+                None => builder.shield_native(recipient, value),
+            };
+            let txs = builder.build(&mut rand::rng())?;
+            wallet
+                .submit("shield", asset, value, recipient.to_string(), txs)
+                .await
+        }
+        Command::Transfer {
+            network,
+            asset,
+            value,
+            to,
+            memo,
+        } => {
+            let mut wallet = Wallet::open(&data_dir, &network).await?;
+            wallet.sync().await?;
+            let builder =
+                wallet
+                    .railgun
+                    .transact()
+                    .transfer(wallet.signer.clone(), to, asset, value, &memo);
+            let tx = wallet.railgun.build(builder, &mut rand::rng()).await?;
+            wallet
+                .submit(
+                    "transfer",
+                    Some(asset),
+                    value,
+                    to.to_string(),
+                    vec![tx.tx_data],
+                )
+                .await
+        }
+        Command::Unshield {
+            network,
+            asset,
+            value,
+            to,
+        } => {
+            let mut wallet = Wallet::open(&data_dir, &network).await?;
+            wallet.sync().await?;
+            let builder =
+                wallet
+                    .railgun
+                    .transact()
+                    .unshield(wallet.signer.clone(), to, asset, value)?;
+            let tx = wallet.railgun.build(builder, &mut rand::rng()).await?;
+            wallet
+                .submit(
+                    "unshield",
+                    Some(asset),
+                    value,
+                    to.to_string(),
+                    vec![tx.tx_data],
+                )
+                .await
+        }
+        Command::Poi(command) => poi(&data_dir, command).await,
+        Command::Export {
+            network,
+            path,
+            include_accounts,
+        } => {
+            let wallet = Wallet::open(&data_dir, &network).await?;
+            let snapshot = wallet.railgun.export_snapshot(include_accounts)?;
+            std::fs::write(&path, snapshot.to_bytes()?)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            Ok(json!(snapshot.header()))
+        }
+        Command::Import {
+            network,
+            path,
+            trusted_signer,
+        } => {
+            let mut wallet = Wallet::open(&data_dir, &network).await?;
+            let bytes = std::fs::read(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let snapshot = Snapshot::from_bytes(&bytes)?;
+            wallet
+                .railgun
+                .import_snapshot(&snapshot, trusted_signer)
+                .await?;
+            Ok(json!(snapshot.header()))
+        }
+    }
+}
+
+fn key(data_dir: &Path, command: KeyCommand) -> anyhow::Result<serde_json::Value> {
+    let (keys, force) = match command {
+        KeyCommand::Generate { force } => {
+            let keys = Keys {
+                spending_key: rand::random(),
+                viewing_key: rand::random(),
+            };
+            (keys, force)
+        }
+        KeyCommand::Import {
+            spending_key,
+            viewing_key,
+            mnemonic,
+            index,
+            force,
+        } => {
+            let keys = match (spending_key, viewing_key, mnemonic) {
+                (Some(spending_key), Some(viewing_key), _) => Keys {
+                    spending_key: SpendingKey::from_hex(&spending_key)?,
+                    viewing_key: ViewingKey::from_hex(&viewing_key)?,
+                },
+                (_, _, Some(mnemonic)) => Keys {
+                    spending_key: SpendingKey::from_hex(&derive_key(
+                        &mnemonic,
+                        &spending_key_path(index),
+                    )?)?,
+                    viewing_key: ViewingKey::from_hex(&derive_key(
+                        &mnemonic,
+                        &viewing_key_path(index),
+                    )?)?,
+                },
+                _ => bail!("Either both hex keys or a mnemonic are required"),
+            };
+            (keys, force)
+        }
+        KeyCommand::Address { chain_id } => {
+            let keys = load_keys(data_dir)?;
+            let address = RailgunAddress::from_private_keys(
+                keys.spending_key,
+                keys.viewing_key,
+                ChainId::evm(chain_id),
+            );
+            return Ok(json!({ "address": address }));
+        }
+    };
+
+    let path = data_dir.join("keys.json");
+    if path.exists() && !force {
+        bail!(
+            "{} already exists, pass --force to overwrite it",
+            path.display()
+        );
+    }
+    std::fs::create_dir_all(data_dir)?;
+    write_private(&path, &serde_json::to_vec_pretty(&keys)?)?;
+
+    let address = PrivateKeySigner::new_evm(keys.spending_key, keys.viewing_key, 1).address();
+    Ok(json!({ "address": address }))
+}
+
+/// Derives a railgun key from a BIP-39 mnemonic with secp256k1 BIP-32, as the railgun plugin
+/// does through the host keystore.
+fn derive_key(mnemonic: &str, path: &str) -> anyhow::Result<String> {
+    let signer = MnemonicBuilder::<English>::default()
+        .phrase(mnemonic)
+        .derivation_path(path)?
+        .build()?;
+    Ok(signer.to_bytes().to_string())
+}
+
+async fn poi(data_dir: &Path, command: PoiCommand) -> anyhow::Result<serde_json::Value> {
+    match command {
+        PoiCommand::Status(network) => {
+            let mut wallet = Wallet::open(data_dir, &network).await?;
+            wallet.sync().await?;
+            let address = wallet.signer.address();
+            Ok(json!({
+                "submissions": wallet.railgun.poi_submissions(),
+                "blockedNotes": wallet.railgun.blocked_notes(address).await,
+                "events": wallet.railgun.poi_events().await?,
+            }))
+        }
+        PoiCommand::Retry { network, txid } => {
+            let mut wallet = Wallet::open(data_dir, &network).await?;
+            wallet.railgun.retry_poi_submission(txid).await?;
+            Ok(json!(wallet.railgun.poi_submissions()))
+        }
+        PoiCommand::Drop { network, txid } => {
+            let mut wallet = Wallet::open(data_dir, &network).await?;
+            wallet.railgun.drop_poi_submission(txid).await?;
+            Ok(json!(wallet.railgun.poi_submissions()))
+        }
+    }
+}
+
+impl Wallet {
+    async fn open(data_dir: &Path, network: &Network) -> anyhow::Result<Self> {
+        let keys = load_keys(data_dir)?;
+
+        let builder = ProviderBuilder::new();
+        let provider = match &network.private_key {
+            Some(key) => {
+                let key: EvmSigner = key.parse().context("Invalid private key")?;
+                builder
+                    .wallet(key)
+                    .connect(&network.rpc_url)
+                    .await?
+                    .erased()
+            }
+            None => builder.connect(&network.rpc_url).await?.erased(),
+        };
+        let chain_id = provider.get_chain_id().await?;
+        let chain = ChainConfig::from_chain_id(chain_id)
+            .with_context(|| format!("Unsupported chain {}", chain_id))?;
+
+        let db = FilesystemDatabase::new(data_dir.join("db")).await?;
+        let mut builder =
+            RailgunBuilder::new(chain.clone(), provider.clone()).with_database(Arc::new(db));
+        if network.poi {
+            builder = builder.with_poi();
+        }
+        let mut railgun = builder.build().await?;
+
+        let signer = PrivateKeySigner::new_evm(keys.spending_key, keys.viewing_key, chain.id);
+        railgun.register(signer.clone()).await?;
+
+        Ok(Wallet {
+            railgun,
+            signer,
+            provider,
+            can_send: network.private_key.is_some(),
+            data_dir: data_dir.to_path_buf(),
+        })
+    }
+
+    /// Syncs to the latest block and records the notes received and spent since the last sync.
+    async fn sync(&mut self) -> anyhow::Result<serde_json::Value> {
+        self.railgun.sync().await?;
+
+        let notes: Vec<HistoryNote> = self
+            .railgun
+            .notes(self.signer.address())
+            .await
+            .into_iter()
+            .map(HistoryNote::from)
+            .collect();
+
+        let mut history = load_history(&self.data_dir)?;
+        let new_entries = history.update(notes, now());
+        save_history(&self.data_dir, &history)?;
+        Ok(json!(new_entries))
+    }
+
+    /// Sends `txs` if a private key was given and prints their hashes, otherwise prints them
+    /// unsigned.
+    async fn submit(
+        &self,
+        action: &str,
+        asset: Option<AssetId>,
+        value: u128,
+        recipient: String,
+        txs: Vec<TxData>,
+    ) -> anyhow::Result<serde_json::Value> {
+        if !self.can_send {
+            return Ok(json!(txs));
+        }
+
+        let mut tx_hashes = Vec::new();
+        for tx in txs {
+            let receipt = self
+                .provider
+                .send_transaction(tx.into())
+                .await?
+                .get_receipt()
+                .await?;
+            if !receipt.status() {
+                bail!("Transaction {} reverted", receipt.transaction_hash);
+            }
+            tx_hashes.push(receipt.transaction_hash);
+        }
+
+        let mut history = load_history(&self.data_dir)?;
+        history.entries.push(HistoryEntry::Sent {
+            action: action.to_string(),
+            asset,
+            value,
+            recipient,
+            tx_hashes: tx_hashes.clone(),
+            timestamp: now(),
+        });
+        save_history(&self.data_dir, &history)?;
+
+        Ok(json!({ "txHashes": tx_hashes }))
+    }
+}
+
+impl History {
+    /// Records the notes received and spent since the last update, given the current unspent
+    /// notes. Returns the new entries.
+    fn update(&mut self, notes: Vec<HistoryNote>, timestamp: u64) -> Vec<HistoryEntry> {
+        let known: HashSet<_> = self.known_notes.iter().cloned().collect();
+        let current: HashSet<_> = notes.iter().cloned().collect();
+        let mut new_entries: Vec<HistoryEntry> = notes
+            .iter()
+            .filter(|n| !known.contains(n))
+            .map(|note| HistoryEntry::Received {
+                note: note.clone(),
+                timestamp,
+            })
+            .collect();
+        new_entries.extend(
+            self.known_notes
+                .iter()
+                .filter(|n| !current.contains(n))
+                .map(|note| HistoryEntry::Spent {
+                    note: note.clone(),
+                    timestamp,
+                }),
+        );
+
+        self.known_notes = notes;
+        self.entries.extend(new_entries.iter().cloned());
+        new_entries
+    }
+}
+
+impl From<NoteEntry> for HistoryNote {
+    fn from(note: NoteEntry) -> Self {
+        HistoryNote {
+            blinded_commitment: note.blinded_commitment,
+            asset: note.asset,
+            amount: note.amount,
+            memo: note.memo,
+        }
+    }
+}
+
+fn load_keys(data_dir: &Path) -> anyhow::Result<Keys> {
+    let path = data_dir.join("keys.json");
+    let bytes = std::fs::read(&path).with_context(|| {
+        format!(
+            "No keys at {}, run `railgun key generate` or `railgun key import` first",
+            path.display()
+        )
+    })?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+fn load_history(data_dir: &Path) -> anyhow::Result<History> {
+    match std::fs::read(data_dir.join("history.json")) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(History::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn save_history(data_dir: &Path, history: &History) -> anyhow::Result<()> {
+    write_private(
+        &data_dir.join("history.json"),
+        &serde_json::to_vec_pretty(history)?,
+    )
+}
+
+/// Replaces `path` with `bytes` through a temp file and a rename, so an interrupted write never
+/// leaves a truncated file behind. The file is only readable by its owner.
+fn write_private(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    let tmp = PathBuf::from(tmp);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let result = options.open(&tmp).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result.with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("railgun-cli-{}", rand::random::<u64>()))
+    }
+
+    fn note(blinded_commitment: &str, amount: u128) -> HistoryNote {
+        HistoryNote {
+            blinded_commitment: blinded_commitment.to_string(),
+            asset: AssetId::Erc20(Address::ZERO),
+            amount,
+            memo: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_derive_key() {
+        //? Expected keys from BIP-39 / BIP-32 as implemented by @scure/bip32, which the
+        //? railgun plugin's host keystore uses.
+        assert_eq!(
+            derive_key(MNEMONIC, &spending_key_path(0)).unwrap(),
+            "0x96efbf7ab4a508d87b20b9d32688fcb4ea6c7c87d9104888bc26631125c3ff73"
+        );
+        assert_eq!(
+            derive_key(MNEMONIC, &viewing_key_path(0)).unwrap(),
+            "0xe2534d5a961988d66177c2d2acc5b6be2dea2cd5f4830e4becab8a0b4e0fd6bf"
+        );
+    }
+
+    #[test]
+    fn test_key_import() {
+        let dir = temp_dir();
+        let import = |force| KeyCommand::Import {
+            spending_key: None,
+            viewing_key: None,
+            mnemonic: Some(MNEMONIC.to_string()),
+            index: 0,
+            force,
+        };
+
+        key(&dir, import(false)).unwrap();
+        let keys = load_keys(&dir).unwrap();
+        assert_eq!(
+            keys.spending_key.to_hex(),
+            "96efbf7ab4a508d87b20b9d32688fcb4ea6c7c87d9104888bc26631125c3ff73"
+        );
+        assert_eq!(
+            keys.viewing_key.to_hex(),
+            "e2534d5a961988d66177c2d2acc5b6be2dea2cd5f4830e4becab8a0b4e0fd6bf"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.join("keys.json"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert!(key(&dir, import(false)).is_err());
+        let hex = KeyCommand::Import {
+            spending_key: Some(format!("0x{}", "11".repeat(32))),
+            viewing_key: Some(format!("0x{}", "22".repeat(32))),
+            mnemonic: None,
+            index: 0,
+            force: true,
+        };
+        key(&dir, hex).unwrap();
+        let keys = load_keys(&dir).unwrap();
+        assert_eq!(keys.spending_key.to_hex(), "11".repeat(32));
+        assert_eq!(keys.viewing_key.to_hex(), "22".repeat(32));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_history_update() {
+        let mut history = History::default();
+
+        let entries = history.update(vec![note("a", 1), note("b", 2)], 10);
+        assert_eq!(entries.len(), 2);
+        assert!(
+            entries
+                .iter()
+                .all(|e| matches!(e, HistoryEntry::Received { timestamp: 10, .. }))
+        );
+
+        //? "a" is spent into change "c", "b" is untouched.
+        let entries = history.update(vec![note("b", 2), note("c", 3)], 20);
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(
+            &entries[0],
+            HistoryEntry::Received { note, timestamp: 20 } if note.blinded_commitment == "c"
+        ));
+        assert!(matches!(
+            &entries[1],
+            HistoryEntry::Spent { note, timestamp: 20 } if note.blinded_commitment == "a"
+        ));
+
+        assert!(
+            history
+                .update(vec![note("b", 2), note("c", 3)], 30)
+                .is_empty()
+        );
+        assert_eq!(history.entries.len(), 4);
+        assert_eq!(history.known_notes, vec![note("b", 2), note("c", 3)]);
+    }
+
+    #[test]
+    fn test_save_history() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut history = History::default();
+        history.update(vec![note("a", 1)], 10);
+        save_history(&dir, &history).unwrap();
+        save_history(&dir, &history).unwrap();
+
+        let loaded = load_history(&dir).unwrap();
+        assert_eq!(loaded.known_notes, history.known_notes);
+        assert_eq!(loaded.entries.len(), 1);
+        //? Only history.json is left, no temp files.
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,9 +1,18 @@
 fn main() {
+    // Precompiled witness modules are native code for the target they were compiled on, so the
+    // target is part of their cache key.
+    println!(
+        "cargo:rustc-env=RAILGUN_TARGET={}",
+        std::env::var("TARGET").unwrap()
+    );
+
     cfg_aliases::cfg_aliases! {
         native: { not(target_arch = "wasm32") },
         wasm: { all(target_arch = "wasm32") },
         js: { all(target_arch = "wasm32", feature = "js") },
         parallel: { feature = "parallel" },
         bench: { all(not(target_arch = "wasm32"), feature = "bench") },
+        sqlite: { all(not(target_arch = "wasm32"), feature = "sqlite") },
+        wasm_witness: { feature = "wasm-witness" },
     }
 }
@@ -1,2 +1,3 @@
 pub mod erc20;
 pub mod railgun;
+pub mod wrappers;
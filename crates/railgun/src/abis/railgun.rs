@@ -7,12 +7,13 @@ use alloy::{
     sol,
     sol_types::SolValue,
 };
+use crypto::poseidon_hash;
 use ruint::aliases::U256;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
-    circuit::proof::Proof,
+    circuit::proof::{G1Affine, G2Affine, Proof},
     crypto::{aes::Ciphertext, railgun_zero::SNARK_PRIME},
 };
 
@@ -100,6 +101,33 @@ impl From<Proof> for SnarkProof {
     }
 }
 
+impl From<&SnarkProof> for Proof {
+    fn from(proof: &SnarkProof) -> Self {
+        Proof {
+            a: G1Affine {
+                x: proof.a.x,
+                y: proof.a.y,
+            },
+            b: G2Affine {
+                x: [proof.b.x[1], proof.b.x[0]],
+                y: [proof.b.y[1], proof.b.y[0]],
+            },
+            c: G1Affine {
+                x: proof.c.x,
+                y: proof.c.y,
+            },
+        }
+    }
+}
+
+impl CommitmentPreimage {
+    /// The commitment's UTXO leaf hash, as computed by the contract.
+    pub fn hash(&self) -> U256 {
+        let npk = U256::from_be_bytes(self.npk.0);
+        poseidon_hash(&[npk, self.token.hash(), U256::from(self.value)]).unwrap()
+    }
+}
+
 impl BoundParams {
     pub fn new(
         tree_number: u16,
@@ -179,6 +207,8 @@ sol! {
         // Getter for rootHistory mapping:
         // treeNumber -> root -> seen
         function rootHistory(uint256 treeNumber, bytes32 root) external view returns (bool);
+        function merkleRoot() external view returns (bytes32);
+        function treeNumber() external view returns (uint256);
 
         // Functions
         function shield(ShieldRequest[] calldata _shieldRequests) external;
@@ -288,14 +318,23 @@ sol! {
         G1Point c;
     }
 
-    /// RelayAdapt: native wrap + shield entrypoint (see Railgun `RelayAdapt.json` ABI).
+    /// RelayAdapt: native wrap + shield and relayed transact entrypoints (see Railgun
+    /// `RelayAdapt.json` ABI).
     contract RelayAdapt {
         struct Call {
             address to;
             bytes data;
             uint256 value;
         }
+        struct ActionData {
+            bytes31 random;
+            bool requireSuccess;
+            uint256 minGasLimit;
+            Call[] calls;
+        }
         function multicall(bool _requireSuccess, Call[] calldata _calls) external payable;
+        function transact(Transaction[] calldata _transactions) external;
+        function relay(Transaction[] calldata _transactions, ActionData calldata _actionData) external payable;
         function wrapBase(uint256 _amount) external;
         function shield(ShieldRequest[] calldata _shieldRequests) external;
     }
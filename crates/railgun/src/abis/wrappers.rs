@@ -0,0 +1,30 @@
+use alloy::sol;
+
+sol! {
+    /// ERC-4337 smart account calls that Railgun transactions are routed through.
+    contract SmartAccount {
+        struct Call {
+            address target;
+            uint256 value;
+            bytes data;
+        }
+        function execute(address dest, uint256 value, bytes calldata func) external;
+        function executeBatch(Call[] calldata calls) external;
+    }
+
+    /// ERC-4337 v0.7+ EntryPoint.
+    contract EntryPoint {
+        #[derive(Default)]
+        struct PackedUserOperation {
+            address sender;
+            uint256 nonce;
+            bytes initCode;
+            bytes callData;
+            bytes32 accountGasLimits;
+            uint256 preVerificationGas;
+            bytes32 gasFees;
+            bytes paymasterAndData;
+        }
+        function handleOps(PackedUserOperation[] calldata ops, address beneficiary) external;
+    }
+}
@@ -0,0 +1,182 @@
+use alloy::primitives::{Address, B256};
+use serde::{Deserialize, de::DeserializeOwned};
+use tracing::info;
+
+use crate::broadcaster::{
+    BroadcastRequest, BroadcastStatus, Broadcaster, BroadcasterError, FeeQuote,
+};
+
+/// A broadcaster reached over HTTP.
+///
+/// Endpoints, relative to the broadcaster's URL:
+/// - `GET /fees/{chainId}/{token}` returns a [`FeeQuote`].
+/// - `POST /transact` takes a [`BroadcastRequest`] and returns `{ "txHash": "0x..." }`.
+/// - `GET /transactions/{txHash}` returns a [`BroadcastStatus`].
+///
+/// The broadcaster sees the submitting client's IP address, so the URL should be reached over
+/// a network that hides it if that matters.
+#[derive(Clone)]
+pub struct HttpBroadcaster {
+    client: reqwest::Client,
+    url: String,
+    wait_interval: common::Duration,
+    timeout: common::Duration,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactResponse {
+    tx_hash: B256,
+}
+
+impl HttpBroadcaster {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into().trim_end_matches('/').to_string(),
+            wait_interval: common::Duration::from_secs(6),
+            timeout: common::Duration::from_secs(300),
+        }
+    }
+
+    /// Sets how often [`Broadcaster::wait_for_result`] polls, and how long it waits in total.
+    pub fn with_wait(mut self, interval: common::Duration, timeout: common::Duration) -> Self {
+        self.wait_interval = interval;
+        self.timeout = timeout;
+        self
+    }
+
+    async fn parse<T: DeserializeOwned>(resp: reqwest::Response) -> Result<T, BroadcasterError> {
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(BroadcasterError::Service { status, body });
+        }
+        Ok(resp.json().await?)
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl Broadcaster for HttpBroadcaster {
+    async fn fee_quote(&self, chain_id: u64, token: Address) -> Result<FeeQuote, BroadcasterError> {
+        let url = format!("{}/fees/{}/{}", self.url, chain_id, token);
+        let resp = self.client.get(&url).send().await?;
+        Self::parse(resp).await
+    }
+
+    async fn submit(&self, request: &BroadcastRequest) -> Result<B256, BroadcasterError> {
+        info!("Submitting transaction to broadcaster {}", self.url);
+        let url = format!("{}/transact", self.url);
+        let resp = self.client.post(&url).json(request).send().await?;
+        let resp: TransactResponse = Self::parse(resp).await?;
+        Ok(resp.tx_hash)
+    }
+
+    async fn status(&self, tx_hash: B256) -> Result<BroadcastStatus, BroadcasterError> {
+        let url = format!("{}/transactions/{}", self.url, tx_hash);
+        let resp = self.client.get(&url).send().await?;
+        Self::parse(resp).await
+    }
+
+    async fn wait_for_result(&self, tx_hash: B256) -> Result<BroadcastStatus, BroadcasterError> {
+        let start = common::Instant::now();
+        while start.elapsed() < self.timeout {
+            match self.status(tx_hash).await? {
+                BroadcastStatus::Pending => {
+                    info!("Broadcast {} not yet included, retrying...", tx_hash);
+                    common::sleep(self.wait_interval).await;
+                }
+                status => return Ok(status),
+            }
+        }
+
+        Err(BroadcasterError::Timeout)
+    }
+}
+
+#[cfg(all(test, native))]
+mod tests {
+    use alloy::primitives::{U256, address};
+
+    use super::*;
+    use crate::{
+        account::signer::{PrivateKeySigner, RailgunSigner},
+        crypto::keys::{ByteKey, SpendingKey, ViewingKey},
+        test_server::serve,
+    };
+
+    fn quote() -> FeeQuote {
+        let signer = PrivateKeySigner::new_evm(
+            SpendingKey::from_bytes([1u8; 32]),
+            ViewingKey::from_bytes([2u8; 32]),
+            1,
+        );
+        FeeQuote {
+            token: address!("0x1234567890123456789012345678901234567890"),
+            fee_per_unit_gas: U256::from(2_000_000_000u64),
+            railgun_address: signer.address(),
+            fees_id: "fees-1".to_string(),
+            expiration: 1_700_000_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_flow() {
+        let tx_hash = B256::repeat_byte(0xab);
+        let (url, requests) = serve(vec![
+            ("200 OK", serde_json::to_string(&quote()).unwrap()),
+            ("200 OK", format!(r#"{{"txHash":"{}"}}"#, tx_hash)),
+            ("200 OK", r#"{"type":"pending"}"#.to_string()),
+            (
+                "200 OK",
+                r#"{"type":"included","blockNumber":42}"#.to_string(),
+            ),
+        ])
+        .await;
+        let broadcaster = HttpBroadcaster::new(url).with_wait(
+            common::Duration::from_millis(10),
+            common::Duration::from_secs(5),
+        );
+
+        let fetched = broadcaster.fee_quote(1, quote().token).await.unwrap();
+        assert_eq!(fetched, quote());
+
+        let request = BroadcastRequest {
+            chain_id: 1,
+            to: Address::ZERO,
+            data: vec![1, 2, 3].into(),
+            fees_id: fetched.fees_id,
+            min_gas_price: 10,
+        };
+        assert_eq!(broadcaster.submit(&request).await.unwrap(), tx_hash);
+
+        let status = broadcaster.wait_for_result(tx_hash).await.unwrap();
+        assert_eq!(status, BroadcastStatus::Included { block_number: 42 });
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(
+            requests[0].method_path,
+            format!("GET /fees/1/{}", quote().token.to_checksum(None))
+        );
+        assert_eq!(requests[1].method_path, "POST /transact");
+        let body: serde_json::Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!(body["feesId"], "fees-1");
+        assert_eq!(body["data"], "0x010203");
+        assert_eq!(
+            requests[2].method_path,
+            format!("GET /transactions/{}", tx_hash)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_broadcaster_error() {
+        let (url, _) = serve(vec![("503 Service Unavailable", "busy".to_string())]).await;
+
+        let err = HttpBroadcaster::new(url)
+            .fee_quote(1, Address::ZERO)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BroadcasterError::Service { body, .. } if body == "busy"));
+    }
+}
@@ -0,0 +1,292 @@
+//! Broadcasters (relayers) submit proved railgun transactions on a wallet's behalf, so the wallet
+//! never needs an EVM account. They're paid with a fee note added to the transaction itself.
+
+pub mod http;
+
+use alloy::primitives::{Address, B256, Bytes, U256};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::account::address::RailgunAddress;
+
+/// A broadcaster's price for relaying a transaction, paid in `token`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(js, derive(tsify::Tsify))]
+#[serde(rename_all = "camelCase")]
+pub struct FeeQuote {
+    #[cfg_attr(js, tsify(type = "`0x${string}`"))]
+    pub token: Address,
+    /// Fee in base units of `token` per 10^18 wei of gas cost, i.e. the broadcaster's exchange
+    /// rate for the chain's base token.
+    #[cfg_attr(js, tsify(type = "`0x${string}`"))]
+    pub fee_per_unit_gas: U256,
+    /// Address the fee note is sent to.
+    pub railgun_address: RailgunAddress,
+    /// Identifies this quote when submitting a transaction paid with it.
+    pub fees_id: String,
+    /// Unix time in seconds after which the broadcaster no longer honors the quote.
+    pub expiration: u64,
+}
+
+impl FeeQuote {
+    /// Returns the fee for a transaction using `gas_limit` gas at `gas_price`, or None if it
+    /// overflows.
+    pub fn fee(&self, gas_limit: u64, gas_price: u128) -> Option<u128> {
+        let cost = U256::from(gas_limit).checked_mul(U256::from(gas_price))?;
+        let fee = cost.checked_mul(self.fee_per_unit_gas)? / U256::from(10u64.pow(18));
+        fee.try_into().ok()
+    }
+}
+
+/// A proved transaction for a broadcaster to submit.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastRequest {
+    pub chain_id: u64,
+    pub to: Address,
+    pub data: Bytes,
+    /// The quote the transaction's fee note was priced with.
+    pub fees_id: String,
+    /// Gas price the fee was computed with. Broadcasters reject requests whose fee no longer
+    /// covers their gas price.
+    pub min_gas_price: u128,
+}
+
+/// Where a broadcast transaction is after submission.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(js, derive(tsify::Tsify))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BroadcastStatus {
+    /// Submitted but not yet included.
+    Pending,
+    #[serde(rename_all = "camelCase")]
+    Included { block_number: u64 },
+    /// The broadcaster gave up on the transaction, e.g. because it reverted.
+    #[serde(rename_all = "camelCase")]
+    Failed { reason: String },
+}
+
+/// A transaction handed to a broadcaster by
+/// [`RailgunProvider::broadcast`](crate::provider::RailgunProvider::broadcast).
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(js, derive(tsify::Tsify))]
+#[serde(rename_all = "camelCase")]
+pub struct Broadcast {
+    #[cfg_attr(js, tsify(type = "`0x${string}`"))]
+    pub tx_hash: B256,
+    /// The fee paid to the broadcaster, in base units of the quote's token.
+    pub fee: u128,
+    pub quote: FeeQuote,
+}
+
+#[derive(Debug, Error)]
+pub enum BroadcasterError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Broadcaster returned {status}: {body}")]
+    Service {
+        status: reqwest::StatusCode,
+        body: String,
+    },
+    #[error("Fee quote {0} expired")]
+    QuoteExpired(String),
+    #[error("Fee quote is in {actual}, expected {expected}")]
+    QuoteTokenMismatch { expected: Address, actual: Address },
+    #[error("Fee {fee} exceeds the maximum of {max}")]
+    FeeExceedsMax { fee: u128, max: u128 },
+    #[error("Fee overflows")]
+    FeeOverflow,
+    #[error("Fee did not converge after {0} gas estimates")]
+    FeeNotConverged(usize),
+    #[error("Timeout")]
+    Timeout,
+}
+
+/// Prices a broadcaster fee note from the gas of the transaction that carries it.
+///
+/// The fee note itself adds gas, so the fee is re-priced from each new estimate until it covers
+/// the transaction paying it. Fees above `max_fee` are rejected, so a broadcaster can't quote
+/// its way to an arbitrary share of the payer's balance.
+pub(crate) struct FeeConvergence<'a> {
+    quote: &'a FeeQuote,
+    gas_price: u128,
+    max_fee: u128,
+    fee: u128,
+    estimates: usize,
+}
+
+impl<'a> FeeConvergence<'a> {
+    /// Gas estimates to try before giving up.
+    const MAX_ESTIMATES: usize = 5;
+
+    /// Starts from the gas estimate of the transaction without a fee note.
+    pub fn new(
+        quote: &'a FeeQuote,
+        gas_price: u128,
+        gas: u64,
+        max_fee: u128,
+    ) -> Result<Self, BroadcasterError> {
+        let mut convergence = Self {
+            quote,
+            gas_price,
+            max_fee,
+            fee: 0,
+            estimates: 0,
+        };
+        convergence.fee = convergence.price(gas)?;
+        Ok(convergence)
+    }
+
+    /// The fee to put in the next fee note.
+    pub fn fee(&self) -> u128 {
+        self.fee
+    }
+
+    /// Takes the gas estimate of a transaction paying [`Self::fee`]. Returns true if the fee
+    /// covers it, otherwise re-prices the fee.
+    pub fn update(&mut self, gas: u64) -> Result<bool, BroadcasterError> {
+        let required = self
+            .quote
+            .fee(gas, self.gas_price)
+            .ok_or(BroadcasterError::FeeOverflow)?;
+        if required <= self.fee {
+            return Ok(true);
+        }
+
+        self.estimates += 1;
+        if self.estimates >= Self::MAX_ESTIMATES {
+            return Err(BroadcasterError::FeeNotConverged(self.estimates));
+        }
+        self.fee = self.price(gas)?;
+        Ok(false)
+    }
+
+    /// Prices `gas` with 10% headroom, so small differences between builds don't force
+    /// another estimate.
+    fn price(&self, gas: u64) -> Result<u128, BroadcasterError> {
+        let fee = self
+            .quote
+            .fee(gas.saturating_add(gas / 10), self.gas_price)
+            .ok_or(BroadcasterError::FeeOverflow)?;
+        if fee > self.max_fee {
+            return Err(BroadcasterError::FeeExceedsMax {
+                fee,
+                max: self.max_fee,
+            });
+        }
+        Ok(fee)
+    }
+}
+
+/// A service that relays proved railgun transactions.
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+pub trait Broadcaster {
+    /// Returns the broadcaster's current fee for relaying on `chain_id`, paid in `token`.
+    async fn fee_quote(&self, chain_id: u64, token: Address) -> Result<FeeQuote, BroadcasterError>;
+    /// Submits a transaction, returning its hash.
+    async fn submit(&self, request: &BroadcastRequest) -> Result<B256, BroadcasterError>;
+    /// Returns the status of a submitted transaction.
+    async fn status(&self, tx_hash: B256) -> Result<BroadcastStatus, BroadcasterError>;
+    /// Waits until a submitted transaction is included or has failed.
+    async fn wait_for_result(&self, tx_hash: B256) -> Result<BroadcastStatus, BroadcasterError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account::signer::{PrivateKeySigner, RailgunSigner},
+        crypto::keys::{ByteKey, SpendingKey, ViewingKey},
+    };
+
+    fn quote() -> FeeQuote {
+        let signer = PrivateKeySigner::new_evm(
+            SpendingKey::from_bytes([1u8; 32]),
+            ViewingKey::from_bytes([2u8; 32]),
+            1,
+        );
+        FeeQuote {
+            token: Address::ZERO,
+            //? 2000 tokens with 6 decimals per 1 ETH of gas.
+            fee_per_unit_gas: U256::from(2_000_000_000u64),
+            railgun_address: signer.address(),
+            fees_id: "id".to_string(),
+            expiration: 0,
+        }
+    }
+
+    #[test]
+    fn test_fee() {
+        let quote = quote();
+
+        //? 1M gas at 10 gwei is 0.01 ETH.
+        assert_eq!(quote.fee(1_000_000, 10_000_000_000), Some(20_000_000));
+        assert_eq!(quote.fee(u64::MAX, u128::MAX), None);
+    }
+
+    #[test]
+    fn test_fee_convergence() {
+        let quote = quote();
+        let gas_price = 10_000_000_000;
+        let fee_for = |gas: u64| quote.fee(gas, gas_price).unwrap();
+
+        //? 1M gas without the fee note, priced with 10% headroom.
+        let mut convergence = FeeConvergence::new(&quote, gas_price, 1_000_000, u128::MAX).unwrap();
+        assert_eq!(convergence.fee(), fee_for(1_100_000));
+
+        //? Adding the fee note costs more than the headroom, so the fee is re-priced.
+        assert!(!convergence.update(1_200_000).unwrap());
+        assert_eq!(convergence.fee(), fee_for(1_320_000));
+
+        //? The re-priced fee covers the next estimate.
+        assert!(convergence.update(1_210_000).unwrap());
+        assert_eq!(convergence.fee(), fee_for(1_320_000));
+    }
+
+    #[test]
+    fn test_fee_not_converged() {
+        let quote = quote();
+        let mut convergence =
+            FeeConvergence::new(&quote, 10_000_000_000, 1_000_000, u128::MAX).unwrap();
+
+        //? Each estimate outgrows the previous fee's headroom.
+        let mut gas = 1_000_000u64;
+        let err = loop {
+            gas *= 2;
+            match convergence.update(gas) {
+                Ok(converged) => assert!(!converged),
+                Err(e) => break e,
+            }
+        };
+        assert!(matches!(
+            err,
+            BroadcasterError::FeeNotConverged(FeeConvergence::MAX_ESTIMATES)
+        ));
+
+        let mut overflow = FeeConvergence::new(&quote, u128::MAX, 0, u128::MAX).unwrap();
+        assert!(matches!(
+            overflow.update(u64::MAX),
+            Err(BroadcasterError::FeeOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_fee_exceeds_max() {
+        let quote = quote();
+        let gas_price = 10_000_000_000;
+        let max_fee = quote.fee(1_200_000, gas_price).unwrap();
+
+        assert!(matches!(
+            FeeConvergence::new(&quote, gas_price, 2_000_000, max_fee),
+            Err(BroadcasterError::FeeExceedsMax { .. })
+        ));
+
+        //? The first fee fits, but the re-priced fee doesn't.
+        let mut convergence = FeeConvergence::new(&quote, gas_price, 1_000_000, max_fee).unwrap();
+        assert!(matches!(
+            convergence.update(1_200_000),
+            Err(BroadcasterError::FeeExceedsMax { fee, max }) if fee > max && max == max_fee
+        ));
+    }
+}
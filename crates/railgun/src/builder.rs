@@ -1,17 +1,33 @@
+#[cfg(all(native, wasm_witness))]
+use std::path::PathBuf;
 use std::sync::Arc;
 
-use eip_1193_provider::provider::{Eip1193Provider, IntoEip1193Provider};
+use eip_1193_provider::{
+    fixture::FixtureMode,
+    middleware::RetryProvider,
+    provider::{Eip1193Provider, IntoEip1193Provider},
+    recording::{RecordingProvider, ReplayProvider},
+};
 
 use crate::{
     chain_config::ChainConfig,
-    circuit::groth16_prover::Groth16Prover,
+    circuit::{
+        artifact_loader::ArtifactLoader,
+        artifact_manifest::ArtifactManifest,
+        artifact_source::{ArtifactSource, HttpArtifactSource},
+        groth16_prover::Groth16Prover,
+        prover::Prover,
+        witness::{WitnessBackend, WitnessGraphs},
+    },
     database::{Database, memory::MemoryDatabase},
     indexer::{
         syncer::{ChainedSyncer, RpcSyncer, SubsquidSyncer, UtxoSyncer},
         utxo_indexer::UtxoIndexer,
     },
-    merkle_tree::SmartWalletUtxoVerifier,
-    poi::provider::PoiProvider,
+    merkle_tree::{
+        MerkleTreeVerifier, SmartWalletUtxoVerifier, StorageProofUtxoVerifier, TrustedCheckpoint,
+    },
+    poi::{PoiPolicy, client::PoiClient, provider::PoiProvider},
     provider::{RailgunProvider, RailgunProviderError},
 };
 
@@ -21,7 +37,16 @@ pub struct RailgunBuilder {
     provider: Arc<dyn Eip1193Provider>,
     db: Option<Arc<dyn Database>>,
     utxo_syncer: Option<Arc<dyn UtxoSyncer>>,
+    prover: Option<Arc<dyn Prover>>,
+    artifact_source: Option<Arc<dyn ArtifactSource>>,
+    artifact_manifest: Option<ArtifactManifest>,
+    witness_backend: Option<WitnessBackend>,
     poi: bool,
+    poi_policy: PoiPolicy,
+    fixture: FixtureMode,
+    checkpoint: Option<TrustedCheckpoint>,
+    verify_txids: bool,
+    rpc_retries: u32,
 }
 
 impl RailgunBuilder {
@@ -32,7 +57,16 @@ impl RailgunBuilder {
             provider: provider.into_eip1193(),
             db: None,
             utxo_syncer: None,
+            prover: None,
+            artifact_source: None,
+            artifact_manifest: None,
+            witness_backend: None,
             poi: false,
+            poi_policy: PoiPolicy::default(),
+            fixture: FixtureMode::Live,
+            checkpoint: None,
+            verify_txids: false,
+            rpc_retries: 3,
         }
     }
 
@@ -56,6 +90,63 @@ impl RailgunBuilder {
         self
     }
 
+    /// Sets the prover used for transaction and POI proofs. If not set, proofs are generated
+    /// locally with [`Groth16Prover`].
+    ///
+    /// Use a [`RemoteProver`](crate::RemoteProver) to offload proving
+    /// to a proving service.
+    #[must_use]
+    pub fn with_prover(mut self, prover: Arc<dyn Prover>) -> Self {
+        self.prover = Some(prover);
+        self
+    }
+
+    /// Loads circuit artifacts from `source` instead of downloading them from the default
+    /// artifact host, e.g. a [`CachedArtifactSource`](crate::CachedArtifactSource) that keeps
+    /// them across restarts. Artifacts are still checked against the pinned manifest.
+    ///
+    /// Only applies to the default prover.
+    #[must_use]
+    pub fn with_artifact_source(mut self, source: Arc<dyn ArtifactSource>) -> Self {
+        self.artifact_source = Some(source);
+        self
+    }
+
+    /// Checks circuit artifacts against `manifest` instead of the manifest pinned in this build,
+    /// e.g. for self-hosted artifacts. Artifacts that `manifest` doesn't pin are rejected.
+    ///
+    /// Only applies to the default prover.
+    #[must_use]
+    pub fn with_artifact_manifest(mut self, manifest: ArtifactManifest) -> Self {
+        self.artifact_manifest = Some(manifest);
+        self
+    }
+
+    /// Generates witnesses natively from each circuit's witness graph instead of running its
+    /// WASM. The artifact source must serve each circuit's witness graph (`graph.bin.br`), which
+    /// the default artifact host does not publish yet.
+    ///
+    /// Only applies to the default prover.
+    #[must_use]
+    pub fn with_witness_graphs(mut self) -> Self {
+        self.witness_backend = Some(WitnessBackend::Graph(WitnessGraphs::new()));
+        self
+    }
+
+    /// Persists compiled witness generators under `dir` so that later processes skip compiling
+    /// each circuit's WASM. `dir` must only be writable by trusted users, since its contents are
+    /// loaded as native code.
+    ///
+    /// Only applies to the default prover.
+    #[cfg(all(native, wasm_witness))]
+    #[must_use]
+    pub fn with_module_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.witness_backend = Some(WitnessBackend::Wasm(
+            crate::circuit::witness::WitnessModules::new().with_cache_dir(dir),
+        ));
+        self
+    }
+
     /// Enables POI (Proof of innocence) support for the provider.
     ///
     /// Uses the default chain-specific POI endpoints and list keys from the chain config. Enabling
@@ -67,39 +158,136 @@ impl RailgunBuilder {
         self
     }
 
+    /// Sets how the statuses of a note on each POI list decide whether it can be spent. Defaults
+    /// to requiring every list to be `Valid`. Only applies when POI support is enabled.
+    #[must_use]
+    pub fn with_poi_policy(mut self, policy: PoiPolicy) -> Self {
+        self.poi_policy = policy;
+        self
+    }
+
+    /// Reconstructs TXIDs from on-chain calldata and cross-checks them against the Subsquid
+    /// operations and the POI node's TXID roots. Only applies when POI support is enabled.
+    ///
+    /// Requires one extra `eth_getTransactionByHash` call per Railgun transaction, but a faulty
+    /// or compromised indexer or POI node is detected instead of silently trusted.
+    #[must_use]
+    pub fn with_txid_verification(mut self) -> Self {
+        self.verify_txids = true;
+        self
+    }
+
+    /// Records all network traffic to, or replays it from, a fixture.
+    ///
+    /// Applies to the EIP-1193 provider, the default Subsquid syncers, and the POI client. A
+    /// custom UTXO syncer set with `with_utxo_syncer` must be configured separately.
+    #[must_use]
+    pub fn with_fixture(mut self, fixture: FixtureMode) -> Self {
+        self.fixture = fixture;
+        self
+    }
+
+    /// Verifies UTXO Merkle roots with storage proofs against a trusted checkpoint instead of
+    /// trusting the RPC's `eth_call` results.
+    ///
+    /// Roots are only verifiable up to the checkpoint block. Move it forward with
+    /// [`RailgunProvider::set_trusted_checkpoint`]. See [`StorageProofUtxoVerifier`].
+    #[must_use]
+    pub fn with_trusted_checkpoint(mut self, checkpoint: TrustedCheckpoint) -> Self {
+        self.checkpoint = Some(checkpoint);
+        self
+    }
+
+    /// Sets how many times an RPC request failing with a transient error, such as rate limiting
+    /// or a timeout, is retried before the error is returned. Defaults to 3.
+    ///
+    /// For rate limiting, caching or multiple endpoints, wrap the provider passed to
+    /// [`RailgunBuilder::new`] in the [`eip_1193_provider::middleware`] providers.
+    #[must_use]
+    pub fn with_rpc_retries(mut self, retries: u32) -> Self {
+        self.rpc_retries = retries;
+        self
+    }
+
     /// Builds the `RailgunProvider` with the specified configuration.
     #[must_use]
-    pub async fn build(self) -> Result<RailgunProvider, RailgunProviderError> {
+    pub async fn build(mut self) -> Result<RailgunProvider, RailgunProviderError> {
         let db = self.db.unwrap_or_else(|| Arc::new(MemoryDatabase::new()));
 
+        let live: Arc<dyn Eip1193Provider> =
+            Arc::new(RetryProvider::new(self.provider).with_max_retries(self.rpc_retries));
+        self.provider = match &self.fixture {
+            FixtureMode::Live => live,
+            FixtureMode::Record(recorder) => {
+                Arc::new(RecordingProvider::new(live, recorder.clone()))
+            }
+            FixtureMode::Replay(replayer) => Arc::new(ReplayProvider::new(replayer.clone())),
+        };
+
         let utxo_syncer = self.utxo_syncer.unwrap_or_else(|| {
             Arc::new(
                 ChainedSyncer::new()
-                    .then(SubsquidSyncer::new(&self.chain.subsquid_endpoint))
+                    .then(
+                        SubsquidSyncer::new(&self.chain.subsquid_endpoint)
+                            .with_fixture(self.fixture.clone()),
+                    )
                     .then(RpcSyncer::new(self.chain.clone(), self.provider.clone())),
             )
         });
 
-        let utxo_verifier = Arc::new(SmartWalletUtxoVerifier::new(
-            self.chain.railgun_smart_wallet,
-            self.provider.clone(),
-        ));
+        let checkpoint_verifier = self.checkpoint.map(|checkpoint| {
+            Arc::new(StorageProofUtxoVerifier::new(
+                self.chain.railgun_smart_wallet,
+                self.provider.clone(),
+                checkpoint,
+            ))
+        });
+        let utxo_verifier: Arc<dyn MerkleTreeVerifier> = match &checkpoint_verifier {
+            Some(verifier) => verifier.clone(),
+            None => Arc::new(SmartWalletUtxoVerifier::new(
+                self.chain.railgun_smart_wallet,
+                self.provider.clone(),
+            )),
+        };
 
         let utxo_indexer = UtxoIndexer::new(db.clone(), utxo_syncer, utxo_verifier).await?;
 
-        let prover = Groth16Prover::new();
+        let prover = self.prover.unwrap_or_else(|| {
+            let source = self
+                .artifact_source
+                .unwrap_or_else(|| Arc::new(HttpArtifactSource::default()));
+            let artifact_loader = match self.artifact_manifest {
+                Some(manifest) => ArtifactLoader::new(source).with_manifest(manifest),
+                None => ArtifactLoader::pinned(source),
+            };
+            let mut prover = Groth16Prover::new().with_artifact_loader(artifact_loader);
+            if let Some(witness_backend) = self.witness_backend {
+                prover = prover.with_witness_backend(witness_backend);
+            }
+            Arc::new(prover)
+        });
 
         let poi_provider = if self.poi {
-            let txid_syncer = Arc::new(SubsquidSyncer::new(&self.chain.subsquid_endpoint));
-
-            let poi_provider = PoiProvider::new(
+            let txid_syncer = Arc::new(
+                SubsquidSyncer::new(&self.chain.subsquid_endpoint)
+                    .with_fixture(self.fixture.clone()),
+            );
+            let poi_client = PoiClient::new(
                 self.chain.id,
-                db,
-                txid_syncer,
                 self.chain.poi_endpoint.clone(),
                 self.chain.list_keys.clone(),
             )
-            .await?;
+            .with_fixture(self.fixture.clone());
+
+            let mut poi_provider = PoiProvider::new(db, txid_syncer, poi_client)
+                .await?
+                .with_policy(self.poi_policy);
+            if self.verify_txids {
+                poi_provider = poi_provider.with_txid_verifier(Arc::new(RpcSyncer::new(
+                    self.chain.clone(),
+                    self.provider.clone(),
+                )));
+            }
             Some(poi_provider)
         } else {
             None
@@ -111,6 +299,7 @@ impl RailgunBuilder {
             utxo_indexer,
             prover,
             poi_provider,
+            checkpoint_verifier,
         )
         .await
     }
@@ -0,0 +1,281 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Cursor,
+    sync::{Arc, Mutex},
+};
+
+use ark_bn254::{Bn254, Fr};
+use ark_circom::index::NPIndex;
+use ark_groth16::ProvingKey;
+use ark_serialize::CanonicalDeserialize;
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+use crate::{
+    circuit::{
+        artifact_manifest::{ArtifactIntegrityError, ArtifactManifest},
+        artifact_source::{ArtifactSource, ArtifactSourceError, HttpArtifactSource},
+        witness_graph::WitnessGraph,
+    },
+    crypto::serializable_np_index::SerializableNpIndex,
+};
+
+/// Loads circuit artifacts from an [`ArtifactSource`], checking them against an
+/// [`ArtifactManifest`] if one is set and caching them in memory.
+#[derive(Clone)]
+pub struct ArtifactLoader {
+    source: Arc<dyn ArtifactSource>,
+    manifest: Option<Arc<ArtifactManifest>>,
+    cache: Arc<Mutex<Cache>>,
+    in_flight: Arc<Mutex<HashMap<String, Arc<OnceCell<Vec<u8>>>>>>,
+}
+
+struct Cache {
+    entries: VecDeque<(String, Vec<u8>)>,
+    total_bytes: usize,
+    max_bytes: usize,
+}
+
+impl Cache {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            total_bytes: 0,
+            max_bytes,
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    }
+
+    fn insert(&mut self, key: String, data: Vec<u8>) {
+        let size = data.len();
+        self.entries.push_back((key, data));
+        self.total_bytes += size;
+        while self.total_bytes > self.max_bytes {
+            if let Some((_, evicted)) = self.entries.pop_front() {
+                self.total_bytes -= evicted.len();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArtifactLoaderError {
+    #[error("Artifact source error: {0}")]
+    Source(#[from] ArtifactSourceError),
+    #[error("Artifact integrity error: {0}")]
+    Integrity(#[from] ArtifactIntegrityError),
+    #[error("Deserialization error: {0}")]
+    DeserializationError(#[from] ark_serialize::SerializationError),
+    #[error("Decompression error: {0}")]
+    DecompressionError(#[from] std::io::Error),
+}
+
+impl Default for ArtifactLoader {
+    /// Downloads artifacts from the default artifact host, checking them against the pinned
+    /// manifest.
+    fn default() -> Self {
+        Self::pinned(Arc::new(HttpArtifactSource::default()))
+    }
+}
+
+impl ArtifactLoader {
+    pub fn new(source: Arc<dyn ArtifactSource>) -> Self {
+        Self {
+            source,
+            manifest: None,
+            cache: Arc::new(Mutex::new(Cache::new(64 * 1024 * 1024))),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Loads artifacts from `source`, checking them against the manifest pinned in this build.
+    /// If this build pins no artifacts, every artifact is rejected.
+    pub fn pinned(source: Arc<dyn ArtifactSource>) -> Self {
+        let manifest = ArtifactManifest::pinned();
+        if manifest.is_empty() {
+            warn!("This build pins no artifact hashes; every artifact will be rejected");
+        }
+        Self::new(source).with_manifest(manifest)
+    }
+
+    /// Rejects any artifact whose hash doesn't match `manifest`, including artifacts the
+    /// manifest doesn't pin.
+    pub fn with_manifest(mut self, manifest: ArtifactManifest) -> Self {
+        self.manifest = Some(Arc::new(manifest));
+        self
+    }
+
+    pub async fn load_wasm(&self, circuit_name: &str) -> Result<Vec<u8>, ArtifactLoaderError> {
+        info!("Loading WASM: {}", circuit_name);
+        let compressed = self.fetch(circuit_name, "wasm.br").await?;
+        Ok(decompress(&compressed)?)
+    }
+
+    pub async fn load_proving_key(
+        &self,
+        circuit_name: &str,
+    ) -> Result<ProvingKey<Bn254>, ArtifactLoaderError> {
+        info!("Loading proving key: {}", circuit_name);
+        let compressed = self.fetch(circuit_name, "proving_key.bin.br").await?;
+        let bytes = decompress(&compressed)?;
+        let pk = ProvingKey::<Bn254>::deserialize_uncompressed_unchecked(Cursor::new(bytes))?;
+        Ok(pk)
+    }
+
+    pub async fn load_matrices(
+        &self,
+        circuit_name: &str,
+    ) -> Result<NPIndex<Fr>, ArtifactLoaderError> {
+        info!("Loading matrices: {}", circuit_name);
+        let compressed = self.fetch(circuit_name, "matrices.bin.br").await?;
+        let bytes = decompress(&compressed)?;
+        let matrices =
+            SerializableNpIndex::<Fr>::deserialize_uncompressed_unchecked(Cursor::new(bytes))?;
+        Ok(matrices.into())
+    }
+
+    /// Loads the circuit's witness graph, for generating witnesses natively.
+    pub async fn load_witness_graph(
+        &self,
+        circuit_name: &str,
+    ) -> Result<WitnessGraph, ArtifactLoaderError> {
+        info!("Loading witness graph: {}", circuit_name);
+        let compressed = self.fetch(circuit_name, "graph.bin.br").await?;
+        let bytes = decompress(&compressed)?;
+        let graph = WitnessGraph::deserialize_uncompressed_unchecked(Cursor::new(bytes))?;
+        Ok(graph)
+    }
+
+    /// Fetches an artifact from the cache or the source. Artifacts are verified against the
+    /// manifest before they're cached, so cached artifacts are always trusted. Concurrent
+    /// fetches of the same artifact share a single request.
+    async fn fetch(&self, circuit_name: &str, file: &str) -> Result<Vec<u8>, ArtifactLoaderError> {
+        let key = format!("{}/{}", circuit_name, file);
+        if let Some(cached) = self.cache.lock().unwrap().get(&key) {
+            return Ok(cached);
+        }
+
+        let cell = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let result = cell
+            .get_or_try_init(|| async {
+                let data = self.source.fetch(circuit_name, file).await?;
+                let verified = match &self.manifest {
+                    Some(manifest) => manifest.verify(circuit_name, file, &data),
+                    None => Ok(()),
+                };
+                if let Err(e) = verified {
+                    //? Otherwise a persistent source would serve the bad artifact forever.
+                    if let Err(e) = self.source.invalidate(circuit_name, file).await {
+                        warn!("Failed to invalidate {}: {}", key, e);
+                    }
+                    return Err(e.into());
+                }
+
+                self.cache.lock().unwrap().insert(key.clone(), data.clone());
+                Ok::<_, ArtifactLoaderError>(data)
+            })
+            .await
+            .cloned();
+
+        //? The bounded cache owns fetched artifacts, so the cell is dropped once it settles.
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight.get(&key).is_some_and(|c| Arc::ptr_eq(c, &cell)) {
+            in_flight.remove(&key);
+        }
+        result
+    }
+}
+
+fn decompress(data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    let mut out = Vec::new();
+    brotli::BrotliDecompress(&mut &data[..], &mut out)?;
+    Ok(out)
+}
+
+#[cfg(all(test, native))]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::circuit::artifact_source::EmbeddedArtifactSource;
+
+    struct SlowSource(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl ArtifactSource for SlowSource {
+        async fn fetch(&self, _: &str, _: &str) -> Result<Vec<u8>, ArtifactSourceError> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            let mut compressed = Vec::new();
+            brotli::BrotliCompress(
+                &mut &b"wasm"[..],
+                &mut compressed,
+                &brotli::enc::BrotliEncoderParams::default(),
+            )?;
+            Ok(compressed)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_fetches_share_request() {
+        let source = Arc::new(SlowSource(AtomicUsize::new(0)));
+        let loader = ArtifactLoader::new(source.clone());
+
+        let loads = (0..4).map(|_| loader.load_wasm("railgun/01x02"));
+        for result in futures::future::join_all(loads).await {
+            assert_eq!(result.unwrap(), b"wasm");
+        }
+        assert_eq!(source.0.load(Ordering::Relaxed), 1);
+        assert!(loader.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pinned_rejects_unpinned_circuit() {
+        let source = EmbeddedArtifactSource::new().with_file("test/unpinned", "wasm.br", b"wasm");
+
+        let loader = ArtifactLoader::pinned(Arc::new(source));
+        assert!(matches!(
+            loader.load_wasm("test/unpinned").await,
+            Err(ArtifactLoaderError::Integrity(
+                ArtifactIntegrityError::Unpinned { .. }
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unpinned_proving_key() {
+        let source = EmbeddedArtifactSource::new()
+            .with_file("railgun/01x02", "proving_key.bin.br", b"swapped")
+            .with_file("railgun/01x02", "wasm.br", b"unpinned");
+        let mut manifest = ArtifactManifest::new();
+        manifest.insert("railgun/01x02", "proving_key.bin.br", b"original");
+
+        let loader = ArtifactLoader::new(Arc::new(source)).with_manifest(manifest);
+        assert!(matches!(
+            loader.load_proving_key("railgun/01x02").await,
+            Err(ArtifactLoaderError::Integrity(
+                ArtifactIntegrityError::Mismatch { .. }
+            ))
+        ));
+        assert!(matches!(
+            loader.load_wasm("railgun/01x02").await,
+            Err(ArtifactLoaderError::Integrity(
+                ArtifactIntegrityError::Unpinned { .. }
+            ))
+        ));
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Hashes of the artifacts published on the default artifact host, copied from the `pins.json`
+/// that `convert_artifacts` writes when the artifacts are converted.
+const PINNED: &str = include_str!("artifact_pins.json");
+
+/// Pinned SHA-256 hashes of circuit artifacts, keyed by circuit name and then file name.
+///
+/// Hashes are of the artifacts as fetched, i.e. still compressed. Serializes as
+/// `{ "railgun/01x02": { "proving_key.bin.br": "<hex>", ... }, ... }`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ArtifactManifest(BTreeMap<String, BTreeMap<String, String>>);
+
+#[derive(Debug, Error)]
+pub enum ArtifactIntegrityError {
+    #[error("No pinned hash for {circuit}/{file}")]
+    Unpinned { circuit: String, file: String },
+    #[error("Hash mismatch for {circuit}/{file}: expected {expected}, got {actual}")]
+    Mismatch {
+        circuit: String,
+        file: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl ArtifactManifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// The manifest pinning the artifacts published on the default artifact host. If this build
+    /// pins none, the manifest is empty and rejects every artifact.
+    pub fn pinned() -> Self {
+        Self::from_json(PINNED).expect("artifact_pins.json is a valid manifest")
+    }
+
+    /// Whether the manifest pins no artifacts.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Pins the hash of `bytes` for `circuit`/`file`.
+    pub fn insert(&mut self, circuit: &str, file: &str, bytes: &[u8]) {
+        self.0
+            .entry(circuit.to_string())
+            .or_default()
+            .insert(file.to_string(), sha256_hex(bytes));
+    }
+
+    /// Checks `bytes` against the pinned hash for `circuit`/`file`. Artifacts without a pinned
+    /// hash are rejected.
+    pub fn verify(
+        &self,
+        circuit: &str,
+        file: &str,
+        bytes: &[u8],
+    ) -> Result<(), ArtifactIntegrityError> {
+        let Some(expected) = self.0.get(circuit).and_then(|files| files.get(file)) else {
+            return Err(ArtifactIntegrityError::Unpinned {
+                circuit: circuit.to_string(),
+                file: file.to_string(),
+            });
+        };
+
+        let actual = sha256_hex(bytes);
+        if !expected.eq_ignore_ascii_case(&actual) {
+            return Err(ArtifactIntegrityError::Mismatch {
+                circuit: circuit.to_string(),
+                file: file.to_string(),
+                expected: expected.clone(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_verify() {
+        let mut manifest = ArtifactManifest::new();
+        manifest.insert("railgun/01x02", "proving_key.bin.br", b"key");
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        let manifest = ArtifactManifest::from_json(&json).unwrap();
+
+        assert!(
+            manifest
+                .verify("railgun/01x02", "proving_key.bin.br", b"key")
+                .is_ok()
+        );
+        assert!(matches!(
+            manifest.verify("railgun/01x02", "proving_key.bin.br", b"evil"),
+            Err(ArtifactIntegrityError::Mismatch { .. })
+        ));
+        assert!(matches!(
+            manifest.verify("railgun/01x02", "wasm.br", b"key"),
+            Err(ArtifactIntegrityError::Unpinned { .. })
+        ));
+    }
+
+    #[test]
+    fn test_pinned_manifest_is_well_formed() {
+        let manifest = ArtifactManifest::from_json(PINNED).unwrap();
+        for (circuit, files) in &manifest.0 {
+            assert!(!files.is_empty(), "{} pins no files", circuit);
+            for (file, hash) in files {
+                assert_eq!(hash.len(), 64, "{}/{} hash isn't SHA-256", circuit, file);
+                assert!(
+                    hex::decode(hash).is_ok(),
+                    "{}/{} hash isn't hex",
+                    circuit,
+                    file
+                );
+            }
+        }
+    }
+}
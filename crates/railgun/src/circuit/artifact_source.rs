@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+#[cfg(native)]
+use std::path::PathBuf;
+
+use thiserror::Error;
+use tracing::info;
+
+/// Where circuit artifacts are read from.
+///
+/// Artifacts are addressed by circuit name (e.g. `railgun/01x02`) and file name (`wasm.br`,
+/// `proving_key.bin.br`, or `matrices.bin.br`), and returned still compressed.
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+pub trait ArtifactSource: common::MaybeSend {
+    async fn fetch(&self, circuit: &str, file: &str) -> Result<Vec<u8>, ArtifactSourceError>;
+
+    /// Discards any stored copy of an artifact that failed verification, so the next fetch
+    /// doesn't return it again.
+    async fn invalidate(&self, _circuit: &str, _file: &str) -> Result<(), ArtifactSourceError> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ArtifactSourceError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Artifact not found: {0}")]
+    NotFound(String),
+}
+
+/// Downloads artifacts from `{base_url}/{circuit}/{file}`.
+#[derive(Clone)]
+pub struct HttpArtifactSource {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+/// Reads artifacts from `{dir}/{circuit}/{file}`, e.g. a mirror of the artifact host for
+/// machines that can't reach it.
+#[cfg(native)]
+pub struct DirectoryArtifactSource {
+    dir: PathBuf,
+}
+
+/// Serves artifacts compiled into the binary.
+#[derive(Default)]
+pub struct EmbeddedArtifactSource {
+    files: HashMap<String, &'static [u8]>,
+}
+
+/// Persists artifacts fetched from `inner` to `dir`, so each is only fetched once across
+/// restarts. Artifacts that fail verification are deleted from `dir`.
+#[cfg(native)]
+pub struct CachedArtifactSource<S> {
+    inner: S,
+    dir: PathBuf,
+}
+
+impl Default for HttpArtifactSource {
+    fn default() -> Self {
+        Self::new(
+            "https://github.com/Robert-MacWha/privacy-protocol-artifacts/raw/refs/heads/main/artifacts/",
+        )
+    }
+}
+
+impl HttpArtifactSource {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl ArtifactSource for HttpArtifactSource {
+    async fn fetch(&self, circuit: &str, file: &str) -> Result<Vec<u8>, ArtifactSourceError> {
+        let url = format!("{}/{}/{}", self.base_url, circuit, file);
+        info!("Downloading {}", url);
+        let resp = self.client.get(&url).send().await?.error_for_status()?;
+        Ok(resp.bytes().await?.to_vec())
+    }
+}
+
+#[cfg(native)]
+impl DirectoryArtifactSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[cfg(native)]
+#[async_trait::async_trait]
+impl ArtifactSource for DirectoryArtifactSource {
+    async fn fetch(&self, circuit: &str, file: &str) -> Result<Vec<u8>, ArtifactSourceError> {
+        let path = self.dir.join(circuit).join(file);
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Ok(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(ArtifactSourceError::NotFound(path.display().to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl EmbeddedArtifactSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an artifact, typically from `include_bytes!`.
+    pub fn with_file(mut self, circuit: &str, file: &str, bytes: &'static [u8]) -> Self {
+        self.files.insert(format!("{}/{}", circuit, file), bytes);
+        self
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl ArtifactSource for EmbeddedArtifactSource {
+    async fn fetch(&self, circuit: &str, file: &str) -> Result<Vec<u8>, ArtifactSourceError> {
+        let key = format!("{}/{}", circuit, file);
+        self.files
+            .get(&key)
+            .map(|bytes| bytes.to_vec())
+            .ok_or(ArtifactSourceError::NotFound(key))
+    }
+}
+
+#[cfg(native)]
+impl<S: ArtifactSource> CachedArtifactSource<S> {
+    pub fn new(inner: S, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+        }
+    }
+}
+
+#[cfg(native)]
+#[async_trait::async_trait]
+impl<S: ArtifactSource> ArtifactSource for CachedArtifactSource<S> {
+    async fn fetch(&self, circuit: &str, file: &str) -> Result<Vec<u8>, ArtifactSourceError> {
+        let path = self.dir.join(circuit).join(file);
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => return Ok(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let bytes = self.inner.fetch(circuit, file).await?;
+
+        write_atomic(&path, &bytes).await?;
+        Ok(bytes)
+    }
+
+    async fn invalidate(&self, circuit: &str, file: &str) -> Result<(), ArtifactSourceError> {
+        match tokio::fs::remove_file(self.dir.join(circuit).join(file)).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.inner.invalidate(circuit, file).await
+    }
+}
+
+/// Writes `bytes` to `path` through a uniquely named temp file, so an interrupted write is never
+/// mistaken for a complete file and concurrent writers of the same path can't interleave.
+#[cfg(native)]
+pub(crate) async fn write_atomic(path: &std::path::Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{:016x}.tmp", rand::random::<u64>()));
+    let tmp = PathBuf::from(tmp);
+    if let Err(e) = tokio::fs::write(&tmp, bytes).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(e);
+    }
+    if let Err(e) = tokio::fs::rename(&tmp, path).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+#[cfg(all(test, native))]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    struct CountingSource(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl ArtifactSource for CountingSource {
+        async fn fetch(&self, circuit: &str, file: &str) -> Result<Vec<u8>, ArtifactSourceError> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(format!("{}/{}", circuit, file).into_bytes())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_source_persists() {
+        let dir = std::env::temp_dir().join(format!("artifact-cache-{}", rand::random::<u64>()));
+
+        let cached = CachedArtifactSource::new(CountingSource(AtomicUsize::new(0)), &dir);
+        let bytes = cached.fetch("railgun/01x02", "wasm.br").await.unwrap();
+        assert_eq!(bytes, b"railgun/01x02/wasm.br");
+        cached.fetch("railgun/01x02", "wasm.br").await.unwrap();
+        assert_eq!(cached.inner.0.load(Ordering::Relaxed), 1);
+
+        // A fresh cache over the same directory, e.g. after a restart, reads from disk.
+        let reopened = CachedArtifactSource::new(CountingSource(AtomicUsize::new(0)), &dir);
+        let bytes = reopened.fetch("railgun/01x02", "wasm.br").await.unwrap();
+        assert_eq!(bytes, b"railgun/01x02/wasm.br");
+        assert_eq!(reopened.inner.0.load(Ordering::Relaxed), 0);
+
+        let dir_source = DirectoryArtifactSource::new(&dir);
+        assert!(dir_source.fetch("railgun/01x02", "wasm.br").await.is_ok());
+        assert!(matches!(
+            dir_source.fetch("railgun/02x02", "wasm.br").await,
+            Err(ArtifactSourceError::NotFound(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cached_source_drops_rejected_artifacts() {
+        use crate::circuit::{
+            artifact_loader::{ArtifactLoader, ArtifactLoaderError},
+            artifact_manifest::ArtifactManifest,
+        };
+
+        let dir = std::env::temp_dir().join(format!("artifact-cache-{}", rand::random::<u64>()));
+        let path = dir.join("railgun/01x02/wasm.br");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"corrupt").unwrap();
+
+        let mut manifest = ArtifactManifest::new();
+        manifest.insert("railgun/01x02", "wasm.br", b"railgun/01x02/wasm.br");
+        let cached = Arc::new(CachedArtifactSource::new(
+            CountingSource(AtomicUsize::new(0)),
+            &dir,
+        ));
+        let loader = ArtifactLoader::new(cached.clone()).with_manifest(manifest);
+
+        assert!(matches!(
+            loader.load_wasm("railgun/01x02").await,
+            Err(ArtifactLoaderError::Integrity(_))
+        ));
+        assert!(!path.exists());
+
+        // The next load fetches a fresh copy instead of the rejected one.
+        let fetched = cached.fetch("railgun/01x02", "wasm.br").await.unwrap();
+        assert_eq!(fetched, b"railgun/01x02/wasm.br");
+        assert_eq!(cached.inner.0.load(Ordering::Relaxed), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_write_atomic_concurrent() {
+        let dir = std::env::temp_dir().join(format!("artifact-write-{}", rand::random::<u64>()));
+        let path = dir.join("railgun/01x02/wasm.br");
+
+        let writes = (0..8u8).map(|i| {
+            let path = path.clone();
+            async move { write_atomic(&path, &[i; 4096]).await }
+        });
+        for result in futures::future::join_all(writes).await {
+            result.unwrap();
+        }
+
+        // One writer wins whole, and no temp files are left behind.
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), 4096);
+        assert!(bytes.iter().all(|&b| b == bytes[0]));
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
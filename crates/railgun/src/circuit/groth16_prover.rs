@@ -1,108 +1,212 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 use alloy::primitives::U256;
 use ark_bn254::{Bn254, Fr};
 use ark_circom::CircomReduction;
 use ark_ff::BigInt;
-use ark_groth16::{Groth16, prepare_verifying_key};
-use ark_relations::gr1cs::SynthesisError;
+use ark_groth16::{Groth16, PreparedVerifyingKey, ProvingKey, prepare_verifying_key};
+use ark_relations::gr1cs::{Matrix, SynthesisError};
 use ark_std::rand::random;
 use thiserror::Error;
+use tokio::sync::OnceCell;
 use tracing::info;
 
 use crate::circuit::{
+    artifact_loader::{ArtifactLoader, ArtifactLoaderError},
+    inputs::{poi_inputs::PoiCircuitInputs, transact_inputs::TransactCircuitInputs},
     proof::Proof,
-    remote_artifact_loader::{RemoteArtifactLoader, RemoteArtifactLoaderError},
-    witness::{CalculateWitnessError, calculate_witness},
+    prover::{Prover, ProverError},
+    witness::{CalculateWitnessError, WitnessBackend, WitnessGenerator},
 };
 
 pub struct Groth16Prover {
-    artifact_loader: RemoteArtifactLoader,
+    artifact_loader: ArtifactLoader,
+    witness_backend: Option<WitnessBackend>,
+    keys: Mutex<HashMap<String, Arc<OnceCell<Arc<CircuitKeys>>>>>,
+}
+
+/// A circuit's deserialized proving key and constraint matrices, shared between proofs.
+struct CircuitKeys {
+    pk: ProvingKey<Bn254>,
+    pvk: PreparedVerifyingKey<Bn254>,
+    /// The A and B constraint matrices, as the prover takes them.
+    matrices: [Matrix<Fr>; 2],
+    num_instance_variables: usize,
+    num_constraints: usize,
 }
 
 #[derive(Debug, Error)]
 pub enum Groth16ProverError {
     #[error("Artifact loader error: {0}")]
-    ArtifactLoaderError(#[from] RemoteArtifactLoaderError),
+    ArtifactLoaderError(#[from] ArtifactLoaderError),
     #[error("Witness calculator error: {0}")]
     WitnessCalculatorError(#[from] CalculateWitnessError),
     #[error("Synthesis Error")]
     SynthesisError(#[from] SynthesisError),
     #[error("Proof verification failed")]
     InvalidProof,
+    #[error(
+        "No witness backend configured; enable the `wasm-witness` feature or set one explicitly"
+    )]
+    MissingWitnessBackend,
+    #[cfg(all(native, parallel))]
+    #[error("Proving task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
 }
 
 impl Groth16Prover {
     pub fn new() -> Self {
-        let artifact_loader = RemoteArtifactLoader::default();
-        Groth16Prover { artifact_loader }
+        let artifact_loader = ArtifactLoader::default();
+        Groth16Prover {
+            artifact_loader,
+            witness_backend: default_witness_backend(),
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Loads circuit artifacts with `artifact_loader` instead of downloading them from the
+    /// default artifact host.
+    pub fn with_artifact_loader(mut self, artifact_loader: ArtifactLoader) -> Self {
+        self.artifact_loader = artifact_loader;
+        self
+    }
+
+    /// Generates witnesses with `witness_backend` instead of the default backend.
+    pub fn with_witness_backend(mut self, witness_backend: WitnessBackend) -> Self {
+        self.witness_backend = Some(witness_backend);
+        self
     }
 }
 
-impl Groth16Prover {
-    #[tracing::instrument(name = "prove_transact", skip_all)]
-    pub async fn prove_transact(
-        &self,
-        inputs: &crate::circuit::inputs::transact_inputs::TransactCircuitInputs,
-    ) -> Result<Proof, Groth16ProverError> {
-        let nullifiers = inputs.nullifiers.len();
-        let commitments = inputs.commitments_out.len();
-        let circuit_name = format!("railgun/{:02}x{:02}", nullifiers, commitments);
+/// Witness graphs for the published circuits are not hosted yet, so without the
+/// `wasm-witness` feature there is no backend that works out of the box.
+#[cfg(wasm_witness)]
+fn default_witness_backend() -> Option<WitnessBackend> {
+    Some(WitnessBackend::default())
+}
 
-        self.prove(&circuit_name, inputs.to_circuit_signals()).await
+#[cfg(not(wasm_witness))]
+fn default_witness_backend() -> Option<WitnessBackend> {
+    None
+}
+
+impl Default for Groth16Prover {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    #[tracing::instrument(name = "prove_poi", skip_all)]
-    pub async fn prove_poi(
-        &self,
-        inputs: &crate::circuit::inputs::poi_inputs::PoiCircuitInputs,
-    ) -> Result<Proof, Groth16ProverError> {
-        let nullifiers = inputs.nullifiers.len();
-        let commitments = inputs.commitments.len();
-        let circuit_name = format!("railgun/poi/{:02}x{:02}", nullifiers, commitments);
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl Prover for Groth16Prover {
+    #[tracing::instrument(name = "prove_transact", skip_all)]
+    async fn prove_transact(&self, inputs: &TransactCircuitInputs) -> Result<Proof, ProverError> {
+        Ok(self
+            .prove(&inputs.circuit_name(), inputs.to_circuit_signals())
+            .await?)
+    }
 
-        self.prove(&circuit_name, inputs.to_circuit_signals()).await
+    #[tracing::instrument(name = "prove_poi", skip_all)]
+    async fn prove_poi(&self, inputs: &PoiCircuitInputs) -> Result<Proof, ProverError> {
+        Ok(self
+            .prove(&inputs.circuit_name(), inputs.to_circuit_signals())
+            .await?)
     }
+}
 
+impl Groth16Prover {
     async fn prove(
         &self,
         circuit_name: &str,
         inputs: HashMap<String, Vec<U256>>,
     ) -> Result<Proof, Groth16ProverError> {
         info!("Loading artifacts");
-        let pk = self.artifact_loader.load_proving_key(circuit_name).await?;
-
-        let matrices = self.artifact_loader.load_matrices(circuit_name).await?;
-
-        info!("Calculating witness");
-        let witnesses = calculate_witness(&self.artifact_loader, circuit_name, inputs).await?;
-        let witnesses: Vec<Fr> = witnesses
-            .iter()
-            .map(|x| Fr::from(BigInt::from(*x)))
-            .collect();
-
-        info!("Creating proof");
-        let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
-            &pk,
-            random(),
-            random(),
-            &[matrices.a, matrices.b],
-            matrices.num_instance_variables,
-            matrices.num_constraints,
-            &witnesses,
-        )?;
-
-        info!("Verifying proof");
-        let public_inputs = &witnesses[1..matrices.num_instance_variables];
-        let pvk = prepare_verifying_key(&pk.vk);
-        let verified =
-            Groth16::<Bn254, CircomReduction>::verify_proof(&pvk, &proof, &public_inputs)?;
-
-        if !verified {
-            return Err(Groth16ProverError::InvalidProof);
-        }
+        let keys = self.circuit_keys(circuit_name).await?;
+        let witness_generator = self
+            .witness_backend
+            .as_ref()
+            .ok_or(Groth16ProverError::MissingWitnessBackend)?
+            .generator(&self.artifact_loader, circuit_name)
+            .await?;
+
+        // Proving is CPU-bound, so move it off the async runtime when threads
+        // are available. This lets independent proofs run concurrently.
+        #[cfg(all(native, parallel))]
+        let proof = tokio::task::spawn_blocking(move || {
+            prove_with_witness(witness_generator, &keys, inputs)
+        })
+        .await??;
+        #[cfg(not(all(native, parallel)))]
+        let proof = prove_with_witness(witness_generator, &keys, inputs)?;
 
         info!("Proof verified successfully");
-        Ok(proof.into())
+        Ok(proof)
     }
+
+    /// Returns the proving key and matrices for `circuit_name`, loading them on first use.
+    /// Concurrent callers share a single load.
+    async fn circuit_keys(
+        &self,
+        circuit_name: &str,
+    ) -> Result<Arc<CircuitKeys>, Groth16ProverError> {
+        let cell = self
+            .keys
+            .lock()
+            .unwrap()
+            .entry(circuit_name.to_string())
+            .or_default()
+            .clone();
+
+        cell.get_or_try_init(|| async {
+            let pk = self.artifact_loader.load_proving_key(circuit_name).await?;
+            let matrices = self.artifact_loader.load_matrices(circuit_name).await?;
+            Ok::<_, Groth16ProverError>(Arc::new(CircuitKeys {
+                pvk: prepare_verifying_key(&pk.vk),
+                pk,
+                matrices: [matrices.a, matrices.b],
+                num_instance_variables: matrices.num_instance_variables,
+                num_constraints: matrices.num_constraints,
+            }))
+        })
+        .await
+        .cloned()
+    }
+}
+
+fn prove_with_witness(
+    witness_generator: WitnessGenerator,
+    keys: &CircuitKeys,
+    inputs: HashMap<String, Vec<U256>>,
+) -> Result<Proof, Groth16ProverError> {
+    info!("Calculating witness");
+    let witnesses = witness_generator.calculate(inputs)?;
+    let witnesses: Vec<Fr> = witnesses
+        .iter()
+        .map(|x| Fr::from(BigInt::from(*x)))
+        .collect();
+
+    info!("Creating proof");
+    let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+        &keys.pk,
+        random(),
+        random(),
+        &keys.matrices,
+        keys.num_instance_variables,
+        keys.num_constraints,
+        &witnesses,
+    )?;
+
+    info!("Verifying proof");
+    let public_inputs = &witnesses[1..keys.num_instance_variables];
+    let verified =
+        Groth16::<Bn254, CircomReduction>::verify_proof(&keys.pvk, &proof, &public_inputs)?;
+
+    if !verified {
+        return Err(Groth16ProverError::InvalidProof);
+    }
+
+    Ok(proof.into())
 }
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use crypto::poseidon_hash;
 use ruint::aliases::U256;
 use thiserror::Error;
 use tracing::info;
@@ -231,6 +232,46 @@ impl PoiCircuitInputs {
     //     poi_in_merkle_proof_path_elements => "poiInMerkleProofPathElements"
     // );
 
+    /// Name of the circuit that proves these inputs, e.g. `railgun/poi/03x03`.
+    pub fn circuit_name(&self) -> String {
+        format!(
+            "railgun/poi/{:02}x{:02}",
+            self.nullifiers.len(),
+            self.commitments.len()
+        )
+    }
+
+    /// Public signals in circom order, as a proof of these inputs is verified against: the
+    /// `blindedCommitmentsOut` outputs, then `anyRailgunTxidMerklerootAfterTransaction`,
+    /// `railgunTxidIfHasUnshield` and `poiMerkleroots`.
+    ///
+    /// Padding outputs have a blinded commitment of zero.
+    pub fn public_signals(&self) -> Vec<U256> {
+        let start = self.utxo_batch_global_start_position_out;
+        let mut signals: Vec<U256> = self
+            .commitments
+            .iter()
+            .zip(&self.npks_out)
+            .enumerate()
+            .map(|(i, (&commitment, &npk))| {
+                if commitment == MerkleTree::zero() {
+                    U256::ZERO
+                } else {
+                    poseidon_hash(&[commitment, npk, start + U256::from(i)]).unwrap()
+                }
+            })
+            .collect();
+
+        signals.push(self.railgun_txid_merkleroot_after_transaction.into());
+        signals.push(self.railgun_txid_if_has_unshield.into());
+        signals.extend(
+            self.poi_merkleroots_padded
+                .iter()
+                .map(|&root| U256::from(root)),
+        );
+        signals
+    }
+
     pub fn to_circuit_signals(&self) -> HashMap<String, Vec<U256>> {
         let mut m = HashMap::with_capacity(20);
         m.insert(
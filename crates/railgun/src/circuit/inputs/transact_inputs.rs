@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, ops::RangeInclusive, sync::Arc};
 
 use crypto::poseidon_hash;
 use ruint::aliases::U256;
@@ -12,6 +12,10 @@ use crate::{
     note::{Note, utxo::UtxoNote},
 };
 
+/// Nullifier and commitment counts covered by the published transact circuits, `railgun/01x01`
+/// through `railgun/05x05`.
+pub const TRANSACT_CIRCUIT_SIZES: RangeInclusive<usize> = 1..=5;
+
 #[derive(Debug, Clone)]
 pub struct TransactCircuitInputs {
     // Public Inputs
@@ -121,6 +125,23 @@ impl TransactCircuitInputs {
         })
     }
 
+    /// Name of the circuit that proves these inputs, e.g. `railgun/02x03`.
+    pub fn circuit_name(&self) -> String {
+        format!(
+            "railgun/{:02}x{:02}",
+            self.nullifiers.len(),
+            self.commitments_out.len()
+        )
+    }
+
+    /// Public signals in circom order, as a proof of these inputs is verified against.
+    pub fn public_signals(&self) -> Vec<U256> {
+        let mut signals = vec![self.merkleroot.into(), self.bound_params_hash];
+        signals.extend_from_slice(&self.nullifiers);
+        signals.extend_from_slice(&self.commitments_out);
+        signals
+    }
+
     pub fn to_circuit_signals(&self) -> HashMap<String, Vec<U256>> {
         let mut m = HashMap::with_capacity(14);
         m.insert("merkleRoot".into(), vec![self.merkleroot.into()]);
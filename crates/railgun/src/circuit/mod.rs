@@ -1,5 +1,11 @@
+pub mod artifact_loader;
+pub mod artifact_manifest;
+pub mod artifact_source;
 pub mod groth16_prover;
 pub mod inputs;
 pub mod proof;
-mod remote_artifact_loader;
-mod witness;
+pub mod prover;
+pub mod remote_prover;
+pub mod verifying_keys;
+pub mod witness;
+pub mod witness_graph;
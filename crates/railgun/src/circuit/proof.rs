@@ -56,6 +56,40 @@ impl From<ark_groth16::Proof<ark_bn254::Bn254>> for Proof {
     }
 }
 
+impl TryFrom<&Proof> for ark_groth16::Proof<ark_bn254::Bn254> {
+    type Error = String;
+
+    /// Converts back into curve points, rejecting coordinates outside the field and points that
+    /// are not on the curve or not in the prime-order subgroup.
+    fn try_from(proof: &Proof) -> Result<Self, Self::Error> {
+        use ark_bn254::{Fq, Fq2};
+        use ark_ff::{BigInt, PrimeField};
+
+        let fq = |v: U256| {
+            Fq::from_bigint(BigInt::from(v)).ok_or_else(|| format!("Coordinate {v} out of range"))
+        };
+
+        let a = ark_bn254::G1Affine::new_unchecked(fq(proof.a.x)?, fq(proof.a.y)?);
+        let b = ark_bn254::G2Affine::new_unchecked(
+            Fq2::new(fq(proof.b.x[0])?, fq(proof.b.x[1])?),
+            Fq2::new(fq(proof.b.y[0])?, fq(proof.b.y[1])?),
+        );
+        let c = ark_bn254::G1Affine::new_unchecked(fq(proof.c.x)?, fq(proof.c.y)?);
+
+        let g1_valid = |p: &ark_bn254::G1Affine| {
+            p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve()
+        };
+        if !g1_valid(&a)
+            || !g1_valid(&c)
+            || !(b.is_on_curve() && b.is_in_correct_subgroup_assuming_on_curve())
+        {
+            return Err("Proof point is not on the curve".to_string());
+        }
+
+        Ok(ark_groth16::Proof { a, b, c })
+    }
+}
+
 impl From<G1Affine> for [String; 2] {
     fn from(point: G1Affine) -> Self {
         [point.x.to_string(), point.y.to_string()]
@@ -125,6 +159,12 @@ mod tests {
         assert_eq!(proof, deserialized);
     }
 
+    #[test]
+    fn test_rejects_invalid_points() {
+        let converted: Result<ark_groth16::Proof<ark_bn254::Bn254>, _> = (&test_proof()).try_into();
+        assert!(converted.is_err());
+    }
+
     fn test_proof() -> Proof {
         Proof {
             a: G1Affine {
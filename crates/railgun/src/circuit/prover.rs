@@ -0,0 +1,18 @@
+use crate::circuit::{
+    inputs::{poi_inputs::PoiCircuitInputs, transact_inputs::TransactCircuitInputs},
+    proof::Proof,
+};
+
+pub type ProverError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Generates Groth16 proofs for the transact and POI circuits.
+///
+/// [`Groth16Prover`](crate::circuit::groth16_prover::Groth16Prover) proves locally with
+/// arkworks. [`RemoteProver`](crate::circuit::remote_prover::RemoteProver) offloads proving to
+/// a proving service.
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+pub trait Prover: common::MaybeSend {
+    async fn prove_transact(&self, inputs: &TransactCircuitInputs) -> Result<Proof, ProverError>;
+    async fn prove_poi(&self, inputs: &PoiCircuitInputs) -> Result<Proof, ProverError>;
+}
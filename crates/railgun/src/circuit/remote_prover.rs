@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use ruint::aliases::U256;
+use serde::Serialize;
+use thiserror::Error;
+use tracing::info;
+
+use crate::circuit::{
+    artifact_loader::{ArtifactLoader, ArtifactLoaderError},
+    inputs::{poi_inputs::PoiCircuitInputs, transact_inputs::TransactCircuitInputs},
+    proof::Proof,
+    prover::{Prover, ProverError},
+    verifying_keys::VerifyingKeys,
+};
+
+/// Offloads proving to a remote proving service.
+///
+/// Each proof is requested with a `POST` to the service's URL, with a JSON body holding the
+/// circuit name and its input signals as decimal strings:
+///
+/// ```json
+/// { "circuit": "railgun/01x02", "inputs": { "merkleRoot": ["123..."], ... } }
+/// ```
+///
+/// The service responds with the proof in SnarkJS format (`pi_a`, `pi_b`, `pi_c`).
+///
+/// The service sees every private input, including the spending signature and note values, so
+/// it must be trusted with them. Returned proofs are verified against the circuit's verifying
+/// key before they're used.
+#[derive(Clone)]
+pub struct RemoteProver {
+    client: reqwest::Client,
+    url: String,
+    verifying_keys: VerifyingKeys,
+}
+
+#[derive(Debug, Error)]
+pub enum RemoteProverError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Proving service returned {status}: {body}")]
+    Service {
+        status: reqwest::StatusCode,
+        body: String,
+    },
+    #[error("Artifact loader error: {0}")]
+    ArtifactLoader(#[from] ArtifactLoaderError),
+    #[error("Proving service returned an invalid {0} proof")]
+    InvalidProof(String),
+}
+
+#[derive(Serialize)]
+struct ProveRequest<'a> {
+    circuit: &'a str,
+    inputs: HashMap<String, Vec<String>>,
+}
+
+impl RemoteProver {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+            verifying_keys: VerifyingKeys::new(ArtifactLoader::default()),
+        }
+    }
+
+    /// Loads the verifying keys for returned proofs with `artifact_loader` instead of
+    /// downloading them from the default artifact host.
+    pub fn with_artifact_loader(mut self, artifact_loader: ArtifactLoader) -> Self {
+        self.verifying_keys = VerifyingKeys::new(artifact_loader);
+        self
+    }
+
+    async fn prove(
+        &self,
+        circuit: &str,
+        signals: HashMap<String, Vec<U256>>,
+        public_signals: &[U256],
+    ) -> Result<Proof, RemoteProverError> {
+        info!("Requesting {} proof from {}", circuit, self.url);
+        let inputs = signals
+            .into_iter()
+            .map(|(name, values)| (name, values.iter().map(U256::to_string).collect()))
+            .collect();
+
+        let resp = self
+            .client
+            .post(&self.url)
+            .json(&ProveRequest { circuit, inputs })
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(RemoteProverError::Service { status, body });
+        }
+
+        let proof: Proof = resp.json().await?;
+        if !self
+            .verifying_keys
+            .verify(circuit, &proof, public_signals)
+            .await?
+        {
+            return Err(RemoteProverError::InvalidProof(circuit.to_string()));
+        }
+        Ok(proof)
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl Prover for RemoteProver {
+    #[tracing::instrument(name = "remote_prove_transact", skip_all)]
+    async fn prove_transact(&self, inputs: &TransactCircuitInputs) -> Result<Proof, ProverError> {
+        Ok(self
+            .prove(
+                &inputs.circuit_name(),
+                inputs.to_circuit_signals(),
+                &inputs.public_signals(),
+            )
+            .await?)
+    }
+
+    #[tracing::instrument(name = "remote_prove_poi", skip_all)]
+    async fn prove_poi(&self, inputs: &PoiCircuitInputs) -> Result<Proof, ProverError> {
+        Ok(self
+            .prove(
+                &inputs.circuit_name(),
+                inputs.to_circuit_signals(),
+                &inputs.public_signals(),
+            )
+            .await?)
+    }
+}
+
+#[cfg(all(test, native))]
+mod tests {
+    use alloy::primitives::address;
+
+    use super::*;
+    use crate::{
+        account::signer::PrivateKeySigner,
+        caip::AssetId,
+        circuit::{
+            proof::{G1Affine, G2Affine},
+            verifying_keys::simulated_proof,
+        },
+        crypto::keys::{ByteKey, SpendingKey, ViewingKey},
+        merkle_tree::UtxoMerkleTree,
+        note::{Note, utxo::test_note},
+        test_server::serve,
+    };
+
+    fn test_inputs() -> TransactCircuitInputs {
+        let signer = PrivateKeySigner::new_evm(
+            SpendingKey::from_bytes([1u8; 32]),
+            ViewingKey::from_bytes([2u8; 32]),
+            1,
+        );
+        let note = test_note();
+        let mut tree = UtxoMerkleTree::new(1);
+        tree.insert_leaves(&[note.hash()], 0);
+
+        let out: Vec<Box<dyn Note>> = vec![Box::new(test_note())];
+        TransactCircuitInputs::from_inputs(
+            &tree,
+            U256::from(1),
+            signer,
+            AssetId::Erc20(address!("0x1234567890123456789012345678901234567890")),
+            &[note],
+            &out,
+        )
+        .unwrap()
+    }
+
+    fn test_proof() -> Proof {
+        Proof {
+            a: G1Affine {
+                x: U256::from(1),
+                y: U256::from(2),
+            },
+            b: G2Affine {
+                x: [U256::from(3), U256::from(4)],
+                y: [U256::from(5), U256::from(6)],
+            },
+            c: G1Affine {
+                x: U256::from(7),
+                y: U256::from(8),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remote_prove_transact() {
+        let inputs = test_inputs();
+        let (loader, expected) = simulated_proof(&inputs.circuit_name(), &inputs.public_signals());
+        let (url, requests) =
+            serve(vec![("200 OK", serde_json::to_string(&expected).unwrap())]).await;
+
+        let proof = RemoteProver::new(url)
+            .with_artifact_loader(loader)
+            .prove_transact(&inputs)
+            .await
+            .unwrap();
+        assert_eq!(proof, expected);
+
+        let request: serde_json::Value =
+            serde_json::from_str(&requests.lock().unwrap()[0].body).unwrap();
+        assert_eq!(request["circuit"], "railgun/01x01");
+        let merkleroot: U256 = inputs.merkleroot.into();
+        assert_eq!(request["inputs"]["merkleRoot"][0], merkleroot.to_string());
+        assert_eq!(request["inputs"]["boundParamsHash"][0], "1");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_proof() {
+        let inputs = test_inputs();
+        let mut other_signals = inputs.public_signals();
+        other_signals[1] += U256::from(1);
+        let (loader, proof) = simulated_proof(&inputs.circuit_name(), &other_signals);
+
+        for body in [proof, test_proof()] {
+            let (url, _) = serve(vec![("200 OK", serde_json::to_string(&body).unwrap())]).await;
+            let err = RemoteProver::new(url)
+                .with_artifact_loader(loader.clone())
+                .prove(
+                    &inputs.circuit_name(),
+                    inputs.to_circuit_signals(),
+                    &inputs.public_signals(),
+                )
+                .await
+                .unwrap_err();
+            assert!(matches!(err, RemoteProverError::InvalidProof(_)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remote_prover_error() {
+        let (url, _) = serve(vec![("500 Internal Server Error", "boom".to_string())]).await;
+
+        let err = RemoteProver::new(url)
+            .prove(
+                &test_inputs().circuit_name(),
+                test_inputs().to_circuit_signals(),
+                &test_inputs().public_signals(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RemoteProverError::Service { body, .. } if body == "boom"));
+    }
+}
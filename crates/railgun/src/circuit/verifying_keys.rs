@@ -0,0 +1,148 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use ark_bn254::{Bn254, Fr};
+use ark_circom::CircomReduction;
+use ark_ff::{BigInt, PrimeField};
+use ark_groth16::{Groth16, PreparedVerifyingKey, prepare_verifying_key};
+use ruint::aliases::U256;
+use tracing::{info, warn};
+
+use crate::circuit::{
+    artifact_loader::{ArtifactLoader, ArtifactLoaderError},
+    proof::Proof,
+};
+
+/// Loads circuits' verifying keys from their proving keys, caching them by circuit name.
+#[derive(Clone)]
+pub(crate) struct VerifyingKeys {
+    artifact_loader: ArtifactLoader,
+    keys: Arc<Mutex<HashMap<String, Arc<PreparedVerifyingKey<Bn254>>>>>,
+}
+
+impl VerifyingKeys {
+    pub fn new(artifact_loader: ArtifactLoader) -> Self {
+        Self {
+            artifact_loader,
+            keys: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns whether `proof` proves `public_inputs` for `circuit_name`.
+    ///
+    /// Malformed proofs and inputs outside the scalar field don't verify.
+    pub async fn verify(
+        &self,
+        circuit_name: &str,
+        proof: &Proof,
+        public_inputs: &[U256],
+    ) -> Result<bool, ArtifactLoaderError> {
+        let proof = match ark_groth16::Proof::try_from(proof) {
+            Ok(proof) => proof,
+            Err(e) => {
+                warn!("Malformed {} proof: {}", circuit_name, e);
+                return Ok(false);
+            }
+        };
+        let Some(inputs) = public_inputs
+            .iter()
+            .map(|&s| Fr::from_bigint(BigInt::from(s)))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Ok(false);
+        };
+
+        let pvk = self.get(circuit_name).await?;
+        Ok(Groth16::<Bn254, CircomReduction>::verify_proof(&pvk, &proof, &inputs).unwrap_or(false))
+    }
+
+    async fn get(
+        &self,
+        circuit_name: &str,
+    ) -> Result<Arc<PreparedVerifyingKey<Bn254>>, ArtifactLoaderError> {
+        if let Some(pvk) = self.keys.lock().unwrap().get(circuit_name) {
+            return Ok(pvk.clone());
+        }
+
+        info!("Loading verifying key for {}", circuit_name);
+        let pk = self.artifact_loader.load_proving_key(circuit_name).await?;
+        let pvk = Arc::new(prepare_verifying_key(&pk.vk));
+        self.keys
+            .lock()
+            .unwrap()
+            .insert(circuit_name.to_string(), pvk.clone());
+        Ok(pvk)
+    }
+}
+
+/// Builds a verifying key for `public_inputs.len()` inputs and a proof of `public_inputs` that
+/// verifies against it, made with the key's trapdoor instead of a circuit. Returns a loader
+/// serving the key as `circuit_name`'s proving key, along with the proof.
+#[cfg(all(test, native))]
+pub(crate) fn simulated_proof(
+    circuit_name: &str,
+    public_inputs: &[U256],
+) -> (ArtifactLoader, Proof) {
+    use ark_bn254::{G1Affine, G2Affine};
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_groth16::{ProvingKey, VerifyingKey};
+    use ark_serialize::CanonicalSerialize;
+
+    use crate::circuit::artifact_source::EmbeddedArtifactSource;
+
+    //? With A = g1, B = g2 and gamma = delta = g2, the pairing check reduces to
+    //? 1 = alpha * beta + sum(k_i * x_i) + c over the scalars, which is solved for c.
+    let g1 = G1Affine::generator();
+    let g2 = G2Affine::generator();
+    let (alpha, beta) = (Fr::from(2u64), Fr::from(3u64));
+    let ks: Vec<Fr> = (0..=public_inputs.len() as u64)
+        .map(|i| Fr::from(i + 5))
+        .collect();
+
+    let mut c = Fr::from(1u64) - alpha * beta - ks[0];
+    for (k, x) in ks[1..].iter().zip(public_inputs) {
+        c -= *k * Fr::from_bigint(BigInt::from(*x)).expect("public input in field");
+    }
+
+    let vk = VerifyingKey::<Bn254> {
+        alpha_g1: (g1 * alpha).into_affine(),
+        beta_g2: (g2 * beta).into_affine(),
+        gamma_g2: g2,
+        delta_g2: g2,
+        gamma_abc_g1: ks.iter().map(|k| (g1 * k).into_affine()).collect(),
+    };
+    let pk = ProvingKey::<Bn254> {
+        vk,
+        beta_g1: (g1 * beta).into_affine(),
+        delta_g1: g1,
+        a_query: vec![],
+        b_g1_query: vec![],
+        b_g2_query: vec![],
+        h_query: vec![],
+        l_query: vec![],
+    };
+
+    let mut bytes = Vec::new();
+    pk.serialize_uncompressed(&mut bytes).unwrap();
+    let mut compressed = Vec::new();
+    brotli::BrotliCompress(
+        &mut &bytes[..],
+        &mut compressed,
+        &brotli::enc::BrotliEncoderParams::default(),
+    )
+    .unwrap();
+    let source = EmbeddedArtifactSource::new().with_file(
+        circuit_name,
+        "proving_key.bin.br",
+        compressed.leak(),
+    );
+
+    let proof = ark_groth16::Proof::<Bn254> {
+        a: g1,
+        b: g2,
+        c: (g1 * c).into_affine(),
+    };
+    (ArtifactLoader::new(Arc::new(source)), proof.into())
+}
@@ -1,36 +1,287 @@
-use std::collections::HashMap;
+#[cfg(all(native, wasm_witness))]
+use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
+#[cfg(wasm_witness)]
 use num_bigint::BigInt;
 use ruint::aliases::U256;
 use thiserror::Error;
+use tokio::sync::OnceCell;
+#[cfg(wasm_witness)]
 use tracing::info;
-use wasmer::{Module, Store};
+#[cfg(all(native, wasm_witness))]
+use tracing::warn;
+#[cfg(wasm_witness)]
+use wasmer::{Engine, Module, Store};
 
-use crate::circuit::remote_artifact_loader::RemoteArtifactLoader;
+#[cfg(all(native, wasm_witness))]
+use crate::circuit::artifact_source::write_atomic;
+use crate::circuit::{
+    artifact_loader::ArtifactLoader,
+    witness_graph::{WitnessGraph, WitnessGraphError},
+};
 
 #[derive(Debug, Error)]
 pub enum CalculateWitnessError {
     #[error("Artifact loader error: {0}")]
     ArtifactLoaderError(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[cfg(wasm_witness)]
     #[error("Compiler error: {0}")]
     CompilerError(#[from] wasmer::CompileError),
+    #[cfg(wasm_witness)]
     #[error("Circom error: {0}")]
     CircomError(String),
+    #[error("Witness graph error: {0}")]
+    GraphError(#[from] WitnessGraphError),
 }
 
-pub async fn calculate_witness(
-    artifact_loader: &RemoteArtifactLoader,
-    circuit_name: &str,
-    inputs: HashMap<String, Vec<U256>>,
-) -> Result<Vec<U256>, CalculateWitnessError> {
-    let wasm_bytes = artifact_loader
-        .load_wasm(circuit_name)
+/// How witnesses are generated for a circuit.
+pub enum WitnessBackend {
+    /// Runs the circuit's circom-generated WASM in wasmer.
+    #[cfg(wasm_witness)]
+    Wasm(WitnessModules),
+    /// Evaluates the circuit's witness graph in pure Rust. Requires the
+    /// `graph.bin.br` artifact for each circuit.
+    ///
+    /// The default artifact host does not publish witness graphs yet, so this
+    /// backend is never picked implicitly.
+    Graph(WitnessGraphs),
+}
+
+impl WitnessBackend {
+    /// Loads whatever this backend needs to generate witnesses for `circuit_name`.
+    pub async fn generator(
+        &self,
+        artifact_loader: &ArtifactLoader,
+        circuit_name: &str,
+    ) -> Result<WitnessGenerator, CalculateWitnessError> {
+        match self {
+            #[cfg(wasm_witness)]
+            WitnessBackend::Wasm(modules) => {
+                let module = modules.module(artifact_loader, circuit_name).await?;
+                Ok(WitnessGenerator::Wasm(modules.store(), module))
+            }
+            WitnessBackend::Graph(graphs) => {
+                let graph = graphs.graph(artifact_loader, circuit_name).await?;
+                Ok(WitnessGenerator::Graph(graph))
+            }
+        }
+    }
+}
+
+#[cfg(wasm_witness)]
+impl Default for WitnessBackend {
+    fn default() -> Self {
+        WitnessBackend::Wasm(WitnessModules::new())
+    }
+}
+
+/// A loaded witness generator for a single circuit.
+pub enum WitnessGenerator {
+    #[cfg(wasm_witness)]
+    Wasm(Store, Module),
+    Graph(Arc<WitnessGraph>),
+}
+
+impl WitnessGenerator {
+    /// Calculates the witness for `inputs`.
+    ///
+    /// This is CPU-bound and does not touch the artifact loader, so it can run on
+    /// a blocking thread.
+    pub fn calculate(
+        self,
+        inputs: HashMap<String, Vec<U256>>,
+    ) -> Result<Vec<U256>, CalculateWitnessError> {
+        match self {
+            #[cfg(wasm_witness)]
+            WitnessGenerator::Wasm(store, module) => calculate_witness(store, module, inputs),
+            WitnessGenerator::Graph(graph) => Ok(graph.evaluate(&inputs)?),
+        }
+    }
+}
+
+/// Deserialized witness graphs, keyed by circuit name.
+#[derive(Default)]
+pub struct WitnessGraphs {
+    graphs: Mutex<HashMap<String, Arc<OnceCell<Arc<WitnessGraph>>>>>,
+}
+
+impl WitnessGraphs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the witness graph for `circuit_name`, loading it on first use. Concurrent callers
+    /// share a single load.
+    pub async fn graph(
+        &self,
+        artifact_loader: &ArtifactLoader,
+        circuit_name: &str,
+    ) -> Result<Arc<WitnessGraph>, CalculateWitnessError> {
+        let cell = self
+            .graphs
+            .lock()
+            .unwrap()
+            .entry(circuit_name.to_string())
+            .or_default()
+            .clone();
+
+        cell.get_or_try_init(|| async {
+            let graph = artifact_loader
+                .load_witness_graph(circuit_name)
+                .await
+                .map_err(|e| CalculateWitnessError::ArtifactLoaderError(Box::new(e)))?;
+            Ok::<_, CalculateWitnessError>(Arc::new(graph))
+        })
         .await
-        .map_err(|e| CalculateWitnessError::ArtifactLoaderError(Box::new(e)))?;
+        .cloned()
+    }
+}
+
+/// Compiled witness generator modules, keyed by circuit name.
+///
+/// Compiling a circuit's WASM dominates witness generation, so each circuit is
+/// compiled once and the module is shared by every subsequent witness. On native
+/// builds the compiled modules can additionally be persisted to a directory so
+/// later processes skip compilation entirely.
+#[cfg(wasm_witness)]
+pub struct WitnessModules {
+    engine: Engine,
+    modules: Mutex<HashMap<String, Arc<OnceCell<Module>>>>,
+    #[cfg(native)]
+    cache_dir: Option<PathBuf>,
+}
+
+#[cfg(wasm_witness)]
+impl WitnessModules {
+    pub fn new() -> Self {
+        WitnessModules {
+            engine: Engine::default(),
+            modules: Mutex::new(HashMap::new()),
+            #[cfg(native)]
+            cache_dir: None,
+        }
+    }
+
+    /// Persists precompiled modules under `dir`, keyed by the hash of their
+    /// source WASM, the wasmer version and the compilation target.
+    ///
+    /// Precompiled modules are loaded as native code without validation, so
+    /// `dir` must only be writable by trusted users.
+    #[cfg(native)]
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Returns a fresh store on this cache's engine, which can instantiate any
+    /// module returned by [`WitnessModules::module`].
+    pub fn store(&self) -> Store {
+        Store::new(self.engine.clone())
+    }
+
+    /// Returns the compiled witness generator for `circuit_name`, compiling it
+    /// on first use. Concurrent callers share a single compilation.
+    pub async fn module(
+        &self,
+        artifact_loader: &ArtifactLoader,
+        circuit_name: &str,
+    ) -> Result<Module, CalculateWitnessError> {
+        let cell = self
+            .modules
+            .lock()
+            .unwrap()
+            .entry(circuit_name.to_string())
+            .or_default()
+            .clone();
 
-    info!("Loading WASM Module");
-    let mut store = Store::default();
-    let module = Module::new(&store, &wasm_bytes)?;
+        cell.get_or_try_init(|| async {
+            let wasm_bytes = artifact_loader
+                .load_wasm(circuit_name)
+                .await
+                .map_err(|e| CalculateWitnessError::ArtifactLoaderError(Box::new(e)))?;
+
+            info!("Compiling WASM Module for circuit {}", circuit_name);
+            self.compile(&wasm_bytes).await
+        })
+        .await
+        .cloned()
+    }
+
+    #[cfg(native)]
+    async fn compile(&self, wasm_bytes: &[u8]) -> Result<Module, CalculateWitnessError> {
+        match &self.cache_dir {
+            Some(dir) => self.compile_cached(dir, wasm_bytes).await,
+            None => Ok(Module::new(&self.engine, wasm_bytes)?),
+        }
+    }
+
+    #[cfg(not(native))]
+    async fn compile(&self, wasm_bytes: &[u8]) -> Result<Module, CalculateWitnessError> {
+        Ok(Module::new(&self.engine, wasm_bytes)?)
+    }
+
+    /// Loads a precompiled module from `dir` if one exists for `wasm_bytes`,
+    /// otherwise compiles it and stores the result. Failing to read or write the
+    /// cache only costs a recompile, so those errors are logged and ignored.
+    #[cfg(native)]
+    async fn compile_cached(
+        &self,
+        dir: &Path,
+        wasm_bytes: &[u8],
+    ) -> Result<Module, CalculateWitnessError> {
+        use sha2::{Digest, Sha256};
+
+        //? Deserializing runs the module as native code without validation, so a module
+        //? compiled by another wasmer version or for another target must never be picked up.
+        let key = Sha256::new()
+            .chain_update(wasmer::VERSION)
+            .chain_update([0u8])
+            .chain_update(env!("RAILGUN_TARGET"))
+            .chain_update([0u8])
+            .chain_update(wasm_bytes)
+            .finalize();
+        let path = dir.join(format!("{}.module", hex::encode(key)));
+        if let Ok(bytes) = tokio::fs::read(&path).await {
+            // SAFETY: files in the cache directory are only ever written by
+            // `Module::serialize` below, and the directory is trusted per
+            // `with_cache_dir`.
+            match unsafe { Module::deserialize(&self.engine, bytes) } {
+                Ok(module) => return Ok(module),
+                Err(e) => warn!("Discarding precompiled module {}: {}", path.display(), e),
+            }
+        }
+
+        let module = Module::new(&self.engine, wasm_bytes)?;
+        match module.serialize() {
+            Ok(bytes) => {
+                if let Err(e) = write_atomic(&path, &bytes).await {
+                    warn!("Failed to persist module {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => warn!("Failed to serialize module: {}", e),
+        }
+        Ok(module)
+    }
+}
+
+#[cfg(wasm_witness)]
+impl Default for WitnessModules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Calculates the witness for `inputs` using a module from [`WitnessModules`].
+#[cfg(wasm_witness)]
+pub fn calculate_witness(
+    mut store: Store,
+    module: Module,
+    inputs: HashMap<String, Vec<U256>>,
+) -> Result<Vec<U256>, CalculateWitnessError> {
     let mut calculator = ark_circom::WitnessCalculator::from_module(&mut store, module)
         .map_err(|e| CalculateWitnessError::CircomError(e.to_string()))?;
 
@@ -41,7 +292,6 @@ pub async fn calculate_witness(
         .collect();
 
     // Calculate witness
-    info!("Calculating witness for circuit {}", circuit_name);
     let witness = calculator
         .calculate_witness(&mut store, inputs, true)
         .map_err(|e| CalculateWitnessError::CircomError(e.to_string()))?;
@@ -49,3 +299,45 @@ pub async fn calculate_witness(
 
     Ok(witness)
 }
+
+#[cfg(all(test, native, wasm_witness))]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::circuit::artifact_source::EmbeddedArtifactSource;
+
+    /// The smallest valid WASM module: the magic number and version, no sections.
+    const EMPTY_WASM: &[u8] = b"\0asm\x01\0\0\0";
+
+    fn loader() -> ArtifactLoader {
+        let mut compressed = Vec::new();
+        brotli::BrotliCompress(
+            &mut &EMPTY_WASM[..],
+            &mut compressed,
+            &brotli::enc::BrotliEncoderParams::default(),
+        )
+        .unwrap();
+        let source =
+            EmbeddedArtifactSource::new().with_file("railgun/01x02", "wasm.br", compressed.leak());
+        ArtifactLoader::new(Arc::new(source))
+    }
+
+    #[tokio::test]
+    async fn test_modules_persist() {
+        let dir = std::env::temp_dir().join(format!("witness-modules-{}", rand::random::<u64>()));
+        let loader = loader();
+
+        let modules = WitnessModules::new().with_cache_dir(&dir);
+        modules.module(&loader, "railgun/01x02").await.unwrap();
+        modules.module(&loader, "railgun/01x02").await.unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        // A fresh cache over the same directory loads the precompiled module.
+        let reopened = WitnessModules::new().with_cache_dir(&dir);
+        let module = reopened.module(&loader, "railgun/01x02").await.unwrap();
+        wasmer::Instance::new(&mut reopened.store(), &module, &wasmer::imports! {}).unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
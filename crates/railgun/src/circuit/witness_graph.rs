@@ -0,0 +1,729 @@
+//! Native witness generation from a circuit's witness graph.
+//!
+//! A witness graph is a circuit's witness computation flattened into a list of
+//! field operations over its inputs. Graphs are compiled from the circuit
+//! sources by circom-witnesscalc's `build-circuit`, converted and packaged
+//! alongside the other artifacts by `bin/convert_artifacts.rs`, and evaluated
+//! here in pure Rust without a WASM runtime.
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ruint::aliases::U256;
+use thiserror::Error;
+
+use crate::crypto::railgun_zero::SNARK_PRIME;
+
+/// Bit width that circom's bitwise operators truncate results to.
+const FIELD_BITS: usize = 254;
+
+/// Prefix of graphs in circom-witnesscalc's format.
+const WITNESSCALC_MAGIC: &[u8] = b"wtns.graph.001";
+
+/// circom-witnesscalc's `DuoOp`s, by protobuf enum value.
+const WITNESSCALC_DUO_OPS: [Op; 20] = [
+    Op::Mul,
+    Op::Div,
+    Op::Add,
+    Op::Sub,
+    Op::Pow,
+    Op::IntDiv,
+    Op::Mod,
+    Op::Eq,
+    Op::Neq,
+    Op::Lt,
+    Op::Gt,
+    Op::Leq,
+    Op::Geq,
+    Op::LAnd,
+    Op::LOr,
+    Op::Shl,
+    Op::Shr,
+    Op::BitOr,
+    Op::BitAnd,
+    Op::BitXor,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct WitnessGraph {
+    /// Field constants referenced by [`Op::Constant`] nodes, as little-endian limbs.
+    constants: Vec<[u64; 4]>,
+    /// Nodes in evaluation order. Operands always refer to earlier nodes.
+    nodes: Vec<Node>,
+    /// Named inputs and their position in the flattened input vector. The
+    /// flattened input at offset 0 is always the constant one.
+    inputs: Vec<InputSignal>,
+    /// The node holding each witness signal, in witness order.
+    signals: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+struct Node {
+    op: u8,
+    args: [u32; 3],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+struct InputSignal {
+    name: String,
+    offset: u32,
+    len: u32,
+}
+
+/// A witness graph operation, following circom's semantics for the operator of
+/// the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Op {
+    /// The flattened input at `args[0]`.
+    Input,
+    /// The constant at `args[0]`.
+    Constant,
+    Add,
+    Sub,
+    Mul,
+    /// Field division.
+    Div,
+    /// Integer division of the canonical representatives.
+    IntDiv,
+    Mod,
+    Pow,
+    Eq,
+    Neq,
+    Lt,
+    Gt,
+    Leq,
+    Geq,
+    LAnd,
+    LOr,
+    Shl,
+    Shr,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Neg,
+    LNot,
+    BitNot,
+    /// `args[0] ? args[1] : args[2]`.
+    Ternary,
+}
+
+const OPS: [Op; 26] = [
+    Op::Input,
+    Op::Constant,
+    Op::Add,
+    Op::Sub,
+    Op::Mul,
+    Op::Div,
+    Op::IntDiv,
+    Op::Mod,
+    Op::Pow,
+    Op::Eq,
+    Op::Neq,
+    Op::Lt,
+    Op::Gt,
+    Op::Leq,
+    Op::Geq,
+    Op::LAnd,
+    Op::LOr,
+    Op::Shl,
+    Op::Shr,
+    Op::BitAnd,
+    Op::BitOr,
+    Op::BitXor,
+    Op::Neg,
+    Op::LNot,
+    Op::BitNot,
+    Op::Ternary,
+];
+
+#[derive(Debug, Error)]
+pub enum WitnessGraphError {
+    #[error("Missing input: {0}")]
+    MissingInput(String),
+    #[error("Input {name} has {actual} values, expected {expected}")]
+    InputLength {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("Malformed node {0}")]
+    MalformedNode(usize),
+    #[error("Division by zero at node {0}")]
+    DivisionByZero(usize),
+    #[error("Malformed witnesscalc graph: {0}")]
+    Witnesscalc(&'static str),
+}
+
+impl WitnessGraph {
+    /// Creates an empty graph whose node 0 is the constant one input.
+    pub fn new() -> Self {
+        let mut graph = WitnessGraph {
+            constants: Vec::new(),
+            nodes: Vec::new(),
+            inputs: Vec::new(),
+            signals: Vec::new(),
+        };
+        graph.push(Op::Input, [0, 0, 0]);
+        graph
+    }
+
+    /// Declares a named input of `len` values and returns the node for each.
+    pub fn input(&mut self, name: &str, len: u32) -> Vec<u32> {
+        let offset = 1 + self.inputs.iter().map(|i| i.len).sum::<u32>();
+        self.inputs.push(InputSignal {
+            name: name.to_string(),
+            offset,
+            len,
+        });
+        (offset..offset + len)
+            .map(|i| self.push(Op::Input, [i, 0, 0]))
+            .collect()
+    }
+
+    /// Adds a constant node.
+    pub fn constant(&mut self, value: U256) -> u32 {
+        self.constants
+            .push(value.reduce_mod(SNARK_PRIME).into_limbs());
+        self.push(Op::Constant, [self.constants.len() as u32 - 1, 0, 0])
+    }
+
+    /// Adds an operation node and returns its index. Unused operands are ignored.
+    pub fn push(&mut self, op: Op, args: [u32; 3]) -> u32 {
+        self.nodes.push(Node { op: op as u8, args });
+        self.nodes.len() as u32 - 1
+    }
+
+    /// Appends `node` to the witness.
+    pub fn signal(&mut self, node: u32) {
+        self.signals.push(node);
+    }
+
+    /// Returns whether `bytes` is a graph in circom-witnesscalc's format, as
+    /// opposed to this type's serialization.
+    pub fn is_witnesscalc(bytes: &[u8]) -> bool {
+        bytes.starts_with(WITNESSCALC_MAGIC)
+    }
+
+    /// Converts a graph written by circom-witnesscalc's `build-circuit`.
+    ///
+    /// The format is the `wtns.graph.001` magic, a little-endian u64 node
+    /// count, that many length-delimited protobuf `Node`s, and a
+    /// length-delimited `GraphMetadata` naming the witness signals' nodes and
+    /// the inputs' offsets.
+    pub fn from_witnesscalc(bytes: &[u8]) -> Result<Self, WitnessGraphError> {
+        let rest = bytes
+            .strip_prefix(WITNESSCALC_MAGIC)
+            .ok_or(WitnessGraphError::Witnesscalc("missing magic"))?;
+        let (count, rest) = rest
+            .split_first_chunk::<8>()
+            .ok_or(WitnessGraphError::Witnesscalc("truncated node count"))?;
+        let count = u64::from_le_bytes(*count) as usize;
+        let mut reader = Protobuf(rest);
+
+        let mut graph = WitnessGraph {
+            constants: Vec::new(),
+            nodes: Vec::new(),
+            inputs: Vec::new(),
+            signals: Vec::new(),
+        };
+        //? Maps witnesscalc node indices to ours, since identity nodes alias
+        //? their operand instead of being copied.
+        let mut index: Vec<u32> = Vec::new();
+        for i in 0..count {
+            let node = Protobuf::fields(reader.bytes()?)?;
+            let Some(&(kind, Field::Bytes(body))) = node.last() else {
+                return Err(WitnessGraphError::MalformedNode(i));
+            };
+            let body = Protobuf::fields(body)?;
+            let arg = |number| {
+                index
+                    .get(uint(&body, number) as usize)
+                    .copied()
+                    .ok_or(WitnessGraphError::MalformedNode(i))
+            };
+
+            let node = match (kind, uint(&body, 1)) {
+                (1, offset) => {
+                    let offset =
+                        u32::try_from(offset).map_err(|_| WitnessGraphError::MalformedNode(i))?;
+                    graph.push(Op::Input, [offset, 0, 0])
+                }
+                (2, _) => {
+                    let value = Protobuf::fields(bytes_field(&body, 1))?;
+                    let value = U256::try_from_le_slice(bytes_field(&value, 1))
+                        .ok_or(WitnessGraphError::MalformedNode(i))?;
+                    graph.constant(value)
+                }
+                (3, 0) => graph.push(Op::Neg, [arg(2)?, 0, 0]),
+                (3, 1) => arg(2)?,
+                (3, 2) => graph.push(Op::LNot, [arg(2)?, 0, 0]),
+                (3, 3) => graph.push(Op::BitNot, [arg(2)?, 0, 0]),
+                (4, op) => {
+                    let op = WITNESSCALC_DUO_OPS
+                        .get(op as usize)
+                        .ok_or(WitnessGraphError::MalformedNode(i))?;
+                    graph.push(*op, [arg(2)?, arg(3)?, 0])
+                }
+                (5, 0) => graph.push(Op::Ternary, [arg(2)?, arg(3)?, arg(4)?]),
+                _ => return Err(WitnessGraphError::MalformedNode(i)),
+            };
+            index.push(node);
+        }
+
+        let metadata = Protobuf::fields(reader.bytes()?)?;
+        for (number, field) in metadata {
+            match (number, field) {
+                (1, Field::Varint(signal)) => graph.signals.push(signal_node(&index, signal)?),
+                (1, Field::Bytes(packed)) => {
+                    let mut packed = Protobuf(packed);
+                    while !packed.0.is_empty() {
+                        graph.signals.push(signal_node(&index, packed.varint()?)?);
+                    }
+                }
+                (2, Field::Bytes(entry)) => {
+                    let entry = Protobuf::fields(entry)?;
+                    let name = std::str::from_utf8(bytes_field(&entry, 1))
+                        .map_err(|_| WitnessGraphError::Witnesscalc("input name isn't UTF-8"))?;
+                    let signal = Protobuf::fields(bytes_field(&entry, 2))?;
+                    let (Ok(offset), Ok(len)) = (
+                        u32::try_from(uint(&signal, 1)),
+                        u32::try_from(uint(&signal, 2)),
+                    ) else {
+                        return Err(WitnessGraphError::Witnesscalc("input offset out of range"));
+                    };
+                    graph.inputs.push(InputSignal {
+                        name: name.to_string(),
+                        offset,
+                        len,
+                    });
+                }
+                _ => {}
+            }
+        }
+        graph.inputs.sort_by_key(|input| input.offset);
+
+        Ok(graph)
+    }
+
+    /// Computes the witness for `inputs`. Matches the output of the circuit's
+    /// WASM witness generator for the same inputs.
+    pub fn evaluate(
+        &self,
+        inputs: &HashMap<String, Vec<U256>>,
+    ) -> Result<Vec<U256>, WitnessGraphError> {
+        //? Inputs needn't be contiguous, e.g. witnesscalc graphs place them
+        //? after the circuit's outputs.
+        let len = self
+            .inputs
+            .iter()
+            .map(|i| i.offset as usize + i.len as usize)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut flat = vec![U256::ZERO; len];
+        flat[0] = U256::from(1);
+        for input in &self.inputs {
+            let values = inputs
+                .get(&input.name)
+                .ok_or_else(|| WitnessGraphError::MissingInput(input.name.clone()))?;
+            if values.len() != input.len as usize {
+                return Err(WitnessGraphError::InputLength {
+                    name: input.name.clone(),
+                    expected: input.len as usize,
+                    actual: values.len(),
+                });
+            }
+            for (i, value) in values.iter().enumerate() {
+                flat[input.offset as usize + i] = value.reduce_mod(SNARK_PRIME);
+            }
+        }
+
+        let mut values: Vec<U256> = Vec::with_capacity(self.nodes.len());
+        for (i, node) in self.nodes.iter().enumerate() {
+            let op = OPS
+                .get(node.op as usize)
+                .ok_or(WitnessGraphError::MalformedNode(i))?;
+            let arg = |k: usize| {
+                values
+                    .get(node.args[k] as usize)
+                    .copied()
+                    .ok_or(WitnessGraphError::MalformedNode(i))
+            };
+
+            let value = match op {
+                Op::Input => *flat
+                    .get(node.args[0] as usize)
+                    .ok_or(WitnessGraphError::MalformedNode(i))?,
+                Op::Constant => self
+                    .constants
+                    .get(node.args[0] as usize)
+                    .map(|limbs| U256::from_limbs(*limbs))
+                    .ok_or(WitnessGraphError::MalformedNode(i))?,
+                Op::Neg => neg(arg(0)?),
+                Op::LNot => bool_to_field(arg(0)?.is_zero()),
+                Op::BitNot => (!arg(0)? & mask()).reduce_mod(SNARK_PRIME),
+                Op::Ternary => {
+                    if arg(0)?.is_zero() {
+                        arg(2)?
+                    } else {
+                        arg(1)?
+                    }
+                }
+                op => binary(*op, arg(0)?, arg(1)?).ok_or(WitnessGraphError::DivisionByZero(i))?,
+            };
+            values.push(value);
+        }
+
+        self.signals
+            .iter()
+            .map(|&s| {
+                values
+                    .get(s as usize)
+                    .copied()
+                    .ok_or(WitnessGraphError::MalformedNode(s as usize))
+            })
+            .collect()
+    }
+}
+
+impl Default for WitnessGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A reader over protobuf-encoded bytes, supporting the varint and
+/// length-delimited wire types that witnesscalc graphs use.
+struct Protobuf<'a>(&'a [u8]);
+
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+impl<'a> Protobuf<'a> {
+    /// Reads every field of the message in `bytes`.
+    fn fields(bytes: &'a [u8]) -> Result<Vec<(u64, Field<'a>)>, WitnessGraphError> {
+        let mut reader = Protobuf(bytes);
+        let mut fields = Vec::new();
+        while !reader.0.is_empty() {
+            let key = reader.varint()?;
+            let field = match key & 7 {
+                0 => Field::Varint(reader.varint()?),
+                2 => Field::Bytes(reader.bytes()?),
+                _ => return Err(WitnessGraphError::Witnesscalc("unsupported wire type")),
+            };
+            fields.push((key >> 3, field));
+        }
+        Ok(fields)
+    }
+
+    fn varint(&mut self) -> Result<u64, WitnessGraphError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self
+                .0
+                .split_first()
+                .ok_or(WitnessGraphError::Witnesscalc("truncated varint"))?;
+            self.0 = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(WitnessGraphError::Witnesscalc("varint too long"))
+    }
+
+    /// Reads a length-delimited value.
+    fn bytes(&mut self) -> Result<&'a [u8], WitnessGraphError> {
+        let len = self.varint()? as usize;
+        if len > self.0.len() {
+            return Err(WitnessGraphError::Witnesscalc("truncated message"));
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+}
+
+/// Returns the varint field `number`, or protobuf's default of zero.
+fn uint(fields: &[(u64, Field)], number: u64) -> u64 {
+    fields
+        .iter()
+        .rev()
+        .find_map(|(n, field)| match field {
+            Field::Varint(value) if *n == number => Some(*value),
+            _ => None,
+        })
+        .unwrap_or(0)
+}
+
+/// Returns the length-delimited field `number`, or protobuf's default of empty.
+fn bytes_field<'a>(fields: &[(u64, Field<'a>)], number: u64) -> &'a [u8] {
+    fields
+        .iter()
+        .rev()
+        .find_map(|(n, field)| match field {
+            Field::Bytes(bytes) if *n == number => Some(*bytes),
+            _ => None,
+        })
+        .unwrap_or(&[])
+}
+
+fn signal_node(index: &[u32], signal: u64) -> Result<u32, WitnessGraphError> {
+    index
+        .get(signal as usize)
+        .copied()
+        .ok_or(WitnessGraphError::MalformedNode(signal as usize))
+}
+
+/// Evaluates a binary operation, returning `None` on division by zero.
+fn binary(op: Op, a: U256, b: U256) -> Option<U256> {
+    let value = match op {
+        Op::Add => a.add_mod(b, SNARK_PRIME),
+        Op::Sub => a.add_mod(neg(b), SNARK_PRIME),
+        Op::Mul => a.mul_mod(b, SNARK_PRIME),
+        Op::Div => a.mul_mod(b.inv_mod(SNARK_PRIME)?, SNARK_PRIME),
+        Op::IntDiv => a.checked_div(b)?,
+        Op::Mod => a.checked_rem(b)?,
+        Op::Pow => a.pow_mod(b, SNARK_PRIME),
+        Op::Eq => bool_to_field(a == b),
+        Op::Neq => bool_to_field(a != b),
+        Op::Lt => bool_to_field(signed_cmp(a, b) == Ordering::Less),
+        Op::Gt => bool_to_field(signed_cmp(a, b) == Ordering::Greater),
+        Op::Leq => bool_to_field(signed_cmp(a, b) != Ordering::Greater),
+        Op::Geq => bool_to_field(signed_cmp(a, b) != Ordering::Less),
+        Op::LAnd => bool_to_field(!a.is_zero() && !b.is_zero()),
+        Op::LOr => bool_to_field(!a.is_zero() || !b.is_zero()),
+        Op::Shl => shift(a, b, true),
+        Op::Shr => shift(a, b, false),
+        Op::BitAnd => a & b,
+        Op::BitOr => (a | b).reduce_mod(SNARK_PRIME),
+        Op::BitXor => (a ^ b).reduce_mod(SNARK_PRIME),
+        Op::Input | Op::Constant | Op::Neg | Op::LNot | Op::BitNot | Op::Ternary => {
+            unreachable!("not a binary operation")
+        }
+    };
+    Some(value)
+}
+
+fn neg(a: U256) -> U256 {
+    if a.is_zero() { a } else { SNARK_PRIME - a }
+}
+
+fn bool_to_field(b: bool) -> U256 {
+    U256::from(b as u8)
+}
+
+fn mask() -> U256 {
+    (U256::from(1) << FIELD_BITS) - U256::from(1)
+}
+
+/// Compares two field elements as circom does, treating values above `p / 2`
+/// as negative.
+fn signed_cmp(a: U256, b: U256) -> Ordering {
+    let half = SNARK_PRIME >> 1;
+    match (a > half, b > half) {
+        (false, true) => Ordering::Greater,
+        (true, false) => Ordering::Less,
+        _ => a.cmp(&b),
+    }
+}
+
+/// Shifts `a` by `b` bits. Negative shift amounts shift in the other direction,
+/// and left shifts are truncated to the field's bit width.
+fn shift(a: U256, b: U256, left: bool) -> U256 {
+    let (left, amount) = if b > SNARK_PRIME >> 1 {
+        (!left, SNARK_PRIME - b)
+    } else {
+        (left, b)
+    };
+    if amount >= U256::from(FIELD_BITS) {
+        return U256::ZERO;
+    }
+
+    let amount = amount.to::<usize>();
+    if left {
+        ((a << amount) & mask()).reduce_mod(SNARK_PRIME)
+    } else {
+        a >> amount
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Builds `out = a * b + 1` and `inv = 1 / a`, mirroring a small circuit's
+    /// witness layout of `[1, out, inv, a, b]`.
+    fn graph() -> WitnessGraph {
+        let mut graph = WitnessGraph::new();
+        let a = graph.input("a", 1)[0];
+        let b = graph.input("b", 1)[0];
+        let one = graph.constant(U256::from(1));
+        let product = graph.push(Op::Mul, [a, b, 0]);
+        let out = graph.push(Op::Add, [product, one, 0]);
+        let inv = graph.push(Op::Div, [one, a, 0]);
+
+        graph.signal(0);
+        graph.signal(out);
+        graph.signal(inv);
+        graph.signal(a);
+        graph.signal(b);
+        graph
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let inputs = HashMap::from([
+            ("a".to_string(), vec![U256::from(3)]),
+            ("b".to_string(), vec![U256::from(5)]),
+        ]);
+        let witness = graph().evaluate(&inputs).unwrap();
+
+        let inv = U256::from(3).inv_mod(SNARK_PRIME).unwrap();
+        assert_eq!(
+            witness,
+            vec![
+                U256::from(1),
+                U256::from(16),
+                inv,
+                U256::from(3),
+                U256::from(5)
+            ]
+        );
+
+        let zero = HashMap::from([
+            ("a".to_string(), vec![U256::ZERO]),
+            ("b".to_string(), vec![U256::from(5)]),
+        ]);
+        assert!(matches!(
+            graph().evaluate(&zero),
+            Err(WitnessGraphError::DivisionByZero(_))
+        ));
+        assert!(matches!(
+            graph().evaluate(&HashMap::new()),
+            Err(WitnessGraphError::MissingInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_circom_semantics() {
+        let minus_one = SNARK_PRIME - U256::from(1);
+        assert_eq!(binary(Op::Lt, minus_one, U256::ZERO), Some(U256::from(1)));
+        assert_eq!(
+            binary(Op::Gt, U256::from(2), U256::from(1)),
+            Some(U256::from(1))
+        );
+        assert_eq!(binary(Op::Sub, U256::ZERO, U256::from(1)), Some(minus_one));
+        assert_eq!(
+            binary(Op::Shr, U256::from(8), U256::from(2)),
+            Some(U256::from(2))
+        );
+        // A negative shift amount shifts the other way.
+        assert_eq!(
+            binary(Op::Shl, U256::from(8), minus_one),
+            Some(U256::from(4))
+        );
+        assert_eq!(
+            binary(Op::Shl, U256::from(1), U256::from(254)),
+            Some(U256::ZERO)
+        );
+        assert_eq!(
+            binary(Op::IntDiv, U256::from(7), U256::from(2)),
+            Some(U256::from(3))
+        );
+        assert_eq!(binary(Op::Mod, U256::from(7), U256::ZERO), None);
+    }
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn uint_field(number: u64, value: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint(number << 3, &mut out);
+        varint(value, &mut out);
+        out
+    }
+
+    fn message_field(number: u64, value: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint((number << 3) | 2, &mut out);
+        varint(value.len() as u64, &mut out);
+        out.extend_from_slice(value);
+        out
+    }
+
+    /// Encodes [`graph`] as circom-witnesscalc would, with an identity node in
+    /// front of `out`.
+    fn witnesscalc_graph() -> Vec<u8> {
+        let duo = |op, a, b| [uint_field(1, op), uint_field(2, a), uint_field(3, b)].concat();
+        let nodes = [
+            message_field(1, &uint_field(1, 0)),
+            message_field(1, &uint_field(1, 1)),
+            message_field(1, &uint_field(1, 2)),
+            message_field(2, &message_field(1, &message_field(1, &[1]))),
+            message_field(4, &duo(0, 1, 2)),
+            message_field(4, &duo(2, 4, 3)),
+            message_field(4, &duo(1, 3, 1)),
+            message_field(3, &[uint_field(1, 1), uint_field(2, 5)].concat()),
+        ];
+        let mut signals = Vec::new();
+        for signal in [0, 7, 6, 1, 2] {
+            varint(signal, &mut signals);
+        }
+        let input = |name: &str, offset| {
+            let signal = [uint_field(1, offset), uint_field(2, 1)].concat();
+            message_field(
+                2,
+                &[message_field(1, name.as_bytes()), message_field(2, &signal)].concat(),
+            )
+        };
+        let metadata = [message_field(1, &signals), input("b", 2), input("a", 1)].concat();
+
+        let mut bytes = WITNESSCALC_MAGIC.to_vec();
+        bytes.extend_from_slice(&(nodes.len() as u64).to_le_bytes());
+        for message in nodes.iter().chain([&metadata]) {
+            varint(message.len() as u64, &mut bytes);
+            bytes.extend_from_slice(message);
+        }
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_from_witnesscalc() {
+        let bytes = witnesscalc_graph();
+        assert!(WitnessGraph::is_witnesscalc(&bytes));
+        let converted = WitnessGraph::from_witnesscalc(&bytes).unwrap();
+
+        let inputs = HashMap::from([
+            ("a".to_string(), vec![U256::from(3)]),
+            ("b".to_string(), vec![U256::from(5)]),
+        ]);
+        assert_eq!(
+            converted.evaluate(&inputs).unwrap(),
+            graph().evaluate(&inputs).unwrap()
+        );
+
+        assert!(WitnessGraph::from_witnesscalc(&bytes[..bytes.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_graph_roundtrip() {
+        let graph = graph();
+        let mut bytes = Vec::new();
+        graph.serialize_uncompressed(&mut bytes).unwrap();
+        let read_back =
+            WitnessGraph::deserialize_uncompressed_unchecked(Cursor::new(bytes)).unwrap();
+        assert_eq!(graph, read_back);
+    }
+}
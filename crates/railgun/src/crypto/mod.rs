@@ -1,6 +1,6 @@
 pub(crate) mod aes;
 pub mod keys;
 pub(crate) mod railgun_base_37;
-pub(crate) mod railgun_txid;
+pub mod railgun_txid;
 pub(crate) mod railgun_zero;
 pub mod serializable_np_index;
@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use crypto::poseidon_hash;
 use ruint::aliases::U256;
 use serde::{Deserialize, Serialize, Serializer};
@@ -83,9 +85,17 @@ impl<'de> Deserialize<'de> for Txid {
         D: serde::Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        let s = s.strip_prefix("0x").unwrap_or(&s);
-        let value = U256::from_str_radix(s, 16).map_err(serde::de::Error::custom)?;
-        Ok(Txid(value))
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for Txid {
+    type Err = ruint::ParseError;
+
+    /// Parses a hex TXID, with or without a `0x` prefix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        Ok(Txid(U256::from_str_radix(s, 16)?))
     }
 }
 
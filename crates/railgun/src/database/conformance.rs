@@ -0,0 +1,167 @@
+//! Shared conformance suite for [`Database`] implementations.
+//!
+//! Every backend (memory, filesystem, SQLite, and host-provided adapters such as
+//! the JS database) is expected to pass [`run`]. The suite assumes it is handed an
+//! empty database and panics on the first violated expectation.
+
+use crate::database::{Database, WriteBatch};
+
+/// Runs every conformance check against `db`.
+pub async fn run(db: &dyn Database) {
+    get_set_delete(db).await;
+    binary_keys(db).await;
+    long_keys(db).await;
+    write_batch(db).await;
+    scan_prefix(db).await;
+}
+
+async fn get_set_delete(db: &dyn Database) {
+    let key = b"conformance:basic";
+
+    assert_eq!(db.get(key).await.unwrap(), None, "missing key");
+
+    db.set(key, b"one").await.unwrap();
+    assert_eq!(db.get(key).await.unwrap(), Some(b"one".to_vec()), "set");
+
+    db.set(key, b"two").await.unwrap();
+    assert_eq!(
+        db.get(key).await.unwrap(),
+        Some(b"two".to_vec()),
+        "overwrite"
+    );
+
+    db.set(key, b"").await.unwrap();
+    assert_eq!(db.get(key).await.unwrap(), Some(Vec::new()), "empty value");
+
+    db.delete(key).await.unwrap();
+    assert_eq!(db.get(key).await.unwrap(), None, "delete");
+
+    db.delete(key).await.unwrap();
+}
+
+async fn binary_keys(db: &dyn Database) {
+    let key = [0x00, 0xff, 0x80, b'/', b'.'];
+    let value: Vec<u8> = (0..=255).collect();
+
+    db.set(&key, &value).await.unwrap();
+    assert_eq!(
+        db.get(&key).await.unwrap(),
+        Some(value),
+        "binary round trip"
+    );
+
+    db.delete(&key).await.unwrap();
+}
+
+async fn long_keys(db: &dyn Database) {
+    //? Account keys embed a 0zk address and run well past 128 bytes.
+    let prefix = b"conformance:long:";
+    let key = [prefix.as_slice(), &[0xab; 200]].concat();
+    let other = [prefix.as_slice(), &[0xcd; 200]].concat();
+
+    db.set(&key, b"one").await.unwrap();
+    assert_eq!(
+        db.get(&key).await.unwrap(),
+        Some(b"one".to_vec()),
+        "long key"
+    );
+
+    let mut batch = WriteBatch::new();
+    batch.set(&other, b"two").set(&key, b"three");
+    db.write_batch(batch).await.unwrap();
+    assert_eq!(
+        db.scan_prefix(prefix).await.unwrap(),
+        vec![
+            (key.clone(), b"three".to_vec()),
+            (other.clone(), b"two".to_vec())
+        ],
+        "long keys in a prefix scan"
+    );
+
+    let mut batch = WriteBatch::new();
+    batch.delete(&key).delete(&other);
+    db.write_batch(batch).await.unwrap();
+    assert_eq!(db.get(&key).await.unwrap(), None, "long key delete");
+    assert!(
+        db.scan_prefix(prefix).await.unwrap().is_empty(),
+        "long key cleanup"
+    );
+}
+
+async fn write_batch(db: &dyn Database) {
+    db.set(b"conformance:batch:stale", b"stale").await.unwrap();
+
+    let mut batch = WriteBatch::new();
+    batch
+        .set(b"conformance:batch:a", b"1")
+        .set(b"conformance:batch:b", b"2")
+        .set(b"conformance:batch:a", b"3")
+        .delete(b"conformance:batch:stale")
+        .set(b"conformance:batch:c", b"4")
+        .delete(b"conformance:batch:c");
+    db.write_batch(batch).await.unwrap();
+
+    let a = db.get(b"conformance:batch:a").await.unwrap();
+    let b = db.get(b"conformance:batch:b").await.unwrap();
+    let c = db.get(b"conformance:batch:c").await.unwrap();
+    let stale = db.get(b"conformance:batch:stale").await.unwrap();
+    assert_eq!(a, Some(b"3".to_vec()), "last write to a key wins");
+    assert_eq!(b, Some(b"2".to_vec()), "batch set");
+    assert_eq!(c, None, "set then delete within a batch");
+    assert_eq!(stale, None, "batch delete");
+
+    db.write_batch(WriteBatch::new()).await.unwrap();
+
+    let mut batch = WriteBatch::new();
+    batch
+        .delete(b"conformance:batch:a")
+        .delete(b"conformance:batch:b");
+    db.write_batch(batch).await.unwrap();
+}
+
+async fn scan_prefix(db: &dyn Database) {
+    let mut batch = WriteBatch::new();
+    batch
+        .set(b"conformance:scan:b", b"2")
+        .set(b"conformance:scan:a", b"1")
+        .set(b"conformance:scan:a:nested", b"3")
+        .set(b"conformance:scan", b"root")
+        .set(b"conformance:scanner", b"other")
+        .set([b'c', 0xff, 0xff], b"high");
+    db.write_batch(batch).await.unwrap();
+
+    let entries = db.scan_prefix(b"conformance:scan:").await.unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            (b"conformance:scan:a".to_vec(), b"1".to_vec()),
+            (b"conformance:scan:a:nested".to_vec(), b"3".to_vec()),
+            (b"conformance:scan:b".to_vec(), b"2".to_vec()),
+        ],
+        "prefix scan is filtered and sorted"
+    );
+
+    let entries = db.scan_prefix(b"conformance:missing").await.unwrap();
+    assert!(entries.is_empty(), "prefix scan with no matches");
+
+    let entries = db.scan_prefix(&[b'c', 0xff]).await.unwrap();
+    assert_eq!(
+        entries,
+        vec![(vec![b'c', 0xff, 0xff], b"high".to_vec())],
+        "prefix scan ending in 0xff"
+    );
+
+    let all = db.scan_prefix(b"").await.unwrap();
+    assert_eq!(all.len(), 6, "empty prefix returns every key");
+    assert!(
+        all.windows(2).all(|w| w[0].0 < w[1].0),
+        "full scan is sorted"
+    );
+
+    let mut batch = WriteBatch::new();
+    for (key, _) in all {
+        batch.delete(key);
+    }
+    db.write_batch(batch).await.unwrap();
+    assert!(db.scan_prefix(b"").await.unwrap().is_empty(), "cleanup");
+}
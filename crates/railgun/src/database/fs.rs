@@ -1,45 +1,396 @@
-use std::{io, path::PathBuf};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
-use crate::database::{Database, DatabaseError};
+use futures::lock::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+use crate::database::{BatchOp, Database, DatabaseError, WriteBatch};
+
+/// Name of the write-ahead journal used for atomic batches. Keys are stored as
+/// hex-encoded or `sha256-` prefixed file names, so this can never collide with a key.
+const JOURNAL_FILE: &str = "batch.journal";
+const JOURNAL_TMP_FILE: &str = "batch.journal.tmp";
+
+/// Longest key stored under its hex-encoded name. 200 hex characters plus the `.tmp`
+/// suffix stays well within the 255 byte file name limit of common filesystems.
+const MAX_HEX_KEY_LEN: usize = 100;
+
+/// File name prefix for longer keys, which are stored under the hash of the key.
+const HASHED_PREFIX: &str = "sha256-";
 
 /// Filesystem-backed KV database. Each key is stored as a hex-encoded file in `dir`.
+/// Keys longer than [`MAX_HEX_KEY_LEN`] are stored under their SHA-256 hash instead,
+/// with the key itself written at the start of the file so prefix scans can recover it.
+///
+/// Single writes go through a temp file + rename + fsync so a crash never leaves a
+/// partially-written value. Batches are first written to a journal which is
+/// replayed on the next open if the process dies while applying it.
 pub struct FilesystemDatabase {
     dir: PathBuf,
+    /// Serializes access, so concurrent batches never share the journal or temp files
+    /// and readers never observe a partially applied batch.
+    write_lock: Mutex<()>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JournalEntry {
+    key: String,
+    value: Option<String>,
 }
 
 impl FilesystemDatabase {
-    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+    /// Opens (or creates) a database rooted at `dir`, replaying any batch that was
+    /// interrupted by a crash.
+    pub async fn new(dir: impl Into<PathBuf>) -> Result<Self, DatabaseError> {
         let dir = dir.into();
-        std::fs::create_dir_all(&dir)?;
-        Ok(Self { dir })
+        fs::create_dir_all(&dir).await.map_err(storage_error)?;
+
+        let db = Self {
+            dir,
+            write_lock: Mutex::new(()),
+        };
+        db.recover().await?;
+        Ok(db)
     }
 
     fn key_path(&self, key: &[u8]) -> PathBuf {
-        self.dir.join(hex::encode(key))
+        self.dir.join(file_name(key))
+    }
+
+    fn tmp_path(&self, key: &[u8]) -> PathBuf {
+        self.dir.join(format!("{}.tmp", file_name(key)))
+    }
+
+    async fn recover(&self) -> Result<(), DatabaseError> {
+        // Temp files were never renamed into place, so their writes never happened. This
+        // includes a leftover tmp journal, whose batch was never committed.
+        let mut dir = fs::read_dir(&self.dir).await.map_err(storage_error)?;
+        while let Some(entry) = dir.next_entry().await.map_err(storage_error)? {
+            if entry.file_name().to_string_lossy().ends_with(".tmp") {
+                remove_if_exists(&entry.path()).await?;
+            }
+        }
+
+        let journal_path = self.dir.join(JOURNAL_FILE);
+        let bytes = match fs::read(&journal_path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(storage_error(e)),
+        };
+
+        let entries: Vec<JournalEntry> = serde_json::from_slice(&bytes)?;
+        tracing::info!("Replaying {} journaled writes", entries.len());
+        self.apply(decode_journal(entries)?).await?;
+        remove_if_exists(&journal_path).await?;
+        sync_dir(&self.dir).await
+    }
+
+    /// Writes `value` to `key` via a temp file + rename. Does not fsync the directory.
+    async fn write_file(&self, key: &[u8], value: &[u8]) -> Result<(), DatabaseError> {
+        let tmp = self.tmp_path(key);
+        if key.len() > MAX_HEX_KEY_LEN {
+            let mut contents = Vec::with_capacity(4 + key.len() + value.len());
+            contents.extend_from_slice(&(key.len() as u32).to_be_bytes());
+            contents.extend_from_slice(key);
+            contents.extend_from_slice(value);
+            write_synced(&tmp, &contents).await?;
+        } else {
+            write_synced(&tmp, value).await?;
+        }
+        fs::rename(&tmp, self.key_path(key))
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Reads the value stored for `key`. The caller must hold `write_lock`.
+    async fn read_file(&self, key: &[u8]) -> Result<Option<Vec<u8>>, DatabaseError> {
+        let data = match fs::read(self.key_path(key)).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(storage_error(e)),
+        };
+
+        if key.len() <= MAX_HEX_KEY_LEN {
+            return Ok(Some(data));
+        }
+
+        let (stored_key, value) = split_hashed(data)?;
+        if stored_key != key {
+            return Err(DatabaseError::StorageError(format!(
+                "Hash collision for key {}",
+                hex::encode(key)
+            )));
+        }
+        Ok(Some(value))
+    }
+
+    async fn apply(&self, ops: Vec<BatchOp>) -> Result<(), DatabaseError> {
+        for op in ops {
+            match op {
+                BatchOp::Set(key, value) => self.write_file(&key, &value).await?,
+                BatchOp::Delete(key) => remove_if_exists(&self.key_path(&key)).await?,
+            }
+        }
+        Ok(())
     }
 }
 
 #[async_trait::async_trait]
 impl Database for FilesystemDatabase {
     async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, DatabaseError> {
-        match tokio::fs::read(self.key_path(key)).await {
-            Ok(data) => Ok(Some(data)),
-            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
-            Err(e) => Err(DatabaseError::StorageError(e.to_string())),
-        }
+        let _guard = self.write_lock.lock().await;
+        self.read_file(key).await
     }
 
     async fn set(&self, key: &[u8], value: &[u8]) -> Result<(), DatabaseError> {
-        tokio::fs::write(self.key_path(key), value)
-            .await
-            .map_err(|e| DatabaseError::StorageError(e.to_string()))
+        let _guard = self.write_lock.lock().await;
+        self.write_file(key, value).await?;
+        sync_dir(&self.dir).await
     }
 
     async fn delete(&self, key: &[u8]) -> Result<(), DatabaseError> {
-        match tokio::fs::remove_file(self.key_path(key)).await {
-            Ok(()) => Ok(()),
-            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
-            Err(e) => Err(DatabaseError::StorageError(e.to_string())),
+        let _guard = self.write_lock.lock().await;
+        remove_if_exists(&self.key_path(key)).await?;
+        sync_dir(&self.dir).await
+    }
+
+    async fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DatabaseError> {
+        let _guard = self.write_lock.lock().await;
+        let mut entries = Vec::new();
+        let mut dir = fs::read_dir(&self.dir).await.map_err(storage_error)?;
+        while let Some(entry) = dir.next_entry().await.map_err(storage_error)? {
+            let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+
+            if let Some(hash) = name.strip_prefix(HASHED_PREFIX) {
+                //? Temp files fail to decode as a 32 byte hash and are skipped.
+                if !hex::decode(hash).is_ok_and(|h| h.len() == 32) {
+                    continue;
+                }
+                let data = match fs::read(entry.path()).await {
+                    Ok(data) => data,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                    Err(e) => return Err(storage_error(e)),
+                };
+                let (key, value) = split_hashed(data)?;
+                if key.starts_with(prefix) {
+                    entries.push((key, value));
+                }
+                continue;
+            }
+
+            // Temp files and the journal are not valid hex, so they are skipped here.
+            let Ok(key) = hex::decode(&name) else {
+                continue;
+            };
+            if !key.starts_with(prefix) {
+                continue;
+            }
+            if let Some(value) = self.read_file(&key).await? {
+                entries.push((key, value));
+            }
+        }
+
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+
+    /// Commits the batch to a journal before applying it. Once the journal rename
+    /// is durable the batch is guaranteed to be fully applied, either now or by
+    /// `recover` on the next open.
+    async fn write_batch(&self, batch: WriteBatch) -> Result<(), DatabaseError> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let ops = batch.into_ops();
+        let entries: Vec<JournalEntry> = ops
+            .iter()
+            .map(|op| match op {
+                BatchOp::Set(key, value) => JournalEntry {
+                    key: hex::encode(key),
+                    value: Some(hex::encode(value)),
+                },
+                BatchOp::Delete(key) => JournalEntry {
+                    key: hex::encode(key),
+                    value: None,
+                },
+            })
+            .collect();
+
+        let _guard = self.write_lock.lock().await;
+        let journal_tmp = self.dir.join(JOURNAL_TMP_FILE);
+        let journal_path = self.dir.join(JOURNAL_FILE);
+        write_synced(&journal_tmp, &serde_json::to_vec(&entries)?).await?;
+        fs::rename(&journal_tmp, &journal_path)
+            .await
+            .map_err(storage_error)?;
+        sync_dir(&self.dir).await?;
+
+        self.apply(ops).await?;
+        sync_dir(&self.dir).await?;
+
+        remove_if_exists(&journal_path).await?;
+        sync_dir(&self.dir).await
+    }
+}
+
+/// File name for `key`: its hex encoding, or the hash of the key if that would be too long.
+fn file_name(key: &[u8]) -> String {
+    if key.len() <= MAX_HEX_KEY_LEN {
+        hex::encode(key)
+    } else {
+        format!("{HASHED_PREFIX}{}", hex::encode(Sha256::digest(key)))
+    }
+}
+
+/// Splits the contents of a hashed key's file into the key and the value.
+fn split_hashed(mut data: Vec<u8>) -> Result<(Vec<u8>, Vec<u8>), DatabaseError> {
+    let corrupt = || DatabaseError::StorageError("Corrupt hashed key file".to_string());
+
+    let len_bytes: [u8; 4] = data.get(..4).ok_or_else(corrupt)?.try_into().unwrap();
+    let key_len = u32::from_be_bytes(len_bytes) as usize;
+    let key = data.get(4..4 + key_len).ok_or_else(corrupt)?.to_vec();
+    let value = data.split_off(4 + key_len);
+    Ok((key, value))
+}
+
+fn decode_journal(entries: Vec<JournalEntry>) -> Result<Vec<BatchOp>, DatabaseError> {
+    let decode = |s: &str| {
+        hex::decode(s).map_err(|e| DatabaseError::StorageError(format!("Corrupt journal: {e}")))
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let key = decode(&entry.key)?;
+            Ok(match entry.value {
+                Some(value) => BatchOp::Set(key, decode(&value)?),
+                None => BatchOp::Delete(key),
+            })
+        })
+        .collect()
+}
+
+async fn write_synced(path: &Path, data: &[u8]) -> Result<(), DatabaseError> {
+    fs::write(path, data).await.map_err(storage_error)?;
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .await
+        .map_err(storage_error)?;
+    file.sync_all().await.map_err(storage_error)
+}
+
+/// Fsyncs the directory so renames and removals within it are durable.
+async fn sync_dir(dir: &Path) -> Result<(), DatabaseError> {
+    #[cfg(unix)]
+    {
+        let dir = fs::File::open(dir).await.map_err(storage_error)?;
+        dir.sync_all().await.map_err(storage_error)?;
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+async fn remove_if_exists(path: &Path) -> Result<(), DatabaseError> {
+    match fs::remove_file(path).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(storage_error(e)),
+    }
+}
+
+fn storage_error(e: io::Error) -> DatabaseError {
+    DatabaseError::StorageError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::conformance;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("railgun-fs-db-{}", rand::random::<u64>()))
+    }
+
+    #[tokio::test]
+    async fn test_conformance() {
+        let dir = temp_dir();
+        let db = FilesystemDatabase::new(&dir).await.unwrap();
+        conformance::run(&db).await;
+        assert!(!dir.join(JOURNAL_FILE).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_batches() {
+        let dir = temp_dir();
+        let db = FilesystemDatabase::new(&dir).await.unwrap();
+
+        let batches = (0..16u8).map(|i| {
+            let mut batch = WriteBatch::new();
+            for j in 0..8u8 {
+                batch.set([i, j], [i ^ j]);
+            }
+            db.write_batch(batch)
+        });
+        for result in futures::future::join_all(batches).await {
+            result.unwrap();
         }
+
+        for i in 0..16u8 {
+            for j in 0..8u8 {
+                assert_eq!(db.get(&[i, j]).await.unwrap(), Some(vec![i ^ j]));
+            }
+        }
+        assert!(!dir.join(JOURNAL_FILE).exists());
+        assert!(!dir.join(JOURNAL_TMP_FILE).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recover_journal() {
+        let dir = temp_dir();
+        let db = FilesystemDatabase::new(&dir).await.unwrap();
+        db.set(b"stale", b"value").await.unwrap();
+        drop(db);
+
+        // Simulate a crash after the journal was committed but before it was applied.
+        let entries = vec![
+            JournalEntry {
+                key: hex::encode(b"a"),
+                value: Some(hex::encode(b"1")),
+            },
+            JournalEntry {
+                key: hex::encode(b"stale"),
+                value: None,
+            },
+        ];
+        std::fs::write(
+            dir.join(JOURNAL_FILE),
+            serde_json::to_vec(&entries).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(dir.join(JOURNAL_TMP_FILE), b"garbage").unwrap();
+        let stray = dir.join(format!("{}.tmp", file_name(&[7; 200])));
+        std::fs::write(&stray, b"partial").unwrap();
+
+        let db = FilesystemDatabase::new(&dir).await.unwrap();
+        assert_eq!(db.get(b"a").await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(db.get(b"stale").await.unwrap(), None);
+        assert!(!dir.join(JOURNAL_FILE).exists());
+        assert!(!dir.join(JOURNAL_TMP_FILE).exists());
+        assert!(!stray.exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
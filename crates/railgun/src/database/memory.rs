@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use futures::lock::Mutex;
 
-use crate::database::{Database, DatabaseError};
+use crate::database::{BatchOp, Database, DatabaseError, WriteBatch};
 
 /// Basic in-memory KV database implementation.
 #[derive(Default)]
@@ -37,4 +37,43 @@ impl Database for MemoryDatabase {
         store.remove(key);
         Ok(())
     }
+
+    async fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DatabaseError> {
+        let store = self.store.lock().await;
+        let mut entries: Vec<_> = store
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+
+    /// Applies the batch while holding the store lock, so readers never observe
+    /// a partially-applied batch.
+    async fn write_batch(&self, batch: WriteBatch) -> Result<(), DatabaseError> {
+        let mut store = self.store.lock().await;
+        for op in batch.into_ops() {
+            match op {
+                BatchOp::Set(key, value) => {
+                    store.insert(key, value);
+                }
+                BatchOp::Delete(key) => {
+                    store.remove(&key);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::conformance;
+
+    #[tokio::test]
+    async fn test_conformance() {
+        conformance::run(&MemoryDatabase::new()).await;
+    }
 }
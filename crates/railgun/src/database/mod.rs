@@ -1,7 +1,11 @@
+#[cfg(any(test, feature = "testing"))]
+pub mod conformance;
+#[cfg(native)]
+pub mod fs;
 pub mod memory;
-// #[cfg(native)]
-// pub mod fs;
 mod railgun_db;
+#[cfg(sqlite)]
+pub mod sqlite;
 
 pub(crate) use railgun_db::RailgunDB;
 
@@ -12,6 +16,45 @@ pub trait Database: common::MaybeSend {
     async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, DatabaseError>;
     async fn set(&self, key: &[u8], value: &[u8]) -> Result<(), DatabaseError>;
     async fn delete(&self, key: &[u8]) -> Result<(), DatabaseError>;
+
+    /// Returns every key-value pair whose key starts with `prefix`, sorted by key.
+    ///
+    /// The default implementation returns an error, for backends that can't enumerate keys.
+    async fn scan_prefix(&self, _prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DatabaseError> {
+        Err(DatabaseError::StorageError(
+            "Prefix scans are not supported by this database".to_string(),
+        ))
+    }
+
+    /// Applies all writes in the batch as a single unit.
+    ///
+    /// Implementations should guarantee that either every write in the batch is persisted or none
+    /// are. The default implementation applies writes sequentially and is NOT atomic; backends
+    /// that can do better should override it.
+    async fn write_batch(&self, batch: WriteBatch) -> Result<(), DatabaseError> {
+        for op in batch.into_ops() {
+            match op {
+                BatchOp::Set(key, value) => self.set(&key, &value).await?,
+                BatchOp::Delete(key) => self.delete(&key).await?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An ordered set of writes applied together by [`Database::write_batch`].
+///
+/// Later writes to the same key take precedence over earlier ones.
+#[derive(Debug, Clone, Default)]
+pub struct WriteBatch {
+    ops: Vec<BatchOp>,
+}
+
+/// A single write within a [`WriteBatch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOp {
+    Set(Vec<u8>, Vec<u8>),
+    Delete(Vec<u8>),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -23,3 +66,37 @@ pub enum DatabaseError {
     #[error("Storage error: {0}")]
     StorageError(String),
 }
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        Self { ops: Vec::new() }
+    }
+
+    /// Queues a write of `value` to `key`.
+    pub fn set(&mut self, key: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) -> &mut Self {
+        self.ops.push(BatchOp::Set(key.into(), value.into()));
+        self
+    }
+
+    /// Queues a deletion of `key`.
+    pub fn delete(&mut self, key: impl Into<Vec<u8>>) -> &mut Self {
+        self.ops.push(BatchOp::Delete(key.into()));
+        self
+    }
+
+    pub fn ops(&self) -> &[BatchOp] {
+        &self.ops
+    }
+
+    pub fn into_ops(self) -> Vec<BatchOp> {
+        self.ops
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
@@ -1,8 +1,12 @@
+#[cfg(native)]
+use ruint::aliases::U256;
 use serde::{Deserialize, Serialize};
 
+#[cfg(native)]
+use crate::poi::{BlindedCommitment, ListKey, node::PoiNodeState};
 use crate::{
     account::address::RailgunAddress,
-    database::{Database, DatabaseError},
+    database::{Database, DatabaseError, WriteBatch},
     indexer::{
         indexed_account::IndexedAccountState, txid_indexer::TxidIndexerState,
         utxo_indexer::UtxoIndexerState,
@@ -28,10 +32,6 @@ pub trait RailgunDB: Database + common::MaybeSend {
         }
     }
 
-    async fn set_utxo_indexer(&self, state: &UtxoIndexerState) -> Result<(), DatabaseError> {
-        self.write_envelope(&utxo_indexer_key(), 1, state).await
-    }
-
     async fn get_account(
         &self,
         addr: &RailgunAddress,
@@ -48,14 +48,6 @@ pub trait RailgunDB: Database + common::MaybeSend {
         }
     }
 
-    async fn set_account(
-        &self,
-        addr: &RailgunAddress,
-        state: &IndexedAccountState,
-    ) -> Result<(), DatabaseError> {
-        self.write_envelope(&account_key(addr), 1, state).await
-    }
-
     async fn get_utxo_tree(
         &self,
         tree_number: u32,
@@ -72,15 +64,6 @@ pub trait RailgunDB: Database + common::MaybeSend {
         }
     }
 
-    async fn set_utxo_tree(
-        &self,
-        tree_number: u32,
-        state: MerkleTreeState,
-    ) -> Result<(), DatabaseError> {
-        self.write_envelope(&utxo_tree_key(tree_number), 1, &state)
-            .await
-    }
-
     async fn get_txid_indexer(&self) -> Result<TxidIndexerState, DatabaseError> {
         let key = txid_indexer_key();
         let Some(bytes) = self.get(&key).await? else {
@@ -94,10 +77,6 @@ pub trait RailgunDB: Database + common::MaybeSend {
         }
     }
 
-    async fn set_txid_indexer(&self, state: &TxidIndexerState) -> Result<(), DatabaseError> {
-        self.write_envelope(&txid_indexer_key(), 1, state).await
-    }
-
     async fn get_txid_tree(
         &self,
         tree_number: u32,
@@ -114,15 +93,6 @@ pub trait RailgunDB: Database + common::MaybeSend {
         }
     }
 
-    async fn set_txid_tree(
-        &self,
-        tree_number: u32,
-        state: MerkleTreeState,
-    ) -> Result<(), DatabaseError> {
-        self.write_envelope(&txid_tree_key(tree_number), 1, &state)
-            .await
-    }
-
     async fn get_poi_provider(&self) -> Result<PoiProviderState, DatabaseError> {
         let key = poi_provider_key();
         let Some(bytes) = self.get(&key).await? else {
@@ -136,24 +106,165 @@ pub trait RailgunDB: Database + common::MaybeSend {
         }
     }
 
-    async fn set_poi_provider(&self, state: &PoiProviderState) -> Result<(), DatabaseError> {
-        self.write_envelope(&poi_provider_key(), 1, state).await
+    #[cfg(native)]
+    async fn get_poi_node(&self) -> Result<PoiNodeState, DatabaseError> {
+        let key = poi_node_key();
+        let Some(bytes) = self.get(&key).await? else {
+            return Ok(Default::default());
+        };
+
+        let envelope: Envelope = serde_json::from_slice(&bytes)?;
+        match envelope.v {
+            2 => Ok(serde_json::from_value(envelope.data)?),
+            v => Err(DatabaseError::UnsupportedVersion(v)),
+        }
+    }
+
+    #[cfg(native)]
+    async fn get_poi_node_txids(&self, page: u32) -> Result<Option<Vec<U256>>, DatabaseError> {
+        let key = poi_node_txids_key(page);
+        let Some(bytes) = self.get(&key).await? else {
+            return Ok(None);
+        };
+
+        let envelope: Envelope = serde_json::from_slice(&bytes)?;
+        match envelope.v {
+            1 => Ok(Some(serde_json::from_value(envelope.data)?)),
+            v => Err(DatabaseError::UnsupportedVersion(v)),
+        }
+    }
+
+    #[cfg(native)]
+    async fn get_poi_node_list(
+        &self,
+        list_key: &ListKey,
+        page: u32,
+    ) -> Result<Option<Vec<BlindedCommitment>>, DatabaseError> {
+        let key = poi_node_list_key(list_key, page);
+        let Some(bytes) = self.get(&key).await? else {
+            return Ok(None);
+        };
+
+        let envelope: Envelope = serde_json::from_slice(&bytes)?;
+        match envelope.v {
+            1 => Ok(Some(serde_json::from_value(envelope.data)?)),
+            v => Err(DatabaseError::UnsupportedVersion(v)),
+        }
     }
 
-    async fn write_envelope<S: Serialize + common::MaybeSend>(
+    #[cfg(native)]
+    async fn get_poi_node_blocked(
         &self,
-        key: &[u8],
+        list_key: &ListKey,
+    ) -> Result<Vec<BlindedCommitment>, DatabaseError> {
+        let key = poi_node_blocked_key(list_key);
+        let Some(bytes) = self.get(&key).await? else {
+            return Ok(Default::default());
+        };
+
+        let envelope: Envelope = serde_json::from_slice(&bytes)?;
+        match envelope.v {
+            1 => Ok(serde_json::from_value(envelope.data)?),
+            v => Err(DatabaseError::UnsupportedVersion(v)),
+        }
+    }
+}
+
+impl<D: Database + ?Sized> RailgunDB for D {}
+
+/// Typed writers for Railgun state, queued into a batch so related state can be
+/// committed atomically via [`Database::write_batch`].
+impl WriteBatch {
+    pub(crate) fn set_utxo_indexer(
+        &mut self,
+        state: &UtxoIndexerState,
+    ) -> Result<(), DatabaseError> {
+        self.set_envelope(utxo_indexer_key(), 1, state)
+    }
+
+    pub(crate) fn set_account(
+        &mut self,
+        addr: &RailgunAddress,
+        state: &IndexedAccountState,
+    ) -> Result<(), DatabaseError> {
+        self.set_envelope(account_key(addr), 1, state)
+    }
+
+    pub(crate) fn set_utxo_tree(
+        &mut self,
+        tree_number: u32,
+        state: MerkleTreeState,
+    ) -> Result<(), DatabaseError> {
+        self.set_envelope(utxo_tree_key(tree_number), 1, &state)
+    }
+
+    pub(crate) fn set_txid_indexer(
+        &mut self,
+        state: &TxidIndexerState,
+    ) -> Result<(), DatabaseError> {
+        self.set_envelope(txid_indexer_key(), 1, state)
+    }
+
+    pub(crate) fn set_txid_tree(
+        &mut self,
+        tree_number: u32,
+        state: MerkleTreeState,
+    ) -> Result<(), DatabaseError> {
+        self.set_envelope(txid_tree_key(tree_number), 1, &state)
+    }
+
+    pub(crate) fn set_poi_provider(
+        &mut self,
+        state: &PoiProviderState,
+    ) -> Result<(), DatabaseError> {
+        self.set_envelope(poi_provider_key(), 1, state)
+    }
+
+    #[cfg(native)]
+    pub(crate) fn set_poi_node(&mut self, state: &PoiNodeState) -> Result<(), DatabaseError> {
+        self.set_envelope(poi_node_key(), 2, state)
+    }
+
+    #[cfg(native)]
+    pub(crate) fn set_poi_node_txids(
+        &mut self,
+        page: u32,
+        leaves: &[U256],
+    ) -> Result<(), DatabaseError> {
+        self.set_envelope(poi_node_txids_key(page), 1, &leaves)
+    }
+
+    #[cfg(native)]
+    pub(crate) fn set_poi_node_list(
+        &mut self,
+        list_key: &ListKey,
+        page: u32,
+        leaves: &[BlindedCommitment],
+    ) -> Result<(), DatabaseError> {
+        self.set_envelope(poi_node_list_key(list_key, page), 1, &leaves)
+    }
+
+    #[cfg(native)]
+    pub(crate) fn set_poi_node_blocked(
+        &mut self,
+        list_key: &ListKey,
+        blocked: &[BlindedCommitment],
+    ) -> Result<(), DatabaseError> {
+        self.set_envelope(poi_node_blocked_key(list_key), 1, &blocked)
+    }
+
+    fn set_envelope<S: Serialize>(
+        &mut self,
+        key: Vec<u8>,
         version: u32,
         data: &S,
     ) -> Result<(), DatabaseError> {
         let bytes = serialize_envelope(version, data)?;
-        self.set(key, &bytes).await?;
+        self.set(key, bytes);
         Ok(())
     }
 }
 
-impl<D: Database + ?Sized> RailgunDB for D {}
-
 #[derive(Serialize, Deserialize)]
 struct Envelope {
     pub v: u32,
@@ -191,3 +302,23 @@ fn txid_tree_key(tree_number: u32) -> Vec<u8> {
 fn poi_provider_key() -> Vec<u8> {
     b"poi_provider".to_vec()
 }
+
+#[cfg(native)]
+fn poi_node_key() -> Vec<u8> {
+    b"poi_node".to_vec()
+}
+
+#[cfg(native)]
+fn poi_node_txids_key(page: u32) -> Vec<u8> {
+    format!("poi_node_txids:{}", page).into_bytes()
+}
+
+#[cfg(native)]
+fn poi_node_list_key(list_key: &ListKey, page: u32) -> Vec<u8> {
+    format!("poi_node_list:{}:{}", list_key, page).into_bytes()
+}
+
+#[cfg(native)]
+fn poi_node_blocked_key(list_key: &ListKey) -> Vec<u8> {
+    format!("poi_node_blocked:{}", list_key).into_bytes()
+}
@@ -0,0 +1,230 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::database::{BatchOp, Database, DatabaseError, WriteBatch};
+
+/// SQLite-backed KV database for native builds.
+///
+/// All keys live in a single `WITHOUT ROWID` table ordered by key, which gives
+/// cheap prefix scans. Batches run inside one SQLite transaction and the database
+/// runs in WAL mode, so batches are atomic and durable across crashes.
+///
+/// Queries run on tokio's blocking thread pool, so this must be used from within a
+/// tokio runtime.
+pub struct SqliteDatabase {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteDatabase {
+    /// Opens (or creates) a database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DatabaseError> {
+        let conn = Connection::open(path).map_err(sqlite_error)?;
+        Self::init(conn)
+    }
+
+    /// Opens a private, in-memory SQLite database. Mostly useful for tests.
+    pub fn open_in_memory() -> Result<Self, DatabaseError> {
+        let conn = Connection::open_in_memory().map_err(sqlite_error)?;
+        Self::init(conn)
+    }
+
+    fn init(conn: Connection) -> Result<Self, DatabaseError> {
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = FULL;
+             PRAGMA auto_vacuum = INCREMENTAL;
+             CREATE TABLE IF NOT EXISTS kv (
+                 key BLOB PRIMARY KEY NOT NULL,
+                 value BLOB NOT NULL
+             ) WITHOUT ROWID;",
+        )
+        .map_err(sqlite_error)?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Reclaims space left behind by overwritten and deleted values and truncates
+    /// the write-ahead log.
+    ///
+    /// Railgun state is rewritten in place on every sync, so long-running services
+    /// should call this periodically.
+    pub async fn compact(&self) -> Result<(), DatabaseError> {
+        self.with_conn(|conn| {
+            conn.execute_batch(
+                "PRAGMA wal_checkpoint(TRUNCATE);
+                 VACUUM;",
+            )
+        })
+        .await
+    }
+
+    async fn with_conn<T, F>(&self, f: F) -> Result<T, DatabaseError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|_| DatabaseError::StorageError("SQLite connection poisoned".into()))?;
+            f(&mut conn).map_err(sqlite_error)
+        })
+        .await
+        .map_err(|e| DatabaseError::StorageError(e.to_string()))?
+    }
+}
+
+#[async_trait::async_trait]
+impl Database for SqliteDatabase {
+    async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, DatabaseError> {
+        let key = key.to_vec();
+        self.with_conn(move |conn| {
+            conn.query_row("SELECT value FROM kv WHERE key = ?1", params![key], |row| {
+                row.get(0)
+            })
+            .optional()
+        })
+        .await
+    }
+
+    async fn set(&self, key: &[u8], value: &[u8]) -> Result<(), DatabaseError> {
+        let key = key.to_vec();
+        let value = value.to_vec();
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO kv (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )
+            .map(|_| ())
+        })
+        .await
+    }
+
+    async fn delete(&self, key: &[u8]) -> Result<(), DatabaseError> {
+        let key = key.to_vec();
+        self.with_conn(move |conn| {
+            conn.execute("DELETE FROM kv WHERE key = ?1", params![key])
+                .map(|_| ())
+        })
+        .await
+    }
+
+    async fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DatabaseError> {
+        let lower = prefix.to_vec();
+        let upper = prefix_upper_bound(prefix);
+        self.with_conn(move |conn| {
+            let mut entries = Vec::new();
+            match upper {
+                Some(upper) => {
+                    let mut stmt = conn.prepare(
+                        "SELECT key, value FROM kv WHERE key >= ?1 AND key < ?2 ORDER BY key",
+                    )?;
+                    let rows = stmt
+                        .query_map(params![lower, upper], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                    for row in rows {
+                        entries.push(row?);
+                    }
+                }
+                None => {
+                    let mut stmt =
+                        conn.prepare("SELECT key, value FROM kv WHERE key >= ?1 ORDER BY key")?;
+                    let rows =
+                        stmt.query_map(params![lower], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                    for row in rows {
+                        entries.push(row?);
+                    }
+                }
+            }
+            Ok(entries)
+        })
+        .await
+    }
+
+    async fn write_batch(&self, batch: WriteBatch) -> Result<(), DatabaseError> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut set =
+                    tx.prepare("INSERT OR REPLACE INTO kv (key, value) VALUES (?1, ?2)")?;
+                let mut delete = tx.prepare("DELETE FROM kv WHERE key = ?1")?;
+                for op in batch.into_ops() {
+                    match op {
+                        BatchOp::Set(key, value) => set.execute(params![key, value])?,
+                        BatchOp::Delete(key) => delete.execute(params![key])?,
+                    };
+                }
+            }
+            tx.commit()
+        })
+        .await
+    }
+}
+
+/// Returns the smallest key that is greater than every key starting with `prefix`,
+/// or `None` if no such key exists (empty or all-`0xff` prefixes).
+fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut upper = prefix.to_vec();
+    while let Some(last) = upper.pop() {
+        if last < u8::MAX {
+            upper.push(last + 1);
+            return Some(upper);
+        }
+    }
+    None
+}
+
+fn sqlite_error(e: rusqlite::Error) -> DatabaseError {
+    DatabaseError::StorageError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::conformance;
+
+    #[tokio::test]
+    async fn test_conformance() {
+        let db = SqliteDatabase::open_in_memory().unwrap();
+        conformance::run(&db).await;
+    }
+
+    #[tokio::test]
+    async fn test_persistence_and_compaction() {
+        let path = std::env::temp_dir().join(format!("railgun-{}.sqlite", rand::random::<u64>()));
+
+        let db = SqliteDatabase::open(&path).unwrap();
+        let mut batch = WriteBatch::new();
+        batch.set(b"a", b"1").set(b"b", b"2").delete(b"b");
+        db.write_batch(batch).await.unwrap();
+        db.compact().await.unwrap();
+        drop(db);
+
+        let db = SqliteDatabase::open(&path).unwrap();
+        assert_eq!(db.get(b"a").await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(db.get(b"b").await.unwrap(), None);
+        drop(db);
+
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+        }
+    }
+
+    #[test]
+    fn test_prefix_upper_bound() {
+        assert_eq!(prefix_upper_bound(b"ab"), Some(b"ac".to_vec()));
+        assert_eq!(prefix_upper_bound(&[b'a', 0xff]), Some(b"b".to_vec()));
+        assert_eq!(prefix_upper_bound(&[0xff, 0xff]), None);
+        assert_eq!(prefix_upper_bound(b""), None);
+    }
+}
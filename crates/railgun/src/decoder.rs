@@ -0,0 +1,915 @@
+//! Human-readable descriptions of Railgun calldata and logs.
+//!
+//! Decodes `RailgunSmartWallet` and `RelayAdapt` calls, privacy paymaster
+//! `paymasterAndData`, and `RailgunSmartWallet` events into serializable
+//! descriptions, so users can review what a transaction does before they sign
+//! or send it. Outputs visible to any configured viewing key are decrypted, and
+//! only reported if the decrypted note hashes to its on-chain commitment.
+
+use alloy::{
+    primitives::{Address, B256, Bytes, FixedBytes, Log, U256},
+    sol_types::{SolCall, SolEvent, SolValue},
+};
+use crypto::poseidon_hash;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    abis::railgun::{
+        CommitmentCiphertext, RailgunSmartWallet, RelayAdapt, ShieldRequest, Transaction,
+        UnshieldType,
+    },
+    adapter_data::{PaymasterData, RailgunFeeAdapter},
+    caip::AssetId,
+    crypto::{
+        aes::Ciphertext,
+        keys::{BlindedKey, ByteKey, MasterPublicKey, U256Key, ViewingKey, ViewingPublicKey},
+    },
+    note::utxo::TransactPlaintext,
+};
+
+/// Length of the paymaster address and its two gas limits at the start of an
+/// ERC-4337 v0.7 `paymasterAndData`.
+const PAYMASTER_DATA_OFFSET: usize = 20 + 16 + 16;
+
+/// Decodes Railgun calldata and logs, decrypting outputs visible to its viewing
+/// keys.
+#[derive(Default, Clone)]
+pub struct Decoder {
+    /// Each viewing key with the master public key of its account.
+    keys: Vec<(ViewingKey, MasterPublicKey)>,
+}
+
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error("Unknown function selector: {0}")]
+    UnknownSelector(Bytes),
+    #[error("Unknown event: {0:?}")]
+    UnknownEvent(Option<B256>),
+    #[error("paymasterAndData is {0} bytes, too short to hold paymaster data")]
+    PaymasterDataTooShort(usize),
+    #[error("ABI decode error: {0}")]
+    Abi(#[from] alloy::sol_types::Error),
+}
+
+/// A decoded contract call.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum DecodedCall {
+    /// `transact` on the smart wallet or relay adapt.
+    #[serde(rename_all = "camelCase")]
+    Transact { operations: Vec<DecodedOperation> },
+    /// `shield` on the smart wallet or relay adapt.
+    #[serde(rename_all = "camelCase")]
+    Shield { shields: Vec<DecodedShield> },
+    /// `RelayAdapt.relay`, which runs `calls` with the unshielded funds.
+    #[serde(rename_all = "camelCase")]
+    Relay {
+        operations: Vec<DecodedOperation>,
+        require_success: bool,
+        min_gas_limit: U256,
+        calls: Vec<DecodedSubcall>,
+    },
+    /// `RelayAdapt.multicall`.
+    #[serde(rename_all = "camelCase")]
+    Multicall {
+        require_success: bool,
+        calls: Vec<DecodedSubcall>,
+    },
+    /// Privacy paymaster `paymasterAndData`, which pays `fee` from `operations`.
+    #[serde(rename_all = "camelCase")]
+    Paymaster {
+        paymaster: Address,
+        adapter: Address,
+        fee: DecodedFee,
+        operations: Vec<DecodedOperation>,
+    },
+}
+
+/// A call made by `RelayAdapt`. `decoded` is set if the call is itself a
+/// Railgun call.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedSubcall {
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+    pub decoded: Option<Box<DecodedCall>>,
+}
+
+/// A single railgun operation (one `Transaction` struct).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedOperation {
+    pub merkle_root: B256,
+    pub nullifier_count: usize,
+    pub commitment_count: usize,
+    pub bound_params: DecodedBoundParams,
+    pub unshield: Option<DecodedUnshield>,
+    /// Outputs decrypted with the decoder's viewing keys.
+    pub outputs: Vec<DecodedOutput>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedBoundParams {
+    pub tree_number: u16,
+    pub min_gas_price: U256,
+    pub unshield_type: UnshieldKind,
+    pub chain_id: u64,
+    pub adapt_contract: Address,
+    pub adapt_params: B256,
+    pub hash: U256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UnshieldKind {
+    None,
+    Normal,
+    Redirect,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedUnshield {
+    pub recipient: Address,
+    pub asset: AssetId,
+    pub amount: U256,
+}
+
+/// A commitment output readable by one of the decoder's viewing keys.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedOutput {
+    pub commitment: B256,
+    /// Whether the viewing key received or sent the output.
+    pub direction: OutputDirection,
+    pub asset: AssetId,
+    pub value: u128,
+    pub memo: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputDirection {
+    Received,
+    Sent,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedShield {
+    pub npk: B256,
+    pub asset: AssetId,
+    pub value: U256,
+    /// Whether the shield pays one of the decoder's accounts.
+    pub received: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedFee {
+    pub asset: AssetId,
+    pub value: U256,
+}
+
+/// A decoded `RailgunSmartWallet` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum DecodedEvent {
+    #[serde(rename_all = "camelCase")]
+    Shield {
+        tree_number: U256,
+        start_position: U256,
+        shields: Vec<DecodedShield>,
+        fees: Vec<U256>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Transact {
+        tree_number: U256,
+        start_position: U256,
+        commitment_count: usize,
+        outputs: Vec<DecodedOutput>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Unshield {
+        recipient: Address,
+        asset: AssetId,
+        amount: U256,
+        fee: U256,
+    },
+    #[serde(rename_all = "camelCase")]
+    Nullified {
+        tree_number: u16,
+        nullifier_count: usize,
+    },
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decrypts outputs that `viewing_key` can read. `master_key` is the master public key
+    /// of the account `viewing_key` belongs to, used to check that received outputs pay it.
+    pub fn with_viewing_key(
+        mut self,
+        viewing_key: ViewingKey,
+        master_key: MasterPublicKey,
+    ) -> Self {
+        self.keys.push((viewing_key, master_key));
+        self
+    }
+
+    /// Decodes calldata for a `RailgunSmartWallet` or `RelayAdapt` call.
+    pub fn decode_calldata(&self, data: &[u8]) -> Result<DecodedCall, DecodeError> {
+        let selector = data.get(..4).unwrap_or(data);
+
+        if selector == RailgunSmartWallet::transactCall::SELECTOR {
+            let call = RailgunSmartWallet::transactCall::abi_decode(data)?;
+            return Ok(DecodedCall::Transact {
+                operations: self.decode_operations(&call._transactions),
+            });
+        }
+        if selector == RailgunSmartWallet::shieldCall::SELECTOR {
+            let call = RailgunSmartWallet::shieldCall::abi_decode(data)?;
+            return Ok(DecodedCall::Shield {
+                shields: self.decode_shields(&call._shieldRequests),
+            });
+        }
+        if selector == RelayAdapt::relayCall::SELECTOR {
+            let call = RelayAdapt::relayCall::abi_decode(data)?;
+            return Ok(DecodedCall::Relay {
+                operations: self.decode_operations(&call._transactions),
+                require_success: call._actionData.requireSuccess,
+                min_gas_limit: call._actionData.minGasLimit,
+                calls: self.decode_subcalls(&call._actionData.calls),
+            });
+        }
+        if selector == RelayAdapt::multicallCall::SELECTOR {
+            let call = RelayAdapt::multicallCall::abi_decode(data)?;
+            return Ok(DecodedCall::Multicall {
+                require_success: call._requireSuccess,
+                calls: self.decode_subcalls(&call._calls),
+            });
+        }
+
+        Err(DecodeError::UnknownSelector(Bytes::copy_from_slice(
+            selector,
+        )))
+    }
+
+    /// Decodes a privacy paymaster userop's `paymasterAndData`.
+    pub fn decode_paymaster_and_data(&self, data: &[u8]) -> Result<DecodedCall, DecodeError> {
+        if data.len() < PAYMASTER_DATA_OFFSET {
+            return Err(DecodeError::PaymasterDataTooShort(data.len()));
+        }
+
+        let paymaster = Address::from_slice(&data[..20]);
+        let paymaster_data = PaymasterData::abi_decode(&data[PAYMASTER_DATA_OFFSET..])?;
+        let adapter_data = RailgunFeeAdapter::AdapterData::abi_decode(&paymaster_data.adapterData)?;
+
+        Ok(DecodedCall::Paymaster {
+            paymaster,
+            adapter: paymaster_data.adapter,
+            fee: DecodedFee {
+                asset: AssetId::Erc20(adapter_data.asset),
+                value: U256::from(adapter_data.value),
+            },
+            operations: self.decode_operations(&adapter_data.transactions),
+        })
+    }
+
+    /// Decodes a `RailgunSmartWallet` event log.
+    pub fn decode_log(&self, log: &Log) -> Result<DecodedEvent, DecodeError> {
+        match log.topics().first() {
+            Some(&RailgunSmartWallet::Shield::SIGNATURE_HASH) => {
+                let event = RailgunSmartWallet::Shield::decode_log(log)?;
+                let shields = event
+                    .commitments
+                    .iter()
+                    .zip(&event.shieldCiphertext)
+                    .map(|(preimage, ciphertext)| ShieldRequest {
+                        preimage: preimage.clone(),
+                        ciphertext: ciphertext.clone(),
+                    })
+                    .collect::<Vec<_>>();
+
+                Ok(DecodedEvent::Shield {
+                    tree_number: event.treeNumber,
+                    start_position: event.startPosition,
+                    shields: self.decode_shields(&shields),
+                    fees: event.fees.clone(),
+                })
+            }
+            Some(&RailgunSmartWallet::Transact::SIGNATURE_HASH) => {
+                let event = RailgunSmartWallet::Transact::decode_log(log)?;
+                Ok(DecodedEvent::Transact {
+                    tree_number: event.treeNumber,
+                    start_position: event.startPosition,
+                    commitment_count: event.hash.len(),
+                    outputs: self.decode_outputs(&event.hash, &event.ciphertext),
+                })
+            }
+            Some(&RailgunSmartWallet::Unshield::SIGNATURE_HASH) => {
+                let event = RailgunSmartWallet::Unshield::decode_log(log)?;
+                Ok(DecodedEvent::Unshield {
+                    recipient: event.to,
+                    asset: AssetId::from(event.token.clone()),
+                    amount: event.amount,
+                    fee: event.fee,
+                })
+            }
+            Some(&RailgunSmartWallet::Nullified::SIGNATURE_HASH) => {
+                let event = RailgunSmartWallet::Nullified::decode_log(log)?;
+                Ok(DecodedEvent::Nullified {
+                    tree_number: event.treeNumber,
+                    nullifier_count: event.nullifier.len(),
+                })
+            }
+            topic => Err(DecodeError::UnknownEvent(topic.copied())),
+        }
+    }
+
+    fn decode_operations(&self, transactions: &[Transaction]) -> Vec<DecodedOperation> {
+        transactions
+            .iter()
+            .map(|tx| self.decode_operation(tx))
+            .collect()
+    }
+
+    fn decode_operation(&self, tx: &Transaction) -> DecodedOperation {
+        let bound_params = &tx.boundParams;
+        let unshield_type = match bound_params.unshield {
+            UnshieldType::NONE => UnshieldKind::None,
+            UnshieldType::NORMAL => UnshieldKind::Normal,
+            _ => UnshieldKind::Redirect,
+        };
+
+        //? Unshields are always the last commitment, and have no ciphertext.
+        let unshield = (unshield_type != UnshieldKind::None).then(|| {
+            let preimage = &tx.unshieldPreimage;
+            DecodedUnshield {
+                recipient: Address::from_slice(&preimage.npk[12..]),
+                asset: AssetId::from(preimage.token.clone()),
+                amount: U256::from(preimage.value),
+            }
+        });
+
+        DecodedOperation {
+            merkle_root: tx.merkleRoot,
+            nullifier_count: tx.nullifiers.len(),
+            commitment_count: tx.commitments.len(),
+            bound_params: DecodedBoundParams {
+                tree_number: bound_params.treeNumber,
+                min_gas_price: U256::from(bound_params.minGasPrice),
+                unshield_type,
+                chain_id: bound_params.chainID,
+                adapt_contract: bound_params.adaptContract,
+                adapt_params: bound_params.adaptParams,
+                hash: bound_params.hash(),
+            },
+            unshield,
+            outputs: self.decode_outputs(&tx.commitments, &bound_params.commitmentCiphertext),
+        }
+    }
+
+    fn decode_subcalls(&self, calls: &[RelayAdapt::Call]) -> Vec<DecodedSubcall> {
+        calls
+            .iter()
+            .map(|call| DecodedSubcall {
+                to: call.to,
+                value: call.value,
+                data: call.data.clone(),
+                decoded: self.decode_calldata(&call.data).ok().map(Box::new),
+            })
+            .collect()
+    }
+
+    fn decode_shields(&self, requests: &[ShieldRequest]) -> Vec<DecodedShield> {
+        requests
+            .iter()
+            .map(|request| {
+                let shield_key = ViewingPublicKey::from_bytes(*request.ciphertext.shieldKey);
+                let ciphertext = Ciphertext::from(request.ciphertext.clone());
+                //? Anyone can encrypt a shield to a viewing key, so only a matching npk proves
+                //? the shield pays the account.
+                let received = self.keys.iter().any(|(vk, master_key)| {
+                    let Ok(shared_key) = vk.derive_shared_key(shield_key) else {
+                        return false;
+                    };
+                    let Ok(bundle) = shared_key.decrypt_gcm(&ciphertext) else {
+                        return false;
+                    };
+                    let Some(random) = bundle.first().and_then(|b| b.get(..16)) else {
+                        return false;
+                    };
+                    note_public_key(master_key.to_u256(), random)
+                        == U256::from_be_bytes(request.preimage.npk.0)
+                });
+
+                DecodedShield {
+                    npk: request.preimage.npk,
+                    asset: AssetId::from(request.preimage.token.clone()),
+                    value: U256::from(request.preimage.value),
+                    received,
+                }
+            })
+            .collect()
+    }
+
+    /// Decrypts every output readable by a viewing key. `ciphertexts[i]` belongs
+    /// to `commitments[i]`.
+    fn decode_outputs(
+        &self,
+        commitments: &[FixedBytes<32>],
+        ciphertexts: &[CommitmentCiphertext],
+    ) -> Vec<DecodedOutput> {
+        commitments
+            .iter()
+            .zip(ciphertexts)
+            .filter_map(|(commitment, ciphertext)| self.decrypt_output(*commitment, ciphertext))
+            .collect()
+    }
+
+    /// Tries each viewing key as the receiver and then as the sender. The blinded
+    /// keys share a blinding factor, so either side derives the same shared key.
+    ///
+    /// Outputs whose plaintext doesn't hash to `commitment` are dropped, since the
+    /// ciphertext is chosen by whoever built the transaction and isn't proved.
+    /// Received outputs are checked against the account's own master public key.
+    fn decrypt_output(
+        &self,
+        commitment: B256,
+        ciphertext: &CommitmentCiphertext,
+    ) -> Option<DecodedOutput> {
+        let blinded_sender = BlindedKey::from_bytes(*ciphertext.blindedSenderViewingKey);
+        let blinded_receiver = BlindedKey::from_bytes(*ciphertext.blindedReceiverViewingKey);
+        let data = Ciphertext::from(ciphertext.clone());
+
+        let candidates = [
+            (blinded_sender, OutputDirection::Received),
+            (blinded_receiver, OutputDirection::Sent),
+        ];
+        self.keys.iter().find_map(|(vk, master_key)| {
+            candidates.iter().find_map(|(blinded, direction)| {
+                let shared_key = vk.derive_shared_key_blinded(*blinded).ok()?;
+                let plaintext = TransactPlaintext::decrypt(&shared_key, &data).ok()?;
+
+                let receivers = match direction {
+                    OutputDirection::Received => vec![master_key.to_u256()],
+                    //? The receiver's key is XORed with the sender's unless the sender is hidden.
+                    OutputDirection::Sent => {
+                        let encoded = U256::from_be_bytes(plaintext.encoded_master_key);
+                        vec![encoded, encoded ^ master_key.to_u256()]
+                    }
+                };
+                let matches = receivers.into_iter().any(|receiver| {
+                    let npk = note_public_key(receiver, &plaintext.random);
+                    note_commitment(npk, &plaintext.asset, plaintext.value) == commitment
+                });
+                if !matches {
+                    return None;
+                }
+
+                Some(DecodedOutput {
+                    commitment,
+                    direction: *direction,
+                    asset: plaintext.asset,
+                    value: plaintext.value,
+                    memo: plaintext.memo,
+                })
+            })
+        })
+    }
+}
+
+/// `poseidon(masterPublicKey, random)`, the npk a note pays.
+fn note_public_key(master_key: U256, random: &[u8]) -> U256 {
+    poseidon_hash(&[master_key, U256::from_be_slice(random)]).unwrap()
+}
+
+/// `poseidon(npk, tokenHash, value)`, the commitment of a note.
+fn note_commitment(npk: U256, asset: &AssetId, value: u128) -> B256 {
+    B256::from(poseidon_hash(&[npk, asset.hash(), U256::from(value)]).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{address, aliases::U120};
+    use rand_chacha::{ChaChaRng, rand_core::SeedableRng};
+
+    use super::*;
+    use crate::{
+        abis::railgun::{BoundParams, CommitmentPreimage, G1Point, G2Point, SnarkProof},
+        account::{address::RailgunAddress, chain::ChainId},
+        adapter_data::{encode_paymaster_data, encode_railgun_adapter_data},
+        crypto::keys::SpendingKey,
+        note::encrypt::{encrypt_note, encrypt_shield},
+    };
+
+    const ASSET: AssetId = AssetId::Erc20(address!("0xeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee"));
+
+    fn sender_key() -> ViewingKey {
+        ViewingKey::from_bytes([2u8; 32])
+    }
+
+    fn receiver_key() -> ViewingKey {
+        ViewingKey::from_bytes([4u8; 32])
+    }
+
+    fn sender() -> RailgunAddress {
+        RailgunAddress::from_private_keys(
+            SpendingKey::from_bytes([1u8; 32]),
+            sender_key(),
+            ChainId::evm(1),
+        )
+    }
+
+    fn receiver() -> RailgunAddress {
+        RailgunAddress::from_private_keys(
+            SpendingKey::from_bytes([3u8; 32]),
+            receiver_key(),
+            ChainId::evm(1),
+        )
+    }
+
+    fn receiver_decoder() -> Decoder {
+        Decoder::new().with_viewing_key(receiver_key(), receiver().master_key())
+    }
+
+    /// The commitment `output_ciphertext` describes.
+    fn output_commitment() -> B256 {
+        let npk = note_public_key(receiver().master_key().to_u256(), &[5u8; 16]);
+        note_commitment(npk, &ASSET, 250)
+    }
+
+    /// A 250 unit output from the sender to the receiver.
+    fn output_ciphertext() -> CommitmentCiphertext {
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        encrypt_note(
+            &receiver(),
+            &[5u8; 16],
+            250,
+            &ASSET,
+            "hello",
+            sender_key(),
+            true,
+            &mut rng,
+        )
+        .unwrap()
+    }
+
+    fn shield_request() -> ShieldRequest {
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        encrypt_shield(receiver(), ASSET, 1000, &mut rng).unwrap()
+    }
+
+    fn log(data: impl SolEvent) -> Log {
+        Log {
+            address: Address::ZERO,
+            data: data.encode_log_data(),
+        }
+    }
+
+    fn unshield_transaction(recipient: Address) -> Transaction {
+        let g1 = || G1Point {
+            x: U256::ZERO,
+            y: U256::ZERO,
+        };
+        let proof = SnarkProof {
+            a: g1(),
+            b: G2Point {
+                x: [U256::ZERO; 2],
+                y: [U256::ZERO; 2],
+            },
+            c: g1(),
+        };
+        let preimage = CommitmentPreimage {
+            npk: recipient.into_word(),
+            token: AssetId::Erc20(Address::repeat_byte(0xee)).into(),
+            value: U120::from(1000),
+        };
+
+        Transaction::new(
+            proof,
+            B256::ZERO,
+            vec![B256::ZERO; 2],
+            vec![B256::ZERO],
+            BoundParams::new(
+                0,
+                0,
+                UnshieldType::NORMAL,
+                1,
+                Address::ZERO,
+                &[0u8; 32],
+                vec![],
+            ),
+            preimage,
+        )
+    }
+
+    #[test]
+    fn test_decode_transact() {
+        let recipient = address!("0x1111111111111111111111111111111111111111");
+        let data = RailgunSmartWallet::transactCall {
+            _transactions: vec![unshield_transaction(recipient)],
+        }
+        .abi_encode();
+
+        let DecodedCall::Transact { operations } = Decoder::new().decode_calldata(&data).unwrap()
+        else {
+            panic!("Expected a transact call");
+        };
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].nullifier_count, 2);
+        assert_eq!(
+            operations[0].bound_params.unshield_type,
+            UnshieldKind::Normal
+        );
+
+        let unshield = operations[0].unshield.as_ref().unwrap();
+        assert_eq!(unshield.recipient, recipient);
+        assert_eq!(unshield.amount, U256::from(1000));
+
+        assert!(matches!(
+            Decoder::new().decode_calldata(&[0xde, 0xad, 0xbe, 0xef]),
+            Err(DecodeError::UnknownSelector(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_paymaster_and_data() {
+        let paymaster = Address::repeat_byte(0x22);
+        let adapter = Address::repeat_byte(0x33);
+        let fee_token = Address::repeat_byte(0x44);
+        let paymaster_data = encode_paymaster_data(
+            adapter,
+            encode_railgun_adapter_data(
+                [0u8; 16],
+                fee_token,
+                500,
+                vec![unshield_transaction(Address::ZERO)],
+            ),
+        );
+
+        let mut data = paymaster.to_vec();
+        data.extend_from_slice(&[0u8; 32]);
+        data.extend_from_slice(&paymaster_data);
+
+        let DecodedCall::Paymaster {
+            paymaster: decoded_paymaster,
+            adapter: decoded_adapter,
+            fee,
+            operations,
+        } = Decoder::new().decode_paymaster_and_data(&data).unwrap()
+        else {
+            panic!("Expected paymaster data");
+        };
+        assert_eq!(decoded_paymaster, paymaster);
+        assert_eq!(decoded_adapter, adapter);
+        assert_eq!(fee.asset, AssetId::Erc20(fee_token));
+        assert_eq!(fee.value, U256::from(500));
+        assert_eq!(operations.len(), 1);
+    }
+
+    #[test]
+    fn test_decrypt_outputs() {
+        let outputs_for = |decoder: Decoder, commitment: B256| {
+            let mut tx = unshield_transaction(Address::ZERO);
+            tx.commitments = vec![commitment, B256::ZERO];
+            tx.boundParams.commitmentCiphertext = vec![output_ciphertext()];
+            let data = RailgunSmartWallet::transactCall {
+                _transactions: vec![tx],
+            }
+            .abi_encode();
+
+            let DecodedCall::Transact { operations } = decoder.decode_calldata(&data).unwrap()
+            else {
+                panic!("Expected a transact call");
+            };
+            operations[0].outputs.clone()
+        };
+        let outputs = |decoder: Decoder| outputs_for(decoder, output_commitment());
+
+        let received = outputs(receiver_decoder());
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].commitment, output_commitment());
+        assert_eq!(received[0].direction, OutputDirection::Received);
+        assert_eq!(received[0].asset, ASSET);
+        assert_eq!(received[0].value, 250);
+        assert_eq!(received[0].memo, "hello");
+
+        let sent = outputs(Decoder::new().with_viewing_key(sender_key(), sender().master_key()));
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].direction, OutputDirection::Sent);
+        assert_eq!(sent[0].value, 250);
+
+        let stranger = ViewingKey::from_bytes([9u8; 32]);
+        let stranger = Decoder::new().with_viewing_key(stranger, receiver().master_key());
+        assert!(outputs(stranger).is_empty());
+        assert!(outputs(Decoder::new()).is_empty());
+
+        // A ciphertext describing a different note than the commitment is dropped.
+        let forged = output_commitment().0.map(|b| b ^ 1).into();
+        assert!(outputs_for(receiver_decoder(), forged).is_empty());
+
+        // As is a note paying someone else, even if it's encrypted to the receiver.
+        let sender_decoder = Decoder::new().with_viewing_key(receiver_key(), sender().master_key());
+        assert!(outputs(sender_decoder).is_empty());
+    }
+
+    #[test]
+    fn test_decode_shield() {
+        let request = shield_request();
+        let data = RailgunSmartWallet::shieldCall {
+            _shieldRequests: vec![request.clone()],
+        }
+        .abi_encode();
+
+        let shields = |decoder: Decoder| {
+            let DecodedCall::Shield { shields } = decoder.decode_calldata(&data).unwrap() else {
+                panic!("Expected a shield call");
+            };
+            shields
+        };
+
+        let received = shields(receiver_decoder());
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].npk, request.preimage.npk);
+        assert_eq!(received[0].asset, ASSET);
+        assert_eq!(received[0].value, U256::from(1000));
+        assert!(received[0].received);
+
+        let other = shields(Decoder::new().with_viewing_key(sender_key(), sender().master_key()));
+        assert!(!other[0].received);
+
+        // A shield encrypted to the receiver's viewing key but paying another npk.
+        let mut forged = request.clone();
+        forged.preimage.npk = B256::repeat_byte(0x01);
+        let data = RailgunSmartWallet::shieldCall {
+            _shieldRequests: vec![forged],
+        }
+        .abi_encode();
+        let DecodedCall::Shield { shields } = receiver_decoder().decode_calldata(&data).unwrap()
+        else {
+            panic!("Expected a shield call");
+        };
+        assert!(!shields[0].received);
+    }
+
+    #[test]
+    fn test_decode_relay_and_multicall() {
+        let adapt = Address::repeat_byte(0x55);
+        let shield_data = RailgunSmartWallet::shieldCall {
+            _shieldRequests: vec![shield_request()],
+        }
+        .abi_encode();
+        let calls = vec![
+            RelayAdapt::Call {
+                to: adapt,
+                data: shield_data.into(),
+                value: U256::ZERO,
+            },
+            RelayAdapt::Call {
+                to: Address::repeat_byte(0x66),
+                data: Bytes::from_static(&[0xde, 0xad, 0xbe, 0xef]),
+                value: U256::from(7),
+            },
+        ];
+
+        let relay = RelayAdapt::relayCall {
+            _transactions: vec![unshield_transaction(adapt)],
+            _actionData: RelayAdapt::ActionData {
+                random: FixedBytes::ZERO,
+                requireSuccess: true,
+                minGasLimit: U256::from(100_000),
+                calls: calls.clone(),
+            },
+        }
+        .abi_encode();
+
+        let DecodedCall::Relay {
+            operations,
+            require_success,
+            min_gas_limit,
+            calls: decoded,
+        } = Decoder::new().decode_calldata(&relay).unwrap()
+        else {
+            panic!("Expected a relay call");
+        };
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].unshield.as_ref().unwrap().recipient, adapt);
+        assert!(require_success);
+        assert_eq!(min_gas_limit, U256::from(100_000));
+        assert_eq!(decoded.len(), 2);
+        assert!(matches!(
+            decoded[0].decoded.as_deref(),
+            Some(DecodedCall::Shield { shields }) if shields.len() == 1
+        ));
+        assert_eq!(decoded[1].value, U256::from(7));
+        assert!(decoded[1].decoded.is_none());
+
+        let multicall = RelayAdapt::multicallCall {
+            _requireSuccess: false,
+            _calls: calls,
+        }
+        .abi_encode();
+        let DecodedCall::Multicall {
+            require_success,
+            calls: decoded,
+        } = Decoder::new().decode_calldata(&multicall).unwrap()
+        else {
+            panic!("Expected a multicall");
+        };
+        assert!(!require_success);
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].to, adapt);
+        assert!(decoded[0].decoded.is_some());
+    }
+
+    #[test]
+    fn test_decode_log() {
+        let decoder = receiver_decoder();
+
+        let transact = log(RailgunSmartWallet::Transact {
+            treeNumber: U256::from(1),
+            startPosition: U256::from(10),
+            hash: vec![output_commitment()],
+            ciphertext: vec![output_ciphertext()],
+        });
+        let DecodedEvent::Transact {
+            tree_number,
+            start_position,
+            commitment_count,
+            outputs,
+        } = decoder.decode_log(&transact).unwrap()
+        else {
+            panic!("Expected a transact event");
+        };
+        assert_eq!(tree_number, U256::from(1));
+        assert_eq!(start_position, U256::from(10));
+        assert_eq!(commitment_count, 1);
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].direction, OutputDirection::Received);
+        assert_eq!(outputs[0].value, 250);
+
+        let request = shield_request();
+        let shield = log(RailgunSmartWallet::Shield {
+            treeNumber: U256::ZERO,
+            startPosition: U256::from(3),
+            commitments: vec![request.preimage.clone()],
+            shieldCiphertext: vec![request.ciphertext.clone()],
+            fees: vec![U256::from(2)],
+        });
+        let DecodedEvent::Shield { shields, fees, .. } = decoder.decode_log(&shield).unwrap()
+        else {
+            panic!("Expected a shield event");
+        };
+        assert_eq!(shields.len(), 1);
+        assert!(shields[0].received);
+        assert_eq!(fees, vec![U256::from(2)]);
+
+        let recipient = Address::repeat_byte(0x11);
+        let unshield = log(RailgunSmartWallet::Unshield {
+            to: recipient,
+            token: ASSET.into(),
+            amount: U256::from(900),
+            fee: U256::from(2),
+        });
+        let DecodedEvent::Unshield {
+            recipient: decoded_recipient,
+            asset,
+            amount,
+            fee,
+        } = decoder.decode_log(&unshield).unwrap()
+        else {
+            panic!("Expected an unshield event");
+        };
+        assert_eq!(decoded_recipient, recipient);
+        assert_eq!(asset, ASSET);
+        assert_eq!(amount, U256::from(900));
+        assert_eq!(fee, U256::from(2));
+
+        let nullified = log(RailgunSmartWallet::Nullified {
+            treeNumber: 2,
+            nullifier: vec![B256::ZERO; 3],
+        });
+        let DecodedEvent::Nullified {
+            tree_number,
+            nullifier_count,
+        } = decoder.decode_log(&nullified).unwrap()
+        else {
+            panic!("Expected a nullified event");
+        };
+        assert_eq!(tree_number, 2);
+        assert_eq!(nullifier_count, 3);
+
+        let unknown = Log::new_unchecked(Address::ZERO, vec![B256::ZERO], Bytes::new());
+        assert!(matches!(
+            decoder.decode_log(&unknown),
+            Err(DecodeError::UnknownEvent(Some(B256::ZERO)))
+        ));
+    }
+}
@@ -51,11 +51,19 @@ impl IndexedAccount {
         self.inner.synced_block
     }
 
+    pub fn set_state(&mut self, state: IndexedAccountState) {
+        self.inner = state;
+    }
+
     pub fn set_synced_block(&mut self, block: u64) {
         self.inner.synced_block = block;
     }
 
-    pub fn handle_shield_event(&mut self, event: &syncer::Shield) -> Result<(), NoteError> {
+    pub fn handle_shield_event(
+        &mut self,
+        event: &syncer::Shield,
+        block_number: u64,
+    ) -> Result<(), NoteError> {
         let note = UtxoNote::decrypt_shield(self.signer.clone(), event);
         let note = match note {
             Err(NoteError::Aes(_)) => {
@@ -68,7 +76,10 @@ impl IndexedAccount {
                 );
                 return Ok(());
             }
-            Ok(n) => n,
+            Ok(n) => UtxoNote {
+                shield_block: Some(block_number),
+                ..n
+            },
         };
 
         info!(?note, "Decrypted Shield Note");
@@ -149,7 +160,7 @@ mod tests {
             hash: None,
         };
 
-        account.handle_shield_event(&event).unwrap();
+        account.handle_shield_event(&event, 0).unwrap();
         let notes = account.unspent();
         assert_eq!(notes.len(), 1);
 
@@ -172,7 +183,7 @@ mod tests {
             hash: None,
         };
 
-        account.handle_shield_event(&other_event).unwrap();
+        account.handle_shield_event(&other_event, 0).unwrap();
         let notes = account.unspent();
         assert_eq!(notes.len(), 1); // Should still only have the first note
 
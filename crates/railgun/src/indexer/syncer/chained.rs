@@ -1,14 +1,58 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, future::Future, sync::Arc};
+
+use thiserror::Error;
+use web_time::Duration;
 
 use crate::indexer::syncer::{SyncEvent, SyncerError, UtxoSyncer};
 
 /// Helper syncer that chains multiple UTXO syncers together.
 ///
 /// Syncers are queried in the order they are added, and the sync range is adjusted based on the
-/// latest block of each syncer.
+/// latest block of each syncer. If a syncer fails for a range after exhausting its retry policy,
+/// or returns leaves with gaps, the same range falls through to the next syncer. If no syncer can
+/// provide a range the whole sync fails, so a failure never leaves a hole in the trees.
+///
+/// Each range is checked against the leaves before it, i.e. the stored trees passed to
+/// [`UtxoSyncer::sync_after`] and the ranges already synced, so a leaf dropped at a range
+/// boundary also falls through.
 #[derive(Default)]
 pub struct ChainedSyncer {
-    syncers: Vec<Arc<dyn UtxoSyncer>>,
+    syncers: Vec<(Arc<dyn UtxoSyncer>, RetryPolicy)>,
+}
+
+/// How often a syncer in a [`ChainedSyncer`] is retried before falling through to the next one.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total attempts per call, including the first.
+    pub max_attempts: u32,
+    /// Delay before the first retry. Doubles after each failed attempt.
+    pub backoff: Duration,
+}
+
+/// Next expected leaf index of each UTXO tree.
+#[derive(Debug, Clone, Default)]
+struct NextLeaves {
+    next: BTreeMap<u32, u32>,
+    /// Whether trees missing from `next` are known to be empty. Otherwise their first leaf is
+    /// unconstrained.
+    known: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum ChainedSyncerError {
+    #[error("No syncer could sync blocks {from_block}-{to_block}")]
+    Unavailable {
+        from_block: u64,
+        to_block: u64,
+        #[source]
+        source: Option<SyncerError>,
+    },
+    #[error("Gap in UTXO tree {tree_number}: expected leaf {expected}, found {found}")]
+    LeafGap {
+        tree_number: u32,
+        expected: u32,
+        found: u32,
+    },
 }
 
 impl ChainedSyncer {
@@ -18,25 +62,77 @@ impl ChainedSyncer {
         }
     }
 
-    /// Adds a syncer to the chain. Syncers are queried in the order they are added.
-    pub fn then<S: UtxoSyncer + 'static>(mut self, syncer: S) -> Self {
-        self.syncers.push(Arc::new(syncer));
-        self
+    /// Adds a syncer to the chain with the default retry policy. Syncers are queried in the order
+    /// they are added.
+    pub fn then<S: UtxoSyncer + 'static>(self, syncer: S) -> Self {
+        self.then_arc(Arc::new(syncer))
+    }
+
+    /// Adds a syncer to the chain with the default retry policy. Syncers are queried in the order
+    /// they are added.
+    pub fn then_arc(self, syncer: Arc<dyn UtxoSyncer>) -> Self {
+        self.then_arc_with_retry(syncer, RetryPolicy::default())
+    }
+
+    /// Adds a syncer to the chain with a custom retry policy.
+    pub fn then_with_retry<S: UtxoSyncer + 'static>(self, syncer: S, retry: RetryPolicy) -> Self {
+        self.then_arc_with_retry(Arc::new(syncer), retry)
     }
 
-    /// Adds a syncer to the chain. Syncers are queried in the order they are added.
-    pub fn then_arc(mut self, syncer: Arc<dyn UtxoSyncer>) -> Self {
-        self.syncers.push(syncer);
+    /// Adds a syncer to the chain with a custom retry policy.
+    pub fn then_arc_with_retry(mut self, syncer: Arc<dyn UtxoSyncer>, retry: RetryPolicy) -> Self {
+        self.syncers.push((syncer, retry));
         self
     }
 }
 
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, backoff: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            backoff,
+        }
+    }
+
+    /// Never retries. Failures fall through to the next syncer immediately.
+    pub fn none() -> Self {
+        Self::new(1, Duration::ZERO)
+    }
+
+    async fn run<T, F, Fut>(&self, mut f: F) -> Result<T, SyncerError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, SyncerError>>,
+    {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match f().await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt >= self.max_attempts => return Err(e),
+                Err(e) => {
+                    tracing::debug!("Attempt {} failed, retrying: {}", attempt, e);
+                    common::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(2, Duration::from_secs(1))
+    }
+}
+
 #[cfg_attr(native, async_trait::async_trait)]
 #[cfg_attr(wasm, async_trait::async_trait(?Send))]
 impl UtxoSyncer for ChainedSyncer {
     async fn latest_block(&self) -> Result<u64, SyncerError> {
         let mut max_block = 0u64;
-        for syncer in &self.syncers {
+        for (syncer, _) in &self.syncers {
             if let Ok(block) = syncer.latest_block().await {
                 max_block = max_block.max(block);
             }
@@ -45,30 +141,337 @@ impl UtxoSyncer for ChainedSyncer {
     }
 
     async fn sync(&self, from_block: u64, to_block: u64) -> Result<Vec<SyncEvent>, SyncerError> {
+        self.sync_checked(from_block, to_block, NextLeaves::default())
+            .await
+    }
+
+    async fn sync_after(
+        &self,
+        from_block: u64,
+        to_block: u64,
+        tree_lengths: &BTreeMap<u32, u32>,
+    ) -> Result<Vec<SyncEvent>, SyncerError> {
+        let next_leaves = NextLeaves {
+            next: tree_lengths.clone(),
+            known: true,
+        };
+        self.sync_checked(from_block, to_block, next_leaves).await
+    }
+}
+
+impl ChainedSyncer {
+    async fn sync_checked(
+        &self,
+        from_block: u64,
+        to_block: u64,
+        mut next_leaves: NextLeaves,
+    ) -> Result<Vec<SyncEvent>, SyncerError> {
         let mut current_from = from_block;
 
         let mut all_events = Vec::new();
-        for (i, syncer) in self.syncers.iter().enumerate() {
-            if current_from > to_block {
-                break;
-            }
+        while current_from <= to_block {
+            let mut last_error = None;
+            let mut synced_to = None;
 
-            let syncer_latest = syncer.latest_block().await?;
-            if syncer_latest < current_from {
-                continue;
-            }
+            for (i, (syncer, retry)) in self.syncers.iter().enumerate() {
+                let syncer_latest = match retry.run(|| syncer.latest_block()).await {
+                    Ok(block) => block,
+                    Err(e) => {
+                        tracing::warn!("Syncer {} failed to fetch latest block: {}", i, e);
+                        last_error = Some(e);
+                        continue;
+                    }
+                };
+                if syncer_latest < current_from {
+                    continue;
+                }
 
-            let range_end = syncer_latest.min(to_block);
-            match syncer.sync(current_from, range_end).await {
-                Ok(events) => all_events.extend(events),
-                Err(e) => {
-                    tracing::warn!("Syncer {} failed: {}", i, e);
+                let range_end = syncer_latest.min(to_block);
+                let expected = &next_leaves;
+                let result = retry
+                    .run(move || async move {
+                        let events = syncer.sync(current_from, range_end).await?;
+                        let next = check_contiguous(&events, expected).map_err(SyncerError::new)?;
+                        Ok((events, next))
+                    })
+                    .await;
+
+                match result {
+                    Ok((events, next)) => {
+                        all_events.extend(events);
+                        next_leaves = next;
+                        synced_to = Some(range_end);
+                        break;
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Syncer {} failed for blocks {}-{}: {}",
+                            i,
+                            current_from,
+                            range_end,
+                            e
+                        );
+                        last_error = Some(e);
+                    }
                 }
             }
 
-            current_from = range_end + 1;
+            let Some(synced_to) = synced_to else {
+                return Err(SyncerError::new(ChainedSyncerError::Unavailable {
+                    from_block: current_from,
+                    to_block,
+                    source: last_error,
+                }));
+            };
+            current_from = synced_to + 1;
         }
 
         Ok(all_events)
     }
 }
+
+/// Checks that the leaves in `events` have contiguous indices within each tree, continuing from
+/// `next_leaves`. Leaves before the next expected one are allowed, since ranges may overlap
+/// leaves that are already stored. Returns the next expected leaves after `events`.
+fn check_contiguous(
+    events: &[SyncEvent],
+    next_leaves: &NextLeaves,
+) -> Result<NextLeaves, ChainedSyncerError> {
+    let mut trees: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    for event in events {
+        let (tree_number, leaf_index) = match event {
+            SyncEvent::Shield(shield, _) => (shield.tree_number, shield.leaf_index),
+            SyncEvent::Transact(transact, _) => (transact.tree_number, transact.leaf_index),
+            SyncEvent::Legacy(legacy, _) => (legacy.tree_number, legacy.leaf_index),
+            SyncEvent::Nullified(..) => continue,
+        };
+        trees.entry(tree_number).or_default().push(leaf_index);
+    }
+
+    let mut advanced = next_leaves.clone();
+    for (tree_number, mut leaves) in trees {
+        leaves.sort_unstable();
+        leaves.dedup();
+
+        let expected = match next_leaves.next.get(&tree_number) {
+            Some(next) => Some(*next),
+            None if next_leaves.known => Some(0),
+            None => None,
+        };
+        if let Some(expected) = expected {
+            if leaves[0] > expected {
+                return Err(ChainedSyncerError::LeafGap {
+                    tree_number,
+                    expected,
+                    found: leaves[0],
+                });
+            }
+        }
+
+        for pair in leaves.windows(2) {
+            if pair[1] != pair[0] + 1 {
+                return Err(ChainedSyncerError::LeafGap {
+                    tree_number,
+                    expected: pair[0] + 1,
+                    found: pair[1],
+                });
+            }
+        }
+
+        let next = advanced.next.entry(tree_number).or_default();
+        *next = (*next).max(leaves[leaves.len() - 1] + 1);
+    }
+
+    Ok(advanced)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use ruint::aliases::U256;
+
+    use super::*;
+    use crate::indexer::syncer::LegacyCommitment;
+
+    /// Syncer that serves one leaf per block in tree 0, optionally skipping a block's leaf or
+    /// failing its first `failures` calls.
+    struct MockSyncer {
+        latest: u64,
+        skip: Option<u64>,
+        failures: AtomicU32,
+        calls: AtomicU32,
+    }
+
+    impl MockSyncer {
+        fn new(latest: u64) -> Self {
+            Self {
+                latest,
+                skip: None,
+                failures: AtomicU32::new(0),
+                calls: AtomicU32::new(0),
+            }
+        }
+
+        fn failing(mut self, failures: u32) -> Self {
+            self.failures = AtomicU32::new(failures);
+            self
+        }
+
+        fn skipping(mut self, block: u64) -> Self {
+            self.skip = Some(block);
+            self
+        }
+    }
+
+    #[derive(Debug, Error)]
+    #[error("mock failure")]
+    struct MockError;
+
+    #[async_trait::async_trait]
+    impl UtxoSyncer for MockSyncer {
+        async fn latest_block(&self) -> Result<u64, SyncerError> {
+            Ok(self.latest)
+        }
+
+        async fn sync(
+            &self,
+            from_block: u64,
+            to_block: u64,
+        ) -> Result<Vec<SyncEvent>, SyncerError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            let failures = self.failures.load(Ordering::Relaxed);
+            if failures > 0 {
+                self.failures.store(failures - 1, Ordering::Relaxed);
+                return Err(SyncerError::new(MockError));
+            }
+
+            Ok((from_block..=to_block)
+                .filter(|block| Some(*block) != self.skip)
+                .map(|block| {
+                    SyncEvent::Legacy(
+                        LegacyCommitment {
+                            hash: U256::from(block),
+                            tree_number: 0,
+                            leaf_index: block as u32,
+                        },
+                        block,
+                    )
+                })
+                .collect())
+        }
+    }
+
+    fn leaves(events: &[SyncEvent]) -> Vec<u32> {
+        events
+            .iter()
+            .map(|e| match e {
+                SyncEvent::Legacy(legacy, _) => legacy.leaf_index,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_failure_falls_through() {
+        let primary = Arc::new(MockSyncer::new(50).failing(u32::MAX));
+        let fallback = Arc::new(MockSyncer::new(100));
+        let chained = ChainedSyncer::new()
+            .then_arc_with_retry(primary.clone(), RetryPolicy::new(3, Duration::ZERO))
+            .then_arc(fallback);
+
+        let events = chained.sync(0, 100).await.unwrap();
+        assert_eq!(leaves(&events), (0..=100).collect::<Vec<_>>());
+        assert_eq!(primary.calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_recovers() {
+        let primary = Arc::new(MockSyncer::new(50).failing(1));
+        let fallback = Arc::new(MockSyncer::new(100));
+        let chained = ChainedSyncer::new()
+            .then_arc_with_retry(primary, RetryPolicy::new(2, Duration::ZERO))
+            .then_arc(fallback.clone());
+
+        let events = chained.sync(0, 100).await.unwrap();
+        assert_eq!(leaves(&events), (0..=100).collect::<Vec<_>>());
+        // The fallback only served the blocks past the primary's head.
+        assert_eq!(fallback.calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_gap_falls_through() {
+        let chained = ChainedSyncer::new()
+            .then_with_retry(MockSyncer::new(100).skipping(10), RetryPolicy::none())
+            .then_with_retry(MockSyncer::new(100), RetryPolicy::none());
+
+        let events = chained.sync(0, 100).await.unwrap();
+        assert_eq!(leaves(&events), (0..=100).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_unfillable_range_errors() {
+        let chained = ChainedSyncer::new()
+            .then_with_retry(MockSyncer::new(50), RetryPolicy::none())
+            .then_with_retry(MockSyncer::new(100).failing(u32::MAX), RetryPolicy::none());
+
+        let err = chained.sync(0, 100).await.unwrap_err();
+        assert!(err.to_string().contains("blocks 51-100"));
+    }
+
+    #[tokio::test]
+    async fn test_boundary_gap_falls_through() {
+        // The second syncer drops the first leaf after the primary's range.
+        let chained = ChainedSyncer::new()
+            .then_with_retry(MockSyncer::new(50), RetryPolicy::none())
+            .then_with_retry(MockSyncer::new(100).skipping(51), RetryPolicy::none())
+            .then_with_retry(MockSyncer::new(100), RetryPolicy::none());
+
+        let events = chained.sync(0, 100).await.unwrap();
+        assert_eq!(leaves(&events), (0..=100).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_checks_stored_trees() {
+        let chained =
+            ChainedSyncer::new().then_with_retry(MockSyncer::new(100), RetryPolicy::none());
+
+        let stored = BTreeMap::from([(0, 10)]);
+        assert!(chained.sync_after(12, 100, &stored).await.is_err());
+        assert!(chained.sync_after(12, 100, &BTreeMap::new()).await.is_err());
+
+        let stored = BTreeMap::from([(0, 12)]);
+        let events = chained.sync_after(8, 100, &stored).await.unwrap();
+        assert_eq!(leaves(&events), (8..=100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_check_contiguous() {
+        let event = |tree_number, leaf_index| {
+            SyncEvent::Legacy(
+                LegacyCommitment {
+                    hash: U256::ZERO,
+                    tree_number,
+                    leaf_index,
+                },
+                0,
+            )
+        };
+
+        let unknown = NextLeaves::default();
+        let next = check_contiguous(
+            &[event(0, 5), event(0, 6), event(1, 0), event(0, 7)],
+            &unknown,
+        )
+        .unwrap();
+        assert_eq!(next.next, BTreeMap::from([(0, 8), (1, 1)]));
+        assert!(matches!(
+            check_contiguous(&[event(0, 5), event(0, 7)], &unknown),
+            Err(ChainedSyncerError::LeafGap {
+                tree_number: 0,
+                expected: 6,
+                found: 7,
+            })
+        ));
+    }
+}
@@ -1,4 +1,4 @@
-pub use chained::ChainedSyncer;
+pub use chained::{ChainedSyncer, ChainedSyncerError, RetryPolicy};
 use crypto::poseidon_hash;
 pub use rpc::RpcSyncer;
 pub use subsquid::SubsquidSyncer;
@@ -9,12 +9,18 @@ mod rpc;
 mod subsquid;
 mod subsquid_types;
 
+use std::collections::BTreeMap;
+
 use alloy::primitives::FixedBytes;
 use ruint::aliases::U256;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{caip::AssetId, crypto::aes::Ciphertext, merkle_tree::UtxoLeafHash};
+use crate::{
+    caip::AssetId,
+    crypto::{aes::Ciphertext, railgun_txid::Txid},
+    merkle_tree::UtxoLeafHash,
+};
 
 /// Syncers that emit note-level events.
 #[cfg_attr(native, async_trait::async_trait)]
@@ -22,6 +28,18 @@ use crate::{caip::AssetId, crypto::aes::Ciphertext, merkle_tree::UtxoLeafHash};
 pub trait UtxoSyncer: common::MaybeSend {
     async fn latest_block(&self) -> Result<u64, SyncerError>;
     async fn sync(&self, from_block: u64, to_block: u64) -> Result<Vec<SyncEvent>, SyncerError>;
+
+    /// Like [`UtxoSyncer::sync`], given the number of leaves already stored in each UTXO tree so
+    /// implementations can check that the returned leaves continue them. Trees missing from
+    /// `tree_lengths` are empty.
+    async fn sync_after(
+        &self,
+        from_block: u64,
+        to_block: u64,
+        _tree_lengths: &BTreeMap<u32, u32>,
+    ) -> Result<Vec<SyncEvent>, SyncerError> {
+        self.sync(from_block, to_block).await
+    }
 }
 
 /// Syncers that fetch full operation data.
@@ -100,6 +118,16 @@ impl SyncerError {
     }
 }
 
+impl Operation {
+    pub fn txid(&self) -> Txid {
+        Txid::new(
+            &self.nullifiers,
+            &self.commitment_hashes,
+            self.bound_params_hash,
+        )
+    }
+}
+
 impl Shield {
     pub fn hash(&self) -> UtxoLeafHash {
         if let Some(hash) = self.hash {
@@ -1,26 +1,83 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 
-use alloy::sol_types::SolEvent;
-use eip_1193_provider::provider::{Eip1193Error, Eip1193Provider, IntoEip1193Provider, RawLog};
+use alloy::{
+    primitives::{Address, B256, Bytes, FixedBytes, U256},
+    sol_types::{SolCall, SolEvent},
+};
+use eip_1193_provider::{
+    middleware::RateLimitedProvider,
+    provider::{
+        Eip1193Blocks, Eip1193Error, Eip1193Logs, Eip1193Provider, Eip1193Request,
+        Eip1193Transactions, IntoEip1193Provider, RawLog,
+    },
+};
+use futures::future::join_all;
+use serde::Deserialize;
+use serde_json::json;
 use tracing::{info, warn};
+use web_time::Duration;
 
 use crate::{
-    abis::railgun::RailgunSmartWallet,
+    abis::{
+        railgun::{RailgunSmartWallet, RelayAdapt, Transaction, UnshieldType},
+        wrappers::{EntryPoint, SmartAccount},
+    },
     chain_config::ChainConfig,
     indexer::syncer::{
-        self, SyncEvent, SyncerError, UtxoSyncer, normalize_tree_position::normalize_tree_position,
+        self, Operation, SyncEvent, SyncerError, TxidSyncer, UtxoSyncer,
+        normalize_tree_position::normalize_tree_position,
     },
 };
 
+/// Events requested from the node. Unshield events are not needed since spent notes are tracked
+/// via Nullified events.
+const EVENT_SIGNATURES: [FixedBytes<32>; 3] = [
+    RailgunSmartWallet::Shield::SIGNATURE_HASH,
+    RailgunSmartWallet::Transact::SIGNATURE_HASH,
+    RailgunSmartWallet::Nullified::SIGNATURE_HASH,
+];
+
+/// UTXO tree and position recorded for transactions that only unshield, since they add no
+/// leaves to the UTXO tree. Matches the engine's `GLOBAL_UTXO_TREE_UNSHIELD_EVENT_HARDCODED_VALUE`
+/// and `GLOBAL_UTXO_POSITION_UNSHIELD_EVENT_HARDCODED_VALUE`, which TXID leaves commit to.
+const UNSHIELD_ONLY_POSITION: u32 = 99999;
+
+/// How many wrapper calls deep to look for `transact` and `relay` calls, e.g. EntryPoint
+/// `handleOps` into a smart account `executeBatch` into a RelayAdapt `multicall`.
+const MAX_WRAPPER_DEPTH: usize = 4;
+
 /// JSON-RPC UTXO syncer.
 ///
 /// Queries an Ethereum node for events emitted by the RailgunSmartWallet and parses them into
 /// SyncEvents.
+///
+/// The block range of each `eth_getLogs` call adapts to the node: it doubles after every round
+/// of successful requests and is halved when the node rejects a range for returning too many
+/// results. Up to `concurrency` ranges are fetched at once.
+///
+/// Other errors are returned as is. To retry transient errors or limit the request rate, wrap
+/// the provider in [`RetryProvider`](eip_1193_provider::middleware::RetryProvider) or
+/// [`RateLimitedProvider`](eip_1193_provider::middleware::RateLimitedProvider).
+///
+/// As a [`TxidSyncer`], operations are reconstructed from the calldata of each Railgun
+/// transaction and checked against the logs it emitted. This needs an extra
+/// `eth_getTransactionByHash` call per transaction, but trusts nothing beyond the node.
+/// Transactions that reach Railgun through contracts the syncer can't unwrap, e.g. a Safe or
+/// MultiSend, are traced with `debug_traceTransaction` instead, which the node must support.
 pub struct RpcSyncer {
     chain: ChainConfig,
     provider: Arc<dyn Eip1193Provider>,
-    batch_size: u64,
-    batch_delay: web_time::Duration,
+    /// Current block range per request. Persists across syncs so later syncs start from a size
+    /// the node is known to accept.
+    batch_size: AtomicU64,
+    max_batch_size: u64,
+    concurrency: usize,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -31,6 +88,39 @@ enum RpcSyncerError {
     LogParseError(String),
     #[error("RPC error: {0}")]
     RpcError(#[from] Eip1193Error),
+    #[error("Could not match the calldata of transaction {0} to its Railgun logs")]
+    UndecodableTransaction(B256),
+    #[error("Could not trace transaction {0}: {1}")]
+    UntraceableTransaction(B256, Eip1193Error),
+}
+
+/// A frame of a `debug_traceTransaction` `callTracer` trace.
+#[derive(Debug, Deserialize)]
+struct CallFrame {
+    #[serde(default)]
+    to: Option<Address>,
+    #[serde(default)]
+    input: Bytes,
+    /// Set if the call reverted, in which case its logs were discarded.
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    calls: Vec<CallFrame>,
+}
+
+/// A log emitted by `RailgunSmartWallet::transact`. Each transaction in a batch emits a
+/// `Nullified` log, followed by a single `Transact` log for all commitments in the batch.
+#[derive(Debug, Clone)]
+enum TransactLog {
+    Nullified {
+        tree_number: u32,
+        nullifiers: Vec<U256>,
+    },
+    Transact {
+        tree_number: u32,
+        start_position: u32,
+        hashes: Vec<U256>,
+    },
 }
 
 impl RpcSyncer {
@@ -38,20 +128,46 @@ impl RpcSyncer {
         Self {
             chain,
             provider: provider.into_eip1193(),
-            batch_size: 10,
-            batch_delay: web_time::Duration::from_millis(1000),
+            batch_size: AtomicU64::new(1000),
+            max_batch_size: 10_000,
+            concurrency: 4,
         }
     }
 
-    /// Sets the batch size for `eth_getLogs` calls.
-    pub fn with_batch_size(mut self, batch_size: u64) -> Self {
-        self.batch_size = batch_size;
+    /// Sets the initial block range for `eth_getLogs` calls. The range adapts to the node as
+    /// syncing progresses.
+    pub fn with_batch_size(self, batch_size: u64) -> Self {
+        self.batch_size.store(batch_size.max(1), Ordering::Relaxed);
+        self
+    }
+
+    /// Sets the largest block range a single `eth_getLogs` call will grow to.
+    pub fn with_max_batch_size(mut self, max_batch_size: u64) -> Self {
+        self.max_batch_size = max_batch_size.max(1);
+        self
+    }
+
+    /// Sets the maximum number of `eth_getLogs` calls in flight at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Limits requests to the node to `requests_per_second` by wrapping the provider in a
+    /// [`RateLimitedProvider`].
+    pub fn with_max_requests_per_second(mut self, requests_per_second: u32) -> Self {
+        self.provider = Arc::new(RateLimitedProvider::new(self.provider, requests_per_second));
         self
     }
 
-    /// Sets the delay between `eth_getLogs` calls.
-    pub fn with_batch_delay(mut self, batch_delay: web_time::Duration) -> Self {
-        self.batch_delay = batch_delay;
+    /// Spaces requests to the node at least `batch_delay` apart. Delays over a second are
+    /// treated as one second.
+    #[deprecated(note = "use `with_max_requests_per_second` or a `RateLimitedProvider` instead")]
+    pub fn with_batch_delay(mut self, batch_delay: Duration) -> Self {
+        let requests_per_second = (1.0 / batch_delay.as_secs_f64()).clamp(1.0, u32::MAX as f64);
+        self.provider = Arc::new(
+            RateLimitedProvider::new(self.provider, requests_per_second as u32).with_burst(1),
+        );
         self
     }
 }
@@ -68,6 +184,18 @@ impl UtxoSyncer for RpcSyncer {
     }
 }
 
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl TxidSyncer for RpcSyncer {
+    async fn latest_block(&self) -> Result<u64, SyncerError> {
+        Ok(self.latest_block().await?)
+    }
+
+    async fn sync(&self, from_block: u64, to_block: u64) -> Result<Vec<Operation>, SyncerError> {
+        Ok(self.operations(from_block, to_block).await?)
+    }
+}
+
 impl RpcSyncer {
     async fn latest_block(&self) -> Result<u64, RpcSyncerError> {
         Ok(self.provider.get_block_number().await?)
@@ -78,40 +206,210 @@ impl RpcSyncer {
         from_block: u64,
         to_block: u64,
     ) -> Result<Vec<SyncEvent>, RpcSyncerError> {
-        let from_block = from_block.max(self.chain.deployment_block);
+        let logs = self
+            .logs(from_block.max(self.chain.deployment_block), to_block)
+            .await?;
 
         let mut all_events = Vec::new();
-        let mut current_from = from_block;
-        while current_from <= to_block {
-            let batch_start = current_from;
-            let batch_end = to_block.min(current_from + self.batch_size - 1);
-
-            let logs = self
-                .provider
-                .logs(
-                    self.chain.railgun_smart_wallet,
-                    None,
-                    Some(batch_start),
-                    Some(batch_end),
-                )
-                .await?;
-            common::sleep(self.batch_delay).await;
-
-            for log in logs {
-                match log_to_sync_events(log) {
-                    Ok(events) => all_events.extend(events),
-                    Err(e) => warn!("Failed to parse log into SyncEvent: {}", e),
+        for log in logs {
+            match log_to_sync_events(log) {
+                Ok(events) => all_events.extend(events),
+                Err(e) => warn!("Failed to parse log into SyncEvent: {}", e),
+            }
+        }
+        Ok(all_events)
+    }
+
+    /// Reconstructs the operations in `[from_block, to_block]` from transaction calldata.
+    async fn operations(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Operation>, RpcSyncerError> {
+        let logs = self
+            .logs(from_block.max(self.chain.deployment_block), to_block)
+            .await?;
+
+        // Logs of a transaction are contiguous, so grouping neighbours keeps on-chain order.
+        let mut transactions: Vec<(B256, u64, VecDeque<TransactLog>)> = Vec::new();
+        for log in logs {
+            let Some(transact_log) = decode_transact_log(&log)? else {
+                continue;
+            };
+            let Some(hash) = log.transaction_hash else {
+                return Err(RpcSyncerError::LogParseError(format!(
+                    "Log missing transaction hash: {:?}",
+                    log
+                )));
+            };
+
+            match transactions.last_mut() {
+                Some((last, _, logs)) if *last == hash => logs.push_back(transact_log),
+                _ => transactions.push((
+                    hash,
+                    log.block_number.unwrap_or(0),
+                    VecDeque::from([transact_log]),
+                )),
+            }
+        }
+
+        let mut operations = Vec::new();
+        for chunk in transactions.chunks(self.concurrency) {
+            let fetched = join_all(
+                chunk
+                    .iter()
+                    .map(|(hash, ..)| self.provider.get_transaction(*hash)),
+            )
+            .await;
+
+            for ((hash, block_number, logs), tx) in chunk.iter().zip(fetched) {
+                let ops =
+                    match transaction_operations(*hash, *block_number, &tx?.input, logs.clone()) {
+                        Ok(ops) => ops,
+                        Err(RpcSyncerError::UndecodableTransaction(_)) => {
+                            self.traced_operations(*hash, *block_number, logs.clone())
+                                .await?
+                        }
+                        Err(e) => return Err(e),
+                    };
+                operations.extend(ops);
+            }
+        }
+        info!(
+            "Reconstructed {} operations from {} transactions",
+            operations.len(),
+            transactions.len()
+        );
+        Ok(operations)
+    }
+
+    /// Reconstructs the operations of transaction `hash` from the calls it made to the smart
+    /// wallet, for transactions whose calldata doesn't reveal them.
+    async fn traced_operations(
+        &self,
+        hash: B256,
+        block_number: u64,
+        logs: VecDeque<TransactLog>,
+    ) -> Result<Vec<Operation>, RpcSyncerError> {
+        info!("Tracing transaction {}", hash);
+        let trace: CallFrame = self
+            .provider
+            .request_as(
+                "debug_traceTransaction",
+                json!([hash, { "tracer": "callTracer" }]),
+            )
+            .await
+            .map_err(|e| RpcSyncerError::UntraceableTransaction(hash, e))?;
+
+        let mut batches = Vec::new();
+        traced_batches(&trace, self.chain.railgun_smart_wallet, &mut batches);
+        batch_operations(hash, block_number, batches, logs)
+    }
+
+    /// Fetches all Railgun logs in `[from_block, to_block]`, ordered by block.
+    async fn logs(&self, from_block: u64, to_block: u64) -> Result<Vec<RawLog>, RpcSyncerError> {
+        // Fetched logs keyed by the first block of their range. Ranges never overlap, so
+        // iterating in key order yields logs in block order regardless of completion order.
+        let mut fetched: BTreeMap<u64, Vec<RawLog>> = BTreeMap::new();
+        let mut pending: VecDeque<(u64, u64)> = VecDeque::new();
+        let mut next_from = from_block;
+        let mut log_count = 0;
+
+        while next_from <= to_block || !pending.is_empty() {
+            while pending.len() < self.concurrency && next_from <= to_block {
+                let size = self.batch_size.load(Ordering::Relaxed);
+                let end = to_block.min(next_from.saturating_add(size - 1));
+                pending.push_back((next_from, end));
+                next_from = end + 1;
+            }
+
+            let round: Vec<_> = pending
+                .drain(..pending.len().min(self.concurrency))
+                .collect();
+            let results = join_all(round.iter().map(|&(from, to)| self.fetch(from, to))).await;
+
+            let mut shrunk = false;
+            for ((from, to), result) in round.into_iter().zip(results) {
+                match result {
+                    Ok(logs) => {
+                        log_count += logs.len();
+                        fetched.insert(from, logs);
+                    }
+                    Err(e) if from < to && is_range_too_large(&e) => {
+                        let mid = from + (to - from) / 2;
+                        pending.push_front((mid + 1, to));
+                        pending.push_front((from, mid));
+                        shrunk = true;
+                        self.batch_size
+                            .fetch_min((mid - from + 1).max(1), Ordering::Relaxed);
+                    }
+                    Err(e) => return Err(e.into()),
                 }
             }
-            current_from = batch_end + 1;
-            info!("{}/{} ({} events)", batch_end, to_block, all_events.len());
+
+            if shrunk {
+                info!(
+                    "Reduced eth_getLogs range to {} blocks",
+                    self.batch_size.load(Ordering::Relaxed)
+                );
+            } else {
+                let size = self.batch_size.load(Ordering::Relaxed);
+                self.batch_size.store(
+                    size.saturating_mul(2).min(self.max_batch_size),
+                    Ordering::Relaxed,
+                );
+            }
+
+            let synced_to = pending
+                .front()
+                .map_or(next_from, |&(from, _)| from)
+                .saturating_sub(1);
+            info!("{}/{} ({} logs)", synced_to, to_block, log_count);
         }
 
-        Ok(all_events)
+        Ok(fetched.into_values().flatten().collect())
     }
+
+    async fn fetch(&self, from_block: u64, to_block: u64) -> Result<Vec<RawLog>, Eip1193Error> {
+        self.provider
+            .logs(
+                self.chain.railgun_smart_wallet,
+                &EVENT_SIGNATURES,
+                Some(from_block),
+                Some(to_block),
+            )
+            .await
+    }
+}
+
+/// Returns whether the node rejected an `eth_getLogs` call's block range or result size, so the
+/// range should be split. Nodes report these limits with inconsistent codes, so this matches on
+/// the messages used by common node and RPC providers.
+//? Invalid ranges, e.g. `fromBlock` past `toBlock`, also mention the block range, and splitting
+//? them would never succeed.
+fn is_range_too_large(e: &Eip1193Error) -> bool {
+    const RANGE_TOO_LARGE: &[&str] = &[
+        "too many results",
+        "returned more than",
+        "exceed maximum block range",
+        "exceeds maximum block range",
+        "block range too large",
+        "block range is too",
+        "range is too large",
+        "range is too wide",
+        "exceeds max results",
+        "response size exceeded",
+        "is limited to",
+    ];
+
+    let Eip1193Error::Rpc(message) = e else {
+        return false;
+    };
+
+    let message = message.to_lowercase();
+    !message.contains("invalid") && RANGE_TOO_LARGE.iter().any(|m| message.contains(m))
 }
 
-// TODO: Test me
 fn log_to_sync_events(log: RawLog) -> Result<Vec<SyncEvent>, RpcSyncerError> {
     let Some(topic0) = log.topics.get(0).cloned() else {
         return Err(RpcSyncerError::LogParseError(format!(
@@ -227,8 +525,626 @@ fn handle_nullified_event(
     Ok(events)
 }
 
+fn decode_transact_log(log: &RawLog) -> Result<Option<TransactLog>, RpcSyncerError> {
+    match log.topics.first() {
+        Some(&RailgunSmartWallet::Nullified::SIGNATURE_HASH) => {
+            let event = RailgunSmartWallet::Nullified::decode_log(&log.inner())?;
+            Ok(Some(TransactLog::Nullified {
+                tree_number: event.treeNumber as u32,
+                nullifiers: event.nullifier.iter().map(|n| (*n).into()).collect(),
+            }))
+        }
+        Some(&RailgunSmartWallet::Transact::SIGNATURE_HASH) => {
+            let event = RailgunSmartWallet::Transact::decode_log(&log.inner())?;
+            Ok(Some(TransactLog::Transact {
+                tree_number: event.treeNumber.saturating_to(),
+                start_position: event.startPosition.saturating_to(),
+                hashes: event.hash.iter().map(|h| (*h).into()).collect(),
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Reconstructs the operations of the Ethereum transaction `hash` from its calldata.
+///
+/// `transact` and `relay` calls are found directly in `input` or inside the known wrappers they
+/// are routed through (see [`embedded_batches`]). A batch is only accepted if it matches the logs
+/// it emitted, and every log must be matched.
+fn transaction_operations(
+    hash: B256,
+    block_number: u64,
+    input: &[u8],
+    logs: VecDeque<TransactLog>,
+) -> Result<Vec<Operation>, RpcSyncerError> {
+    batch_operations(hash, block_number, embedded_batches(input), logs)
+}
+
+/// Matches the batches found in transaction `hash` against its logs. Every log must be matched.
+fn batch_operations(
+    hash: B256,
+    block_number: u64,
+    batches: Vec<Vec<Transaction>>,
+    mut logs: VecDeque<TransactLog>,
+) -> Result<Vec<Operation>, RpcSyncerError> {
+    let mut operations = Vec::new();
+    for batch in batches {
+        if let Some(ops) = match_batch(&batch, &mut logs, block_number) {
+            operations.extend(ops);
+        }
+    }
+
+    if !logs.is_empty() {
+        return Err(RpcSyncerError::UndecodableTransaction(hash));
+    }
+    Ok(operations)
+}
+
+/// Decodes the `transact` and `relay` calls in `input`, unwrapping RelayAdapt `multicall`, smart
+/// account `execute`/`executeBatch` and EntryPoint `handleOps` calls.
+fn embedded_batches(input: &[u8]) -> Vec<Vec<Transaction>> {
+    let mut batches = Vec::new();
+    collect_batches(input, MAX_WRAPPER_DEPTH, &mut batches);
+    batches
+}
+
+fn collect_batches(input: &[u8], depth: usize, batches: &mut Vec<Vec<Transaction>>) {
+    if input.len() < 4 {
+        return;
+    }
+    let selector = &input[..4];
+
+    if *selector == RailgunSmartWallet::transactCall::SELECTOR {
+        if let Ok(call) = RailgunSmartWallet::transactCall::abi_decode(input) {
+            batches.push(call._transactions);
+        }
+        return;
+    }
+    if *selector == RelayAdapt::relayCall::SELECTOR {
+        if let Ok(call) = RelayAdapt::relayCall::abi_decode(input) {
+            batches.push(call._transactions);
+        }
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+
+    let inner: Vec<Bytes> = if *selector == RelayAdapt::multicallCall::SELECTOR {
+        RelayAdapt::multicallCall::abi_decode(input)
+            .map(|call| call._calls.into_iter().map(|c| c.data).collect())
+            .unwrap_or_default()
+    } else if *selector == SmartAccount::executeCall::SELECTOR {
+        SmartAccount::executeCall::abi_decode(input)
+            .map(|call| vec![call.func])
+            .unwrap_or_default()
+    } else if *selector == SmartAccount::executeBatchCall::SELECTOR {
+        SmartAccount::executeBatchCall::abi_decode(input)
+            .map(|call| call.calls.into_iter().map(|c| c.data).collect())
+            .unwrap_or_default()
+    } else if *selector == EntryPoint::handleOpsCall::SELECTOR {
+        EntryPoint::handleOpsCall::abi_decode(input)
+            .map(|call| call.ops.into_iter().map(|op| op.callData).collect())
+            .unwrap_or_default()
+    } else {
+        vec![]
+    };
+    for data in inner {
+        collect_batches(&data, depth - 1, batches);
+    }
+}
+
+/// Collects the batches of every call to `railgun_smart_wallet` in `frame`, skipping reverted
+/// calls.
+fn traced_batches(
+    frame: &CallFrame,
+    railgun_smart_wallet: Address,
+    batches: &mut Vec<Vec<Transaction>>,
+) {
+    if frame.error.is_some() {
+        return;
+    }
+    if frame.to == Some(railgun_smart_wallet) {
+        collect_batches(&frame.input, 0, batches);
+    }
+    for call in &frame.calls {
+        traced_batches(call, railgun_smart_wallet, batches);
+    }
+}
+
+/// Matches `batch` against the next logs in `logs`, consuming them and returning the batch's
+/// operations if every nullifier and inserted commitment agrees.
+fn match_batch(
+    batch: &[Transaction],
+    logs: &mut VecDeque<TransactLog>,
+    block_number: u64,
+) -> Option<Vec<Operation>> {
+    let mut remaining = logs.clone();
+    for tx in batch {
+        match remaining.pop_front() {
+            Some(TransactLog::Nullified {
+                tree_number,
+                nullifiers,
+            }) if tree_number == tx.boundParams.treeNumber as u32
+                && nullifiers == to_u256s(&tx.nullifiers) => {}
+            _ => return None,
+        }
+    }
+
+    // An unshield's commitment is the last of its transaction and is not inserted into the tree.
+    let inserted: Vec<usize> = batch
+        .iter()
+        .map(|tx| match tx.boundParams.unshield {
+            UnshieldType::NONE => tx.commitments.len(),
+            _ => tx.commitments.len().saturating_sub(1),
+        })
+        .collect();
+    let expected: Vec<U256> = batch
+        .iter()
+        .zip(&inserted)
+        .flat_map(|(tx, &n)| to_u256s(&tx.commitments[..n]))
+        .collect();
+
+    let position = match remaining.front() {
+        Some(TransactLog::Transact {
+            tree_number,
+            start_position,
+            hashes,
+        }) if *hashes == expected => {
+            let position = (*tree_number, *start_position);
+            remaining.pop_front();
+            Some(position)
+        }
+        _ if expected.is_empty() => None,
+        _ => return None,
+    };
+
+    let mut offset = 0;
+    let mut operations = Vec::with_capacity(batch.len());
+    for (tx, n) in batch.iter().zip(inserted) {
+        let (utxo_tree_out, utxo_out_start_index) = match position {
+            Some((tree_number, start)) if n > 0 => {
+                normalize_tree_position(tree_number, start + offset)
+            }
+            _ => (UNSHIELD_ONLY_POSITION, UNSHIELD_ONLY_POSITION),
+        };
+        offset += n as u32;
+
+        operations.push(Operation {
+            block_number,
+            nullifiers: to_u256s(&tx.nullifiers),
+            commitment_hashes: to_u256s(&tx.commitments),
+            bound_params_hash: tx.boundParams.hash(),
+            utxo_tree_in: tx.boundParams.treeNumber as u32,
+            utxo_tree_out,
+            utxo_out_start_index,
+        });
+    }
+
+    *logs = remaining;
+    Some(operations)
+}
+
+fn to_u256s(values: &[FixedBytes<32>]) -> Vec<U256> {
+    values.iter().map(|v| (*v).into()).collect()
+}
+
 impl From<RpcSyncerError> for SyncerError {
     fn from(e: RpcSyncerError) -> Self {
         SyncerError::new(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use alloy::primitives::{Address, B256, U64};
+    use eip_1193_provider::{
+        fixture::{Fixture, Recorder, Replayer},
+        recording::{RecordingProvider, ReplayProvider},
+    };
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::{
+        abis::railgun::{BoundParams, CommitmentPreimage, G1Point, G2Point, SnarkProof},
+        crypto::railgun_txid::Txid,
+    };
+
+    /// Encodes a Nullified event for `block` as a raw log.
+    fn nullified_log(address: Address, block: u64) -> RawLog {
+        let event = RailgunSmartWallet::Nullified {
+            treeNumber: 0,
+            nullifier: vec![B256::left_padding_from(&block.to_be_bytes())],
+        };
+        let data = event.encode_log_data();
+
+        RawLog {
+            block_number: Some(block),
+            block_timestamp: Some(block * 12),
+            transaction_hash: None,
+            address,
+            topics: data.topics().to_vec(),
+            data: data.data,
+        }
+    }
+
+    /// Provider that rejects ranges wider than `max_range`. Each successful call returns one
+    /// Nullified log per block.
+    struct MockProvider {
+        max_range: u64,
+        calls: Mutex<Vec<(u64, u64, Vec<FixedBytes<32>>)>>,
+    }
+
+    impl MockProvider {
+        fn new(max_range: u64) -> Self {
+            Self {
+                max_range,
+                calls: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn get_logs(&self, filter: &Value) -> Result<Value, Eip1193Error> {
+            let block = |key: &str| serde_json::from_value::<U64>(filter[key].clone()).unwrap();
+            let (from, to) = (block("fromBlock").to::<u64>(), block("toBlock").to::<u64>());
+            let event_signatures = serde_json::from_value(filter["topics"][0].clone()).unwrap();
+            self.calls
+                .lock()
+                .unwrap()
+                .push((from, to, event_signatures));
+
+            if to - from + 1 > self.max_range {
+                return Err(Eip1193Error::Rpc(
+                    "query returned more than 10000 results".into(),
+                ));
+            }
+
+            let address = serde_json::from_value(filter["address"].clone()).unwrap();
+            Ok((from..=to)
+                .map(|block| {
+                    let log = nullified_log(address, block);
+                    json!({
+                        "address": log.address,
+                        "topics": log.topics,
+                        "data": log.data,
+                        "blockNumber": log.block_number.map(U64::from),
+                        "blockTimestamp": log.block_timestamp.map(U64::from),
+                        "transactionHash": log.transaction_hash,
+                    })
+                })
+                .collect())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Eip1193Provider for MockProvider {
+        async fn request(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+            match method {
+                "eth_chainId" => Ok(json!("0x1")),
+                "eth_blockNumber" => Ok(json!("0x0")),
+                "eth_getLogs" => self.get_logs(&params[0]),
+                _ => Err(Eip1193Error::Rpc(format!("Unsupported method {method}"))),
+            }
+        }
+    }
+
+    fn syncer(provider: Arc<MockProvider>) -> RpcSyncer {
+        RpcSyncer::new(ChainConfig::mainnet(), provider)
+    }
+
+    fn blocks(logs: &[RawLog]) -> Vec<u64> {
+        logs.iter().map(|l| l.block_number.unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_shrinks_on_range_errors() {
+        let provider = Arc::new(MockProvider::new(100));
+        let syncer = syncer(provider.clone()).with_batch_size(1000);
+
+        let logs = syncer.logs(1, 2000).await.unwrap();
+        assert_eq!(blocks(&logs), (1..=2000).collect::<Vec<_>>());
+
+        let calls = provider.calls.lock().unwrap();
+        assert!(calls.iter().all(|(_, _, sigs)| sigs == &EVENT_SIGNATURES));
+    }
+
+    #[tokio::test]
+    async fn test_grows_on_success() {
+        let provider = Arc::new(MockProvider::new(u64::MAX));
+        let syncer = syncer(provider.clone())
+            .with_batch_size(10)
+            .with_max_batch_size(80)
+            .with_concurrency(1);
+
+        let logs = syncer.logs(0, 999).await.unwrap();
+        assert_eq!(blocks(&logs), (0..=999).collect::<Vec<_>>());
+
+        let sizes: Vec<u64> = provider
+            .calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(from, to, _)| to - from + 1)
+            .collect();
+        assert_eq!(sizes[..5], [10, 20, 40, 80, 80]);
+    }
+
+    #[tokio::test]
+    async fn test_fails_on_single_block_range_error() {
+        //? A single block over the node's limit can't be split further.
+        let provider = Arc::new(MockProvider::new(0));
+        let syncer = syncer(provider.clone()).with_concurrency(1);
+
+        assert!(syncer.logs(0, 9).await.is_err());
+        let calls = provider.calls.lock().unwrap();
+        assert_eq!(calls.last().map(|(from, to, _)| (*from, *to)), Some((0, 0)));
+    }
+
+    #[tokio::test]
+    #[allow(deprecated)]
+    async fn test_deprecated_batch_delay_still_syncs() {
+        let provider = Arc::new(MockProvider::new(u64::MAX));
+        let syncer = syncer(provider.clone()).with_batch_delay(Duration::from_millis(10));
+
+        let logs = syncer.logs(0, 9).await.unwrap();
+        assert_eq!(blocks(&logs), (0..=9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_log_to_sync_events() {
+        let events = log_to_sync_events(nullified_log(Address::ZERO, 7)).unwrap();
+        let [SyncEvent::Nullified(nullified, timestamp)] = events.as_slice() else {
+            panic!("expected a single Nullified event, got {:?}", events);
+        };
+        assert_eq!(nullified.tree_number, 0);
+        assert_eq!(
+            nullified.nullifier,
+            B256::left_padding_from(&7u64.to_be_bytes())
+        );
+        assert_eq!(*timestamp, 7 * 12);
+
+        let unknown = RawLog {
+            topics: vec![B256::ZERO],
+            ..nullified_log(Address::ZERO, 7)
+        };
+        assert!(log_to_sync_events(unknown).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_matches_recording() {
+        let chain = ChainConfig::mainnet();
+        let (from, to) = (chain.deployment_block, chain.deployment_block + 500);
+
+        let recorder = Arc::new(Recorder::new());
+        let mock: Arc<dyn Eip1193Provider> = Arc::new(MockProvider::new(100));
+        let recording = Arc::new(RecordingProvider::new(mock, recorder.clone()));
+        let recorded = RpcSyncer::new(chain.clone(), recording)
+            .events(from, to)
+            .await
+            .unwrap();
+        assert_eq!(recorded.len(), 501);
+
+        let fixture = Fixture::from_json(&recorder.fixture().to_json().unwrap()).unwrap();
+        let replay = Arc::new(ReplayProvider::new(Arc::new(Replayer::new(fixture))));
+        let replayed = RpcSyncer::new(chain, replay)
+            .events(from, to)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&recorded).unwrap(),
+            serde_json::to_value(&replayed).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_is_range_too_large() {
+        let range_too_large = |m: &str| is_range_too_large(&Eip1193Error::Rpc(m.into()));
+
+        assert!(range_too_large("query returned more than 10000 results"));
+        assert!(range_too_large("Log response size exceeded."));
+        assert!(range_too_large("eth_getLogs is limited to a 10000 range"));
+        assert!(range_too_large("block range is too wide"));
+        assert!(range_too_large("exceed maximum block range: 50000"));
+        assert!(!range_too_large("invalid block range params"));
+        assert!(!range_too_large(
+            "invalid block range: fromBlock is greater than toBlock"
+        ));
+        assert!(!range_too_large(
+            "HTTP error 429 with body: rate limit exceeded"
+        ));
+        assert!(!range_too_large("connection reset"));
+        assert!(!is_range_too_large(&Eip1193Error::Decode(
+            "too many results".into()
+        )));
+    }
+
+    fn transaction(nullifiers: &[u8], commitments: &[u8], unshield: UnshieldType) -> Transaction {
+        let bytes = |values: &[u8]| values.iter().map(|v| B256::repeat_byte(*v)).collect();
+        Transaction::new(
+            SnarkProof {
+                a: G1Point {
+                    x: U256::ZERO,
+                    y: U256::ZERO,
+                },
+                b: G2Point {
+                    x: [U256::ZERO; 2],
+                    y: [U256::ZERO; 2],
+                },
+                c: G1Point {
+                    x: U256::ZERO,
+                    y: U256::ZERO,
+                },
+            },
+            B256::ZERO,
+            bytes(nullifiers),
+            bytes(commitments),
+            BoundParams::new(1, 0, unshield, 1, Address::ZERO, &[0; 32], vec![]),
+            CommitmentPreimage::default(),
+        )
+    }
+
+    fn u256s(values: &[u8]) -> Vec<U256> {
+        values
+            .iter()
+            .map(|v| B256::repeat_byte(*v).into())
+            .collect()
+    }
+
+    #[test]
+    fn test_reconstructs_embedded_batch() {
+        let batch = vec![
+            transaction(&[1, 2], &[10, 11], UnshieldType::NONE),
+            transaction(&[3], &[12, 13], UnshieldType::NORMAL),
+        ];
+        let expected_txid = Txid::new(&u256s(&[3]), &u256s(&[12, 13]), batch[1].boundParams.hash());
+
+        // Routed through a user operation.
+        let transact = RailgunSmartWallet::transactCall {
+            _transactions: batch,
+        };
+        let execute = SmartAccount::executeBatchCall {
+            calls: vec![SmartAccount::Call {
+                target: Address::ZERO,
+                value: U256::ZERO,
+                data: transact.abi_encode().into(),
+            }],
+        };
+        let input = EntryPoint::handleOpsCall {
+            ops: vec![EntryPoint::PackedUserOperation {
+                callData: execute.abi_encode().into(),
+                ..Default::default()
+            }],
+            beneficiary: Address::ZERO,
+        }
+        .abi_encode();
+
+        let logs = VecDeque::from([
+            TransactLog::Nullified {
+                tree_number: 1,
+                nullifiers: u256s(&[1, 2]),
+            },
+            TransactLog::Nullified {
+                tree_number: 1,
+                nullifiers: u256s(&[3]),
+            },
+            TransactLog::Transact {
+                tree_number: 1,
+                start_position: 40,
+                hashes: u256s(&[10, 11, 12]),
+            },
+        ]);
+
+        let ops = transaction_operations(B256::ZERO, 7, &input, logs).unwrap();
+        assert_eq!(ops.len(), 2);
+        assert_eq!(
+            (
+                ops[0].utxo_tree_in,
+                ops[0].utxo_tree_out,
+                ops[0].utxo_out_start_index
+            ),
+            (1, 1, 40)
+        );
+        assert_eq!((ops[1].utxo_tree_out, ops[1].utxo_out_start_index), (1, 42));
+        assert_eq!(ops[1].block_number, 7);
+        assert_eq!(
+            Txid::new(
+                &ops[1].nullifiers,
+                &ops[1].commitment_hashes,
+                ops[1].bound_params_hash
+            ),
+            expected_txid
+        );
+    }
+
+    #[test]
+    fn test_reconstructs_traced_batch() {
+        let railgun = Address::repeat_byte(0xaa);
+        let transact = |nullifier| {
+            RailgunSmartWallet::transactCall {
+                _transactions: vec![transaction(&[nullifier], &[10], UnshieldType::NONE)],
+            }
+            .abi_encode()
+        };
+        //? A Safe calling the smart wallet, after a reverted attempt whose logs were discarded.
+        let trace: CallFrame = serde_json::from_value(json!({
+            "to": Address::repeat_byte(0x5a),
+            "input": "0x6a761202",
+            "calls": [
+                {
+                    "to": railgun,
+                    "input": Bytes::from(transact(2)),
+                    "error": "execution reverted",
+                },
+                {
+                    "to": railgun,
+                    "input": Bytes::from(transact(1)),
+                    "calls": [{ "to": Address::repeat_byte(0xbb), "input": "0x" }],
+                },
+            ],
+        }))
+        .unwrap();
+
+        let mut batches = Vec::new();
+        traced_batches(&trace, railgun, &mut batches);
+        assert_eq!(batches.len(), 1);
+
+        let logs = VecDeque::from([
+            TransactLog::Nullified {
+                tree_number: 1,
+                nullifiers: u256s(&[1]),
+            },
+            TransactLog::Transact {
+                tree_number: 1,
+                start_position: 5,
+                hashes: u256s(&[10]),
+            },
+        ]);
+        let ops = batch_operations(B256::ZERO, 3, batches, logs.clone()).unwrap();
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].nullifiers, u256s(&[1]));
+        assert_eq!(ops[0].utxo_out_start_index, 5);
+
+        // The Safe's calldata alone doesn't reveal the batch.
+        assert!(matches!(
+            transaction_operations(B256::ZERO, 3, &trace.input, logs),
+            Err(RpcSyncerError::UndecodableTransaction(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_mismatched_logs() {
+        let batch = vec![transaction(&[1], &[10], UnshieldType::NONE)];
+        let input = RelayAdapt::relayCall {
+            _transactions: batch,
+            _actionData: RelayAdapt::ActionData {
+                random: Default::default(),
+                requireSuccess: true,
+                minGasLimit: U256::ZERO,
+                calls: vec![],
+            },
+        }
+        .abi_encode();
+
+        let logs = |hash| {
+            VecDeque::from([
+                TransactLog::Nullified {
+                    tree_number: 1,
+                    nullifiers: u256s(&[1]),
+                },
+                TransactLog::Transact {
+                    tree_number: 1,
+                    start_position: 0,
+                    hashes: u256s(&[hash]),
+                },
+            ])
+        };
+
+        transaction_operations(B256::ZERO, 0, &input, logs(10)).unwrap();
+        assert!(matches!(
+            transaction_operations(B256::ZERO, 0, &input, logs(11)),
+            Err(RpcSyncerError::UndecodableTransaction(_))
+        ));
+    }
+}
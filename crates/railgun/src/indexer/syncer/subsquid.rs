@@ -1,3 +1,4 @@
+use eip_1193_provider::fixture::{FixtureError, FixtureMode};
 use serde::{Serialize, de::DeserializeOwned};
 use thiserror::Error;
 use tracing::{info, warn};
@@ -15,6 +16,7 @@ pub struct SubsquidSyncer {
     batch_size: u64,
     max_retries: usize,
     retry_delay: web_time::Duration,
+    fixture: FixtureMode,
 
     /// Override for latest block, used for testing to sync to a specific block.
     latest_block_override: Option<u64>,
@@ -30,6 +32,10 @@ enum SubsquidSyncerError {
     Request(reqwest::StatusCode, String),
     #[error("GraphQL error: {0}")]
     GraphQL(String),
+    #[error("Fixture error: {0}")]
+    Fixture(#[from] FixtureError),
+    #[error("Recorded error: {0}")]
+    Recorded(String),
 }
 
 const COMMITMENTS_QUERY: &str = include_str!("./subsquid_graphql/commitments.graphql");
@@ -37,6 +43,9 @@ const NULLIFIERS_QUERY: &str = include_str!("./subsquid_graphql/nullifiers.graph
 const OPERATIONS_QUERY: &str = include_str!("./subsquid_graphql/operations.graphql");
 const BLOCK_NUMBER_QUERY: &str = include_str!("./subsquid_graphql/block_number.graphql");
 
+/// Fixture method name for recorded GraphQL requests.
+const FIXTURE_METHOD: &str = "subsquid_graphql";
+
 impl SubsquidSyncer {
     pub fn new(url: impl Into<String>) -> Self {
         Self {
@@ -45,10 +54,17 @@ impl SubsquidSyncer {
             batch_size: 20000,
             max_retries: 3,
             retry_delay: web_time::Duration::from_secs(1),
+            fixture: FixtureMode::Live,
             latest_block_override: None,
         }
     }
 
+    /// Records GraphQL traffic to, or replays it from, a fixture.
+    pub fn with_fixture(mut self, fixture: FixtureMode) -> Self {
+        self.fixture = fixture;
+        self
+    }
+
     /// Sets the latest block override, which causes the syncer to only sync
     /// up to this block. Used in testing to sync against chain forks.
     #[cfg(any(test, feature = "testing"))]
@@ -271,15 +287,19 @@ impl SubsquidSyncer {
         &self,
         body: &GraphqlRequest<V>,
     ) -> Result<R, SubsquidSyncerError> {
-        let resp = self.client.post(&self.url).json(&body).send().await?;
-        if !resp.status().is_success() {
-            return Err(SubsquidSyncerError::Request(
-                resp.status(),
-                resp.text().await.unwrap_or_default(),
-            ));
-        }
+        let value = match &self.fixture {
+            FixtureMode::Live => self.post_json(body).await?,
+            FixtureMode::Record(recorder) => {
+                let result = self.post_json(body).await;
+                let recorded = result.as_ref().map_err(|e| e.to_string());
+                recorder.record(FIXTURE_METHOD, body, recorded);
+                result?
+            }
+            FixtureMode::Replay(replayer) => replayer
+                .replay_as::<_, serde_json::Value, String>(FIXTURE_METHOD, body)?
+                .map_err(SubsquidSyncerError::Recorded)?,
+        };
 
-        let value: serde_json::Value = resp.json().await?;
         // info!("Deserializing: {}", &value);
         let graphql_resp: GraphqlResponse<R> = serde_json::from_value(value)?;
         if let Some(errors) = graphql_resp.errors {
@@ -300,6 +320,21 @@ impl SubsquidSyncer {
 
         Ok(data)
     }
+
+    async fn post_json<V: Serialize>(
+        &self,
+        body: &GraphqlRequest<V>,
+    ) -> Result<serde_json::Value, SubsquidSyncerError> {
+        let resp = self.client.post(&self.url).json(&body).send().await?;
+        if !resp.status().is_success() {
+            return Err(SubsquidSyncerError::Request(
+                resp.status(),
+                resp.text().await.unwrap_or_default(),
+            ));
+        }
+
+        Ok(resp.json().await?)
+    }
 }
 
 impl From<SubsquidSyncerError> for SyncerError {
@@ -307,3 +342,72 @@ impl From<SubsquidSyncerError> for SyncerError {
         SyncerError::new(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy::primitives::{Address, B256};
+    use eip_1193_provider::fixture::{Fixture, Replayer};
+    use ruint::aliases::U256;
+
+    use super::*;
+    use crate::{caip::AssetId, indexer::syncer::SyncEvent};
+
+    /// Hand-written responses for blocks 100-200: a shield and a transact commitment, one
+    /// nullifier, and the operation that produced them.
+    const SYNC_FIXTURE: &str = include_str!("../../../tests/fixtures/subsquid/sync.json");
+
+    fn replay_syncer() -> SubsquidSyncer {
+        let fixture = Fixture::from_json(SYNC_FIXTURE).unwrap();
+        //? The URL is never contacted in replay mode
+        SubsquidSyncer::new("http://localhost:0")
+            .with_fixture(FixtureMode::Replay(Arc::new(Replayer::new(fixture))))
+    }
+
+    #[tokio::test]
+    async fn test_replay_utxo_sync() {
+        let syncer = replay_syncer();
+        assert_eq!(UtxoSyncer::latest_block(&syncer).await.unwrap(), 200);
+
+        let events = UtxoSyncer::sync(&syncer, 100, 200).await.unwrap();
+        assert_eq!(events.len(), 3);
+
+        let SyncEvent::Shield(shield, 120) = &events[0] else {
+            panic!("expected shield at block 120, got {:?}", events[0]);
+        };
+        assert_eq!((shield.tree_number, shield.leaf_index), (0, 0));
+        assert_eq!(shield.token, AssetId::Erc20(Address::repeat_byte(0x22)));
+        assert_eq!(shield.value, U256::from(1000));
+        assert_eq!(shield.shield_key, [0x33; 32]);
+
+        let SyncEvent::Transact(transact, 150) = &events[1] else {
+            panic!("expected transact at block 150, got {:?}", events[1]);
+        };
+        assert_eq!((transact.tree_number, transact.leaf_index), (0, 1));
+        assert_eq!(transact.hash, U256::from(67890));
+        assert_eq!(transact.ciphertext.data.len(), 3, "data chunks and memo");
+        assert_eq!(transact.annotation_data, vec![0xdd]);
+
+        let SyncEvent::Nullified(nullified, 150) = &events[2] else {
+            panic!("expected nullifier at block 150, got {:?}", events[2]);
+        };
+        assert_eq!(nullified.nullifier, B256::repeat_byte(0xee));
+    }
+
+    #[tokio::test]
+    async fn test_replay_txid_sync() {
+        let syncer = replay_syncer();
+        let operations = TxidSyncer::sync(&syncer, 100, 200).await.unwrap();
+
+        assert_eq!(operations.len(), 1);
+        let operation = &operations[0];
+        assert_eq!(operation.block_number, 150);
+        assert_eq!(operation.commitment_hashes, vec![U256::from(67890)]);
+        assert_eq!(operation.nullifiers, vec![U256::from_be_bytes([0xee; 32])]);
+        assert_eq!(
+            (operation.utxo_tree_out, operation.utxo_out_start_index),
+            (0, 1)
+        );
+    }
+}
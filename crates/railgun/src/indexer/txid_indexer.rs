@@ -1,12 +1,16 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
+use ruint::aliases::U256;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{info, warn};
 
 use crate::{
     crypto::railgun_txid::Txid,
-    database::{Database, DatabaseError, RailgunDB},
+    database::{Database, DatabaseError, RailgunDB, WriteBatch},
     indexer::syncer::{Operation, SyncerError, TxidSyncer},
     merkle_tree::{TOTAL_LEAVES, TxidLeafHash, TxidMerkleTree, UtxoTreeIndex},
     poi::client::{PoiClientError, PoiNodeClient},
@@ -18,6 +22,9 @@ pub struct TxidIndexer {
 
     db: Arc<dyn Database>,
     txid_syncer: Arc<dyn TxidSyncer>,
+    /// Independent source of operations, e.g. reconstructed from on-chain calldata. When set,
+    /// every operation from `txid_syncer` is cross-checked against it before being indexed.
+    verifier: Option<Arc<dyn TxidSyncer>>,
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -29,18 +36,41 @@ pub(crate) struct TxidIndexerState {
     pub txid_to_txid_position: HashMap<Txid, (u32, u32)>,
 }
 
+/// Portable copy of the TXID indexer state used by wallet snapshots. Trees are
+/// carried as raw leaves and rebuilt on import.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct TxidSnapshot {
+    pub synced_block: u64,
+    pub trees: BTreeMap<u32, Vec<U256>>,
+    pub pending: Vec<Operation>,
+    pub txid_to_utxo_position: HashMap<Txid, (u32, u32)>,
+    pub txid_to_txid_position: HashMap<Txid, (u32, u32)>,
+}
+
 #[derive(Debug, Error)]
 pub enum TxidIndexerError {
     #[error("Syncer error: {0}")]
     SyncerError(#[from] SyncerError),
     #[error("POI client error: {0}")]
     PoiClient(#[from] PoiClientError),
-    #[error("TXID tree root mismatch for tree {tree_number}")]
+    #[error("POI node rejected the root of TXID tree {tree_number}")]
     RootMismatch { tree_number: u32 },
+    #[error(
+        "POI node reports {validated} validated TXIDs, but only {verified} are verified on-chain"
+    )]
+    UnverifiedTxids { validated: u32, verified: usize },
+    #[error("TXID sources disagree on {txid:?}: {reason}")]
+    SourceMismatch { txid: Txid, reason: &'static str },
     #[error("Database error: {0}")]
     DatabaseError(#[from] DatabaseError),
 }
 
+/// A [`TxidSnapshot`] whose trees have been rebuilt and validated, ready to import.
+pub(crate) struct VerifiedTxidSnapshot {
+    trees: HashMap<u32, TxidMerkleTree>,
+    inner: TxidIndexerState,
+}
+
 impl TxidIndexer {
     pub async fn new(
         db: Arc<dyn Database>,
@@ -67,9 +97,17 @@ impl TxidIndexer {
             trees: txid_trees,
             db,
             txid_syncer,
+            verifier: None,
         })
     }
 
+    /// Cross-checks every synced operation against `verifier`. Operations are only indexed if
+    /// both sources agree on them, and the POI node's TXID roots are checked against the result.
+    pub(crate) fn with_verifier(mut self, verifier: Arc<dyn TxidSyncer>) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
     pub fn tree(&self, tree_number: u32) -> Option<&TxidMerkleTree> {
         self.trees.get(&tree_number)
     }
@@ -91,11 +129,19 @@ impl TxidIndexer {
         let from_block = self.inner.synced_block + 1;
 
         let syncer = self.txid_syncer.clone();
-        let latest_block = syncer.latest_block().await?;
+        let mut latest_block = syncer.latest_block().await?;
+        if let Some(verifier) = &self.verifier {
+            latest_block = latest_block.min(verifier.latest_block().await?);
+        }
         let to_block = to_block.min(latest_block);
 
-        let ops = syncer.sync(from_block, to_block).await?;
+        let mut ops = syncer.sync(from_block, to_block).await?;
         info!("Fetched {} operations from syncer", ops.len());
+        if let Some(verifier) = &self.verifier {
+            let verified = verifier.sync(from_block, to_block).await?;
+            ops = cross_check(&ops, verified)?;
+            info!("Verified {} operations against on-chain data", ops.len());
+        }
         for op in ops {
             self.inner.pending.push(op);
         }
@@ -118,6 +164,16 @@ impl TxidIndexer {
         let current_total = self.trees.values().map(|t| t.leaves_len() as u32).sum();
         let target_total = validated.tree() * TOTAL_LEAVES + validated.leaf_index() + 1;
 
+        if self.verifier.is_some() {
+            let available = current_total as usize + self.inner.pending.len();
+            if target_total as usize > available {
+                return Err(TxidIndexerError::UnverifiedTxids {
+                    validated: target_total,
+                    verified: available,
+                });
+            }
+        }
+
         let to_drain = target_total.saturating_sub(current_total) as usize;
         if to_drain == 0 {
             return Ok(());
@@ -129,7 +185,7 @@ impl TxidIndexer {
         let mut total = current_total;
         let mut tree_leaves: HashMap<u32, Vec<(u32, TxidLeafHash)>> = HashMap::new();
         for op in drained {
-            let txid = Txid::new(&op.nullifiers, &op.commitment_hashes, op.bound_params_hash);
+            let txid = op.txid();
 
             if let Some(&existing_pos) = self.inner.txid_to_txid_position.get(&txid) {
                 warn!(
@@ -204,7 +260,84 @@ impl TxidIndexer {
         Ok(())
     }
 
+    pub(crate) fn export(&self) -> TxidSnapshot {
+        TxidSnapshot {
+            synced_block: self.inner.synced_block,
+            trees: self
+                .trees
+                .iter()
+                .map(|(number, tree)| (*number, tree.leaves()))
+                .collect(),
+            pending: self.inner.pending.clone(),
+            txid_to_utxo_position: self.inner.txid_to_utxo_position.clone(),
+            txid_to_txid_position: self.inner.txid_to_txid_position.clone(),
+        }
+    }
+
+    /// Rebuilds every tree in a snapshot from its leaves and validates its root against the POI
+    /// node. The indexer is left untouched.
+    pub(crate) async fn verify_snapshot(
+        &self,
+        snapshot: TxidSnapshot,
+        poi_client: &impl PoiNodeClient,
+    ) -> Result<VerifiedTxidSnapshot, TxidIndexerError> {
+        let mut trees = HashMap::new();
+        for (tree_number, leaves) in snapshot.trees {
+            if leaves.is_empty() {
+                continue;
+            }
+
+            let hashes: Vec<TxidLeafHash> = leaves.into_iter().map(Into::into).collect();
+            let mut tree = TxidMerkleTree::new(tree_number);
+            tree.insert_leaves(&hashes, 0);
+
+            let index = tree.leaves_len() as u32 - 1;
+            if !poi_client
+                .validate_txid_merkleroot(tree_number, index, tree.root())
+                .await?
+            {
+                return Err(TxidIndexerError::RootMismatch { tree_number });
+            }
+            trees.insert(tree_number, tree);
+        }
+        info!(
+            "Validated {} snapshot TXID trees at block {}",
+            trees.len(),
+            snapshot.synced_block
+        );
+
+        Ok(VerifiedTxidSnapshot {
+            trees,
+            inner: TxidIndexerState {
+                synced_block: snapshot.synced_block,
+                trees: vec![],
+                pending: snapshot.pending,
+                txid_to_utxo_position: snapshot.txid_to_utxo_position,
+                txid_to_txid_position: snapshot.txid_to_txid_position,
+            },
+        })
+    }
+
+    /// Replaces the indexer state with a verified snapshot, queuing its writes in `batch`.
+    pub(crate) fn import(
+        &mut self,
+        snapshot: VerifiedTxidSnapshot,
+        batch: &mut WriteBatch,
+    ) -> Result<(), DatabaseError> {
+        self.trees = snapshot.trees;
+        self.inner = snapshot.inner;
+        self.inner.trees = self.trees.keys().cloned().collect();
+        self.queue_save(batch)
+    }
+
     async fn save(&self) -> Result<(), DatabaseError> {
+        let mut batch = WriteBatch::new();
+        self.queue_save(&mut batch)?;
+        self.db.write_batch(batch).await
+    }
+
+    /// Queues writes of the indexer state and trees in `batch`.
+    fn queue_save(&self, batch: &mut WriteBatch) -> Result<(), DatabaseError> {
         let state = TxidIndexerState {
             synced_block: self.inner.synced_block,
             trees: self.trees.keys().cloned().collect(),
@@ -212,12 +345,73 @@ impl TxidIndexer {
             txid_to_utxo_position: self.inner.txid_to_utxo_position.clone(),
             txid_to_txid_position: self.inner.txid_to_txid_position.clone(),
         };
-        self.db.set_txid_indexer(&state).await?;
 
+        batch.set_txid_indexer(&state)?;
         for (tree_number, tree) in self.trees.iter() {
-            self.db.set_txid_tree(*tree_number, tree.state()).await?;
+            batch.set_txid_tree(*tree_number, tree.state())?;
         }
-
         Ok(())
     }
 }
+
+/// Checks that `indexed` and `verified` contain the same operations, returning the verified
+/// operations in their on-chain order.
+fn cross_check(
+    indexed: &[Operation],
+    verified: Vec<Operation>,
+) -> Result<Vec<Operation>, TxidIndexerError> {
+    let positions = |op: &Operation| (op.utxo_tree_in, op.utxo_tree_out, op.utxo_out_start_index);
+    let mismatch = |txid, reason| Err(TxidIndexerError::SourceMismatch { txid, reason });
+
+    let indexed: HashMap<Txid, &Operation> = indexed.iter().map(|op| (op.txid(), op)).collect();
+    let onchain: HashMap<Txid, &Operation> = verified.iter().map(|op| (op.txid(), op)).collect();
+    for (txid, op) in &indexed {
+        match onchain.get(txid) {
+            Some(verified) if positions(verified) == positions(op) => {}
+            Some(_) => return mismatch(*txid, "UTXO tree positions differ"),
+            None => return mismatch(*txid, "not found on-chain"),
+        }
+    }
+    if let Some(txid) = onchain.keys().find(|txid| !indexed.contains_key(*txid)) {
+        return mismatch(*txid, "missing from syncer");
+    }
+
+    Ok(verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(nullifier: u64, start_index: u32) -> Operation {
+        Operation {
+            block_number: 0,
+            nullifiers: vec![U256::from(nullifier)],
+            commitment_hashes: vec![U256::from(nullifier + 100)],
+            bound_params_hash: U256::ZERO,
+            utxo_tree_in: 0,
+            utxo_tree_out: 0,
+            utxo_out_start_index: start_index,
+        }
+    }
+
+    #[test]
+    fn test_cross_check() {
+        let verified = || vec![op(1, 0), op(2, 1)];
+
+        // Order and duplicates from the syncer don't matter. The on-chain order is kept.
+        let ops = cross_check(&[op(2, 1), op(1, 0), op(2, 1)], verified()).unwrap();
+        assert_eq!(ops[0].txid(), op(1, 0).txid());
+
+        let reason = |indexed: &[Operation]| match cross_check(indexed, verified()) {
+            Err(TxidIndexerError::SourceMismatch { reason, .. }) => reason,
+            _ => panic!("expected mismatch"),
+        };
+        assert_eq!(reason(&[op(1, 0), op(2, 5)]), "UTXO tree positions differ");
+        assert_eq!(
+            reason(&[op(1, 0), op(2, 1), op(3, 2)]),
+            "not found on-chain"
+        );
+        assert_eq!(reason(&[op(1, 0)]), "missing from syncer");
+    }
+}
@@ -4,15 +4,16 @@ use std::{
     u64,
 };
 
+use ruint::aliases::U256;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::info;
 
 use crate::{
     account::{address::RailgunAddress, signer::RailgunSigner},
-    database::{Database, DatabaseError, RailgunDB},
+    database::{Database, DatabaseError, RailgunDB, WriteBatch},
     indexer::{
-        indexed_account::IndexedAccount,
+        indexed_account::{IndexedAccount, IndexedAccountState},
         syncer::{self, SyncEvent, SyncerError, UtxoSyncer},
     },
     merkle_tree::{MerkleTreeVerifier, UtxoLeafHash, UtxoMerkleTree},
@@ -49,6 +50,26 @@ pub enum UtxoIndexerError {
     DatabaseError(#[from] DatabaseError),
     #[error("Timed out waiting for commitments")]
     Timeout,
+    #[error("UTXO tree root mismatch for tree {tree_number}")]
+    RootMismatch { tree_number: u32 },
+}
+
+/// Portable copy of the UTXO indexer state used by wallet snapshots.
+///
+/// Trees are carried as raw leaves and rebuilt on import, so the imported roots
+/// are always derived from the leaves rather than trusted as-is.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct UtxoSnapshot {
+    pub synced_block: u64,
+    pub trees: BTreeMap<u32, Vec<U256>>,
+    pub accounts: Vec<(RailgunAddress, IndexedAccountState)>,
+}
+
+/// A [`UtxoSnapshot`] whose trees have been rebuilt and verified, ready to import.
+pub(crate) struct VerifiedUtxoSnapshot {
+    synced_block: u64,
+    trees: BTreeMap<u32, UtxoMerkleTree>,
+    accounts: Vec<(RailgunAddress, IndexedAccountState)>,
 }
 
 impl UtxoIndexer {
@@ -135,7 +156,15 @@ impl UtxoIndexer {
         }
 
         // Sync
-        let events = self.utxo_syncer.sync(from_block, to_block).await?;
+        let tree_lengths: BTreeMap<u32, u32> = self
+            .utxo_trees
+            .iter()
+            .map(|(number, tree)| (*number, tree.leaves_len() as u32))
+            .collect();
+        let events = self
+            .utxo_syncer
+            .sync_after(from_block, to_block, &tree_lengths)
+            .await?;
         info!("Fetched {} events from syncer", events.len());
 
         let mut tree_leaves: HashMap<u32, Vec<(u32, UtxoLeafHash)>> = HashMap::new();
@@ -180,7 +209,9 @@ impl UtxoIndexer {
         tree_leaves: &mut HashMap<u32, Vec<(u32, UtxoLeafHash)>>,
     ) -> Result<(), UtxoIndexerError> {
         match event {
-            SyncEvent::Shield(shield, _) => self.handle_shield(shield, tree_leaves)?,
+            SyncEvent::Shield(shield, block_number) => {
+                self.handle_shield(shield, *block_number, tree_leaves)?
+            }
             SyncEvent::Transact(transact, _) => self.handle_transact(transact, tree_leaves)?,
             SyncEvent::Nullified(nullified, ts) => self.handle_nullified(nullified, *ts),
             SyncEvent::Legacy(legacy, _) => self.handle_legacy(legacy, tree_leaves),
@@ -192,6 +223,7 @@ impl UtxoIndexer {
     fn handle_shield(
         &mut self,
         event: &syncer::Shield,
+        block_number: u64,
         tree_leaves: &mut HashMap<u32, Vec<(u32, UtxoLeafHash)>>,
     ) -> Result<(), UtxoIndexerError> {
         tree_leaves
@@ -200,7 +232,7 @@ impl UtxoIndexer {
             .push((event.leaf_index, event.hash()));
 
         for account in self.accounts.iter_mut() {
-            account.handle_shield_event(event)?;
+            account.handle_shield_event(event, block_number)?;
         }
 
         Ok(())
@@ -244,36 +276,132 @@ impl UtxoIndexer {
 
     async fn verify(&self) -> Result<(), UtxoIndexerError> {
         for tree in self.utxo_trees.values() {
-            if tree.leaves_len() == 0 {
-                continue;
-            }
+            self.verify_tree(tree).await?;
+        }
+        Ok(())
+    }
 
-            self.utxo_verifier
-                .verify_root(tree.number(), tree.leaves_len() as u32 - 1, tree.root())
-                .await
-                .map_err(|e| UtxoIndexerError::VerificationError(e))?;
+    async fn verify_tree(&self, tree: &UtxoMerkleTree) -> Result<(), UtxoIndexerError> {
+        if tree.leaves_len() == 0 {
+            return Ok(());
+        }
+
+        let valid = self
+            .utxo_verifier
+            .verify_root(tree.number(), tree.leaves_len() as u32 - 1, tree.root())
+            .await
+            .map_err(|e| UtxoIndexerError::VerificationError(e))?;
+        if !valid {
+            return Err(UtxoIndexerError::RootMismatch {
+                tree_number: tree.number(),
+            });
         }
         Ok(())
     }
 
+    /// Exports the UTXO trees and indexer state, optionally including the notes of
+    /// every registered account.
+    pub(crate) fn export(&self, include_accounts: bool) -> UtxoSnapshot {
+        let trees = self
+            .utxo_trees
+            .iter()
+            .map(|(number, tree)| (*number, tree.leaves()))
+            .collect();
+
+        let accounts = if include_accounts {
+            self.accounts
+                .iter()
+                .map(|a| (a.address(), a.state()))
+                .collect()
+        } else {
+            vec![]
+        };
+
+        UtxoSnapshot {
+            synced_block: self.synced_block,
+            trees,
+            accounts,
+        }
+    }
+
+    /// Rebuilds every tree in a snapshot from its leaves and checks its root with the UTXO
+    /// verifier, so an untrusted snapshot can never introduce a tree the verifier does not
+    /// recognize. The indexer is left untouched.
+    pub(crate) async fn verify_snapshot(
+        &self,
+        snapshot: UtxoSnapshot,
+    ) -> Result<VerifiedUtxoSnapshot, UtxoIndexerError> {
+        let mut trees = BTreeMap::new();
+        for (number, leaves) in snapshot.trees {
+            let hashes: Vec<UtxoLeafHash> = leaves.into_iter().map(Into::into).collect();
+            let mut tree = UtxoMerkleTree::new(number);
+            tree.insert_leaves(&hashes, 0);
+
+            self.verify_tree(&tree).await?;
+            trees.insert(number, tree);
+        }
+        info!(
+            "Verified {} snapshot UTXO trees at block {}",
+            trees.len(),
+            snapshot.synced_block
+        );
+
+        Ok(VerifiedUtxoSnapshot {
+            synced_block: snapshot.synced_block,
+            trees,
+            accounts: snapshot.accounts,
+        })
+    }
+
+    /// Replaces the indexer state with a verified snapshot, queuing its writes in `batch`.
+    /// Snapshot accounts that are not registered are persisted and picked up when they are
+    /// registered.
+    pub(crate) fn import(
+        &mut self,
+        snapshot: VerifiedUtxoSnapshot,
+        batch: &mut WriteBatch,
+    ) -> Result<(), DatabaseError> {
+        for (address, state) in snapshot.accounts {
+            match self.accounts.iter_mut().find(|a| a.address() == address) {
+                Some(account) => account.set_state(state),
+                None => batch.set_account(&address, &state)?,
+            }
+        }
+
+        self.utxo_trees = snapshot.trees;
+        self.synced_block = snapshot.synced_block;
+        self.queue_save(batch)
+    }
+
+    /// Applies `batch` to the indexer's database.
+    pub(crate) async fn write_batch(&self, batch: WriteBatch) -> Result<(), DatabaseError> {
+        self.db.write_batch(batch).await
+    }
+
     /// Saves the current state of the indexer to the database.
+    ///
+    /// The indexer state, trees, and accounts are written as a single batch so a
+    /// crash can never leave `synced_block` ahead of the stored trees or accounts.
     async fn save(&self) -> Result<(), DatabaseError> {
+        let mut batch = WriteBatch::new();
+        self.queue_save(&mut batch)?;
+        self.db.write_batch(batch).await
+    }
+
+    /// Queues writes of the indexer state, trees, and accounts in `batch`.
+    fn queue_save(&self, batch: &mut WriteBatch) -> Result<(), DatabaseError> {
         let state = UtxoIndexerState {
             synced_block: self.synced_block,
             trees: self.utxo_trees.keys().cloned().collect(),
         };
-        self.db.set_utxo_indexer(&state).await?;
 
+        batch.set_utxo_indexer(&state)?;
         for (tree_number, tree) in self.utxo_trees.iter() {
-            self.db.set_utxo_tree(*tree_number, tree.state()).await?;
+            batch.set_utxo_tree(*tree_number, tree.state())?;
         }
-
         for account in self.accounts.iter() {
-            self.db
-                .set_account(&account.address(), &account.state())
-                .await?;
+            batch.set_account(&account.address(), &account.state())?;
         }
-
         Ok(())
     }
 }
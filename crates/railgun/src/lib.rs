@@ -3,22 +3,49 @@
 mod abis;
 pub mod account;
 mod adapter_data;
+pub mod broadcaster;
 pub mod builder;
 pub mod caip;
 pub mod chain_config;
 mod circuit;
 pub mod crypto;
 pub mod database;
+pub mod decoder;
 pub mod indexer;
 mod merkle_tree;
 mod note;
 pub mod poi;
 pub mod provider;
+pub mod snapshot;
+#[cfg(all(test, native))]
+mod test_server;
 pub mod transact;
 
+#[cfg(native)]
+pub use circuit::artifact_source::{CachedArtifactSource, DirectoryArtifactSource};
+pub use circuit::{
+    artifact_manifest::ArtifactManifest,
+    artifact_source::{
+        ArtifactSource, ArtifactSourceError, EmbeddedArtifactSource, HttpArtifactSource,
+    },
+    remote_prover::RemoteProver,
+    witness_graph::WitnessGraph,
+};
+pub use merkle_tree::TrustedCheckpoint;
+
 #[cfg(all(wasm, parallel))]
 compile_error!("The `parallel` feature is not supported in WASM builds.");
 
+#[cfg(all(native, feature = "testing"))]
+pub mod test_helpers {
+    #[cfg(wasm_witness)]
+    pub use crate::circuit::witness::WitnessModules;
+    pub use crate::circuit::{
+        artifact_loader::ArtifactLoader,
+        witness::{WitnessBackend, WitnessGraphs},
+    };
+}
+
 #[cfg(bench)]
 pub mod bench_helpers {
     pub use crate::{indexer::indexed_account::*, note::*};
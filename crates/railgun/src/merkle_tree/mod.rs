@@ -2,6 +2,7 @@ pub mod verifier;
 
 mod merkle_tree;
 mod smart_wallet_verifier;
+mod storage_proof_verifier;
 mod txid_tree;
 mod utxo_tree;
 
@@ -9,6 +10,9 @@ pub use merkle_tree::{
     MerkleProof, MerkleRoot, MerkleTree, MerkleTreeError, MerkleTreeState, TOTAL_LEAVES, TREE_DEPTH,
 };
 pub use smart_wallet_verifier::SmartWalletUtxoVerifier;
+pub use storage_proof_verifier::{
+    ROOT_HISTORY_SLOT, StorageProofError, StorageProofUtxoVerifier, TrustedCheckpoint,
+};
 pub use txid_tree::{TxidLeafHash, TxidMerkleTree, UtxoTreeIndex};
 pub use utxo_tree::{UtxoLeafHash, UtxoMerkleTree};
 pub use verifier::MerkleTreeVerifier;
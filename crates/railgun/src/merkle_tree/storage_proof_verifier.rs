@@ -0,0 +1,648 @@
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+
+use alloy::{
+    consensus::Header,
+    primitives::{Address, B256, Bytes, U64, U256, keccak256},
+    rlp::{Encodable, Header as RlpHeader},
+    sol_types::SolCall,
+    trie::{Nibbles, proof::verify_proof},
+};
+use eip_1193_provider::provider::{
+    Eip1193Blocks, Eip1193Error, Eip1193Proofs, Eip1193Provider, Eip1193Request,
+};
+use serde_json::json;
+use thiserror::Error;
+
+use crate::{
+    abis::railgun::RailgunSmartWallet,
+    merkle_tree::{MerkleRoot, verifier::MerkleTreeVerifier},
+};
+
+/// Storage slot of the `rootHistory` mapping (`treeNumber => root => seen`) in the
+/// `RailgunSmartWallet` storage layout.
+pub const ROOT_HISTORY_SLOT: u64 = 147;
+
+/// Verifies UTXO Merkle roots with EIP-1186 storage proofs instead of trusting `eth_call`.
+///
+/// Each root is looked up in the `RailgunSmartWallet` `rootHistory` mapping via `eth_getProof`,
+/// and both the account and storage proofs are checked against the state root of a trusted
+/// checkpoint. A malicious RPC can still withhold data, but it cannot make a fake root verify.
+///
+/// The first time a root doesn't verify, the contract's current root is proved too. It is always
+/// in the root history, so if it doesn't verify either the root history slot is wrong and
+/// [`StorageProofError::RootHistorySlotMismatch`] is returned instead of rejecting every root.
+///
+/// Roots are checked as of the checkpoint block, so roots created after it will not verify.
+/// Sync no further than the checkpoint, or move it forward with
+/// [`StorageProofUtxoVerifier::set_checkpoint`] (or
+/// [`RailgunProvider::set_trusted_checkpoint`](crate::provider::RailgunProvider::set_trusted_checkpoint)
+/// when built by the `RailgunBuilder`).
+pub struct StorageProofUtxoVerifier {
+    address: Address,
+    provider: Arc<dyn Eip1193Provider>,
+    root_history_slot: U256,
+    checkpoint: Mutex<TrustedCheckpoint>,
+    /// Whether the contract's current root has been proved at `root_history_slot`.
+    slot_checked: AtomicBool,
+}
+
+/// A block whose state root the caller trusts.
+#[derive(Debug, Clone)]
+pub enum TrustedCheckpoint {
+    /// A full header obtained from a trusted source, e.g. a light client.
+    Header(Header),
+    /// A trusted block hash. The header is fetched from the provider and must hash to `hash`.
+    BlockHash { number: u64, hash: B256 },
+}
+
+#[derive(Debug, Error)]
+pub enum StorageProofError {
+    #[error("RPC error: {0}")]
+    Rpc(#[from] Eip1193Error),
+    #[error("Header for block {number} hashes to {actual}, expected {expected}")]
+    HeaderMismatch {
+        number: u64,
+        expected: B256,
+        actual: B256,
+    },
+    #[error("Proof is for account {actual}, expected {expected}")]
+    AccountMismatch { expected: Address, actual: Address },
+    #[error("Missing storage proof for slot {0}")]
+    MissingStorageProof(B256),
+    #[error("Invalid account proof: {0}")]
+    InvalidAccountProof(String),
+    #[error("Invalid storage proof: {0}")]
+    InvalidStorageProof(String),
+    #[error("The contract's current root is not in the rootHistory mapping at slot {0}")]
+    RootHistorySlotMismatch(U256),
+}
+
+impl StorageProofUtxoVerifier {
+    pub fn new(
+        address: Address,
+        provider: Arc<dyn Eip1193Provider>,
+        checkpoint: TrustedCheckpoint,
+    ) -> Self {
+        Self {
+            address,
+            provider,
+            root_history_slot: U256::from(ROOT_HISTORY_SLOT),
+            checkpoint: Mutex::new(checkpoint),
+            slot_checked: AtomicBool::new(false),
+        }
+    }
+
+    /// Overrides the storage slot of the `rootHistory` mapping, for deployments with a
+    /// different storage layout.
+    pub fn with_root_history_slot(mut self, slot: u64) -> Self {
+        self.root_history_slot = U256::from(slot);
+        self
+    }
+
+    /// Replaces the trusted checkpoint.
+    pub fn set_checkpoint(&self, checkpoint: TrustedCheckpoint) {
+        *self.checkpoint.lock().unwrap_or_else(|e| e.into_inner()) = checkpoint;
+    }
+
+    /// Returns whether `root` is in the `rootHistory` of `tree_number` at the checkpoint.
+    pub async fn root_seen(&self, tree_number: u32, root: B256) -> Result<bool, StorageProofError> {
+        let header = self.trusted_header().await?;
+        let seen = self.prove_root(&header, tree_number, root).await?;
+        if !seen && !self.slot_checked.load(Ordering::Relaxed) {
+            self.check_root_history_slot(&header).await?;
+        }
+        Ok(seen)
+    }
+
+    /// Proves the contract's current root at `header`, which every insertion adds to the root
+    /// history, so that a wrong root history slot fails loudly.
+    async fn check_root_history_slot(&self, header: &Header) -> Result<(), StorageProofError> {
+        let tree_number: U256 = self
+            .call_at(header.number, RailgunSmartWallet::treeNumberCall {})
+            .await?;
+        let root = self
+            .call_at(header.number, RailgunSmartWallet::merkleRootCall {})
+            .await?;
+
+        if !self
+            .prove_root(header, tree_number.saturating_to(), root)
+            .await?
+        {
+            return Err(StorageProofError::RootHistorySlotMismatch(
+                self.root_history_slot,
+            ));
+        }
+        self.slot_checked.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn call_at<C: SolCall + common::MaybeSend>(
+        &self,
+        block_number: u64,
+        call: C,
+    ) -> Result<C::Return, StorageProofError> {
+        let data: Bytes = call.abi_encode().into();
+        let params = json!([{ "to": self.address, "data": data }, U64::from(block_number)]);
+        let ret: Bytes = self.provider.request_as("eth_call", params).await?;
+        Ok(C::abi_decode_returns(&ret).map_err(|e| Eip1193Error::Decode(e.to_string()))?)
+    }
+
+    /// Checks the storage proof of `rootHistory[tree_number][root]` against `header`.
+    async fn prove_root(
+        &self,
+        header: &Header,
+        tree_number: u32,
+        root: B256,
+    ) -> Result<bool, StorageProofError> {
+        let slot = self.root_history_key(tree_number, root);
+
+        let proof = self
+            .provider
+            .get_proof(self.address, vec![slot], header.number)
+            .await?;
+        if proof.address != self.address {
+            return Err(StorageProofError::AccountMismatch {
+                expected: self.address,
+                actual: proof.address,
+            });
+        }
+
+        let account = encode_account(
+            proof.nonce,
+            proof.balance,
+            proof.storage_hash,
+            proof.code_hash,
+        );
+        verify_proof(
+            header.state_root,
+            Nibbles::unpack(keccak256(self.address)),
+            Some(account),
+            &proof.account_proof,
+        )
+        .map_err(|e| StorageProofError::InvalidAccountProof(e.to_string()))?;
+
+        let storage = proof
+            .storage_proof
+            .iter()
+            .find(|p| p.key.as_b256() == slot)
+            .ok_or(StorageProofError::MissingStorageProof(slot))?;
+        let expected = (!storage.value.is_zero()).then(|| alloy::rlp::encode(storage.value));
+        verify_proof(
+            proof.storage_hash,
+            Nibbles::unpack(keccak256(slot)),
+            expected,
+            &storage.proof,
+        )
+        .map_err(|e| StorageProofError::InvalidStorageProof(e.to_string()))?;
+
+        Ok(!storage.value.is_zero())
+    }
+
+    /// Resolves the checkpoint to a header, fetching and checking it against the trusted hash
+    /// if needed. Verified headers are cached in place of the hash.
+    async fn trusted_header(&self) -> Result<Header, StorageProofError> {
+        let checkpoint = self
+            .checkpoint
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let (number, expected) = match checkpoint {
+            TrustedCheckpoint::Header(header) => return Ok(header),
+            TrustedCheckpoint::BlockHash { number, hash } => (number, hash),
+        };
+
+        let header = self.provider.get_block(number).await?;
+        let actual = header.hash_slow();
+        if header.number != number || actual != expected {
+            return Err(StorageProofError::HeaderMismatch {
+                number,
+                expected,
+                actual,
+            });
+        }
+
+        let mut checkpoint = self.checkpoint.lock().unwrap_or_else(|e| e.into_inner());
+        if matches!(*checkpoint, TrustedCheckpoint::BlockHash { hash, .. } if hash == expected) {
+            *checkpoint = TrustedCheckpoint::Header(header.clone());
+        }
+        Ok(header)
+    }
+
+    /// Storage key of `rootHistory[tree_number][root]`, following Solidity's layout for nested
+    /// mappings: `keccak256(root . keccak256(tree_number . slot))`.
+    fn root_history_key(&self, tree_number: u32, root: B256) -> B256 {
+        let mut inner = [0u8; 64];
+        inner[..32].copy_from_slice(&U256::from(tree_number).to_be_bytes::<32>());
+        inner[32..].copy_from_slice(&self.root_history_slot.to_be_bytes::<32>());
+
+        let mut outer = [0u8; 64];
+        outer[..32].copy_from_slice(root.as_slice());
+        outer[32..].copy_from_slice(keccak256(inner).as_slice());
+        keccak256(outer)
+    }
+}
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl MerkleTreeVerifier for StorageProofUtxoVerifier {
+    async fn verify_root(
+        &self,
+        tree_number: u32,
+        _tree_index: u32,
+        root: MerkleRoot,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let root: U256 = root.into();
+        Ok(self.root_seen(tree_number, root.into()).await?)
+    }
+}
+
+/// RLP-encodes an account as stored in the state trie.
+fn encode_account(nonce: u64, balance: U256, storage_root: B256, code_hash: B256) -> Vec<u8> {
+    let payload_length =
+        nonce.length() + balance.length() + storage_root.length() + code_hash.length();
+
+    let mut out = Vec::new();
+    RlpHeader {
+        list: true,
+        payload_length,
+    }
+    .encode(&mut out);
+    nonce.encode(&mut out);
+    balance.encode(&mut out);
+    storage_root.encode(&mut out);
+    code_hash.encode(&mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{Bytes, U64},
+        providers::{Provider, ProviderBuilder},
+        rpc::types::{EIP1186AccountProofResponse, EIP1186StorageProof},
+        trie::{HashBuilder, proof::ProofRetainer},
+    };
+    use eip_1193_provider::{
+        fixture::{Fixture, Recorder, Replayer},
+        provider::Eip1193Caller,
+        recording::{RecordingProvider, ReplayProvider},
+    };
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::{abis::railgun::RailgunSmartWallet, chain_config::ChainConfig};
+
+    /// Provider that serves a fixed header and proofs, and reports `current_root` as the
+    /// contract's root of tree 0.
+    struct ProofProvider {
+        header: Header,
+        proofs: Vec<EIP1186AccountProofResponse>,
+        current_root: B256,
+    }
+
+    impl ProofProvider {
+        fn new(header: Header, proof: EIP1186AccountProofResponse) -> Self {
+            Self {
+                header,
+                proofs: vec![proof],
+                current_root: B256::ZERO,
+            }
+        }
+
+        fn get_proof(&self, params: &Value) -> Result<Value, Eip1193Error> {
+            let slot: B256 = serde_json::from_value(params[1][0].clone()).unwrap();
+            self.proofs
+                .iter()
+                .find(|p| p.storage_proof.iter().any(|s| s.key.as_b256() == slot))
+                .map(|proof| json!(proof))
+                .ok_or_else(|| Eip1193Error::Rpc("no proof".into()))
+        }
+
+        fn call(&self, params: &Value) -> Result<Value, Eip1193Error> {
+            let data: Bytes = serde_json::from_value(params[0]["data"].clone()).unwrap();
+            if data[..4] == RailgunSmartWallet::treeNumberCall::SELECTOR {
+                Ok(json!(Bytes::from(U256::ZERO.to_be_bytes::<32>())))
+            } else {
+                Ok(json!(self.current_root))
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Eip1193Provider for ProofProvider {
+        async fn request(&self, method: &str, params: Value) -> Result<Value, Eip1193Error> {
+            match method {
+                "eth_chainId" => Ok(json!("0x1")),
+                "eth_blockNumber" => Ok(json!(U64::from(self.header.number))),
+                "eth_getBlockByNumber" => Ok(json!(self.header)),
+                "eth_getProof" => self.get_proof(&params),
+                "eth_call" => self.call(&params),
+                _ => Err(Eip1193Error::Rpc(format!("Unsupported method {method}"))),
+            }
+        }
+    }
+
+    /// A proof claiming `root` was seen, with no trie nodes to back it up.
+    fn forged_proof(
+        verifier: &StorageProofUtxoVerifier,
+        address: Address,
+    ) -> EIP1186AccountProofResponse {
+        let slot = verifier.root_history_key(0, B256::repeat_byte(1));
+        EIP1186AccountProofResponse {
+            address,
+            balance: U256::ZERO,
+            code_hash: B256::ZERO,
+            nonce: 0,
+            storage_hash: B256::repeat_byte(2),
+            account_proof: vec![],
+            storage_proof: vec![EIP1186StorageProof {
+                key: slot.into(),
+                value: U256::from(1),
+                proof: vec![],
+            }],
+        }
+    }
+
+    fn verifier(header: Header, checkpoint: TrustedCheckpoint) -> StorageProofUtxoVerifier {
+        let address = Address::repeat_byte(0xaa);
+        let unproved = StorageProofUtxoVerifier::new(
+            address,
+            Arc::new(ProofProvider::new(
+                header.clone(),
+                EIP1186AccountProofResponse::default(),
+            )),
+            checkpoint.clone(),
+        );
+        let proof = forged_proof(&unproved, address);
+        StorageProofUtxoVerifier::new(
+            address,
+            Arc::new(ProofProvider::new(header, proof)),
+            checkpoint,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_rejects_untrusted_header() {
+        let header = Header {
+            number: 10,
+            state_root: B256::repeat_byte(3),
+            ..Default::default()
+        };
+        let verifier = verifier(
+            header,
+            TrustedCheckpoint::BlockHash {
+                number: 10,
+                hash: B256::repeat_byte(4),
+            },
+        );
+
+        let err = verifier
+            .root_seen(0, B256::repeat_byte(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, StorageProofError::HeaderMismatch { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_forged_proof() {
+        let header = Header {
+            number: 10,
+            state_root: B256::repeat_byte(3),
+            ..Default::default()
+        };
+        let checkpoint = TrustedCheckpoint::BlockHash {
+            number: 10,
+            hash: header.hash_slow(),
+        };
+        let verifier = verifier(header, checkpoint);
+
+        let err = verifier
+            .root_seen(0, B256::repeat_byte(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, StorageProofError::InvalidAccountProof(_)));
+    }
+
+    /// Builds a trie over `leaves`, keyed by already hashed keys, and returns its root and the
+    /// proof for `target`.
+    fn trie_proof(leaves: &[(B256, Vec<u8>)], target: B256) -> (B256, Vec<Bytes>) {
+        let target = Nibbles::unpack(target);
+        let mut leaves: Vec<_> = leaves
+            .iter()
+            .map(|(key, value)| (Nibbles::unpack(key), value))
+            .collect();
+        leaves.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut builder =
+            HashBuilder::default().with_proof_retainer(ProofRetainer::new(vec![target]));
+        for (key, value) in leaves {
+            builder.add_leaf(key, value);
+        }
+        let root = builder.root();
+        let proof = builder
+            .take_proof_nodes()
+            .matching_nodes_sorted(&target)
+            .into_iter()
+            .map(|(_, node)| node)
+            .collect();
+        (root, proof)
+    }
+
+    /// Serves proofs of `slots` against a state where the contract at `address` stores `set`
+    /// with the value 1, and reports `current_root` as its root.
+    fn state_provider(
+        address: Address,
+        set: &[B256],
+        slots: &[B256],
+        current_root: B256,
+    ) -> ProofProvider {
+        let mut leaves: Vec<_> = set
+            .iter()
+            .map(|slot| (keccak256(slot), alloy::rlp::encode(U256::from(1))))
+            .collect();
+        leaves.push((
+            keccak256(B256::repeat_byte(5)),
+            alloy::rlp::encode(U256::from(7)),
+        ));
+        let (storage_hash, _) = trie_proof(&leaves, B256::ZERO);
+
+        let code_hash = B256::repeat_byte(6);
+        let (state_root, account_proof) = trie_proof(
+            &[
+                (
+                    keccak256(address),
+                    encode_account(1, U256::ZERO, storage_hash, code_hash),
+                ),
+                (
+                    keccak256(Address::repeat_byte(0xbb)),
+                    encode_account(0, U256::from(1), B256::ZERO, B256::ZERO),
+                ),
+            ],
+            keccak256(address),
+        );
+
+        let proofs = slots
+            .iter()
+            .map(|slot| {
+                let (_, storage_proof) = trie_proof(&leaves, keccak256(slot));
+                let value = U256::from(set.contains(slot) as u8);
+                EIP1186AccountProofResponse {
+                    address,
+                    balance: U256::ZERO,
+                    code_hash,
+                    nonce: 1,
+                    storage_hash,
+                    account_proof: account_proof.clone(),
+                    storage_proof: vec![EIP1186StorageProof {
+                        key: (*slot).into(),
+                        value,
+                        proof: storage_proof,
+                    }],
+                }
+            })
+            .collect();
+
+        ProofProvider {
+            header: Header {
+                number: 10,
+                state_root,
+                ..Default::default()
+            },
+            proofs,
+            current_root,
+        }
+    }
+
+    fn state_verifier(provider: ProofProvider, slot: u64) -> StorageProofUtxoVerifier {
+        let checkpoint = TrustedCheckpoint::BlockHash {
+            number: 10,
+            hash: provider.header.hash_slow(),
+        };
+        StorageProofUtxoVerifier::new(provider.proofs[0].address, Arc::new(provider), checkpoint)
+            .with_root_history_slot(slot)
+    }
+
+    /// Storage key of `rootHistory[0][root]` with the mapping at `slot`.
+    fn key_at(slot: u64, root: B256) -> B256 {
+        verifier(
+            Header::default(),
+            TrustedCheckpoint::Header(Header::default()),
+        )
+        .with_root_history_slot(slot)
+        .root_history_key(0, root)
+    }
+
+    #[tokio::test]
+    async fn test_verifies_root_history_proof() {
+        let address = Address::repeat_byte(0xaa);
+        let (root, current_root) = (B256::repeat_byte(1), B256::repeat_byte(2));
+        let slot = ROOT_HISTORY_SLOT;
+        let seen = [key_at(slot, root), key_at(slot, current_root)];
+        let unseen = key_at(slot, B256::repeat_byte(3));
+
+        let provider = state_provider(address, &seen, &[seen[0], seen[1], unseen], current_root);
+        let verifier = state_verifier(provider, slot);
+
+        assert!(verifier.root_seen(0, root).await.unwrap());
+        //? The current root verifies, so the unseen root is rejected rather than the slot.
+        assert!(!verifier.root_seen(0, B256::repeat_byte(3)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_detects_wrong_root_history_slot() {
+        let address = Address::repeat_byte(0xaa);
+        let (root, current_root) = (B256::repeat_byte(1), B256::repeat_byte(2));
+        //? The contract keeps its root history at slot 7, but the verifier looks in slot 8.
+        let seen = [key_at(7, root), key_at(7, current_root)];
+        let wrong = [key_at(8, root), key_at(8, current_root)];
+
+        let provider = state_provider(address, &seen, &wrong, current_root);
+        let verifier = state_verifier(provider, 8);
+
+        assert!(matches!(
+            verifier.root_seen(0, root).await,
+            Err(StorageProofError::RootHistorySlotMismatch(slot)) if slot == U256::from(8)
+        ));
+    }
+
+    /// Checks [`ROOT_HISTORY_SLOT`] against mainnet storage by proving the current root of the
+    /// mainnet `RailgunSmartWallet`.
+    ///
+    /// Replays `tests/fixtures/storage_proof_mainnet.json` if it exists. Otherwise records it
+    /// from `RPC_URL_MAINNET`, so it can be committed.
+    #[tokio::test]
+    #[ignore]
+    async fn test_root_history_slot_mainnet() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/storage_proof_mainnet.json"
+        );
+        let recorder = Arc::new(Recorder::new());
+        let provider: Arc<dyn Eip1193Provider> = match Fixture::load(path) {
+            Ok(fixture) => Arc::new(ReplayProvider::new(Arc::new(Replayer::new(fixture)))),
+            Err(_) => {
+                let rpc_url = std::env::var("RPC_URL_MAINNET")
+                    .expect("RPC_URL_MAINNET must be set to record the fixture");
+                let live = ProviderBuilder::new()
+                    .connect(&rpc_url)
+                    .await
+                    .unwrap()
+                    .erased();
+                Arc::new(RecordingProvider::new(live, recorder.clone()))
+            }
+        };
+
+        let address = ChainConfig::mainnet().railgun_smart_wallet;
+        let tree_number: U256 = provider
+            .sol_call(address, RailgunSmartWallet::treeNumberCall {})
+            .await
+            .unwrap();
+        let root: B256 = provider
+            .sol_call(address, RailgunSmartWallet::merkleRootCall {})
+            .await
+            .unwrap();
+        //? The root is read first, so it is in the root history of any later checkpoint.
+        let number = provider.get_block_number().await.unwrap();
+        let header = provider.get_block(number).await.unwrap();
+
+        let verifier =
+            StorageProofUtxoVerifier::new(address, provider, TrustedCheckpoint::Header(header));
+        let seen = verifier
+            .root_seen(tree_number.to::<u32>(), root)
+            .await
+            .unwrap();
+        assert!(
+            seen,
+            "root {root} of tree {tree_number} not in slot {ROOT_HISTORY_SLOT}"
+        );
+
+        if !std::path::Path::new(path).exists() {
+            recorder.fixture().save(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_root_history_key() {
+        let verifier = StorageProofUtxoVerifier::new(
+            Address::ZERO,
+            Arc::new(ProofProvider::new(
+                Header::default(),
+                EIP1186AccountProofResponse::default(),
+            )),
+            TrustedCheckpoint::Header(Header::default()),
+        );
+
+        let key = verifier.root_history_key(1, B256::repeat_byte(1));
+        assert_ne!(key, verifier.root_history_key(0, B256::repeat_byte(1)));
+        assert_ne!(key, verifier.root_history_key(1, B256::repeat_byte(2)));
+        assert_ne!(
+            key,
+            verifier
+                .with_root_history_slot(0)
+                .root_history_key(1, B256::repeat_byte(1))
+        );
+    }
+}
@@ -75,6 +75,11 @@ impl TxidMerkleTree {
         self.inner.state()
     }
 
+    /// Returns the tree's leaves in insertion order.
+    pub fn leaves(&self) -> Vec<U256> {
+        self.inner.leaves()
+    }
+
     pub fn generate_proof(&self, leaf: TxidLeafHash) -> Result<MerkleProof, MerkleTreeError> {
         self.inner.generate_proof(leaf.into())
     }
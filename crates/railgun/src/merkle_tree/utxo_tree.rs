@@ -41,6 +41,11 @@ impl UtxoMerkleTree {
         self.inner.state()
     }
 
+    /// Returns the tree's leaves in insertion order.
+    pub fn leaves(&self) -> Vec<U256> {
+        self.inner.leaves()
+    }
+
     pub fn generate_proof(&self, leaf: UtxoLeafHash) -> Result<MerkleProof, MerkleTreeError> {
         self.inner.generate_proof(leaf.into())
     }
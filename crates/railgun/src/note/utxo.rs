@@ -13,10 +13,10 @@ use crate::{
     account::signer::RailgunSigner,
     caip::AssetId,
     crypto::{
-        aes::AesError,
+        aes::{AesError, Ciphertext},
         keys::{
-            BlindedKey, ByteKey, KeyError, MasterPublicKey, NullifyingKey, SpendingPublicKey,
-            U256Key, ViewingPublicKey,
+            BlindedKey, ByteKey, KeyError, MasterPublicKey, NullifyingKey, SharedKey,
+            SpendingPublicKey, U256Key, ViewingPublicKey,
         },
     },
     indexer::syncer,
@@ -45,6 +45,9 @@ pub struct UtxoNote {
     pub nullifying_key: NullifyingKey,
     pub blinded_commitment: U256,
     pub commitment_type: BlindedCommitmentType,
+    /// Block of the shield that created the note. None for transact notes.
+    #[serde(default)]
+    pub shield_block: Option<u64>,
 }
 
 #[derive(Debug, Error)]
@@ -55,6 +58,62 @@ pub enum NoteError {
     TokenData(#[from] TokenDataError),
     #[error("Key error: {0}")]
     Key(#[from] KeyError),
+    #[error("Malformed ciphertext")]
+    MalformedCiphertext,
+}
+
+/// The decrypted contents of a transact commitment's ciphertext.
+pub(crate) struct TransactPlaintext {
+    /// The receiver's master public key as encoded by the sender. XORed with the sender's master
+    /// public key unless the sender is hidden.
+    pub encoded_master_key: [u8; 32],
+    pub asset: AssetId,
+    pub value: u128,
+    pub random: [u8; 16],
+    pub memo: String,
+}
+
+impl TransactPlaintext {
+    pub fn decrypt(shared_key: &SharedKey, ciphertext: &Ciphertext) -> Result<Self, NoteError> {
+        // iv (16) | tag (16)
+        // master_public_key (32)
+        // token_hash (32)
+        // random (16) | value (16)
+        // memo (optional)
+        let bundle = shared_key.decrypt_gcm(ciphertext)?;
+        let encoded_master_key: [u8; 32] = bundle
+            .first()
+            .and_then(|b| b.as_slice().try_into().ok())
+            .ok_or(NoteError::MalformedCiphertext)?;
+        let token_hash = bundle.get(1).ok_or(NoteError::MalformedCiphertext)?;
+        let random_value: &[u8; 32] = bundle
+            .get(2)
+            .and_then(|b| b.as_slice().try_into().ok())
+            .ok_or(NoteError::MalformedCiphertext)?;
+
+        let token_data = TokenData::from_hash(token_hash)?;
+        let asset = AssetId::from(token_data);
+
+        let mut random = [0u8; 16];
+        random.copy_from_slice(&random_value[..16]);
+
+        let mut value_bytes = [0u8; 16];
+        value_bytes.copy_from_slice(&random_value[16..]);
+        let value = u128::from_be_bytes(value_bytes);
+
+        let memo = bundle
+            .get(3)
+            .and_then(|m| std::str::from_utf8(m).ok())
+            .unwrap_or("");
+
+        Ok(TransactPlaintext {
+            encoded_master_key,
+            asset,
+            value,
+            random,
+            memo: memo.to_string(),
+        })
+    }
 }
 
 impl UtxoNote {
@@ -91,6 +150,7 @@ impl UtxoNote {
             nullifier,
             blinded_commitment,
             commitment_type,
+            shield_block: None,
         }
     }
 
@@ -103,36 +163,16 @@ impl UtxoNote {
         let shared_key = signer
             .viewing_key()
             .derive_shared_key_blinded(blinded_sender)?;
-
-        // iv (16) | tag (16)
-        // token_hash (32)
-        // random (16) | value (16)
-        // memo (optional)
-        let bundle = shared_key.decrypt_gcm(&transact.ciphertext)?;
-        let token_data = TokenData::from_hash(&bundle[1])?;
-        let asset_id = AssetId::from(token_data);
-
-        let mut random = [0u8; 16];
-        random.copy_from_slice(&bundle[2][..16]);
-
-        let mut value_bytes = [0u8; 16];
-        value_bytes.copy_from_slice(&bundle[2][16..]);
-        let value = u128::from_be_bytes(value_bytes);
-
-        let memo = if bundle.len() > 3 {
-            std::str::from_utf8(&bundle[3]).unwrap_or("")
-        } else {
-            ""
-        };
+        let plaintext = TransactPlaintext::decrypt(&shared_key, &transact.ciphertext)?;
 
         Ok(UtxoNote::new(
             transact.tree_number,
             transact.leaf_index,
             signer,
-            asset_id,
-            value,
-            random,
-            memo,
+            plaintext.asset,
+            plaintext.value,
+            plaintext.random,
+            &plaintext.memo,
             BlindedCommitmentType::Transact,
         ))
     }
@@ -307,4 +347,27 @@ mod tests {
 
         insta::assert_debug_snapshot!(pub_key);
     }
+
+    #[test]
+    #[traced_test]
+    fn test_decrypt_short_ciphertext() {
+        use crate::crypto::keys::ViewingKey;
+
+        let viewing_key = ViewingKey::from_bytes([2u8; 32]);
+        let shared_key = viewing_key
+            .derive_shared_key(ViewingKey::from_bytes([3u8; 32]).public_key())
+            .unwrap();
+
+        let truncated = shared_key
+            .encrypt_gcm(&[&[0u8; 32], &[0u8; 32]], &[4u8; 16])
+            .unwrap();
+        let result = TransactPlaintext::decrypt(&shared_key, &truncated);
+        assert!(matches!(result, Err(NoteError::MalformedCiphertext)));
+
+        let short_value = shared_key
+            .encrypt_gcm(&[&[0u8; 32], &[0u8; 32], &[0u8; 16]], &[4u8; 16])
+            .unwrap();
+        let result = TransactPlaintext::decrypt(&shared_key, &short_value);
+        assert!(matches!(result, Err(NoteError::MalformedCiphertext)));
+    }
 }
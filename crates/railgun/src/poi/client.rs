@@ -7,6 +7,7 @@ use std::{
 };
 
 use alloy::primitives::ChainId;
+use eip_1193_provider::fixture::{FixtureError, FixtureMode};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use thiserror::Error;
 
@@ -30,6 +31,13 @@ pub trait PoiNodeClient {
         blinded_commitment: BlindedCommitment,
         commitment_type: BlindedCommitmentType,
     ) -> Result<PoiStatus, PoiClientError>;
+    /// Returns the POI statuses of many blinded commitments across `list_keys`. Statuses the
+    /// node doesn't report are omitted from the result.
+    async fn poi_statuses(
+        &self,
+        list_keys: &[ListKey],
+        blinded_commitments: &[BlindedCommitmentData],
+    ) -> Result<PoisPerListMap, PoiClientError>;
     async fn merkle_proof(
         &self,
         list_key: &ListKey,
@@ -48,10 +56,14 @@ pub trait PoiNodeClient {
     ) -> Result<bool, PoiClientError>;
 }
 
+/// Maximum number of blinded commitments per `ppoi_pois_per_list` request.
+const POI_STATUS_BATCH_SIZE: usize = 100;
+
 #[derive(Clone)]
 pub struct PoiClient {
     inner: Arc<PoiClientInner>,
     list_keys: Vec<ListKey>,
+    fixture: FixtureMode,
 }
 
 pub struct PoiClientInner {
@@ -76,6 +88,10 @@ pub enum PoiClientError {
     InvalidPoiMerkleRoot(ListKey, MerkleRoot),
     #[error("Proof not found for blinded commitment {1:?} and list key {0:?}")]
     ProofNotFound(ListKey, BlindedCommitment),
+    #[error("Fixture error: {0}")]
+    Fixture(#[from] FixtureError),
+    #[error("Recorded error: {0}")]
+    Recorded(String),
 }
 
 #[derive(Debug, Serialize)]
@@ -117,9 +133,16 @@ impl PoiClient {
                 chain,
             }),
             list_keys,
+            fixture: FixtureMode::Live,
         }
     }
 
+    /// Records JSON-RPC traffic to, or replays it from, a fixture.
+    pub fn with_fixture(mut self, fixture: FixtureMode) -> Self {
+        self.fixture = fixture;
+        self
+    }
+
     fn chain(&self) -> ChainParams {
         ChainParams {
             chain_type: 0.to_string(), // EVM
@@ -170,6 +193,30 @@ impl PoiNodeClient for PoiClient {
         Ok(poi_status)
     }
 
+    /// Returns the POI statuses for many blinded commitments, split into requests of at most
+    /// `POI_STATUS_BATCH_SIZE` commitments.
+    async fn poi_statuses(
+        &self,
+        list_keys: &[ListKey],
+        blinded_commitments: &[BlindedCommitmentData],
+    ) -> Result<PoisPerListMap, PoiClientError> {
+        let mut statuses = PoisPerListMap::new();
+        for chunk in blinded_commitments.chunks(POI_STATUS_BATCH_SIZE) {
+            let pois_per_list: PoisPerListMap = self
+                .call(
+                    "ppoi_pois_per_list",
+                    GetPoisPerListParams {
+                        chain: self.chain(),
+                        list_keys: list_keys.to_vec(),
+                        blinded_commitment_datas: chunk.to_vec(),
+                    },
+                )
+                .await?;
+            statuses.extend(pois_per_list);
+        }
+        Ok(statuses)
+    }
+
     /// Returns the Merkle proof for a given list key and blinded commitment.
     ///
     /// NOTE: Fetches a single proof rather than batching many blinded commitments
@@ -277,6 +324,7 @@ impl PoiClient {
             &self.inner.next_id,
             &self.inner.http,
             &self.inner.url,
+            &self.fixture,
             method,
             params,
         )
@@ -288,6 +336,7 @@ async fn call<P: Serialize, R: DeserializeOwned>(
     next_id: &AtomicU64,
     http: &reqwest::Client,
     url: &str,
+    fixture: &FixtureMode,
     method: &'static str,
     params: P,
 ) -> Result<R, PoiClientError> {
@@ -302,13 +351,36 @@ async fn call<P: Serialize, R: DeserializeOwned>(
     // let req_json = serde_json::to_string(&req).unwrap();
     // info!("Sending JSON-RPC request: {}", req_json);
 
-    let resp: JsonRpcResponse<R> = http.post(url).json(&req).send().await?.json().await?;
+    // Fixtures are keyed by method and params only, since request ids differ between runs.
+    let value = match fixture {
+        FixtureMode::Live => post_json(http, url, &req).await?,
+        FixtureMode::Record(recorder) => {
+            let result = post_json(http, url, &req).await;
+            let recorded = result.as_ref().map_err(|e| e.to_string());
+            recorder.record(method, &req.params, recorded);
+            result?
+        }
+        FixtureMode::Replay(replayer) => replayer
+            .replay_as::<_, serde_json::Value, String>(method, &req.params)?
+            .map_err(PoiClientError::Recorded)?,
+    };
+
+    let resp: JsonRpcResponse<R> = serde_json::from_value(value)
+        .map_err(|e| PoiClientError::UnexpectedResponse(e.to_string()))?;
     if let Some(err) = resp.error {
         return Err(PoiClientError::Rpc(err));
     }
     resp.result.ok_or(PoiClientError::NullResult)
 }
 
+async fn post_json<P: Serialize>(
+    http: &reqwest::Client,
+    url: &str,
+    req: &JsonRpcRequest<P>,
+) -> Result<serde_json::Value, PoiClientError> {
+    Ok(http.post(url).json(req).send().await?.json().await?)
+}
+
 impl std::fmt::Display for JsonRpcError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "RPC error {}: {}", self.code, self.message)
@@ -1,6 +1,10 @@
 pub(crate) mod client;
+#[cfg(native)]
+pub mod node;
 pub(crate) mod note;
+pub(crate) mod policy;
 pub(crate) mod provider;
 pub(crate) mod types;
 
-pub use types::PoiStatus;
+pub use policy::{ListPoiStatus, PoiPolicy};
+pub use types::{BlindedCommitment, ListKey, PoiStatus};
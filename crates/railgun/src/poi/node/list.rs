@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    merkle_tree::{MerkleProof, MerkleRoot, MerkleTree, TOTAL_LEAVES},
+    poi::{
+        node::unsaved_pages,
+        types::{BlindedCommitment, PoiStatus},
+    },
+};
+
+/// A POI list: the blinded commitments it has admitted, in a sequence of Merkle trees, and
+/// the shields it has blocked.
+#[derive(Default)]
+pub(super) struct PoiList {
+    trees: Vec<MerkleTree>,
+    /// Admitted blinded commitments in admission order.
+    leaves: Vec<BlindedCommitment>,
+    positions: HashMap<BlindedCommitment, (u32, u32)>,
+    blocked: HashSet<BlindedCommitment>,
+    /// Every root any of the trees has had. Submitted proofs may be generated against any of
+    /// them.
+    roots: HashSet<MerkleRoot>,
+
+    /// Number of leaves already persisted.
+    saved_leaves: usize,
+    blocked_changed: bool,
+}
+
+impl PoiList {
+    /// Rebuilds a list from its persisted leaves and blocked shields.
+    pub fn from_saved(admitted: Vec<BlindedCommitment>, blocked: Vec<BlindedCommitment>) -> Self {
+        let mut list = Self::default();
+        for blinded_commitment in admitted {
+            list.admit(blinded_commitment);
+        }
+        list.blocked = blocked.into_iter().collect();
+        list.mark_saved();
+        list
+    }
+
+    /// Returns the pages of leaves that changed since the last [`mark_saved`](Self::mark_saved).
+    pub fn unsaved_pages(&self) -> impl Iterator<Item = (u32, &[BlindedCommitment])> {
+        unsaved_pages(&self.leaves, self.saved_leaves)
+    }
+
+    /// Returns every blocked shield if any were blocked since the last save.
+    pub fn unsaved_blocked(&self) -> Option<Vec<BlindedCommitment>> {
+        self.blocked_changed
+            .then(|| self.blocked.iter().copied().collect())
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_leaves = self.leaves.len();
+        self.blocked_changed = false;
+    }
+
+    /// Appends a blinded commitment to the list. No-op if it's already admitted.
+    pub fn admit(&mut self, blinded_commitment: BlindedCommitment) {
+        if self.positions.contains_key(&blinded_commitment) {
+            return;
+        }
+
+        let full = self
+            .trees
+            .last()
+            .is_none_or(|tree| tree.leaves_len() >= TOTAL_LEAVES as usize);
+        if full {
+            self.trees.push(MerkleTree::new(self.trees.len() as u32));
+        }
+
+        let tree_number = self.trees.len() as u32 - 1;
+        let tree = self.trees.last_mut().expect("tree was just ensured");
+        let leaf_index = tree.leaves_len();
+        tree.insert_leaves(&[blinded_commitment.into()], leaf_index);
+
+        self.roots.insert(tree.root());
+        self.leaves.push(blinded_commitment);
+        self.positions
+            .insert(blinded_commitment, (tree_number, leaf_index as u32));
+    }
+
+    pub fn block(&mut self, blinded_commitment: BlindedCommitment) {
+        if self.blocked.insert(blinded_commitment) {
+            self.blocked_changed = true;
+        }
+    }
+
+    /// Whether the list has already decided on a shield.
+    pub fn screened(&self, blinded_commitment: &BlindedCommitment) -> bool {
+        self.positions.contains_key(blinded_commitment) || self.blocked.contains(blinded_commitment)
+    }
+
+    pub fn status(&self, blinded_commitment: &BlindedCommitment) -> PoiStatus {
+        if self.positions.contains_key(blinded_commitment) {
+            PoiStatus::Valid
+        } else if self.blocked.contains(blinded_commitment) {
+            PoiStatus::ShieldBlocked
+        } else {
+            PoiStatus::Missing
+        }
+    }
+
+    pub fn proof(&self, blinded_commitment: &BlindedCommitment) -> Option<MerkleProof> {
+        let (tree_number, _) = self.positions.get(blinded_commitment)?;
+        self.trees[*tree_number as usize]
+            .generate_proof((*blinded_commitment).into())
+            .ok()
+    }
+
+    pub fn is_known_root(&self, root: &MerkleRoot) -> bool {
+        self.roots.contains(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ruint::aliases::U256;
+
+    use super::*;
+
+    #[test]
+    fn test_list_roundtrip() {
+        let a = BlindedCommitment::from(U256::from(1));
+        let b = BlindedCommitment::from(U256::from(2));
+        let c = BlindedCommitment::from(U256::from(3));
+
+        let mut list = PoiList::default();
+        list.admit(a);
+        let first_root = list.proof(&a).unwrap().root;
+        list.admit(b);
+        list.admit(a);
+        list.block(c);
+
+        assert_eq!(list.status(&a), PoiStatus::Valid);
+        assert_eq!(list.status(&c), PoiStatus::ShieldBlocked);
+        assert_eq!(
+            list.status(&BlindedCommitment::from(U256::from(4))),
+            PoiStatus::Missing
+        );
+        assert!(list.is_known_root(&first_root));
+
+        let proof = list.proof(&b).unwrap();
+        assert!(proof.verify());
+
+        let pages: Vec<_> = list.unsaved_pages().collect();
+        assert_eq!(pages, vec![(0, &[a, b][..])]);
+        let blocked = list.unsaved_blocked().unwrap();
+        list.mark_saved();
+        assert_eq!(list.unsaved_pages().count(), 0);
+        assert!(list.unsaved_blocked().is_none());
+
+        let restored = PoiList::from_saved(vec![a, b], blocked);
+        assert_eq!(restored.proof(&b).unwrap(), proof);
+        assert!(restored.is_known_root(&first_root));
+        assert_eq!(restored.status(&c), PoiStatus::ShieldBlocked);
+    }
+}
@@ -0,0 +1,76 @@
+use ruint::aliases::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    caip::AssetId,
+    indexer::syncer::Shield,
+    note::utxo,
+    poi::types::{BlindedCommitment, ListKey},
+};
+
+/// Decides which shields a list admits to its POI tree.
+///
+/// Transactions are never screened: their outputs are admitted once a valid POI proof shows
+/// every input was already on the list.
+#[async_trait::async_trait]
+pub trait ListPolicy: Send + Sync {
+    async fn screen_shield(&self, list_key: &ListKey, shield: &ShieldCandidate) -> ShieldDecision;
+}
+
+/// A synced shield waiting to be screened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShieldCandidate {
+    pub blinded_commitment: BlindedCommitment,
+    pub block_number: u64,
+    pub tree_number: u32,
+    pub leaf_index: u32,
+    pub npk: U256,
+    pub token: AssetId,
+    pub value: U256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShieldDecision {
+    /// Admit the shield. Its note becomes `Valid`.
+    Allow,
+    /// Reject the shield. Its note becomes `ShieldBlocked`.
+    Block,
+    /// Not decided yet, e.g. during a waiting period. The shield is screened again on the next
+    /// sync and stays `Missing` until then.
+    Pending,
+}
+
+/// Admits every shield.
+pub struct AllowAll;
+
+impl ShieldCandidate {
+    pub(crate) fn new(shield: &Shield, block_number: u64) -> Self {
+        let blinded_commitment = utxo::blinded_commitment(
+            shield.hash().into(),
+            shield.npk,
+            shield.tree_number,
+            shield.leaf_index,
+        );
+
+        Self {
+            blinded_commitment: blinded_commitment.into(),
+            block_number,
+            tree_number: shield.tree_number,
+            leaf_index: shield.leaf_index,
+            npk: shield.npk,
+            token: shield.token,
+            value: shield.value,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ListPolicy for AllowAll {
+    async fn screen_shield(
+        &self,
+        _list_key: &ListKey,
+        _shield: &ShieldCandidate,
+    ) -> ShieldDecision {
+        ShieldDecision::Allow
+    }
+}
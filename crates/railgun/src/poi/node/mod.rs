@@ -0,0 +1,1089 @@
+//! A self-hostable POI node.
+//!
+//! Serves the same `ppoi_*` JSON-RPC methods as the public POI aggregator, so a wallet can point
+//! its `poi_endpoint` at it. The node keeps its own TXID trees from synced operations and one POI
+//! Merkle tree per list. Shields are admitted or blocked by a [`ListPolicy`]; transaction outputs
+//! are admitted once a submitted POI proof verifies.
+
+mod list;
+mod list_policy;
+mod txid;
+mod verifier;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+pub use list_policy::{AllowAll, ListPolicy, ShieldCandidate, ShieldDecision};
+use ruint::aliases::U256;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use thiserror::Error;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+use crate::{
+    chain_config::ChainConfig,
+    circuit::artifact_loader::{ArtifactLoader, ArtifactLoaderError},
+    database::{Database, DatabaseError, RailgunDB, WriteBatch},
+    indexer::syncer::{SubsquidSyncer, SyncEvent, SyncerError, TxidSyncer, UtxoSyncer},
+    merkle_tree::{MerkleProof, MerkleRoot, TOTAL_LEAVES, TxidLeafHash, UtxoTreeIndex},
+    poi::{
+        node::{list::PoiList, txid::TxidTrees, verifier::PoiProofVerifier},
+        types::{
+            BlindedCommitment, ChainParams, GetMerkleProofsParams, GetPoisPerListParams, ListKey,
+            PoisPerListMap, SubmitTransactProofParams, TransactProofData,
+            ValidateTxidMerklerootParams, ValidatedRailgunTxidStatus,
+        },
+    },
+};
+
+/// Leaves are persisted in pages of this many, so a save only rewrites the pages that grew.
+const PAGE_SIZE: usize = 1024;
+
+pub struct PoiNode {
+    chain: ChainConfig,
+    db: Arc<dyn Database>,
+    utxo_syncer: Arc<dyn UtxoSyncer>,
+    txid_syncer: Arc<dyn TxidSyncer>,
+    policy: Arc<dyn ListPolicy>,
+    verifier: PoiProofVerifier,
+
+    //? Syncs fetch and screen without holding `state`, so requests are only held up while
+    //? the results are applied. `sync_lock` keeps two syncs from fetching the same range.
+    state: RwLock<NodeState>,
+    sync_lock: Mutex<()>,
+}
+
+struct NodeState {
+    synced_block: u64,
+    /// Number of leaves synced into each UTXO tree, so each sync continues where the last one
+    /// stopped. None for nodes saved before these were tracked.
+    utxo_tree_lengths: Option<BTreeMap<u32, u32>>,
+    lists: HashMap<ListKey, PoiList>,
+    txids: TxidTrees,
+    /// Shields that at least one list hasn't decided on yet.
+    pending_shields: Vec<ShieldCandidate>,
+}
+
+/// Persisted node state other than the lists and TXID leaves, which are saved in pages.
+#[derive(Serialize, Deserialize, Default)]
+pub(crate) struct PoiNodeState {
+    synced_block: Option<u64>,
+    utxo_tree_lengths: Option<BTreeMap<u32, u32>>,
+    pending_shields: Vec<ShieldCandidate>,
+}
+
+#[derive(Debug, Error)]
+pub enum PoiNodeError {
+    #[error("Unknown method: {0}")]
+    UnknownMethod(String),
+    #[error("Invalid params: {0}")]
+    InvalidParams(#[from] serde_json::Error),
+    #[error("Wrong chain: {0}")]
+    WrongChain(String),
+    #[error("Unknown list key: {0}")]
+    UnknownList(ListKey),
+    #[error("No TXIDs have been synced yet")]
+    NoValidatedTxid,
+    #[error("Unknown TXID Merkle root {0:?} at index {1}")]
+    UnknownTxidRoot(MerkleRoot, u64),
+    #[error("Unknown POI Merkle root {0:?} for list {1}")]
+    UnknownPoiRoot(MerkleRoot, ListKey),
+    #[error("Invalid POI proof")]
+    InvalidProof,
+    #[error("No Merkle proof for {1} on list {0}")]
+    ProofNotFound(ListKey, BlindedCommitment),
+    #[error("Artifact loader error: {0}")]
+    Artifacts(#[from] ArtifactLoaderError),
+    #[error("Syncer error: {0}")]
+    Syncer(#[from] SyncerError),
+    #[error("Database error: {0}")]
+    Database(#[from] DatabaseError),
+}
+
+impl PoiNode {
+    /// Creates a node serving `list_keys` on `chain`, loading any state saved in `db`.
+    ///
+    /// Shields and operations are synced from the chain's Subsquid endpoint by default. The node
+    /// should have a database of its own; it isn't meant to share one with a wallet.
+    pub async fn new(
+        chain: ChainConfig,
+        db: Arc<dyn Database>,
+        list_keys: Vec<ListKey>,
+        policy: Arc<dyn ListPolicy>,
+    ) -> Result<Self, PoiNodeError> {
+        let syncer = Arc::new(SubsquidSyncer::new(&chain.subsquid_endpoint));
+        let state = db.get_poi_node().await?;
+
+        let mut lists = HashMap::new();
+        for key in list_keys {
+            let mut admitted = Vec::new();
+            for page in 0.. {
+                let Some(leaves) = db.get_poi_node_list(&key, page).await? else {
+                    break;
+                };
+                admitted.extend(leaves);
+            }
+            let blocked = db.get_poi_node_blocked(&key).await?;
+            lists.insert(key, PoiList::from_saved(admitted, blocked));
+        }
+
+        let mut txid_leaves = Vec::new();
+        for page in 0.. {
+            let Some(leaves) = db.get_poi_node_txids(page).await? else {
+                break;
+            };
+            txid_leaves.extend(leaves);
+        }
+
+        let synced_block = state.synced_block.unwrap_or(chain.deployment_block);
+        info!(
+            "Loaded POI node state: synced_block={}, lists={}, txids={}, pending_shields={}",
+            synced_block,
+            lists.len(),
+            txid_leaves.len(),
+            state.pending_shields.len()
+        );
+
+        Ok(Self {
+            chain,
+            db,
+            utxo_syncer: syncer.clone(),
+            txid_syncer: syncer,
+            policy,
+            verifier: PoiProofVerifier::new(ArtifactLoader::default()),
+            state: RwLock::new(NodeState {
+                synced_block,
+                //? A fresh node starts from empty trees.
+                utxo_tree_lengths: state
+                    .utxo_tree_lengths
+                    .or_else(|| state.synced_block.is_none().then(BTreeMap::new)),
+                lists,
+                txids: TxidTrees::from_leaves(txid_leaves),
+                pending_shields: state.pending_shields,
+            }),
+            sync_lock: Mutex::new(()),
+        })
+    }
+
+    /// Syncs shields from `syncer` instead of Subsquid.
+    pub fn with_utxo_syncer(mut self, syncer: Arc<dyn UtxoSyncer>) -> Self {
+        self.utxo_syncer = syncer;
+        self
+    }
+
+    /// Syncs operations from `syncer` instead of Subsquid.
+    pub fn with_txid_syncer(mut self, syncer: Arc<dyn TxidSyncer>) -> Self {
+        self.txid_syncer = syncer;
+        self
+    }
+
+    /// Loads the POI verifying keys with `artifact_loader` instead of downloading them from the
+    /// default artifact host.
+    pub fn with_artifact_loader(mut self, artifact_loader: ArtifactLoader) -> Self {
+        self.verifier = PoiProofVerifier::new(artifact_loader);
+        self
+    }
+
+    pub async fn synced_block(&self) -> u64 {
+        self.state.read().await.synced_block
+    }
+
+    /// Syncs new shields and operations up to `to_block`, then screens every pending shield.
+    ///
+    /// Requests are served from the previous state until the sync's results are applied.
+    #[tracing::instrument(name = "poi_node_sync", skip_all)]
+    pub async fn sync_to(&self, to_block: u64) -> Result<(), PoiNodeError> {
+        let _sync = self.sync_lock.lock().await;
+
+        let latest_block = self
+            .utxo_syncer
+            .latest_block()
+            .await?
+            .min(self.txid_syncer.latest_block().await?);
+        let to_block = to_block.min(latest_block);
+
+        let (synced_block, mut utxo_tree_lengths, mut candidates) = {
+            let state = self.state.read().await;
+            (
+                state.synced_block,
+                state.utxo_tree_lengths.clone(),
+                state.pending_shields.clone(),
+            )
+        };
+
+        let mut txid_leaves = Vec::new();
+        if to_block > synced_block {
+            let from_block = synced_block + 1;
+
+            let events = match &utxo_tree_lengths {
+                Some(lengths) => {
+                    self.utxo_syncer
+                        .sync_after(from_block, to_block, lengths)
+                        .await?
+                }
+                None => self.utxo_syncer.sync(from_block, to_block).await?,
+            };
+            let operations = self.txid_syncer.sync(from_block, to_block).await?;
+
+            if let Some(lengths) = &mut utxo_tree_lengths {
+                extend_tree_lengths(lengths, &events);
+            }
+            for event in events {
+                if let SyncEvent::Shield(shield, block_number) = event {
+                    candidates.push(ShieldCandidate::new(&shield, block_number));
+                }
+            }
+            txid_leaves = operations
+                .iter()
+                .map(|op| {
+                    let included =
+                        UtxoTreeIndex::included(op.utxo_tree_out, op.utxo_out_start_index);
+                    TxidLeafHash::new(op.txid(), op.utxo_tree_in, included)
+                })
+                .collect();
+        }
+
+        let screenings = self.screen_shields(candidates).await;
+
+        let mut state = self.state.write().await;
+        info!("Inserting {} operations into TXID trees", txid_leaves.len());
+        for leaf in txid_leaves {
+            state.txids.push(leaf);
+        }
+        if to_block > synced_block {
+            state.synced_block = to_block;
+            state.utxo_tree_lengths = utxo_tree_lengths;
+        }
+
+        state.pending_shields.clear();
+        for (candidate, decisions) in screenings {
+            let mut decided = true;
+            for (key, decision) in decisions {
+                let Some(list) = state.lists.get_mut(&key) else {
+                    continue;
+                };
+                match decision {
+                    ShieldDecision::Allow => list.admit(candidate.blinded_commitment),
+                    ShieldDecision::Block => list.block(candidate.blinded_commitment),
+                    ShieldDecision::Pending => decided = false,
+                }
+            }
+
+            if !decided {
+                state.pending_shields.push(candidate);
+            }
+        }
+
+        self.save(&mut state).await
+    }
+
+    /// Runs each shield past the policy of every list that hasn't decided on it, returning the
+    /// decisions by list.
+    async fn screen_shields(
+        &self,
+        candidates: Vec<ShieldCandidate>,
+    ) -> Vec<(ShieldCandidate, Vec<(ListKey, ShieldDecision)>)> {
+        let unscreened: Vec<(ShieldCandidate, Vec<ListKey>)> = {
+            let state = self.state.read().await;
+            candidates
+                .into_iter()
+                .map(|candidate| {
+                    let keys = state
+                        .lists
+                        .iter()
+                        .filter(|(_, list)| !list.screened(&candidate.blinded_commitment))
+                        .map(|(key, _)| key.clone())
+                        .collect();
+                    (candidate, keys)
+                })
+                .collect()
+        };
+
+        let mut screenings = Vec::with_capacity(unscreened.len());
+        for (candidate, keys) in unscreened {
+            let mut decisions = Vec::with_capacity(keys.len());
+            for key in keys {
+                let decision = self.policy.screen_shield(&key, &candidate).await;
+                decisions.push((key, decision));
+            }
+            screenings.push((candidate, decisions));
+        }
+        screenings
+    }
+
+    /// Returns the status of each blinded commitment on each of `list_keys`.
+    async fn pois_per_list(
+        &self,
+        list_keys: &[ListKey],
+        blinded_commitments: &[BlindedCommitment],
+    ) -> Result<PoisPerListMap, PoiNodeError> {
+        let state = self.state.read().await;
+        let lists = list_keys
+            .iter()
+            .map(|key| Ok((key, state.list(key)?)))
+            .collect::<Result<Vec<_>, PoiNodeError>>()?;
+
+        Ok(blinded_commitments
+            .iter()
+            .map(|bc| {
+                let statuses = lists
+                    .iter()
+                    .map(|(key, list)| ((*key).clone(), list.status(bc)))
+                    .collect();
+                (*bc, statuses)
+            })
+            .collect())
+    }
+
+    /// Returns a POI Merkle proof for each blinded commitment, in order.
+    async fn merkle_proofs(
+        &self,
+        list_key: &ListKey,
+        blinded_commitments: &[BlindedCommitment],
+    ) -> Result<Vec<MerkleProof>, PoiNodeError> {
+        let state = self.state.read().await;
+        let list = state.list(list_key)?;
+        blinded_commitments
+            .iter()
+            .map(|bc| {
+                list.proof(bc)
+                    .ok_or_else(|| PoiNodeError::ProofNotFound(list_key.clone(), *bc))
+            })
+            .collect()
+    }
+
+    /// Verifies a transaction's POI proof and admits its outputs to `list_key`.
+    ///
+    /// The proof must be generated against a TXID root this node has had, and against POI roots
+    /// the list has had, so every input was already on the list.
+    async fn submit_proof(
+        &self,
+        list_key: &ListKey,
+        data: &TransactProofData,
+    ) -> Result<(), PoiNodeError> {
+        {
+            let state = self.state.read().await;
+            let list = state.list(list_key)?;
+
+            let index = data.txid_merkleroot_index;
+            let tree_number = (index / TOTAL_LEAVES as u64) as u32;
+            let leaf_index = (index % TOTAL_LEAVES as u64) as u32;
+            if state.txids.root_at(tree_number, leaf_index) != Some(data.txid_merkleroot) {
+                return Err(PoiNodeError::UnknownTxidRoot(data.txid_merkleroot, index));
+            }
+
+            if let Some(root) = data
+                .poi_merkleroots
+                .iter()
+                .find(|root| !list.is_known_root(root))
+            {
+                return Err(PoiNodeError::UnknownPoiRoot(*root, list_key.clone()));
+            }
+        }
+
+        //? Verifying may download a verifying key, so it runs without the state lock. Roots are
+        //? never forgotten, so the checks above still hold afterwards.
+        if !self.verifier.verify(data).await? {
+            return Err(PoiNodeError::InvalidProof);
+        }
+
+        let mut state = self.state.write().await;
+        let list = state
+            .lists
+            .get_mut(list_key)
+            .ok_or_else(|| PoiNodeError::UnknownList(list_key.clone()))?;
+        for bc in &data.blinded_commitments_out {
+            if U256::from(*bc) != U256::ZERO {
+                list.admit(*bc);
+            }
+        }
+
+        // Unshields are tracked by their TXID.
+        let unshield: U256 = data.railgun_txid_if_has_unshield.into();
+        if unshield != U256::ZERO {
+            list.admit(unshield.into());
+        }
+
+        self.save(&mut state).await
+    }
+
+    /// Returns the position and root of the latest TXID.
+    async fn validated_txid(&self) -> Result<ValidatedRailgunTxidStatus, PoiNodeError> {
+        let state = self.state.read().await;
+        state.txids.latest().ok_or(PoiNodeError::NoValidatedTxid)
+    }
+
+    /// Returns whether `merkleroot` was the root of TXID tree `tree` right after leaf `index`.
+    async fn validate_txid_merkleroot(
+        &self,
+        tree: u32,
+        index: u32,
+        merkleroot: MerkleRoot,
+    ) -> bool {
+        let state = self.state.read().await;
+        state.txids.root_at(tree, index) == Some(merkleroot)
+    }
+
+    /// Handles a single JSON-RPC request and returns its response.
+    pub async fn handle(&self, request: Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        match self.dispatch(method, params).await {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => {
+                warn!("POI node request {} failed: {}", method, e);
+                let code = match e {
+                    PoiNodeError::UnknownMethod(_) => -32601,
+                    PoiNodeError::InvalidParams(_) => -32602,
+                    _ => -32000,
+                };
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": code, "message": e.to_string() },
+                })
+            }
+        }
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> Result<Value, PoiNodeError> {
+        let result = match method {
+            "ppoi_pois_per_list" => {
+                let p: GetPoisPerListParams = self.params(params)?;
+                let bcs: Vec<_> = p
+                    .blinded_commitment_datas
+                    .iter()
+                    .map(|d| d.blinded_commitment)
+                    .collect();
+                serde_json::to_value(self.pois_per_list(&p.list_keys, &bcs).await?)?
+            }
+            "ppoi_merkle_proofs" => {
+                let p: GetMerkleProofsParams = self.params(params)?;
+                serde_json::to_value(
+                    self.merkle_proofs(&p.list_key, &p.blinded_commitments)
+                        .await?,
+                )?
+            }
+            "ppoi_submit_transact_proof" => {
+                let p: SubmitTransactProofParams = self.params(params)?;
+                self.submit_proof(&p.list_key, &p.transact_proof_data)
+                    .await?;
+                Value::Null
+            }
+            "ppoi_validated_txid" => {
+                let _: ChainParams = self.params(params)?;
+                serde_json::to_value(self.validated_txid().await?)?
+            }
+            "ppoi_validate_txid_merkleroot" => {
+                let p: ValidateTxidMerklerootParams = self.params(params)?;
+                Value::Bool(
+                    self.validate_txid_merkleroot(p.tree, p.index, p.merkleroot)
+                        .await,
+                )
+            }
+            _ => return Err(PoiNodeError::UnknownMethod(method.to_string())),
+        };
+        Ok(result)
+    }
+
+    /// Parses request params and checks they're for this node's chain.
+    fn params<P: DeserializeOwned>(&self, params: Value) -> Result<P, PoiNodeError> {
+        let chain: ChainParams = serde_json::from_value(params.clone())?;
+        if chain.chain_type != "0" || chain.chain_id != self.chain.id.to_string() {
+            return Err(PoiNodeError::WrongChain(format!(
+                "{}:{}",
+                chain.chain_type, chain.chain_id
+            )));
+        }
+        Ok(serde_json::from_value(params)?)
+    }
+
+    /// Saves the pages of leaves that grew since the last save, along with the sync position
+    /// and pending shields.
+    async fn save(&self, state: &mut NodeState) -> Result<(), PoiNodeError> {
+        let mut batch = WriteBatch::new();
+        batch.set_poi_node(&PoiNodeState {
+            synced_block: Some(state.synced_block),
+            utxo_tree_lengths: state.utxo_tree_lengths.clone(),
+            pending_shields: state.pending_shields.clone(),
+        })?;
+        for (page, leaves) in state.txids.unsaved_pages() {
+            batch.set_poi_node_txids(page, leaves)?;
+        }
+        for (key, list) in &state.lists {
+            for (page, leaves) in list.unsaved_pages() {
+                batch.set_poi_node_list(key, page, leaves)?;
+            }
+            if let Some(blocked) = list.unsaved_blocked() {
+                batch.set_poi_node_blocked(key, &blocked)?;
+            }
+        }
+        self.db.write_batch(batch).await?;
+
+        state.txids.mark_saved();
+        for list in state.lists.values_mut() {
+            list.mark_saved();
+        }
+        Ok(())
+    }
+}
+
+impl NodeState {
+    fn list(&self, list_key: &ListKey) -> Result<&PoiList, PoiNodeError> {
+        self.lists
+            .get(list_key)
+            .ok_or_else(|| PoiNodeError::UnknownList(list_key.clone()))
+    }
+}
+
+/// Advances `lengths` past the UTXO tree leaves in `events`.
+fn extend_tree_lengths(lengths: &mut BTreeMap<u32, u32>, events: &[SyncEvent]) {
+    for event in events {
+        let (tree_number, leaf_index) = match event {
+            SyncEvent::Shield(shield, _) => (shield.tree_number, shield.leaf_index),
+            SyncEvent::Transact(transact, _) => (transact.tree_number, transact.leaf_index),
+            SyncEvent::Legacy(legacy, _) => (legacy.tree_number, legacy.leaf_index),
+            SyncEvent::Nullified(..) => continue,
+        };
+        let length = lengths.entry(tree_number).or_default();
+        *length = (*length).max(leaf_index + 1);
+    }
+}
+
+/// Splits `leaves` into pages of [`PAGE_SIZE`], skipping the pages that were full when
+/// `saved` leaves had been persisted.
+fn unsaved_pages<T>(leaves: &[T], saved: usize) -> impl Iterator<Item = (u32, &[T])> {
+    leaves
+        .chunks(PAGE_SIZE)
+        .enumerate()
+        .skip(saved / PAGE_SIZE)
+        .map(|(page, leaves)| (page as u32, leaves))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use alloy::primitives::address;
+
+    use super::*;
+    use crate::{
+        account::signer::PrivateKeySigner,
+        caip::AssetId,
+        circuit::{
+            groth16_prover::Groth16Prover, inputs::poi_inputs::PoiCircuitInputs, prover::Prover,
+        },
+        crypto::{
+            aes::Ciphertext,
+            keys::{ByteKey, SpendingKey, ViewingKey},
+            railgun_txid::Txid,
+        },
+        database::memory::MemoryDatabase,
+        indexer::syncer::{Operation, Shield},
+        merkle_tree::TxidMerkleTree,
+        note::utxo::{self, UtxoNote},
+        poi::{
+            note::PoiNote,
+            types::{BlindedCommitmentType, PoiStatus},
+        },
+    };
+
+    struct MockSyncer {
+        shields: Vec<(Shield, u64)>,
+        operations: Vec<Operation>,
+    }
+
+    #[async_trait::async_trait]
+    impl UtxoSyncer for MockSyncer {
+        async fn latest_block(&self) -> Result<u64, SyncerError> {
+            Ok(100)
+        }
+
+        async fn sync(
+            &self,
+            from_block: u64,
+            to_block: u64,
+        ) -> Result<Vec<SyncEvent>, SyncerError> {
+            Ok(self
+                .shields
+                .iter()
+                .filter(|(_, block)| (from_block..=to_block).contains(block))
+                .map(|(shield, block)| SyncEvent::Shield(shield.clone(), *block))
+                .collect())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TxidSyncer for MockSyncer {
+        async fn latest_block(&self) -> Result<u64, SyncerError> {
+            Ok(100)
+        }
+
+        async fn sync(
+            &self,
+            from_block: u64,
+            to_block: u64,
+        ) -> Result<Vec<Operation>, SyncerError> {
+            Ok(self
+                .operations
+                .iter()
+                .filter(|op| (from_block..=to_block).contains(&op.block_number))
+                .cloned()
+                .collect())
+        }
+    }
+
+    /// Fails the first TXID sync, then returns no operations.
+    #[derive(Default)]
+    struct FlakyTxidSyncer {
+        failed: AtomicBool,
+    }
+
+    #[async_trait::async_trait]
+    impl TxidSyncer for FlakyTxidSyncer {
+        async fn latest_block(&self) -> Result<u64, SyncerError> {
+            Ok(100)
+        }
+
+        async fn sync(&self, _from: u64, _to: u64) -> Result<Vec<Operation>, SyncerError> {
+            if self.failed.swap(true, Ordering::SeqCst) {
+                Ok(vec![])
+            } else {
+                Err(SyncerError::new(std::io::Error::other("flaky")))
+            }
+        }
+    }
+
+    /// Blocks shields above a value and leaves shields from block 50 onwards pending.
+    struct TestPolicy;
+
+    #[async_trait::async_trait]
+    impl ListPolicy for TestPolicy {
+        async fn screen_shield(
+            &self,
+            _list_key: &ListKey,
+            shield: &ShieldCandidate,
+        ) -> ShieldDecision {
+            if shield.block_number >= 50 {
+                ShieldDecision::Pending
+            } else if shield.value > U256::from(1000) {
+                ShieldDecision::Block
+            } else {
+                ShieldDecision::Allow
+            }
+        }
+    }
+
+    fn shield(leaf_index: u32, value: u64) -> Shield {
+        Shield {
+            tree_number: 0,
+            leaf_index,
+            npk: U256::from(leaf_index + 1),
+            token: AssetId::erc20(address!("0xDEADDEADDEADDEADDEADDEADDEADDEADDEADDEAD")),
+            value: U256::from(value),
+            ciphertext: Ciphertext {
+                iv: [0; 16],
+                tag: [0; 16],
+                data: vec![],
+            },
+            shield_key: [0; 32],
+            hash: None,
+        }
+    }
+
+    fn operation(block_number: u64, seed: u64) -> Operation {
+        Operation {
+            block_number,
+            nullifiers: vec![U256::from(seed)],
+            commitment_hashes: vec![U256::from(seed + 1)],
+            bound_params_hash: U256::from(seed + 2),
+            utxo_tree_in: 0,
+            utxo_tree_out: 0,
+            utxo_out_start_index: seed as u32,
+        }
+    }
+
+    async fn test_node(syncer: MockSyncer) -> PoiNode {
+        let syncer = Arc::new(syncer);
+        let node = PoiNode::new(
+            ChainConfig::sepolia(),
+            Arc::new(MemoryDatabase::new()),
+            vec!["list".into()],
+            Arc::new(TestPolicy),
+        )
+        .await
+        .unwrap()
+        .with_utxo_syncer(syncer.clone())
+        .with_txid_syncer(syncer);
+        node.state.write().await.synced_block = 0;
+        node
+    }
+
+    fn request(node: &PoiNode, method: &str, params: Value) -> Value {
+        let mut params = params;
+        params["chainType"] = json!("0");
+        params["chainID"] = json!(node.chain.id.to_string());
+        params["txidVersion"] = json!("V2_PoseidonMerkle");
+        json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params })
+    }
+
+    #[tokio::test]
+    async fn test_shield_screening() {
+        let shields = vec![
+            (shield(0, 100), 10),
+            (shield(1, 5000), 20),
+            (shield(2, 100), 60),
+        ];
+        let bcs: Vec<BlindedCommitment> = shields
+            .iter()
+            .map(|(s, block)| ShieldCandidate::new(s, *block).blinded_commitment)
+            .collect();
+        let node = test_node(MockSyncer {
+            shields,
+            operations: vec![],
+        })
+        .await;
+
+        node.sync_to(100).await.unwrap();
+        assert_eq!(node.state.read().await.pending_shields.len(), 1);
+
+        let datas: Vec<_> = bcs
+            .iter()
+            .map(|bc| json!({ "type": "Shield", "blindedCommitment": bc }))
+            .collect();
+        let resp = node
+            .handle(request(
+                &node,
+                "ppoi_pois_per_list",
+                json!({ "listKeys": ["list"], "blindedCommitmentDatas": datas }),
+            ))
+            .await;
+        let statuses: PoisPerListMap = serde_json::from_value(resp["result"].clone()).unwrap();
+        let status = |bc: &BlindedCommitment| statuses[bc][&ListKey::from("list")];
+        assert_eq!(status(&bcs[0]), PoiStatus::Valid);
+        assert_eq!(status(&bcs[1]), PoiStatus::ShieldBlocked);
+        assert_eq!(status(&bcs[2]), PoiStatus::Missing);
+
+        let resp = node
+            .handle(request(
+                &node,
+                "ppoi_merkle_proofs",
+                json!({ "listKey": "list", "blindedCommitments": [bcs[0]] }),
+            ))
+            .await;
+        let proofs: Vec<MerkleProof> = serde_json::from_value(resp["result"].clone()).unwrap();
+        assert!(proofs[0].verify());
+
+        let resp = node
+            .handle(request(
+                &node,
+                "ppoi_merkle_proofs",
+                json!({ "listKey": "list", "blindedCommitments": [bcs[1]] }),
+            ))
+            .await;
+        assert_eq!(resp["error"]["code"], -32000);
+    }
+
+    #[tokio::test]
+    async fn test_txid_roots() {
+        let node = test_node(MockSyncer {
+            shields: vec![],
+            operations: vec![operation(10, 0), operation(20, 10), operation(30, 20)],
+        })
+        .await;
+
+        let resp = node
+            .handle(request(&node, "ppoi_validated_txid", json!({})))
+            .await;
+        assert_eq!(resp["error"]["code"], -32000);
+
+        node.sync_to(25).await.unwrap();
+        let first = node.validated_txid().await.unwrap();
+        assert_eq!((first.tree(), first.leaf_index()), (0, 1));
+
+        node.sync_to(100).await.unwrap();
+        let resp = node
+            .handle(request(&node, "ppoi_validated_txid", json!({})))
+            .await;
+        let latest: ValidatedRailgunTxidStatus =
+            serde_json::from_value(resp["result"].clone()).unwrap();
+        assert_eq!(latest.leaf_index(), 2);
+
+        // Historical roots stay valid.
+        assert!(node.validate_txid_merkleroot(0, 1, first.merkleroot).await);
+        assert!(!node.validate_txid_merkleroot(0, 2, first.merkleroot).await);
+
+        let resp = node
+            .handle(request(
+                &node,
+                "ppoi_validate_txid_merkleroot",
+                json!({ "tree": 0, "index": 2, "merkleroot": latest.merkleroot }),
+            ))
+            .await;
+        assert_eq!(resp["result"], json!(true));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_bad_requests() {
+        let node = test_node(MockSyncer {
+            shields: vec![],
+            operations: vec![operation(10, 0)],
+        })
+        .await;
+        node.sync_to(100).await.unwrap();
+
+        let resp = node.handle(request(&node, "ppoi_unknown", json!({}))).await;
+        assert_eq!(resp["error"]["code"], -32601);
+
+        let mut wrong_chain = request(&node, "ppoi_validated_txid", json!({}));
+        wrong_chain["params"]["chainID"] = json!("999999");
+        let resp = node.handle(wrong_chain).await;
+        assert_eq!(resp["error"]["code"], -32000);
+
+        let resp = node
+            .handle(request(
+                &node,
+                "ppoi_merkle_proofs",
+                json!({ "listKey": "list" }),
+            ))
+            .await;
+        assert_eq!(resp["error"]["code"], -32602);
+
+        // Proofs against a TXID root the node never had are rejected before verification.
+        let data = TransactProofData {
+            proof: serde_json::from_value(json!({
+                "pi_a": ["0", "0"],
+                "pi_b": [["0", "0"], ["0", "0"]],
+                "pi_c": ["0", "0"],
+            }))
+            .unwrap(),
+            poi_merkleroots: vec![],
+            txid_merkleroot: MerkleRoot::new(U256::from(1)),
+            txid_merkleroot_index: 0,
+            blinded_commitments_out: vec![],
+            railgun_txid_if_has_unshield: U256::ZERO.into(),
+        };
+        let err = node.submit_proof(&"list".into(), &data).await.unwrap_err();
+        assert!(matches!(err, PoiNodeError::UnknownTxidRoot(..)));
+    }
+
+    #[tokio::test]
+    async fn test_failed_sync_applies_nothing() {
+        let shields = vec![(shield(0, 100), 10), (shield(1, 100), 60)];
+        let node = PoiNode::new(
+            ChainConfig::sepolia(),
+            Arc::new(MemoryDatabase::new()),
+            vec!["list".into()],
+            Arc::new(TestPolicy),
+        )
+        .await
+        .unwrap()
+        .with_utxo_syncer(Arc::new(MockSyncer {
+            shields,
+            operations: vec![],
+        }))
+        .with_txid_syncer(Arc::new(FlakyTxidSyncer::default()));
+        node.state.write().await.synced_block = 0;
+
+        node.sync_to(100).await.unwrap_err();
+        assert_eq!(node.synced_block().await, 0);
+        assert!(node.state.read().await.pending_shields.is_empty());
+
+        node.sync_to(100).await.unwrap();
+        assert_eq!(node.synced_block().await, 100);
+        assert_eq!(node.state.read().await.pending_shields.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_state_reloads() {
+        let db = Arc::new(MemoryDatabase::new());
+        let syncer = Arc::new(MockSyncer {
+            shields: vec![
+                (shield(0, 100), 10),
+                (shield(1, 5000), 20),
+                (shield(2, 100), 60),
+            ],
+            operations: (0..PAGE_SIZE as u64 + 1)
+                .map(|i| operation(10, i * 10))
+                .collect(),
+        });
+        let node = PoiNode::new(
+            ChainConfig::sepolia(),
+            db.clone(),
+            vec!["list".into()],
+            Arc::new(TestPolicy),
+        )
+        .await
+        .unwrap()
+        .with_utxo_syncer(syncer.clone())
+        .with_txid_syncer(syncer.clone());
+        node.state.write().await.synced_block = 0;
+        node.sync_to(100).await.unwrap();
+
+        let reloaded = PoiNode::new(
+            ChainConfig::sepolia(),
+            db,
+            vec!["list".into()],
+            Arc::new(TestPolicy),
+        )
+        .await
+        .unwrap();
+        assert_eq!(reloaded.synced_block().await, 100);
+        let latest = node.validated_txid().await.unwrap();
+        let reloaded_latest = reloaded.validated_txid().await.unwrap();
+        assert_eq!(latest.tree(), 0);
+        assert_eq!(latest.leaf_index(), PAGE_SIZE as u32);
+        assert_eq!(reloaded_latest.leaf_index(), latest.leaf_index());
+        assert_eq!(reloaded_latest.merkleroot, latest.merkleroot);
+
+        let bcs: Vec<BlindedCommitment> = syncer
+            .shields
+            .iter()
+            .map(|(s, block)| ShieldCandidate::new(s, *block).blinded_commitment)
+            .collect();
+        let list_keys = vec![ListKey::from("list")];
+        assert_eq!(
+            reloaded.pois_per_list(&list_keys, &bcs).await.unwrap(),
+            node.pois_per_list(&list_keys, &bcs).await.unwrap()
+        );
+        assert_eq!(reloaded.state.read().await.pending_shields.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_continues_from_stored_leaves() {
+        use crate::indexer::syncer::{ChainedSyncer, RetryPolicy};
+
+        //? Leaf 1 is missing, which only shows once the next range is checked against the
+        //? leaves synced before it.
+        let syncer = MockSyncer {
+            shields: vec![(shield(0, 100), 10), (shield(2, 100), 20)],
+            operations: vec![],
+        };
+        let node = test_node(MockSyncer {
+            shields: vec![],
+            operations: vec![],
+        })
+        .await
+        .with_utxo_syncer(Arc::new(
+            ChainedSyncer::new().then_with_retry(syncer, RetryPolicy::none()),
+        ));
+
+        node.sync_to(15).await.unwrap();
+        assert_eq!(
+            node.state.read().await.utxo_tree_lengths,
+            Some(BTreeMap::from([(0, 1)]))
+        );
+
+        node.sync_to(100).await.unwrap_err();
+        assert_eq!(node.synced_block().await, 15);
+    }
+
+    /// Proves a one-input, one-output transaction with the real POI circuit and submits it.
+    ///
+    /// Downloads the POI circuit artifacts.
+    #[tokio::test]
+    #[ignore]
+    async fn test_accepts_genuine_proof() {
+        let node = test_node(MockSyncer {
+            shields: vec![],
+            operations: vec![],
+        })
+        .await;
+        let list_key = ListKey::from("list");
+
+        let signer = PrivateKeySigner::new_evm(
+            SpendingKey::from_bytes([1u8; 32]),
+            ViewingKey::from_bytes([2u8; 32]),
+            1,
+        );
+        let asset = AssetId::erc20(address!("0xDEADDEADDEADDEADDEADDEADDEADDEADDEADDEAD"));
+        let note_in = UtxoNote::new(
+            0,
+            0,
+            signer.clone(),
+            asset,
+            100,
+            [3u8; 16],
+            "",
+            BlindedCommitmentType::Shield,
+        );
+        let note_out = UtxoNote::new(
+            0,
+            1,
+            signer,
+            asset,
+            100,
+            [4u8; 16],
+            "",
+            BlindedCommitmentType::Transact,
+        );
+        let commitment_out: U256 = note_out.hash.into();
+        let bound_params_hash = U256::from(1);
+
+        let utxo_tree_out = UtxoTreeIndex::included(note_in.tree_number, 1);
+        let txid = Txid::new(&[note_in.nullifier], &[commitment_out], bound_params_hash);
+        let txid_leaf = TxidLeafHash::new(txid, note_in.tree_number, utxo_tree_out);
+        let mut txid_tree = TxidMerkleTree::new(0);
+        txid_tree.insert_leaves(&[txid_leaf], 0);
+
+        let poi_proof = {
+            let mut state = node.state.write().await;
+            state.txids.push(txid_leaf);
+            let list = state.lists.get_mut(&list_key).unwrap();
+            list.admit(note_in.blinded_commitment.into());
+            list.proof(&note_in.blinded_commitment.into()).unwrap()
+        };
+
+        let inputs = PoiCircuitInputs::from_inputs(
+            note_in.spending_pubkey,
+            note_in.nullifying_key,
+            note_in.tree_number,
+            bound_params_hash,
+            &[PoiNote::new(
+                note_in.clone(),
+                HashMap::from([(list_key.clone(), poi_proof)]),
+            )],
+            &[commitment_out],
+            &[note_out.note_public_key],
+            &[U256::from(note_out.value)],
+            note_in.asset.hash(),
+            false,
+            list_key.clone(),
+            utxo_tree_out,
+            &txid_tree,
+        )
+        .unwrap();
+        let proof = Groth16Prover::new().prove_poi(&inputs).await.unwrap();
+
+        let blinded_commitment_out: BlindedCommitment = utxo::blinded_commitment(
+            commitment_out,
+            note_out.note_public_key,
+            note_out.tree_number,
+            note_out.leaf_index,
+        )
+        .into();
+        let data = TransactProofData {
+            proof,
+            poi_merkleroots: inputs.poi_merkleroots,
+            txid_merkleroot: inputs.railgun_txid_merkleroot_after_transaction,
+            txid_merkleroot_index: 0,
+            blinded_commitments_out: vec![blinded_commitment_out],
+            railgun_txid_if_has_unshield: inputs.railgun_txid_if_has_unshield,
+        };
+        node.submit_proof(&list_key, &data).await.unwrap();
+
+        let statuses = node
+            .pois_per_list(&[list_key.clone()], &[blinded_commitment_out])
+            .await
+            .unwrap();
+        assert_eq!(
+            statuses[&blinded_commitment_out][&list_key],
+            PoiStatus::Valid
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+
+use ruint::aliases::U256;
+
+use crate::{
+    merkle_tree::{MerkleRoot, TOTAL_LEAVES, TxidLeafHash, TxidMerkleTree},
+    poi::{node::unsaved_pages, types::ValidatedRailgunTxidStatus},
+};
+
+/// The node's TXID trees, along with the root each tree had after every leaf so historical
+/// roots can be validated.
+#[derive(Default)]
+pub(super) struct TxidTrees {
+    trees: BTreeMap<u32, TxidMerkleTree>,
+    roots: BTreeMap<u32, Vec<MerkleRoot>>,
+    /// Every leaf in global order.
+    leaves: Vec<U256>,
+    /// Number of leaves already persisted.
+    saved_leaves: usize,
+}
+
+impl TxidTrees {
+    pub fn from_leaves(leaves: Vec<U256>) -> Self {
+        let mut trees = Self::default();
+        for leaf in leaves {
+            trees.push(leaf.into());
+        }
+        trees.mark_saved();
+        trees
+    }
+
+    /// Returns the pages of leaves pushed since the last [`mark_saved`](Self::mark_saved).
+    pub fn unsaved_pages(&self) -> impl Iterator<Item = (u32, &[U256])> {
+        unsaved_pages(&self.leaves, self.saved_leaves)
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_leaves = self.leaves.len();
+    }
+
+    pub fn push(&mut self, leaf: TxidLeafHash) {
+        let len = self.leaves.len() as u64;
+        let tree_number = (len / TOTAL_LEAVES as u64) as u32;
+        let leaf_index = (len % TOTAL_LEAVES as u64) as usize;
+
+        let tree = self
+            .trees
+            .entry(tree_number)
+            .or_insert_with(|| TxidMerkleTree::new(tree_number));
+        tree.insert_leaves(&[leaf], leaf_index);
+        self.roots.entry(tree_number).or_default().push(tree.root());
+        self.leaves.push(leaf.into());
+    }
+
+    /// Returns the root of `tree_number` right after leaf `index` was inserted.
+    pub fn root_at(&self, tree_number: u32, index: u32) -> Option<MerkleRoot> {
+        self.roots.get(&tree_number)?.get(index as usize).copied()
+    }
+
+    /// Returns the position and root of the latest leaf. None if the trees are empty.
+    pub fn latest(&self) -> Option<ValidatedRailgunTxidStatus> {
+        let (tree_number, roots) = self.roots.last_key_value()?;
+        let leaf_index = roots.len() as u32 - 1;
+
+        Some(ValidatedRailgunTxidStatus {
+            index: (tree_number << 16) | leaf_index,
+            merkleroot: *roots.last()?,
+        })
+    }
+}
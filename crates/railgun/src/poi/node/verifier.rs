@@ -0,0 +1,74 @@
+use ruint::aliases::U256;
+
+use crate::{
+    circuit::{
+        artifact_loader::{ArtifactLoader, ArtifactLoaderError},
+        verifying_keys::VerifyingKeys,
+    },
+    merkle_tree::MerkleTree,
+    poi::types::TransactProofData,
+};
+
+/// POI circuit sizes, smallest first. Each supports up to `size` inputs and outputs.
+const CIRCUIT_SIZES: [usize; 2] = [3, 13];
+
+/// Verifies submitted POI proofs against the POI circuits' verifying keys.
+///
+/// Each key is loaded once, by whichever verification needs it first.
+pub(super) struct PoiProofVerifier {
+    verifying_keys: VerifyingKeys,
+}
+
+impl PoiProofVerifier {
+    pub fn new(artifact_loader: ArtifactLoader) -> Self {
+        Self {
+            verifying_keys: VerifyingKeys::new(artifact_loader),
+        }
+    }
+
+    /// Returns whether `data.proof` is a valid POI proof for the data's public inputs.
+    ///
+    /// The submission doesn't say which circuit produced the proof, so every circuit large
+    /// enough for the data is tried.
+    pub async fn verify(&self, data: &TransactProofData) -> Result<bool, ArtifactLoaderError> {
+        for size in CIRCUIT_SIZES {
+            if data.blinded_commitments_out.len() > size || data.poi_merkleroots.len() > size {
+                continue;
+            }
+
+            let circuit_name = format!("railgun/poi/{:02}x{:02}", size, size);
+            let inputs = public_inputs(data, size);
+            if self
+                .verifying_keys
+                .verify(&circuit_name, &data.proof, &inputs)
+                .await?
+            {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+/// Assembles the POI circuit's public signals in circom order: the `blindedCommitmentsOut`
+/// outputs, then `anyRailgunTxidMerklerootAfterTransaction`, `railgunTxidIfHasUnshield` and
+/// `poiMerkleroots`.
+fn public_inputs(data: &TransactProofData, size: usize) -> Vec<U256> {
+    let mut signals: Vec<U256> = Vec::with_capacity(2 * size + 2);
+
+    signals.extend(
+        data.blinded_commitments_out
+            .iter()
+            .map(|&bc| U256::from(bc)),
+    );
+    signals.resize(size, U256::ZERO);
+
+    signals.push(data.txid_merkleroot.into());
+    signals.push(data.railgun_txid_if_has_unshield.into());
+
+    signals.extend(data.poi_merkleroots.iter().map(|&root| U256::from(root)));
+    signals.resize(2 * size + 2, MerkleTree::zero());
+
+    signals
+}
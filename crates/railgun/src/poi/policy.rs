@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+use crate::poi::types::{ListKey, PoiStatus};
+
+/// How the per-list POI statuses of a note combine into the status that decides whether it
+/// can be spent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(js, derive(tsify::Tsify))]
+#[cfg_attr(js, tsify(into_wasm_abi, from_wasm_abi))]
+#[serde(tag = "type", content = "listKeys")]
+pub enum PoiPolicy {
+    /// Every configured list must consider the note valid.
+    #[default]
+    All,
+    /// At least one configured list must consider the note valid.
+    Any,
+    /// Every list in the subset must consider the note valid. Other lists are still reported.
+    Required(Vec<ListKey>),
+}
+
+/// The POI status of a note according to a single list.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(js, derive(tsify::Tsify))]
+pub struct ListPoiStatus {
+    #[serde(rename = "listKey")]
+    pub list_key: ListKey,
+    pub status: PoiStatus,
+}
+
+impl PoiPolicy {
+    /// Combines per-list statuses into a single status. Lists without a status count as
+    /// `Missing`.
+    pub fn evaluate(&self, lists: &[ListPoiStatus]) -> PoiStatus {
+        let combined = match self {
+            PoiPolicy::All => lists.iter().map(|l| l.status).max(),
+            PoiPolicy::Any => lists.iter().map(|l| l.status).min(),
+            PoiPolicy::Required(required) => required.iter().map(|key| status_of(lists, key)).max(),
+        };
+        combined.unwrap_or(PoiStatus::Valid)
+    }
+
+    /// Returns the lists among `list_keys` the policy considers.
+    pub fn lists(&self, list_keys: Vec<ListKey>) -> Vec<ListKey> {
+        match self {
+            PoiPolicy::All | PoiPolicy::Any => list_keys,
+            PoiPolicy::Required(required) => list_keys
+                .into_iter()
+                .filter(|key| required.contains(key))
+                .collect(),
+        }
+    }
+
+    /// Returns the lists that prevent a note with these statuses from being spent.
+    pub fn blocking(&self, lists: &[ListPoiStatus]) -> Vec<ListPoiStatus> {
+        if self.evaluate(lists) == PoiStatus::Valid {
+            return Vec::new();
+        }
+
+        match self {
+            PoiPolicy::All | PoiPolicy::Any => lists
+                .iter()
+                .filter(|l| l.status != PoiStatus::Valid)
+                .cloned()
+                .collect(),
+            PoiPolicy::Required(required) => required
+                .iter()
+                .map(|key| ListPoiStatus {
+                    list_key: key.clone(),
+                    status: status_of(lists, key),
+                })
+                .filter(|l| l.status != PoiStatus::Valid)
+                .collect(),
+        }
+    }
+}
+
+fn status_of(lists: &[ListPoiStatus], key: &ListKey) -> PoiStatus {
+    lists
+        .iter()
+        .find(|l| &l.list_key == key)
+        .map_or(PoiStatus::Missing, |l| l.status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lists() -> Vec<ListPoiStatus> {
+        vec![
+            ListPoiStatus {
+                list_key: "a".into(),
+                status: PoiStatus::Valid,
+            },
+            ListPoiStatus {
+                list_key: "b".into(),
+                status: PoiStatus::ShieldBlocked,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(PoiPolicy::All.evaluate(&lists()), PoiStatus::ShieldBlocked);
+        assert_eq!(PoiPolicy::Any.evaluate(&lists()), PoiStatus::Valid);
+        assert_eq!(
+            PoiPolicy::Required(vec!["a".into()]).evaluate(&lists()),
+            PoiStatus::Valid
+        );
+        assert_eq!(
+            PoiPolicy::Required(vec!["c".into()]).evaluate(&lists()),
+            PoiStatus::Missing
+        );
+        assert_eq!(PoiPolicy::All.evaluate(&[]), PoiStatus::Valid);
+    }
+
+    #[test]
+    fn test_blocking() {
+        let blocking = PoiPolicy::All.blocking(&lists());
+        assert_eq!(blocking, vec![lists()[1].clone()]);
+
+        assert!(PoiPolicy::Any.blocking(&lists()).is_empty());
+
+        let blocking = PoiPolicy::Required(vec!["a".into(), "c".into()]).blocking(&lists());
+        assert_eq!(
+            blocking,
+            vec![ListPoiStatus {
+                list_key: "c".into(),
+                status: PoiStatus::Missing,
+            }]
+        );
+    }
+}
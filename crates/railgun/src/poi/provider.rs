@@ -1,46 +1,112 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
 
-use alloy::primitives::ChainId;
 use ruint::aliases::U256;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{debug, info, warn};
+use web_time::Duration;
 
 use crate::{
     circuit::{
-        groth16_prover::Groth16Prover,
         inputs::poi_inputs::{PoiCircuitInputs, PoiCircuitInputsError},
+        prover::Prover,
     },
     crypto::{
         keys::{NullifyingKey, SpendingPublicKey},
         railgun_txid::Txid,
     },
-    database::{Database, DatabaseError, RailgunDB},
+    database::{Database, DatabaseError, RailgunDB, WriteBatch},
     indexer::{
         syncer::TxidSyncer,
-        txid_indexer::{TxidIndexer, TxidIndexerError},
+        txid_indexer::{TxidIndexer, TxidIndexerError, TxidSnapshot, VerifiedTxidSnapshot},
     },
     merkle_tree::{MerkleProof, TOTAL_LEAVES, TxidMerkleTree, UtxoTreeIndex},
     note::utxo::{self, UtxoNote},
     poi::{
         client::{PoiClient, PoiClientError, PoiNodeClient},
         note::PoiNote,
-        types::{BlindedCommitment, BlindedCommitmentType, ListKey, PoiStatus, TransactProofData},
+        policy::{ListPoiStatus, PoiPolicy},
+        types::{
+            BlindedCommitment, BlindedCommitmentData, BlindedCommitmentType, ListKey, PoiStatus,
+            TransactProofData,
+        },
     },
     transact::proved_transaction::ProvedOperation,
 };
 
+/// How long a non-terminal POI status is cached before it is fetched again.
+const DEFAULT_STATUS_TTL: Duration = Duration::from_secs(5 * 60);
+/// Delay before retrying a failed submission. Doubles after each failed attempt.
+const RETRY_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60 * 60);
+/// How long a submitted entry is listed before it is pruned, unless all of its outputs are
+/// `Valid` sooner.
+const SUBMITTED_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+/// Delay before re-checking a pending note's status. Doubles after each check.
+const RECHECK_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_RECHECK_BACKOFF: Duration = Duration::from_secs(30 * 60);
+/// Rough time list providers take to process a shield, and any other commitment.
+const SHIELD_PROCESSING_TIME: Duration = Duration::from_secs(60 * 60);
+const PROCESSING_TIME: Duration = Duration::from_secs(10 * 60);
+/// Events beyond this many are dropped, oldest first, if they aren't taken.
+const MAX_EVENTS: usize = 1000;
+
 pub struct PoiProvider {
     inner: PoiProviderState,
     db: Arc<dyn Database>,
     poi_client: PoiClient,
     txid_indexer: TxidIndexer,
+    status_ttl: Duration,
+    policy: PoiPolicy,
 }
 
 #[derive(Serialize, Deserialize, Default)]
 pub(crate) struct PoiProviderState {
     pub pending: Vec<PendingPoiEntry>,
+    /// Cached POI statuses.
     pub pois: HashMap<BlindedCommitment, HashMap<ListKey, PoiInfo>>,
+    /// Notes whose status is re-checked until they are `Valid` or blocked.
+    #[serde(default)]
+    pub tracked: HashMap<BlindedCommitment, TrackedNote>,
+    /// Status changes of tracked notes that haven't been taken yet.
+    #[serde(default)]
+    pub events: VecDeque<PoiEvent>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct TrackedNote {
+    commitment_type: BlindedCommitmentType,
+    /// Unix time in seconds at which tracking started.
+    first_seen: u64,
+    /// Unix time in seconds at which the note was created, if known.
+    #[serde(default)]
+    created_at: Option<u64>,
+    checks: u32,
+    /// Unix time in seconds of the next scheduled check.
+    next_check_at: u64,
+}
+
+/// A change in a tracked note's POI status under the configured policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(js, derive(tsify::Tsify))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PoiEvent {
+    /// The note became spendable.
+    #[serde(rename_all = "camelCase")]
+    Valid {
+        #[cfg_attr(js, tsify(type = "`0x${string}`"))]
+        blinded_commitment: BlindedCommitment,
+    },
+    /// The note was rejected by the lists in `blocked_by` and will not become spendable.
+    #[serde(rename_all = "camelCase")]
+    ShieldBlocked {
+        #[cfg_attr(js, tsify(type = "`0x${string}`"))]
+        blinded_commitment: BlindedCommitment,
+        blocked_by: Vec<ListPoiStatus>,
+    },
 }
 
 #[derive(Debug, Error)]
@@ -53,12 +119,17 @@ pub enum PoiProviderError {
     ProofNotFound(BlindedCommitment, ListKey),
     #[error("Database error: {0}")]
     Database(#[from] DatabaseError),
+    #[error("No pending POI submission for txid {0:?}")]
+    SubmissionNotFound(Txid),
 }
 
 #[derive(Clone, Serialize, Deserialize, Default)]
 pub struct PoiInfo {
     status: Option<PoiStatus>,
     proof: Option<MerkleProof>,
+    /// Unix time in seconds at which `status` was fetched.
+    #[serde(default)]
+    checked_at: Option<u64>,
 }
 
 /// Serializable snapshot needed to re-prove and submit a post-transaction POI
@@ -80,6 +151,50 @@ pub struct PendingPoiEntry {
     pub token_hash: U256,
     pub has_unshield: bool,
     pub list_keys: Vec<ListKey>,
+    /// Entries persisted before progress was tracked start out waiting for their TXID.
+    #[serde(default)]
+    pub progress: SubmissionProgress,
+}
+
+/// Where a pending POI submission is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(js, derive(tsify::Tsify))]
+#[serde(rename_all = "camelCase")]
+pub enum SubmissionState {
+    /// The transaction hasn't been included in the TXID tree yet.
+    #[default]
+    WaitingForTxid,
+    /// A proof is being generated. Only observed if the process stopped mid-attempt, in which
+    /// case the attempt is repeated on the next sync.
+    Proving,
+    /// Proofs were accepted by the POI node.
+    Submitted,
+    /// The last attempt failed. It is retried once `next_attempt_at` has passed.
+    Failed,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(js, derive(tsify::Tsify))]
+#[serde(rename_all = "camelCase")]
+pub struct SubmissionProgress {
+    pub state: SubmissionState,
+    /// Number of failed attempts since the entry was registered or last retried manually.
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// Unix time in seconds before which the entry is not retried.
+    pub next_attempt_at: Option<u64>,
+    /// Unix time in seconds of the last state change.
+    pub updated_at: u64,
+}
+
+/// A pending POI submission, as reported by [`PoiProvider::submissions`].
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(js, derive(tsify::Tsify))]
+pub struct PoiSubmission {
+    #[cfg_attr(js, tsify(type = "string"))]
+    pub txid: Txid,
+    #[serde(flatten)]
+    pub progress: SubmissionProgress,
 }
 
 #[derive(Debug, Error)]
@@ -100,71 +215,430 @@ enum PendingPoiError {
 
 impl PoiProvider {
     pub async fn new(
-        chain_id: ChainId,
         db: Arc<dyn Database>,
         txid_syncer: Arc<dyn TxidSyncer>,
-        poi_endpoint: impl Into<String>,
-        list_keys: Vec<ListKey>,
+        poi_client: PoiClient,
     ) -> Result<Self, PoiProviderError> {
         let inner = db.get_poi_provider().await?;
         let txid_indexer = TxidIndexer::new(db.clone(), txid_syncer).await?;
-        let poi_client = PoiClient::new(chain_id, poi_endpoint, list_keys);
 
         Ok(Self {
             inner,
             db,
             poi_client,
             txid_indexer,
+            status_ttl: DEFAULT_STATUS_TTL,
+            policy: PoiPolicy::default(),
         })
     }
 
+    /// Sets how long statuses other than `Valid` are cached. `Valid` is terminal and is cached
+    /// indefinitely.
+    pub fn with_status_ttl(mut self, ttl: Duration) -> Self {
+        self.status_ttl = ttl;
+        self
+    }
+
+    /// Sets how per-list statuses combine into the status that decides whether a note can be
+    /// spent.
+    pub fn with_policy(mut self, policy: PoiPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> &PoiPolicy {
+        &self.policy
+    }
+
+    /// Cross-checks synced operations against `verifier` and the POI node. See
+    /// [`TxidIndexer::with_verifier`].
+    pub(crate) fn with_txid_verifier(mut self, verifier: Arc<dyn TxidSyncer>) -> Self {
+        self.txid_indexer = self.txid_indexer.with_verifier(verifier);
+        self
+    }
+
     pub async fn sync_to(
         &mut self,
-        prover: &Groth16Prover,
+        prover: &dyn Prover,
         to_block: u64,
     ) -> Result<(), PoiProviderError> {
         let poi_client = self.poi_client.clone();
         self.txid_indexer.sync_to(to_block, &poi_client).await?;
-        self.submit_pending(prover).await;
+        if let Err(e) = self.recheck_tracked().await {
+            warn!("Failed to re-check pending POI statuses: {}", e);
+        }
+        self.submit_pending(prover).await?;
         self.save().await?;
         Ok(())
     }
 
+    /// Starts tracking the given notes, re-checking their status on every sync with backoff
+    /// until they are `Valid` or blocked. Each note comes with the unix time in seconds at which
+    /// it was created, if known. Notes already tracked or known to be settled are skipped.
+    pub fn track(
+        &mut self,
+        commitments: &[(BlindedCommitment, BlindedCommitmentType, Option<u64>)],
+    ) {
+        let now = common::unix_time();
+        for (blinded_commitment, commitment_type, created_at) in commitments {
+            if !self.needs_tracking(blinded_commitment) {
+                continue;
+            }
+
+            self.inner.tracked.insert(
+                *blinded_commitment,
+                TrackedNote {
+                    commitment_type: *commitment_type,
+                    first_seen: now,
+                    created_at: *created_at,
+                    checks: 0,
+                    next_check_at: now,
+                },
+            );
+        }
+    }
+
+    /// Returns whether [`PoiProvider::track`] would start tracking the note, i.e. it is neither
+    /// tracked nor known to be settled.
+    pub fn needs_tracking(&self, blinded_commitment: &BlindedCommitment) -> bool {
+        if self.inner.tracked.contains_key(blinded_commitment) {
+            return false;
+        }
+        let status = self
+            .policy
+            .evaluate(&self.known_statuses(blinded_commitment));
+        !matches!(status, PoiStatus::Valid | PoiStatus::ShieldBlocked)
+    }
+
+    /// Stops tracking the notes that are no longer in `unspent` and drops their cached
+    /// statuses. Spent notes are settled for good, so the cache only holds the wallet's current
+    /// notes and the outputs of submitted entries, which decide when those are pruned.
+    pub fn retain_unspent(&mut self, unspent: &HashSet<BlindedCommitment>) {
+        let submitted: HashSet<BlindedCommitment> =
+            self.submitted_commitments().into_iter().collect();
+        self.inner
+            .tracked
+            .retain(|blinded_commitment, _| unspent.contains(blinded_commitment));
+        self.inner.pois.retain(|blinded_commitment, _| {
+            unspent.contains(blinded_commitment) || submitted.contains(blinded_commitment)
+        });
+    }
+
+    /// Estimated unix time in seconds at which a tracked note becomes spendable, based on how
+    /// long list providers usually take since the note was created. None if the note isn't
+    /// tracked.
+    pub fn spendable_at(&self, blinded_commitment: &BlindedCommitment) -> Option<u64> {
+        let tracked = self.inner.tracked.get(blinded_commitment)?;
+        let processing_time = match tracked.commitment_type {
+            BlindedCommitmentType::Shield => SHIELD_PROCESSING_TIME,
+            _ => PROCESSING_TIME,
+        };
+        let created_at = tracked.created_at.unwrap_or(tracked.first_seen);
+        Some((created_at + processing_time.as_secs()).max(tracked.next_check_at))
+    }
+
+    /// Takes the status changes observed since the last call. Events are persisted until taken.
+    pub async fn take_events(&mut self) -> Result<Vec<PoiEvent>, PoiProviderError> {
+        if self.inner.events.is_empty() {
+            return Ok(Vec::new());
+        }
+        let events = self.inner.events.drain(..).collect();
+        self.save().await?;
+        Ok(events)
+    }
+
+    /// Returns the pending POI submissions and their progress, including recently submitted ones.
+    pub fn submissions(&self) -> Vec<PoiSubmission> {
+        self.inner
+            .pending
+            .iter()
+            .map(|entry| PoiSubmission {
+                txid: entry.txid,
+                progress: entry.progress.clone(),
+            })
+            .collect()
+    }
+
+    /// Clears the backoff of a submission so it is attempted again on the next sync.
+    pub async fn retry_submission(&mut self, txid: Txid) -> Result<(), PoiProviderError> {
+        let entry = self
+            .inner
+            .pending
+            .iter_mut()
+            .find(|entry| entry.txid == txid)
+            .ok_or(PoiProviderError::SubmissionNotFound(txid))?;
+        entry.progress.attempts = 0;
+        entry.progress.next_attempt_at = None;
+        self.save().await
+    }
+
+    /// Stops tracking a submission. Its outputs stay `Missing` unless a proof is submitted
+    /// elsewhere.
+    pub async fn drop_submission(&mut self, txid: Txid) -> Result<(), PoiProviderError> {
+        let len = self.inner.pending.len();
+        self.inner.pending.retain(|entry| entry.txid != txid);
+        if self.inner.pending.len() == len {
+            return Err(PoiProviderError::SubmissionNotFound(txid));
+        }
+        self.save().await
+    }
+
     pub async fn register_ops(
         &mut self,
         operations: &[ProvedOperation],
     ) -> Result<(), PoiProviderError> {
-        let list_keys = self.poi_client.list_keys();
         for op in operations {
-            self.register(op, list_keys.clone());
+            let in_commitments: Vec<BlindedCommitment> = op
+                .inner
+                .in_notes()
+                .iter()
+                .map(|note| note.blinded_commitment.into())
+                .collect();
+            let list_keys = self.proof_lists(&in_commitments);
+            self.register(op, list_keys);
         }
         self.save().await?;
         Ok(())
     }
 
+    pub(crate) fn export_txid(&self) -> TxidSnapshot {
+        self.txid_indexer.export()
+    }
+
+    pub(crate) async fn verify_txid_snapshot(
+        &self,
+        snapshot: TxidSnapshot,
+    ) -> Result<VerifiedTxidSnapshot, PoiProviderError> {
+        Ok(self
+            .txid_indexer
+            .verify_snapshot(snapshot, &self.poi_client)
+            .await?)
+    }
+
+    pub(crate) fn import_txid(
+        &mut self,
+        snapshot: VerifiedTxidSnapshot,
+        batch: &mut WriteBatch,
+    ) -> Result<(), PoiProviderError> {
+        self.txid_indexer.import(snapshot, batch)?;
+        Ok(())
+    }
+
     pub fn list_keys(&self) -> Vec<ListKey> {
         self.poi_client.list_keys()
     }
 
-    /// Returns the worst-case POI status across all configured list keys.
+    /// Returns the POI status of a commitment under the configured policy.
     pub async fn status(
         &mut self,
         blinded_commitment: BlindedCommitment,
         commitment_type: BlindedCommitmentType,
     ) -> Result<PoiStatus, PoiProviderError> {
-        let mut worst = PoiStatus::Valid;
-        for list_key in self.list_keys() {
-            let status = self
-                .poi_client
-                .poi_status(&list_key, blinded_commitment, commitment_type)
-                .await?;
+        let statuses = self
+            .statuses(&[(blinded_commitment, commitment_type)])
+            .await?;
+        Ok(statuses[&blinded_commitment])
+    }
+
+    /// Returns the POI status of each commitment under the configured policy.
+    pub async fn statuses(
+        &mut self,
+        commitments: &[(BlindedCommitment, BlindedCommitmentType)],
+    ) -> Result<HashMap<BlindedCommitment, PoiStatus>, PoiProviderError> {
+        Ok(self
+            .list_statuses(commitments)
+            .await?
+            .into_iter()
+            .map(|(blinded_commitment, lists)| (blinded_commitment, self.policy.evaluate(&lists)))
+            .collect())
+    }
+
+    /// Returns the status of each commitment on every configured list, in list key order.
+    ///
+    /// Cached statuses are reused while fresh and the rest are fetched in batches, so a wallet
+    /// whose notes are all `Valid` makes no requests at all. Statuses the POI node doesn't report
+    /// are treated as `Missing`.
+    pub async fn list_statuses(
+        &mut self,
+        commitments: &[(BlindedCommitment, BlindedCommitmentType)],
+    ) -> Result<HashMap<BlindedCommitment, Vec<ListPoiStatus>>, PoiProviderError> {
+        let list_keys = self.list_keys();
+        let now = common::unix_time();
+
+        let stale: Vec<BlindedCommitmentData> = commitments
+            .iter()
+            .filter(|(blinded_commitment, _)| {
+                list_keys.iter().any(|list_key| {
+                    self.cached_status(blinded_commitment, list_key, now)
+                        .is_none()
+                })
+            })
+            .map(
+                |(blinded_commitment, commitment_type)| BlindedCommitmentData {
+                    blinded_commitment: *blinded_commitment,
+                    commitment_type: *commitment_type,
+                },
+            )
+            .collect();
+
+        if !stale.is_empty() {
             debug!(
-                "POI status for {} ({list_key}): {status:?}",
-                blinded_commitment
+                "Fetching POI statuses for {} of {} commitments",
+                stale.len(),
+                commitments.len()
             );
-            worst = worst.max(status);
+            if self.fetch_statuses(&list_keys, &stale, now).await? {
+                self.save().await?;
+            }
+        }
+
+        Ok(commitments
+            .iter()
+            .map(|(blinded_commitment, _)| {
+                let lists = list_keys
+                    .iter()
+                    .map(|list_key| ListPoiStatus {
+                        list_key: list_key.clone(),
+                        status: self
+                            .cached_status(blinded_commitment, list_key, now)
+                            .unwrap_or(PoiStatus::Missing),
+                    })
+                    .collect();
+                (*blinded_commitment, lists)
+            })
+            .collect())
+    }
+
+    /// Fetches statuses from the POI node into the cache, regardless of freshness. Returns
+    /// whether any status changed.
+    //? Only status changes are worth persisting. A restart with older `checked_at` times costs
+    //? at most one extra fetch per note.
+    async fn fetch_statuses(
+        &mut self,
+        list_keys: &[ListKey],
+        commitments: &[BlindedCommitmentData],
+        now: u64,
+    ) -> Result<bool, PoiProviderError> {
+        let fetched = self.poi_client.poi_statuses(list_keys, commitments).await?;
+        let mut changed = false;
+        for (blinded_commitment, statuses) in fetched {
+            let cached = self.inner.pois.entry(blinded_commitment).or_default();
+            for (list_key, status) in statuses {
+                let info = cached.entry(list_key).or_default();
+                changed |= info.status != Some(status);
+                info.status = Some(status);
+                info.checked_at = Some(now);
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Re-checks the tracked notes that are due, emitting an event for each one that settles
+    /// and scheduling the rest with backoff.
+    async fn recheck_tracked(&mut self) -> Result<(), PoiProviderError> {
+        let now = common::unix_time();
+        let mut due: Vec<BlindedCommitmentData> = self
+            .inner
+            .tracked
+            .iter()
+            .filter(|(_, tracked)| tracked.next_check_at <= now)
+            .map(|(blinded_commitment, tracked)| BlindedCommitmentData {
+                blinded_commitment: *blinded_commitment,
+                commitment_type: tracked.commitment_type,
+            })
+            .collect();
+        if due.is_empty() {
+            return Ok(());
+        }
+        // Sorted so requests are deterministic and replayable from fixtures.
+        due.sort_by_key(|data| data.blinded_commitment);
+
+        debug!("Re-checking POI status of {} pending notes", due.len());
+        let list_keys = self.list_keys();
+        self.fetch_statuses(&list_keys, &due, now).await?;
+
+        for data in due {
+            let blinded_commitment = data.blinded_commitment;
+            let lists = self.known_statuses(&blinded_commitment);
+            let event = match self.policy.evaluate(&lists) {
+                PoiStatus::Valid => PoiEvent::Valid { blinded_commitment },
+                PoiStatus::ShieldBlocked => PoiEvent::ShieldBlocked {
+                    blinded_commitment,
+                    blocked_by: self.policy.blocking(&lists),
+                },
+                _ => {
+                    if let Some(tracked) = self.inner.tracked.get_mut(&blinded_commitment) {
+                        tracked.checks += 1;
+                        tracked.next_check_at = now
+                            + backoff(RECHECK_BACKOFF, MAX_RECHECK_BACKOFF, tracked.checks - 1)
+                                .as_secs();
+                    }
+                    continue;
+                }
+            };
+
+            info!("POI status settled: {:?}", event);
+            self.inner.tracked.remove(&blinded_commitment);
+            if self.inner.events.len() >= MAX_EVENTS {
+                self.inner.events.pop_front();
+            }
+            self.inner.events.push_back(event);
+        }
+        Ok(())
+    }
+
+    /// Returns the lists to prove a transaction spending `in_commitments` on: those the policy
+    /// selects on which every input is `Valid`. Other lists have no POI Merkle proof for some
+    /// input, so proving on them would fail.
+    fn proof_lists(&self, in_commitments: &[BlindedCommitment]) -> Vec<ListKey> {
+        self.policy
+            .lists(self.list_keys())
+            .into_iter()
+            .filter(|list_key| {
+                in_commitments.iter().all(|blinded_commitment| {
+                    self.inner
+                        .pois
+                        .get(blinded_commitment)
+                        .and_then(|lists| lists.get(list_key))
+                        .and_then(|info| info.status)
+                        == Some(PoiStatus::Valid)
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the last known status on every list, however old. Missing if never fetched.
+    fn known_statuses(&self, blinded_commitment: &BlindedCommitment) -> Vec<ListPoiStatus> {
+        let cached = self.inner.pois.get(blinded_commitment);
+        self.list_keys()
+            .into_iter()
+            .map(|list_key| {
+                let status = cached
+                    .and_then(|lists| lists.get(&list_key))
+                    .and_then(|info| info.status)
+                    .unwrap_or(PoiStatus::Missing);
+                ListPoiStatus { list_key, status }
+            })
+            .collect()
+    }
+
+    /// Returns the cached status if it's still fresh at `now`.
+    fn cached_status(
+        &self,
+        blinded_commitment: &BlindedCommitment,
+        list_key: &ListKey,
+        now: u64,
+    ) -> Option<PoiStatus> {
+        let info = self.inner.pois.get(blinded_commitment)?.get(list_key)?;
+        match (info.status?, info.checked_at) {
+            (PoiStatus::Valid, _) => Some(PoiStatus::Valid),
+            (status, Some(checked_at))
+                if now.saturating_sub(checked_at) < self.status_ttl.as_secs() =>
+            {
+                Some(status)
+            }
+            _ => None,
         }
-        Ok(worst)
     }
 
     fn register(&mut self, op: &ProvedOperation, list_keys: Vec<ListKey>) {
@@ -197,30 +671,123 @@ impl PoiProvider {
             token_hash: op.inner.asset.hash(),
             has_unshield: op.inner.unshield_note().is_some(),
             list_keys,
+            progress: SubmissionProgress {
+                updated_at: common::unix_time(),
+                ..Default::default()
+            },
         });
     }
 
-    async fn submit_pending(&mut self, prover: &Groth16Prover) {
-        for i in (0..self.inner.pending.len()).rev() {
+    /// Attempts every due submission, recording the outcome on each entry. Only database
+    /// errors are returned.
+    async fn submit_pending(&mut self, prover: &dyn Prover) -> Result<(), PoiProviderError> {
+        let now = common::unix_time();
+        if let Err(e) = self.check_submitted().await {
+            warn!("Failed to check submitted POI outputs: {}", e);
+        }
+        self.prune_submitted(now);
+
+        for i in 0..self.inner.pending.len() {
+            let progress = &self.inner.pending[i].progress;
+            if progress.state == SubmissionState::Submitted
+                || progress.next_attempt_at.is_some_and(|at| at > now)
+            {
+                continue;
+            }
+
+            self.inner.pending[i]
+                .progress
+                .set_state(SubmissionState::Proving, now);
+            self.save().await?;
+
             let entry = self.inner.pending[i].clone();
-            match self.submit_poi(prover, &entry).await {
-                Ok(_) => {
+            let result = self.submit_poi(prover, &entry).await;
+            let progress = &mut self.inner.pending[i].progress;
+            match result {
+                Ok(()) => {
                     info!("Submitted POI for {:?}", entry.txid);
-                    self.inner.pending.remove(i);
+                    progress.set_state(SubmissionState::Submitted, common::unix_time());
+                    progress.last_error = None;
                 }
                 Err(PendingPoiError::MissingTxid(_)) => {
                     info!("Waiting for txid to be indexed: {:?}", entry.txid);
+                    progress.set_state(SubmissionState::WaitingForTxid, common::unix_time());
                 }
                 Err(e) => {
-                    warn!("Failed to submit POI for pending entry: {:?}", e);
+                    warn!("Failed to submit POI for {:?}: {}", entry.txid, e);
+                    progress.fail(e.to_string(), common::unix_time());
                 }
             }
         }
+        Ok(())
+    }
+
+    /// Fetches the statuses of submitted entries' outputs. Outputs sent to other wallets aren't
+    /// among the wallet's notes, so their statuses are only fetched here.
+    async fn check_submitted(&mut self) -> Result<(), PoiProviderError> {
+        let commitments: Vec<_> = self
+            .submitted_commitments()
+            .into_iter()
+            .map(|blinded_commitment| (blinded_commitment, BlindedCommitmentType::Transact))
+            .collect();
+        if !commitments.is_empty() {
+            self.list_statuses(&commitments).await?;
+        }
+        Ok(())
+    }
+
+    /// Blinded commitments of the outputs of submitted entries whose TXID is indexed, sorted so
+    /// requests are deterministic.
+    fn submitted_commitments(&self) -> Vec<BlindedCommitment> {
+        let mut commitments: Vec<BlindedCommitment> = self
+            .inner
+            .pending
+            .iter()
+            .filter(|entry| entry.progress.state == SubmissionState::Submitted)
+            .filter_map(|entry| {
+                let (tree_number, leaf_index) = self.txid_indexer.utxo_position(&entry.txid)?;
+                Some(blinded_commitments(entry, tree_number, leaf_index))
+            })
+            .flatten()
+            .collect();
+        commitments.sort();
+        commitments.dedup();
+        commitments
+    }
+
+    /// Removes submitted entries whose outputs are all `Valid` on every list, or that were
+    /// submitted longer than [`SUBMITTED_RETENTION`] ago.
+    fn prune_submitted(&mut self, now: u64) {
+        let pois = &self.inner.pois;
+        let txid_indexer = &self.txid_indexer;
+        self.inner.pending.retain(|entry| {
+            if entry.progress.state != SubmissionState::Submitted {
+                return true;
+            }
+            if entry.progress.updated_at + SUBMITTED_RETENTION.as_secs() <= now {
+                return false;
+            }
+
+            let Some((tree_number, leaf_index)) = txid_indexer.utxo_position(&entry.txid) else {
+                return true;
+            };
+            let all_valid = blinded_commitments(entry, tree_number, leaf_index)
+                .iter()
+                .all(|blinded_commitment| {
+                    entry.list_keys.iter().all(|list_key| {
+                        pois.get(blinded_commitment)
+                            .and_then(|lists| lists.get(list_key))
+                            .and_then(|info| info.status)
+                            == Some(PoiStatus::Valid)
+                    })
+                });
+            !all_valid
+        });
     }
 
     async fn submit_poi(
         &self,
-        prover: &Groth16Prover,
+        prover: &dyn Prover,
         entry: &PendingPoiEntry,
     ) -> Result<(), PendingPoiError> {
         let txid_tree_number = match self.txid_indexer.txid_position(&entry.txid) {
@@ -259,7 +826,7 @@ impl PoiProvider {
 
     async fn create_proof(
         &self,
-        prover: &Groth16Prover,
+        prover: &dyn Prover,
         entry: &PendingPoiEntry,
         txid_tree_number: u32,
         utxo_tree_number: u32,
@@ -269,7 +836,9 @@ impl PoiProvider {
     ) -> Result<HashMap<ListKey, TransactProofData>, PendingPoiError> {
         let mut proof_data = HashMap::new();
 
-        for list_key in &entry.list_keys {
+        //? The policy may have changed since the entry was registered.
+        let selected = self.policy.lists(entry.list_keys.clone());
+        for list_key in &selected {
             let mut in_notes = Vec::new();
             for note in entry.in_notes.clone() {
                 let proof = self
@@ -301,7 +870,7 @@ impl PoiProvider {
             let proof = prover
                 .prove_poi(&inputs)
                 .await
-                .map_err(|e| PendingPoiError::Prover(Box::new(e)))?;
+                .map_err(PendingPoiError::Prover)?;
             let blinded_commitments_out =
                 blinded_commitments(entry, utxo_tree_number, utxo_leaf_index);
 
@@ -324,11 +893,34 @@ impl PoiProvider {
     }
 
     async fn save(&self) -> Result<(), PoiProviderError> {
-        self.db.set_poi_provider(&self.inner).await?;
+        let mut batch = WriteBatch::new();
+        batch.set_poi_provider(&self.inner)?;
+        self.db.write_batch(batch).await?;
         Ok(())
     }
 }
 
+impl SubmissionProgress {
+    fn set_state(&mut self, state: SubmissionState, now: u64) {
+        self.state = state;
+        self.updated_at = now;
+    }
+
+    /// Records a failed attempt and schedules the next one with exponential backoff.
+    fn fail(&mut self, error: String, now: u64) {
+        let delay = backoff(RETRY_BACKOFF, MAX_RETRY_BACKOFF, self.attempts);
+        self.set_state(SubmissionState::Failed, now);
+        self.attempts += 1;
+        self.last_error = Some(error);
+        self.next_attempt_at = Some(now + delay.as_secs());
+    }
+}
+
+/// Returns `base` doubled `attempt` times, capped at `max`.
+fn backoff(base: Duration, max: Duration, attempt: u32) -> Duration {
+    base.saturating_mul(2u32.saturating_pow(attempt)).min(max)
+}
+
 fn blinded_commitments(
     entry: &PendingPoiEntry,
     utxo_tree_number: u32,
@@ -352,3 +944,427 @@ fn blinded_commitments(
     }
     blinded_commitments_out
 }
+
+#[cfg(test)]
+mod tests {
+    use eip_1193_provider::fixture::{
+        Fixture, FixtureEntry, FixtureMode, FixtureResponse, Replayer,
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        crypto::keys::{ByteKey, SpendingKey, ViewingKey},
+        database::memory::MemoryDatabase,
+        indexer::{syncer::SubsquidSyncer, txid_indexer::TxidIndexerState},
+        poi::types::{ChainParams, GetPoisPerListParams, PoisPerListMap, TxidVersion},
+    };
+
+    fn data(blinded_commitment: BlindedCommitment) -> BlindedCommitmentData {
+        BlindedCommitmentData {
+            blinded_commitment,
+            commitment_type: BlindedCommitmentType::Transact,
+        }
+    }
+
+    fn result(
+        list_key: &ListKey,
+        statuses: &[(BlindedCommitment, PoiStatus)],
+    ) -> serde_json::Value {
+        let map: PoisPerListMap = statuses
+            .iter()
+            .map(|(c, s)| (*c, HashMap::from([(list_key.clone(), *s)])))
+            .collect();
+        serde_json::to_value(map).unwrap()
+    }
+
+    /// A recorded `ppoi_pois_per_list` call for `commitments` that returns `result`.
+    fn entry(
+        list_key: &ListKey,
+        commitments: &[BlindedCommitment],
+        result: serde_json::Value,
+    ) -> FixtureEntry {
+        lists_entry(std::slice::from_ref(list_key), commitments, result)
+    }
+
+    fn lists_entry(
+        list_keys: &[ListKey],
+        commitments: &[BlindedCommitment],
+        result: serde_json::Value,
+    ) -> FixtureEntry {
+        let params = GetPoisPerListParams {
+            chain: ChainParams {
+                chain_type: "0".into(),
+                chain_id: "1".into(),
+                txid_version: TxidVersion::V2PoseidonMerkle,
+            },
+            list_keys: list_keys.to_vec(),
+            blinded_commitment_datas: commitments.iter().map(|c| data(*c)).collect(),
+        };
+        FixtureEntry {
+            method: "ppoi_pois_per_list".into(),
+            params: serde_json::to_value(params).unwrap(),
+            response: FixtureResponse::Ok(json!({ "jsonrpc": "2.0", "id": 1, "result": result })),
+        }
+    }
+
+    #[test]
+    fn test_submission_backoff() {
+        let mut progress = SubmissionProgress::default();
+        progress.fail("unreachable".into(), 1000);
+        assert_eq!(progress.state, SubmissionState::Failed);
+        assert_eq!(progress.next_attempt_at, Some(1030));
+
+        progress.fail("unreachable".into(), 2000);
+        progress.fail("unreachable".into(), 3000);
+        assert_eq!(progress.attempts, 3);
+        assert_eq!(progress.next_attempt_at, Some(3120));
+        assert_eq!(progress.last_error.as_deref(), Some("unreachable"));
+
+        for _ in 0..20 {
+            progress.fail("unreachable".into(), 4000);
+        }
+        assert_eq!(
+            progress.next_attempt_at,
+            Some(4000 + MAX_RETRY_BACKOFF.as_secs())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tracked_notes_settle() {
+        let list_key = ListKey::from("list");
+        let a = BlindedCommitment::from(U256::from(1));
+        let b = BlindedCommitment::from(U256::from(2));
+
+        let fixture = Fixture {
+            entries: vec![
+                entry(
+                    &list_key,
+                    &[a, b],
+                    result(
+                        &list_key,
+                        &[(a, PoiStatus::Missing), (b, PoiStatus::ShieldBlocked)],
+                    ),
+                ),
+                entry(&list_key, &[a], result(&list_key, &[(a, PoiStatus::Valid)])),
+            ],
+        };
+        let mode = FixtureMode::Replay(Arc::new(Replayer::new(fixture)));
+
+        let client =
+            PoiClient::new(1, "http://poi.invalid", vec![list_key]).with_fixture(mode.clone());
+        let syncer = Arc::new(SubsquidSyncer::new("http://subsquid.invalid").with_fixture(mode));
+        let mut provider = PoiProvider::new(Arc::new(MemoryDatabase::new()), syncer, client)
+            .await
+            .unwrap();
+
+        provider.track(&[
+            (a, BlindedCommitmentType::Shield, None),
+            (b, BlindedCommitmentType::Shield, None),
+        ]);
+        provider.recheck_tracked().await.unwrap();
+        let events = provider.take_events().await.unwrap();
+        assert!(matches!(
+            events.as_slice(),
+            [PoiEvent::ShieldBlocked { blinded_commitment, .. }] if *blinded_commitment == b
+        ));
+        assert!(provider.spendable_at(&b).is_none());
+        let first_seen = provider.inner.tracked[&a].first_seen;
+        assert!(
+            provider.spendable_at(&a).unwrap() >= first_seen + SHIELD_PROCESSING_TIME.as_secs()
+        );
+
+        // `a` isn't due again yet, so nothing is fetched.
+        provider.recheck_tracked().await.unwrap();
+        assert!(provider.take_events().await.unwrap().is_empty());
+
+        provider.inner.tracked.get_mut(&a).unwrap().next_check_at = 0;
+        provider.recheck_tracked().await.unwrap();
+        assert!(matches!(
+            provider.take_events().await.unwrap().as_slice(),
+            [PoiEvent::Valid { blinded_commitment }] if *blinded_commitment == a
+        ));
+        assert!(provider.inner.tracked.is_empty());
+
+        // Settled notes aren't tracked again.
+        provider.track(&[
+            (a, BlindedCommitmentType::Shield, None),
+            (b, BlindedCommitmentType::Shield, None),
+        ]);
+        assert!(provider.inner.tracked.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tracked_notes_persist() {
+        let list_key = ListKey::from("list");
+        let a = BlindedCommitment::from(U256::from(1));
+        let b = BlindedCommitment::from(U256::from(2));
+
+        let fixture = Fixture {
+            entries: vec![entry(
+                &list_key,
+                &[a, b],
+                result(&list_key, &[(a, PoiStatus::Valid), (b, PoiStatus::Missing)]),
+            )],
+        };
+        let mode = FixtureMode::Replay(Arc::new(Replayer::new(fixture)));
+        let db: Arc<dyn Database> = Arc::new(MemoryDatabase::new());
+        let open = || {
+            let client = PoiClient::new(1, "http://poi.invalid", vec![list_key.clone()])
+                .with_fixture(mode.clone());
+            let syncer =
+                Arc::new(SubsquidSyncer::new("http://subsquid.invalid").with_fixture(mode.clone()));
+            PoiProvider::new(db.clone(), syncer, client)
+        };
+
+        // Estimates start at the shield, not when the wallet first saw the note.
+        let shielded_at = common::unix_time() - 30 * 60;
+        let mut provider = open().await.unwrap();
+        provider.track(&[
+            (a, BlindedCommitmentType::Shield, Some(shielded_at)),
+            (b, BlindedCommitmentType::Shield, None),
+        ]);
+        assert_eq!(
+            provider.spendable_at(&a),
+            Some(shielded_at + SHIELD_PROCESSING_TIME.as_secs())
+        );
+
+        provider.recheck_tracked().await.unwrap();
+        provider.save().await.unwrap();
+
+        // Events settled before a restart are still delivered after it.
+        let mut provider = open().await.unwrap();
+        assert!(matches!(
+            provider.take_events().await.unwrap().as_slice(),
+            [PoiEvent::Valid { blinded_commitment }] if *blinded_commitment == a
+        ));
+        let mut reopened = open().await.unwrap();
+        assert!(reopened.take_events().await.unwrap().is_empty());
+
+        // `b` was spent before it settled.
+        assert!(provider.inner.tracked.contains_key(&b));
+        provider.retain_unspent(&HashSet::new());
+        assert!(provider.inner.tracked.is_empty());
+        assert!(provider.spendable_at(&b).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_statuses_cached() {
+        let list_key = ListKey::from("list");
+        let a = BlindedCommitment::from(U256::from(1));
+        let b = BlindedCommitment::from(U256::from(2));
+
+        // Only `a` is re-fetched, since `b` is `Valid`. Any other request fails the replay.
+        let fixture = Fixture {
+            entries: vec![
+                entry(
+                    &list_key,
+                    &[a, b],
+                    result(
+                        &list_key,
+                        &[(a, PoiStatus::ProofSubmitted), (b, PoiStatus::Valid)],
+                    ),
+                ),
+                entry(&list_key, &[a], result(&list_key, &[(a, PoiStatus::Valid)])),
+            ],
+        };
+        let mode = FixtureMode::Replay(Arc::new(Replayer::new(fixture)));
+
+        let client =
+            PoiClient::new(1, "http://poi.invalid", vec![list_key]).with_fixture(mode.clone());
+        let syncer = Arc::new(SubsquidSyncer::new("http://subsquid.invalid").with_fixture(mode));
+        let mut provider = PoiProvider::new(Arc::new(MemoryDatabase::new()), syncer, client)
+            .await
+            .unwrap()
+            .with_status_ttl(Duration::ZERO);
+
+        let commitments = [
+            (a, BlindedCommitmentType::Transact),
+            (b, BlindedCommitmentType::Transact),
+        ];
+        let statuses = provider.statuses(&commitments).await.unwrap();
+        assert_eq!(statuses[&a], PoiStatus::ProofSubmitted);
+        assert_eq!(statuses[&b], PoiStatus::Valid);
+
+        let statuses = provider.statuses(&commitments).await.unwrap();
+        assert_eq!(statuses[&a], PoiStatus::Valid);
+
+        // Everything is `Valid` now, so nothing is fetched.
+        provider.statuses(&commitments).await.unwrap();
+        assert_eq!(
+            provider
+                .status(a, BlindedCommitmentType::Transact)
+                .await
+                .unwrap(),
+            PoiStatus::Valid
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_cache_saved_on_change_and_evicted() {
+        let list_key = ListKey::from("list");
+        let a = BlindedCommitment::from(U256::from(1));
+        let b = BlindedCommitment::from(U256::from(2));
+
+        let missing = result(
+            &list_key,
+            &[(a, PoiStatus::Missing), (b, PoiStatus::Missing)],
+        );
+        let fixture = Fixture {
+            entries: vec![
+                entry(&list_key, &[a, b], missing.clone()),
+                entry(&list_key, &[a, b], missing),
+                entry(
+                    &list_key,
+                    &[a, b],
+                    result(&list_key, &[(a, PoiStatus::Valid), (b, PoiStatus::Missing)]),
+                ),
+            ],
+        };
+        let mode = FixtureMode::Replay(Arc::new(Replayer::new(fixture)));
+
+        let db = Arc::new(MemoryDatabase::new());
+        let client = PoiClient::new(1, "http://poi.invalid", vec![list_key.clone()])
+            .with_fixture(mode.clone());
+        let syncer = Arc::new(SubsquidSyncer::new("http://subsquid.invalid").with_fixture(mode));
+        let mut provider = PoiProvider::new(db.clone(), syncer, client)
+            .await
+            .unwrap()
+            .with_status_ttl(Duration::ZERO);
+
+        let commitments = [
+            (a, BlindedCommitmentType::Transact),
+            (b, BlindedCommitmentType::Transact),
+        ];
+        provider.statuses(&commitments).await.unwrap();
+        assert_eq!(db.get_poi_provider().await.unwrap().pois.len(), 2);
+
+        // Unchanged statuses aren't saved.
+        let mut batch = WriteBatch::new();
+        batch
+            .set_poi_provider(&PoiProviderState::default())
+            .unwrap();
+        db.write_batch(batch).await.unwrap();
+        provider.statuses(&commitments).await.unwrap();
+        assert!(db.get_poi_provider().await.unwrap().pois.is_empty());
+
+        provider.statuses(&commitments).await.unwrap();
+        let saved = db.get_poi_provider().await.unwrap();
+        assert_eq!(saved.pois[&a][&list_key].status, Some(PoiStatus::Valid));
+
+        // `b` was spent.
+        provider.retain_unspent(&HashSet::from([a]));
+        assert!(provider.inner.pois.contains_key(&a));
+        assert!(!provider.inner.pois.contains_key(&b));
+    }
+
+    #[tokio::test]
+    async fn test_submitted_entries_pruned_once_outputs_valid() {
+        let list_key = ListKey::from("list");
+        let txid = Txid::from(U256::from(9));
+        let pending = PendingPoiEntry {
+            txid,
+            spending_pubkey: SpendingKey::from_bytes([1; 32]).public_key(),
+            nullifying_key: ViewingKey::from_bytes([2; 32]).nullifying_key(),
+            utxo_tree_in: 0,
+            bound_params_hash: U256::from(3),
+            in_notes: vec![],
+            out_commitments: vec![U256::from(4)],
+            out_npks: vec![U256::from(5)],
+            out_values: vec![U256::from(6)],
+            token_hash: U256::from(7),
+            has_unshield: false,
+            list_keys: vec![list_key.clone()],
+            progress: SubmissionProgress {
+                state: SubmissionState::Submitted,
+                updated_at: common::unix_time(),
+                ..Default::default()
+            },
+        };
+        let output = blinded_commitments(&pending, 0, 10)[0];
+
+        let db: Arc<dyn Database> = Arc::new(MemoryDatabase::new());
+        let mut batch = WriteBatch::new();
+        batch
+            .set_txid_indexer(&TxidIndexerState {
+                synced_block: 0,
+                trees: vec![],
+                pending: vec![],
+                txid_to_utxo_position: HashMap::from([(txid, (0, 10))]),
+                txid_to_txid_position: HashMap::new(),
+            })
+            .unwrap();
+        db.write_batch(batch).await.unwrap();
+
+        let fixture = Fixture {
+            entries: vec![entry(
+                &list_key,
+                &[output],
+                result(&list_key, &[(output, PoiStatus::Valid)]),
+            )],
+        };
+        let mode = FixtureMode::Replay(Arc::new(Replayer::new(fixture)));
+        let client =
+            PoiClient::new(1, "http://poi.invalid", vec![list_key]).with_fixture(mode.clone());
+        let syncer = Arc::new(SubsquidSyncer::new("http://subsquid.invalid").with_fixture(mode));
+        let mut provider = PoiProvider::new(db, syncer, client).await.unwrap();
+        provider.inner.pending.push(pending);
+
+        // The output was sent to another wallet, so it's never among the unspent notes.
+        provider.check_submitted().await.unwrap();
+        provider.retain_unspent(&HashSet::new());
+        assert!(provider.inner.pois.contains_key(&output));
+
+        provider.prune_submitted(common::unix_time());
+        assert!(provider.inner.pending.is_empty());
+        provider.retain_unspent(&HashSet::new());
+        assert!(provider.inner.pois.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_proves_on_policy_lists() {
+        let valid = ListKey::from("valid");
+        let blocked = ListKey::from("blocked");
+        let list_keys = vec![blocked.clone(), valid.clone()];
+        let note = BlindedCommitment::from(U256::from(1));
+
+        let statuses: PoisPerListMap = [(
+            note,
+            HashMap::from([
+                (valid.clone(), PoiStatus::Valid),
+                (blocked.clone(), PoiStatus::ShieldBlocked),
+            ]),
+        )]
+        .into_iter()
+        .collect();
+        let fixture = Fixture {
+            entries: vec![lists_entry(
+                &list_keys,
+                &[note],
+                serde_json::to_value(statuses).unwrap(),
+            )],
+        };
+        let mode = FixtureMode::Replay(Arc::new(Replayer::new(fixture)));
+
+        let client =
+            PoiClient::new(1, "http://poi.invalid", list_keys.clone()).with_fixture(mode.clone());
+        let syncer = Arc::new(SubsquidSyncer::new("http://subsquid.invalid").with_fixture(mode));
+        let mut provider = PoiProvider::new(Arc::new(MemoryDatabase::new()), syncer, client)
+            .await
+            .unwrap()
+            .with_policy(PoiPolicy::Any);
+
+        // Spendable under `Any`, but only the list that accepts the note can prove its spend.
+        let status = provider
+            .status(note, BlindedCommitmentType::Transact)
+            .await
+            .unwrap();
+        assert_eq!(status, PoiStatus::Valid);
+        assert_eq!(provider.proof_lists(&[note]), vec![valid.clone()]);
+
+        let provider = provider.with_policy(PoiPolicy::Required(vec![valid.clone()]));
+        assert_eq!(provider.proof_lists(&[note]), vec![valid]);
+        let provider = provider.with_policy(PoiPolicy::Required(vec![blocked]));
+        assert!(provider.proof_lists(&[note]).is_empty());
+    }
+}
@@ -14,7 +14,7 @@ use crate::{circuit::proof::Proof, crypto::railgun_txid::Txid, merkle_tree::Merk
 #[cfg_attr(js, tsify(into_wasm_abi, from_wasm_abi, type = "string"))]
 pub struct ListKey(String);
 
-#[derive(Debug, Copy, Clone, PartialEq, Deserialize, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize, Eq, Hash, PartialOrd, Ord)]
 pub struct BlindedCommitment(U256);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -79,7 +79,7 @@ pub struct GetMerkleProofsParams {
     pub blinded_commitments: Vec<BlindedCommitment>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidatedRailgunTxidStatus {
     #[serde(rename = "validatedTxidIndex")]
     pub index: u32,
@@ -1,10 +1,13 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use alloy::{
-    primitives::{Address, B256, Bytes, U256},
+    primitives::{Address, B256, Bytes, U256, address},
     sol_types::SolCall,
 };
-use eip_1193_provider::provider::{Eip1193Error, Eip1193Provider};
+use eip_1193_provider::provider::{Eip1193Blocks, Eip1193Error, Eip1193Gas, Eip1193Provider};
 use rand::Rng;
 use serde::Serialize;
 use thiserror::Error;
@@ -19,15 +22,25 @@ use userop_kit::{
 use crate::{
     account::{address::RailgunAddress, chain::ChainId, signer::RailgunSigner},
     adapter_data::{encode_paymaster_data, encode_railgun_adapter_data, paymaster_railgun_address},
+    broadcaster::{Broadcast, BroadcastRequest, Broadcaster, BroadcasterError, FeeConvergence},
     caip::AssetId,
     chain_config::ChainConfig,
-    circuit::groth16_prover::Groth16Prover,
+    circuit::{
+        inputs::{poi_inputs::PoiCircuitInputs, transact_inputs::TransactCircuitInputs},
+        proof::{G1Affine, G2Affine, Proof},
+        prover::{Prover, ProverError},
+    },
+    crypto::railgun_txid::Txid,
+    database::WriteBatch,
     indexer::utxo_indexer::{UtxoIndexer, UtxoIndexerError},
+    merkle_tree::{StorageProofUtxoVerifier, TrustedCheckpoint},
     note::{Note, utxo::UtxoNote},
     poi::{
-        provider::{PoiProvider, PoiProviderError},
-        types::{BlindedCommitmentType, PoiStatus},
+        policy::ListPoiStatus,
+        provider::{PoiEvent, PoiProvider, PoiProviderError, PoiSubmission},
+        types::{BlindedCommitment, BlindedCommitmentType, PoiStatus},
     },
+    snapshot::{Snapshot, SnapshotContent, SnapshotError},
     transact::{
         ShieldBuilder, TransactionBuilder, TransactionBuilderError,
         proved_transaction::{ProvedOperation, ProvedTx},
@@ -42,6 +55,9 @@ pub struct BalanceEntry {
     /// Otherwise None.
     #[serde(rename = "poiStatus")]
     pub poi_status: Option<PoiStatus>,
+    /// If POI is enabled, the status of the notes on each list. Otherwise None.
+    #[serde(rename = "poiLists")]
+    pub poi_lists: Option<Vec<ListPoiStatus>>,
     pub amount: u128,
 }
 
@@ -53,6 +69,13 @@ pub struct NoteEntry {
     /// Otherwise None.
     #[serde(rename = "poiStatus")]
     pub poi_status: Option<PoiStatus>,
+    /// If POI is enabled, the status of the note on each list. Otherwise None.
+    #[serde(rename = "poiLists")]
+    pub poi_lists: Option<Vec<ListPoiStatus>>,
+    /// If the note is waiting on the POI lists, the estimated unix time in seconds at which it
+    /// becomes spendable. Otherwise None.
+    #[serde(rename = "spendableAt")]
+    pub spendable_at: Option<u64>,
     pub amount: u128,
     #[serde(rename = "treeNumber")]
     pub tree_number: u32,
@@ -66,11 +89,35 @@ pub struct NoteEntry {
     pub memo: String,
 }
 
+/// An unspent note that can't be spent under the POI policy.
+#[derive(Debug, Serialize)]
+#[cfg_attr(js, derive(tsify::Tsify))]
+pub struct BlockedNote {
+    pub note: NoteEntry,
+    /// The lists whose status blocks the note.
+    #[serde(rename = "blockedBy")]
+    pub blocked_by: Vec<ListPoiStatus>,
+}
+
+/// POI status of a note under the configured policy, and on each list.
+struct NotePoi {
+    status: PoiStatus,
+    lists: Vec<ListPoiStatus>,
+    spendable_at: Option<u64>,
+}
+
 impl NoteEntry {
-    fn from_note(note: UtxoNote, poi_status: Option<PoiStatus>) -> Self {
+    fn from_note(note: UtxoNote, poi: Option<NotePoi>) -> Self {
+        let (poi_status, poi_lists, spendable_at) = match poi {
+            Some(poi) => (Some(poi.status), Some(poi.lists), poi.spendable_at),
+            None => (None, None, None),
+        };
+
         Self {
             asset: note.asset(),
             poi_status,
+            poi_lists,
+            spendable_at,
             amount: note.value(),
             tree_number: note.tree_number,
             leaf_index: note.leaf_index,
@@ -86,8 +133,9 @@ pub struct RailgunProvider {
     chain: ChainConfig,
     provider: Arc<dyn Eip1193Provider>,
     utxo_indexer: UtxoIndexer,
-    prover: Groth16Prover,
+    prover: Arc<dyn Prover>,
     poi_provider: Option<PoiProvider>,
+    checkpoint_verifier: Option<Arc<StorageProofUtxoVerifier>>,
 }
 
 #[derive(Debug, Error)]
@@ -104,10 +152,16 @@ pub enum RailgunProviderError {
     Signer(#[from] alloy::signers::Error),
     #[error("Bundler error: {0}")]
     Bundler(#[from] BundlerError),
+    #[error("Broadcaster error: {0}")]
+    Broadcaster(#[from] BroadcasterError),
     #[error("RPC error: {0}")]
     Rpc(#[from] Eip1193Error),
+    #[error("Provider was built without a trusted checkpoint")]
+    NoTrustedCheckpoint,
     #[error("Privacy Paymaster not configured for chain: {0}")]
     PrivacyPaymasterNotConfigured(u64),
+    #[error("Snapshot error: {0}")]
+    Snapshot(#[from] SnapshotError),
     #[error("Other: {0}")]
     Other(Box<dyn std::error::Error + Send + Sync>),
 }
@@ -117,8 +171,9 @@ impl RailgunProvider {
         chain: ChainConfig,
         provider: Arc<dyn Eip1193Provider>,
         utxo_indexer: UtxoIndexer,
-        prover: Groth16Prover,
+        prover: Arc<dyn Prover>,
         poi_provider: Option<PoiProvider>,
+        checkpoint_verifier: Option<Arc<StorageProofUtxoVerifier>>,
     ) -> Result<Self, RailgunProviderError> {
         Ok(Self {
             chain,
@@ -126,6 +181,7 @@ impl RailgunProvider {
             utxo_indexer,
             prover,
             poi_provider,
+            checkpoint_verifier,
         })
     }
 
@@ -139,6 +195,21 @@ impl RailgunProvider {
         Ok(())
     }
 
+    /// Replaces the trusted checkpoint UTXO Merkle roots are verified against, so roots created
+    /// after the previous checkpoint verify. Fails unless the provider was built with
+    /// [`RailgunBuilder::with_trusted_checkpoint`](crate::builder::RailgunBuilder::with_trusted_checkpoint).
+    pub fn set_trusted_checkpoint(
+        &self,
+        checkpoint: TrustedCheckpoint,
+    ) -> Result<(), RailgunProviderError> {
+        let verifier = self
+            .checkpoint_verifier
+            .as_ref()
+            .ok_or(RailgunProviderError::NoTrustedCheckpoint)?;
+        verifier.set_checkpoint(checkpoint);
+        Ok(())
+    }
+
     /// Syncs the provider to the latest block.
     pub async fn sync(&mut self) -> Result<(), RailgunProviderError> {
         self.sync_to(u64::MAX).await
@@ -149,9 +220,158 @@ impl RailgunProvider {
         self.utxo_indexer.sync_to(to_block).await?;
 
         if let Some(poi_provider) = &mut self.poi_provider {
-            poi_provider.sync_to(&self.prover, to_block).await?;
+            let notes: Vec<UtxoNote> = self
+                .utxo_indexer
+                .registered()
+                .into_iter()
+                .flat_map(|address| self.utxo_indexer.unspent(address))
+                .collect();
+            let unspent: HashSet<BlindedCommitment> = notes
+                .iter()
+                .map(|note| note.blinded_commitment.into())
+                .collect();
+            poi_provider.retain_unspent(&unspent);
+
+            let mut block_times = HashMap::new();
+            let mut commitments = Vec::new();
+            for note in notes {
+                let blinded_commitment = note.blinded_commitment.into();
+                if !poi_provider.needs_tracking(&blinded_commitment) {
+                    continue;
+                }
+                let created_at = match note.shield_block {
+                    Some(block) => match block_times.get(&block) {
+                        Some(time) => *time,
+                        None => {
+                            let time = block_time(self.provider.as_ref(), block).await;
+                            block_times.insert(block, time);
+                            time
+                        }
+                    },
+                    None => None,
+                };
+                commitments.push((blinded_commitment, note.commitment_type, created_at));
+            }
+            poi_provider.track(&commitments);
+            poi_provider.sync_to(self.prover.as_ref(), to_block).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Takes the POI status changes of this wallet's notes observed since the last call, such
+    /// as a shield becoming spendable. Statuses are re-checked on every sync.
+    pub async fn poi_events(&mut self) -> Result<Vec<PoiEvent>, RailgunProviderError> {
+        match &mut self.poi_provider {
+            Some(poi_provider) => Ok(poi_provider.take_events().await?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns the POI proofs waiting to be submitted for this wallet's transactions, and
+    /// those submitted recently. Empty if POI is disabled.
+    pub fn poi_submissions(&self) -> Vec<PoiSubmission> {
+        self.poi_provider
+            .as_ref()
+            .map(|p| p.submissions())
+            .unwrap_or_default()
+    }
+
+    /// Retries a failed POI submission on the next sync instead of waiting out its backoff.
+    pub async fn retry_poi_submission(&mut self, txid: Txid) -> Result<(), RailgunProviderError> {
+        if let Some(poi_provider) = &mut self.poi_provider {
+            poi_provider.retry_submission(txid).await?;
         }
+        Ok(())
+    }
 
+    /// Stops submitting the POI proof for a transaction.
+    pub async fn drop_poi_submission(&mut self, txid: Txid) -> Result<(), RailgunProviderError> {
+        if let Some(poi_provider) = &mut self.poi_provider {
+            poi_provider.drop_submission(txid).await?;
+        }
+        Ok(())
+    }
+
+    /// Exports the synced UTXO and TXID state as a portable snapshot.
+    ///
+    /// If `include_accounts` is set, the decrypted notes of every registered account
+    /// are included. Such snapshots contain private data and must never be
+    /// published.
+    pub fn export_snapshot(
+        &self,
+        include_accounts: bool,
+    ) -> Result<Snapshot, RailgunProviderError> {
+        let content = SnapshotContent {
+            utxo: self.utxo_indexer.export(include_accounts),
+            txid: self.poi_provider.as_ref().map(|p| p.export_txid()),
+        };
+        Ok(Snapshot::new(self.chain.id, &content)?)
+    }
+
+    /// Replaces the synced state with a snapshot, so syncing resumes from the
+    /// snapshot's block instead of the deployment block.
+    ///
+    /// Every UTXO tree root is checked on-chain, and TXID tree roots are checked
+    /// with the POI node when POI is enabled, before any state is replaced. If
+    /// `trusted_signer` is set the snapshot must also be signed by that address.
+    /// Once everything is validated, the state is replaced in a single write batch.
+    ///
+    /// The snapshot must carry the notes of every registered account, since a
+    /// registered account would otherwise have to rescan from the deployment block.
+    /// Published snapshots carry no notes, so import them before registering accounts.
+    /// It must also carry TXID trees if and only if POI is enabled.
+    pub async fn import_snapshot(
+        &mut self,
+        snapshot: &Snapshot,
+        trusted_signer: Option<Address>,
+    ) -> Result<(), RailgunProviderError> {
+        let header = snapshot.header();
+        if header.chain_id != self.chain.id {
+            return Err(SnapshotError::ChainMismatch {
+                expected: self.chain.id,
+                actual: header.chain_id,
+            }
+            .into());
+        }
+        if let Some(trusted_signer) = trusted_signer {
+            snapshot.verify_signer(trusted_signer)?;
+        }
+
+        let content = snapshot.content()?;
+        let missing = self
+            .utxo_indexer
+            .registered()
+            .into_iter()
+            .find(|address| !content.utxo.accounts.iter().any(|(a, _)| a == address));
+        if let Some(address) = missing {
+            return Err(SnapshotError::MissingAccount(address).into());
+        }
+
+        let utxo = self.utxo_indexer.verify_snapshot(content.utxo).await?;
+        //? Importing only the UTXO trees would leave the TXID trees behind the synced block.
+        let txid = match (&self.poi_provider, content.txid) {
+            (Some(poi_provider), Some(txid)) => {
+                Some(poi_provider.verify_txid_snapshot(txid).await?)
+            }
+            (Some(_), None) => return Err(SnapshotError::MissingTxid.into()),
+            (None, Some(_)) => return Err(SnapshotError::UnexpectedTxid.into()),
+            (None, None) => None,
+        };
+
+        let mut batch = WriteBatch::new();
+        self.utxo_indexer
+            .import(utxo, &mut batch)
+            .map_err(UtxoIndexerError::from)?;
+        if let (Some(poi_provider), Some(txid)) = (&mut self.poi_provider, txid) {
+            poi_provider.import_txid(txid, &mut batch)?;
+        }
+        self.utxo_indexer
+            .write_batch(batch)
+            .await
+            .map_err(UtxoIndexerError::from)?;
+
+        info!("Imported snapshot at block {}", header.block_number);
         Ok(())
     }
 
@@ -160,7 +380,30 @@ impl RailgunProvider {
         self.unspent(address)
             .await
             .into_iter()
-            .map(|(note, poi_status)| NoteEntry::from_note(note, poi_status))
+            .map(|(note, poi)| NoteEntry::from_note(note, poi))
+            .collect()
+    }
+
+    /// Returns the unspent notes of the given address that can't be spent under the POI
+    /// policy, along with the lists blocking each of them. Empty if POI is disabled.
+    pub async fn blocked_notes(&mut self, address: RailgunAddress) -> Vec<BlockedNote> {
+        let notes = self.unspent(address).await;
+        let Some(poi_provider) = &self.poi_provider else {
+            return Vec::new();
+        };
+
+        notes
+            .into_iter()
+            .filter_map(|(note, poi)| {
+                let poi = poi?;
+                if poi.status == PoiStatus::Valid {
+                    return None;
+                }
+                Some(BlockedNote {
+                    blocked_by: poi_provider.policy().blocking(&poi.lists),
+                    note: NoteEntry::from_note(note, Some(poi)),
+                })
+            })
             .collect()
     }
 
@@ -171,15 +414,16 @@ impl RailgunProvider {
         let mut balance_map = HashMap::new();
         for note in self.notes(address).await {
             *balance_map
-                .entry((note.asset, note.poi_status))
+                .entry((note.asset, note.poi_status, note.poi_lists))
                 .or_insert(0) += note.amount;
         }
 
         balance_map
             .into_iter()
-            .map(|((asset, poi_status), amount)| BalanceEntry {
+            .map(|((asset, poi_status, poi_lists), amount)| BalanceEntry {
                 asset,
                 poi_status,
+                poi_lists,
                 amount,
             })
             .collect()
@@ -210,6 +454,84 @@ impl RailgunProvider {
         Ok(proved_tx)
     }
 
+    /// Builds a transaction that pays `broadcaster` with a fee note, and submits it through the
+    /// broadcaster.
+    ///
+    /// The fee note is a transfer from `fee_payer` to the broadcaster's address in `fee_token`,
+    /// priced from the broadcaster's quote and the transaction's estimated gas. Quotes in
+    /// another token, or that price the fee above `max_fee` base units of `fee_token`, are
+    /// rejected before anything is proved. Use [`Broadcaster::wait_for_result`] with the
+    /// returned hash to track inclusion.
+    pub async fn broadcast<R: Rng>(
+        &mut self,
+        builder: TransactionBuilder,
+        broadcaster: &dyn Broadcaster,
+        fee_payer: Arc<dyn RailgunSigner>,
+        fee_token: Address,
+        max_fee: u128,
+        rng: &mut R,
+    ) -> Result<Broadcast, RailgunProviderError> {
+        let quote = broadcaster.fee_quote(self.chain.id, fee_token).await?;
+        if quote.token != fee_token {
+            return Err(BroadcasterError::QuoteTokenMismatch {
+                expected: fee_token,
+                actual: quote.token,
+            }
+            .into());
+        }
+        if quote.expiration <= common::unix_time() {
+            return Err(BroadcasterError::QuoteExpired(quote.fees_id).into());
+        }
+        let gas_price = self.provider.gas_price().await?;
+        let fee_asset = AssetId::Erc20(fee_token);
+        let builder = builder.min_gas_price(gas_price);
+
+        //? Gas is estimated on unproved builds, so the transaction is only proved once the
+        //? fee has converged.
+        info!("Estimating gas to converge on broadcaster fee");
+        let gas = self.estimate_unproved(builder.clone(), rng).await?;
+        let mut convergence = FeeConvergence::new(&quote, gas_price, gas, max_fee)?;
+        let fee_builder = loop {
+            let fee_builder = builder.clone().transfer(
+                fee_payer.clone(),
+                quote.railgun_address,
+                fee_asset,
+                convergence.fee(),
+                "",
+            );
+            let gas = self.estimate_unproved(fee_builder.clone(), rng).await?;
+            info!("Estimated gas {} with fee {}", gas, convergence.fee());
+            if convergence.update(gas)? {
+                break fee_builder;
+            }
+        };
+
+        let fee = convergence.fee();
+        info!("Building broadcast transaction with fee: {}", fee);
+        let operations = self.build_operation(fee_builder, rng).await?;
+        let proved_tx = ProvedTx::new(self.chain.railgun_smart_wallet, operations);
+        if let Some(poi_provider) = &mut self.poi_provider {
+            poi_provider
+                .register_ops(&proved_tx.proved_operations)
+                .await?;
+        }
+
+        let request = BroadcastRequest {
+            chain_id: self.chain.id,
+            to: proved_tx.tx_data.to,
+            data: proved_tx.tx_data.data.clone(),
+            fees_id: quote.fees_id.clone(),
+            min_gas_price: gas_price,
+        };
+        let tx_hash = broadcaster.submit(&request).await?;
+        info!("Broadcast transaction {}", tx_hash);
+        Ok(Broadcast {
+            tx_hash,
+            fee,
+            quote,
+        })
+    }
+
     /// Build a transaction builder into a broadcastable 7702 UserOperation.
     ///
     /// Constructs a UserOperation sent from the `delegator_address` that executes the provided
@@ -331,53 +653,117 @@ impl RailgunProvider {
         ))));
     }
 
-    async fn all_unspent(&mut self) -> Vec<(UtxoNote, Option<PoiStatus>)> {
-        let addresses = self.utxo_indexer.registered();
-        let mut all_notes = Vec::new();
-
-        for address in addresses {
-            let mut notes = self.unspent(address).await;
-            all_notes.append(&mut notes);
-        }
-        all_notes
+    async fn all_unspent(&mut self) -> Vec<(UtxoNote, Option<NotePoi>)> {
+        let notes = self
+            .utxo_indexer
+            .registered()
+            .into_iter()
+            .flat_map(|address| self.utxo_indexer.unspent(address))
+            .collect();
+        self.with_poi_status(notes).await
     }
 
-    async fn unspent(&mut self, address: RailgunAddress) -> Vec<(UtxoNote, Option<PoiStatus>)> {
+    async fn unspent(&mut self, address: RailgunAddress) -> Vec<(UtxoNote, Option<NotePoi>)> {
         let notes = self.utxo_indexer.unspent(address);
+        self.with_poi_status(notes).await
+    }
 
+    /// Annotates notes with their POI status, fetching all statuses in one batch.
+    async fn with_poi_status(&mut self, notes: Vec<UtxoNote>) -> Vec<(UtxoNote, Option<NotePoi>)> {
         let Some(poi_provider) = &mut self.poi_provider else {
             return notes.into_iter().map(|note| (note, None)).collect();
         };
 
-        let mut annotated_notes = Vec::new();
-        for note in notes {
-            let status = poi_provider
-                .status(note.blinded_commitment.into(), note.commitment_type)
-                .await;
-            match status {
-                Ok(status) => {
-                    annotated_notes.push((note, Some(status)));
-                }
-                Err(e) => {
-                    warn!("Error checking POI for note {}: {}", note, e);
-                    annotated_notes.push((note, Some(PoiStatus::Missing)));
-                }
+        let commitments: Vec<(BlindedCommitment, BlindedCommitmentType)> = notes
+            .iter()
+            .map(|note| (note.blinded_commitment.into(), note.commitment_type))
+            .collect();
+        let statuses = match poi_provider.list_statuses(&commitments).await {
+            Ok(statuses) => statuses,
+            Err(e) => {
+                warn!("Error checking POI for {} notes: {}", notes.len(), e);
+                HashMap::new()
             }
-        }
+        };
 
-        annotated_notes
+        notes
+            .into_iter()
+            .zip(commitments)
+            .map(|(note, (blinded_commitment, _))| {
+                let lists = statuses
+                    .get(&blinded_commitment)
+                    .cloned()
+                    .unwrap_or_else(|| {
+                        poi_provider
+                            .list_keys()
+                            .into_iter()
+                            .map(|list_key| ListPoiStatus {
+                                list_key,
+                                status: PoiStatus::Missing,
+                            })
+                            .collect()
+                    });
+                let status = poi_provider.policy().evaluate(&lists);
+                let spendable_at = match status {
+                    PoiStatus::Valid | PoiStatus::ShieldBlocked => None,
+                    _ => poi_provider.spendable_at(&blinded_commitment),
+                };
+                (
+                    note,
+                    Some(NotePoi {
+                        status,
+                        lists,
+                        spendable_at,
+                    }),
+                )
+            })
+            .collect()
     }
 
     async fn build_operation<R: Rng>(
         &mut self,
         builder: TransactionBuilder,
         rng: &mut R,
+    ) -> Result<Vec<ProvedOperation>, RailgunProviderError> {
+        let prover = self.prover.clone();
+        self.build_operation_with(builder, prover.as_ref(), rng)
+            .await
+    }
+
+    /// Estimates the gas of a transaction without proving it. The railgun contracts skip proof
+    /// verification for calls from [`VERIFICATION_BYPASS`], so the estimate is made from it
+    /// with empty proofs.
+    async fn estimate_unproved<R: Rng>(
+        &mut self,
+        builder: TransactionBuilder,
+        rng: &mut R,
+    ) -> Result<u64, RailgunProviderError> {
+        let operations = self
+            .build_operation_with(builder, &UnprovedProver, rng)
+            .await?;
+        let tx = ProvedTx::new(self.chain.railgun_smart_wallet, operations);
+        let gas = self
+            .provider
+            .estimate_gas(
+                tx.tx_data.to,
+                tx.tx_data.data.clone(),
+                Some(VERIFICATION_BYPASS),
+            )
+            .await?;
+        Ok(gas)
+    }
+
+    async fn build_operation_with<R: Rng>(
+        &mut self,
+        builder: TransactionBuilder,
+        prover: &dyn Prover,
+        rng: &mut R,
     ) -> Result<Vec<ProvedOperation>, RailgunProviderError> {
         let in_notes = self.all_unspent().await;
         let spendable_notes: Vec<UtxoNote> = if let Some(_) = self.poi_provider {
             in_notes
                 .into_iter()
-                .filter(|(_, status)| *status == Some(PoiStatus::Valid))
+                .filter(|(_, poi)| poi.as_ref().map(|p| p.status) == Some(PoiStatus::Valid))
                 .map(|(note, _)| note)
                 .collect()
         } else {
@@ -386,7 +772,7 @@ impl RailgunProvider {
 
         let operations = builder
             .build(
-                &self.prover,
+                prover,
                 self.chain.id,
                 &spendable_notes,
                 &self.utxo_indexer.utxo_trees,
@@ -465,6 +851,51 @@ async fn estimate_paymaster_verification_gas_limit(
         .await? as u128)
 }
 
+/// Unix time in seconds of `block`, or None if its header can't be fetched.
+async fn block_time(provider: &dyn Eip1193Provider, block: u64) -> Option<u64> {
+    match provider.get_block(block).await {
+        Ok(header) => Some(header.timestamp),
+        Err(e) => {
+            warn!("Failed to fetch block {}: {}", block, e);
+            None
+        }
+    }
+}
+
+/// Origin for which the railgun verifier accepts any proof, so transactions can be gas
+/// estimated before they're proved.
+///
+/// <https://github.com/Railgun-Privacy/contract/blob/main/contracts/logic/Verifier.sol>
+const VERIFICATION_BYPASS: Address = address!("0x000000000000000000000000000000000000dEaD");
+
+/// Prover returning empty proofs, for gas estimates from [`VERIFICATION_BYPASS`].
+struct UnprovedProver;
+
+#[cfg_attr(native, async_trait::async_trait)]
+#[cfg_attr(wasm, async_trait::async_trait(?Send))]
+impl Prover for UnprovedProver {
+    async fn prove_transact(&self, _: &TransactCircuitInputs) -> Result<Proof, ProverError> {
+        Ok(Proof {
+            a: G1Affine {
+                x: U256::ZERO,
+                y: U256::ZERO,
+            },
+            b: G2Affine {
+                x: [U256::ZERO; 2],
+                y: [U256::ZERO; 2],
+            },
+            c: G1Affine {
+                x: U256::ZERO,
+                y: U256::ZERO,
+            },
+        })
+    }
+
+    async fn prove_poi(&self, _: &PoiCircuitInputs) -> Result<Proof, ProverError> {
+        Err("POI proofs are not needed for gas estimates".into())
+    }
+}
+
 mod abi {
     use alloy::sol;
 
@@ -0,0 +1,323 @@
+//! Portable wallet snapshots.
+//!
+//! A snapshot captures the UTXO trees, TXID trees, and indexer state at a block,
+//! optionally along with the notes of the exported accounts. It is used to move a
+//! wallet between machines and to bootstrap new installs from a published snapshot
+//! instead of replaying every event since the deployment block.
+//!
+//! Snapshots are not trusted on import. Trees are rebuilt from their leaves and
+//! each root is checked with the provider's UTXO verifier (and the POI node for
+//! TXID trees), so a tampered snapshot is rejected rather than silently used. The
+//! content hash and optional signature only identify who published a snapshot.
+//!
+//! ## Format
+//!
+//! ```text
+//! magic         8 bytes   b"RGSNAP\0\0"
+//! header_len    u32 LE
+//! header        JSON      SnapshotHeader
+//! content       brotli    JSON SnapshotContent
+//! ```
+//!
+//! `header.content_hash` is the SHA-256 of the compressed content bytes.
+
+use std::io::Read;
+
+use alloy::{
+    primitives::{Address, B256, ChainId, Signature, keccak256},
+    signers::Signer,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{
+    account::address::RailgunAddress,
+    indexer::{txid_indexer::TxidSnapshot, utxo_indexer::UtxoSnapshot},
+};
+
+/// Current snapshot format version.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Largest decompressed content accepted, so a small snapshot can't expand without bound.
+const MAX_CONTENT_SIZE: usize = 1 << 30;
+
+const MAGIC: &[u8; 8] = b"RGSNAP\0\0";
+const SIGNING_DOMAIN: &[u8] = b"railgun-snapshot";
+
+/// A versioned, content-addressed wallet snapshot.
+#[derive(Clone)]
+pub struct Snapshot {
+    header: SnapshotHeader,
+    content: Vec<u8>,
+}
+
+/// Snapshot metadata. Stored uncompressed so it can be inspected without
+/// decoding the content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotHeader {
+    pub version: u32,
+    pub chain_id: ChainId,
+    /// Block the snapshot was taken at. Syncing resumes from the next block.
+    pub block_number: u64,
+    pub content_hash: B256,
+    /// Whether the snapshot carries account notes. Snapshots meant for publishing
+    /// should never include them.
+    pub includes_accounts: bool,
+    /// Publisher signature over [`SnapshotHeader::signing_hash`].
+    pub signature: Option<Signature>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SnapshotContent {
+    pub utxo: UtxoSnapshot,
+    pub txid: Option<TxidSnapshot>,
+}
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Not a snapshot file")]
+    InvalidMagic,
+    #[error("Truncated snapshot file")]
+    Truncated,
+    #[error("Unsupported snapshot version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("Content hash mismatch: expected {expected}, got {actual}")]
+    HashMismatch { expected: B256, actual: B256 },
+    #[error("Snapshot is for chain {actual}, expected {expected}")]
+    ChainMismatch { expected: ChainId, actual: ChainId },
+    #[error("Snapshot has no notes for registered account {0}")]
+    MissingAccount(RailgunAddress),
+    #[error("Snapshot has no TXID trees, but POI is enabled")]
+    MissingTxid,
+    #[error("Snapshot has TXID trees, but POI is disabled")]
+    UnexpectedTxid,
+    #[error("Snapshot is unsigned")]
+    Unsigned,
+    #[error("Snapshot signed by {actual}, expected {expected}")]
+    UntrustedSigner { expected: Address, actual: Address },
+    #[error("Signature error: {0}")]
+    Signature(#[from] alloy::primitives::SignatureError),
+    #[error("Signer error: {0}")]
+    Signer(#[from] alloy::signers::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Compression error: {0}")]
+    Compression(#[from] std::io::Error),
+    #[error("Snapshot content decompresses to more than {0} bytes")]
+    ContentTooLarge(usize),
+}
+
+impl Snapshot {
+    pub(crate) fn new(chain_id: ChainId, content: &SnapshotContent) -> Result<Self, SnapshotError> {
+        let json = serde_json::to_vec(content)?;
+        let mut compressed = Vec::new();
+        brotli::BrotliCompress(
+            &mut json.as_slice(),
+            &mut compressed,
+            &brotli::enc::BrotliEncoderParams::default(),
+        )?;
+
+        Ok(Self {
+            header: SnapshotHeader {
+                version: SNAPSHOT_VERSION,
+                chain_id,
+                block_number: content.utxo.synced_block,
+                content_hash: content_hash(&compressed),
+                includes_accounts: !content.utxo.accounts.is_empty(),
+                signature: None,
+            },
+            content: compressed,
+        })
+    }
+
+    /// Parses a snapshot file and checks its content hash.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let rest = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .ok_or(SnapshotError::InvalidMagic)?;
+        let (len, rest) = rest
+            .split_first_chunk::<4>()
+            .ok_or(SnapshotError::Truncated)?;
+        let len = u32::from_le_bytes(*len) as usize;
+        if rest.len() < len {
+            return Err(SnapshotError::Truncated);
+        }
+        let (header, content) = rest.split_at(len);
+
+        let header: SnapshotHeader = serde_json::from_slice(header)?;
+        if header.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(header.version));
+        }
+
+        let actual = content_hash(content);
+        if actual != header.content_hash {
+            return Err(SnapshotError::HashMismatch {
+                expected: header.content_hash,
+                actual,
+            });
+        }
+
+        Ok(Self {
+            header,
+            content: content.to_vec(),
+        })
+    }
+
+    /// Serializes the snapshot to its file format.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        let header = serde_json::to_vec(&self.header)?;
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 4 + header.len() + self.content.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&self.content);
+        Ok(bytes)
+    }
+
+    pub fn header(&self) -> &SnapshotHeader {
+        &self.header
+    }
+
+    /// Signs the snapshot as its publisher, replacing any existing signature.
+    pub async fn sign(&mut self, signer: &dyn Signer) -> Result<(), SnapshotError> {
+        let signature = signer.sign_hash(&self.header.signing_hash()).await?;
+        self.header.signature = Some(signature);
+        Ok(())
+    }
+
+    /// Returns the publisher address recovered from the signature, if signed.
+    pub fn signer(&self) -> Result<Option<Address>, SnapshotError> {
+        let Some(signature) = self.header.signature else {
+            return Ok(None);
+        };
+        let address = signature.recover_address_from_prehash(&self.header.signing_hash())?;
+        Ok(Some(address))
+    }
+
+    /// Checks that the snapshot was signed by `expected`.
+    pub fn verify_signer(&self, expected: Address) -> Result<(), SnapshotError> {
+        match self.signer()? {
+            Some(actual) if actual == expected => Ok(()),
+            Some(actual) => Err(SnapshotError::UntrustedSigner { expected, actual }),
+            None => Err(SnapshotError::Unsigned),
+        }
+    }
+
+    pub(crate) fn content(&self) -> Result<SnapshotContent, SnapshotError> {
+        let json = decompress(&self.content, MAX_CONTENT_SIZE)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+impl SnapshotHeader {
+    /// Hash signed by snapshot publishers. Binds the content hash to the chain,
+    /// block, and format version.
+    pub fn signing_hash(&self) -> B256 {
+        let mut preimage = Vec::with_capacity(SIGNING_DOMAIN.len() + 4 + 8 + 8 + 1 + 32);
+        preimage.extend_from_slice(SIGNING_DOMAIN);
+        preimage.extend_from_slice(&self.version.to_be_bytes());
+        preimage.extend_from_slice(&self.chain_id.to_be_bytes());
+        preimage.extend_from_slice(&self.block_number.to_be_bytes());
+        preimage.push(self.includes_accounts as u8);
+        preimage.extend_from_slice(self.content_hash.as_slice());
+        keccak256(preimage)
+    }
+}
+
+/// Decompresses `content`, failing once the output exceeds `limit` bytes.
+fn decompress(content: &[u8], limit: usize) -> Result<Vec<u8>, SnapshotError> {
+    let mut out = Vec::new();
+    brotli::Decompressor::new(content, 4096)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut out)?;
+    if out.len() > limit {
+        return Err(SnapshotError::ContentTooLarge(limit));
+    }
+    Ok(out)
+}
+
+fn content_hash(content: &[u8]) -> B256 {
+    B256::from_slice(&Sha256::digest(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use alloy::signers::local::PrivateKeySigner;
+    use ruint::aliases::U256;
+
+    use super::*;
+
+    fn snapshot() -> Snapshot {
+        let content = SnapshotContent {
+            utxo: UtxoSnapshot {
+                synced_block: 42,
+                trees: BTreeMap::from([(0, vec![U256::from(1), U256::from(2)])]),
+                accounts: vec![],
+            },
+            txid: None,
+        };
+        Snapshot::new(1, &content).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let snapshot = snapshot();
+        let bytes = snapshot.to_bytes().unwrap();
+        let parsed = Snapshot::from_bytes(&bytes).unwrap();
+
+        assert_eq!(parsed.header().block_number, 42);
+        assert_eq!(parsed.header().content_hash, snapshot.header().content_hash);
+        assert!(!parsed.header().includes_accounts);
+
+        let content = parsed.content().unwrap();
+        assert_eq!(content.utxo.trees[&0], vec![U256::from(1), U256::from(2)]);
+    }
+
+    #[test]
+    fn test_content_size_limit() {
+        let snapshot = snapshot();
+        let json = decompress(&snapshot.content, MAX_CONTENT_SIZE).unwrap();
+
+        decompress(&snapshot.content, json.len()).unwrap();
+        assert!(matches!(
+            decompress(&snapshot.content, json.len() - 1),
+            Err(SnapshotError::ContentTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn test_tampered_content() {
+        let mut bytes = snapshot().to_bytes().unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+
+        assert!(matches!(
+            Snapshot::from_bytes(&bytes),
+            Err(SnapshotError::HashMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_signature() {
+        let publisher = PrivateKeySigner::random();
+        let mut snapshot = snapshot();
+        assert!(matches!(
+            snapshot.verify_signer(publisher.address()),
+            Err(SnapshotError::Unsigned)
+        ));
+
+        snapshot.sign(&publisher).await.unwrap();
+        let parsed = Snapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap();
+        parsed.verify_signer(publisher.address()).unwrap();
+
+        let other = PrivateKeySigner::random();
+        assert!(matches!(
+            parsed.verify_signer(other.address()),
+            Err(SnapshotError::UntrustedSigner { .. })
+        ));
+    }
+}
@@ -0,0 +1,76 @@
+//! A stand-in HTTP server for testing HTTP clients.
+
+use std::sync::{Arc, Mutex};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// A request received by [`serve`].
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    /// Method and path, e.g. `GET /fees/1`.
+    pub method_path: String,
+    pub body: String,
+}
+
+/// Serves one request per entry of `responses`, answering with its status and JSON body.
+/// Returns the server's URL and the requests received so far.
+pub(crate) async fn serve(
+    responses: Vec<(&'static str, String)>,
+) -> (String, Arc<Mutex<Vec<Request>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let recorded = requests.clone();
+    tokio::spawn(async move {
+        for (status, body) in responses {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let body_start = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(i) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break i + 4;
+                }
+            };
+
+            let head = String::from_utf8_lossy(&request[..body_start]).to_string();
+            let content_length: usize = head
+                .to_lowercase()
+                .lines()
+                .find_map(|l| l.strip_prefix("content-length:"))
+                .map(|v| v.trim().parse().unwrap())
+                .unwrap_or(0);
+            while request.len() < body_start + content_length {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+
+            let request_line = head.lines().next().unwrap_or_default();
+            let mut parts = request_line.split(' ');
+            let method_path = format!(
+                "{} {}",
+                parts.next().unwrap_or_default(),
+                parts.next().unwrap_or_default()
+            );
+            recorded.lock().unwrap().push(Request {
+                method_path,
+                body: String::from_utf8_lossy(&request[body_start..]).to_string(),
+            });
+
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    (url, requests)
+}
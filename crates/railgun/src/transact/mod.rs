@@ -1,6 +1,8 @@
 pub(crate) mod proved_transaction;
 mod shield_builder;
 mod transaction_builder;
+mod verifier;
 
 pub use shield_builder::{ShieldBuilder, ShieldError};
 pub use transaction_builder::{TransactionBuilder, TransactionBuilderError};
+pub use verifier::{TransactionVerificationError, TransactionVerifier};
@@ -29,8 +29,9 @@ use crate::{
     account::{address::RailgunAddress, signer::RailgunSigner},
     caip::AssetId,
     circuit::{
-        groth16_prover::Groth16Prover,
         inputs::transact_inputs::{TransactCircuitInputs, TransactCircuitInputsError},
+        proof::Proof,
+        prover::Prover,
     },
     merkle_tree::UtxoMerkleTree,
     note::{
@@ -57,6 +58,7 @@ pub struct TransactionBuilder {
 
     adapt_contract: Option<Address>,
     adapt_params: Option<[u8; 32]>,
+    min_gas_price: u128,
 }
 
 #[derive(Debug, Error)]
@@ -109,6 +111,7 @@ impl TransactionBuilder {
             unshields: HashSet::new(),
             adapt_contract: None,
             adapt_params: None,
+            min_gas_price: 0,
         }
     }
 }
@@ -167,10 +170,17 @@ impl TransactionBuilder {
         self
     }
 
+    /// Sets the minimum gas price bound into each operation. Broadcasters require it to match
+    /// the gas price their fee was quoted at.
+    pub fn min_gas_price(mut self, min_gas_price: u128) -> Self {
+        self.min_gas_price = min_gas_price;
+        self
+    }
+
     /// Builds and proves a set of operations for railgun, without packaging into a transaction.
     pub(crate) async fn build<R: Rng>(
         &self,
-        prover: &Groth16Prover,
+        prover: &dyn Prover,
         chain_id: u64,
         in_notes: &[UtxoNote],
         utxo_trees: &BTreeMap<u32, UtxoMerkleTree>,
@@ -182,6 +192,7 @@ impl TransactionBuilder {
         for op in &mut operations {
             op.adapt_contract = self.adapt_contract;
             op.adapt_params = self.adapt_params;
+            op.min_gas_price = self.min_gas_price;
             op.verify()?;
         }
 
@@ -392,31 +403,49 @@ fn add_change_note<R: Rng>(operation: &mut Operation, asset: AssetId, rng: &mut
 }
 
 async fn prove_operations(
-    prover: &Groth16Prover,
+    prover: &dyn Prover,
     utxo_trees: &BTreeMap<u32, UtxoMerkleTree>,
     chain_id: u64,
     operations: &[Operation],
     rng: &mut impl Rng,
 ) -> Result<Vec<ProvedOperation>, TransactionBuilderError> {
-    let mut proved = Vec::new();
+    let mut prepared = Vec::new();
     for op in operations {
         let tree = op.utxo_tree_number;
         let Some(utxo_tree) = utxo_trees.get(&tree) else {
             return Err(TransactionBuilderError::MissingTree(tree));
         };
-        let proved_op = prove_operation(prover, utxo_tree, chain_id, op, rng).await?;
-        proved.push(proved_op);
+        prepared.push(prepare_operation(utxo_tree, chain_id, op, rng)?);
     }
-    Ok(proved)
+
+    //? Operations are independent once their inputs are built, so they're proved
+    //? concurrently. Whether that runs in parallel is up to the prover.
+    let proofs =
+        futures::future::try_join_all(prepared.iter().map(|p| prover.prove_transact(&p.inputs)))
+            .await
+            .map_err(TransactionBuilderError::Prover)?;
+
+    Ok(prepared
+        .into_iter()
+        .zip(proofs)
+        .map(|(p, proof)| p.into_proved(proof))
+        .collect())
 }
 
-async fn prove_operation(
-    prover: &Groth16Prover,
+/// An operation whose circuit inputs are built and which is ready to be proved.
+struct PreparedOperation {
+    operation: Operation,
+    inputs: TransactCircuitInputs,
+    bound_params: abis::railgun::BoundParams,
+    unshield_preimage: abis::railgun::CommitmentPreimage,
+}
+
+fn prepare_operation(
     utxo_tree: &UtxoMerkleTree,
     chain_id: u64,
     operation: &Operation,
     rng: &mut impl Rng,
-) -> Result<ProvedOperation, TransactionBuilderError> {
+) -> Result<PreparedOperation, TransactionBuilderError> {
     info!("Constructing circuit inputs");
     let unshield_note = operation.unshield_note();
     let unshield_type = unshield_note.map(|n| n.unshield_type()).unwrap_or_default();
@@ -428,11 +457,9 @@ async fn prove_operation(
         .map(|n| n.encrypt(rng))
         .collect::<Result<_, _>>()?;
 
-    //? min_gas_price, adapt_contract, and adapt_input are all vestigial fields for
-    //? railgun relayers.
     let bound_params = abis::railgun::BoundParams::new(
         utxo_tree.number() as u16,
-        0,
+        operation.min_gas_price,
         unshield_type,
         chain_id,
         operation.adapt_contract.unwrap_or(Address::ZERO),
@@ -448,24 +475,35 @@ async fn prove_operation(
         operation.in_notes(),
         &operation.out_notes(),
     )?;
-    let proof = prover
-        .prove_transact(&inputs)
-        .await
-        .map_err(|e| TransactionBuilderError::Prover(Box::new(e)))?;
-
-    let merkleroot: U256 = inputs.merkleroot.into();
-    let transaction = abis::railgun::Transaction::new(
-        proof.into(),
-        merkleroot.into(),
-        inputs.nullifiers.iter().map(|n| n.clone().into()).collect(),
-        inputs
-            .commitments_out
-            .iter()
-            .map(|c| c.clone().into())
-            .collect(),
+
+    Ok(PreparedOperation {
+        operation: operation.clone(),
+        inputs,
         bound_params,
         unshield_preimage,
-    );
+    })
+}
 
-    Ok(ProvedOperation::new(operation.clone(), inputs, transaction))
+impl PreparedOperation {
+    fn into_proved(self, proof: Proof) -> ProvedOperation {
+        let merkleroot: U256 = self.inputs.merkleroot.into();
+        let transaction = abis::railgun::Transaction::new(
+            proof.into(),
+            merkleroot.into(),
+            self.inputs
+                .nullifiers
+                .iter()
+                .map(|n| n.clone().into())
+                .collect(),
+            self.inputs
+                .commitments_out
+                .iter()
+                .map(|c| c.clone().into())
+                .collect(),
+            self.bound_params,
+            self.unshield_preimage,
+        );
+
+        ProvedOperation::new(self.operation, self.inputs, transaction)
+    }
 }
@@ -0,0 +1,284 @@
+use alloy::primitives::FixedBytes;
+use ark_bn254::Fr;
+use ark_ff::{BigInt, PrimeField};
+use ruint::aliases::U256;
+use thiserror::Error;
+
+use crate::{
+    abis::railgun::{Transaction, UnshieldType},
+    circuit::{
+        artifact_loader::{ArtifactLoader, ArtifactLoaderError},
+        inputs::transact_inputs::TRANSACT_CIRCUIT_SIZES,
+        proof::Proof,
+        verifying_keys::VerifyingKeys,
+    },
+};
+
+/// Verifies railgun transactions produced by third parties without submitting
+/// them, so broadcasters can reject invalid proofs before paying gas for them.
+///
+/// Only checks what can be checked offline. Whether the merkle root is in the
+/// contract's root history and whether the nullifiers are unspent must still be
+/// checked against the chain.
+pub struct TransactionVerifier {
+    verifying_keys: VerifyingKeys,
+}
+
+#[derive(Debug, Error)]
+pub enum TransactionVerificationError {
+    #[error("Transaction is bound to chain {actual}, expected {expected}")]
+    WrongChain { expected: u64, actual: u64 },
+    #[error("No circuit for {nullifiers} nullifiers and {commitments} commitments")]
+    UnsupportedCircuit {
+        nullifiers: usize,
+        commitments: usize,
+    },
+    #[error("Expected {expected} commitment ciphertexts, got {actual}")]
+    CiphertextCount { expected: usize, actual: usize },
+    #[error("Unshield preimage does not match the last commitment")]
+    UnshieldMismatch,
+    #[error("Public input {0} is outside the scalar field")]
+    InputOutOfRange(U256),
+    #[error("Malformed proof: {0}")]
+    MalformedProof(String),
+    #[error("Proof does not verify against the transaction's public inputs")]
+    InvalidProof,
+    #[error("Artifact loader error: {0}")]
+    ArtifactLoader(#[from] ArtifactLoaderError),
+}
+
+impl TransactionVerifier {
+    pub fn new(artifact_loader: ArtifactLoader) -> Self {
+        TransactionVerifier {
+            verifying_keys: VerifyingKeys::new(artifact_loader),
+        }
+    }
+
+    /// Checks that `transaction` would pass the contract's validation on
+    /// `chain_id`, apart from the checks that need chain state.
+    ///
+    /// The public inputs (merkle root, bound params hash, nullifiers and
+    /// commitments) are recomputed from the transaction itself, and the proof
+    /// is verified against the verifying key of the circuit matching its shape.
+    pub async fn verify_transaction(
+        &self,
+        transaction: &Transaction,
+        chain_id: u64,
+    ) -> Result<(), TransactionVerificationError> {
+        let bound_params = &transaction.boundParams;
+        if bound_params.chainID != chain_id {
+            return Err(TransactionVerificationError::WrongChain {
+                expected: chain_id,
+                actual: bound_params.chainID,
+            });
+        }
+
+        let nullifiers = transaction.nullifiers.len();
+        let commitments = transaction.commitments.len();
+        if !TRANSACT_CIRCUIT_SIZES.contains(&nullifiers)
+            || !TRANSACT_CIRCUIT_SIZES.contains(&commitments)
+        {
+            return Err(TransactionVerificationError::UnsupportedCircuit {
+                nullifiers,
+                commitments,
+            });
+        }
+
+        //? Unshields don't get a ciphertext, and must be the last commitment.
+        let has_unshield = !matches!(bound_params.unshield, UnshieldType::NONE);
+        let expected = commitments - has_unshield as usize;
+        if bound_params.commitmentCiphertext.len() != expected {
+            return Err(TransactionVerificationError::CiphertextCount {
+                expected,
+                actual: bound_params.commitmentCiphertext.len(),
+            });
+        }
+        if has_unshield
+            && transaction.unshieldPreimage.hash() != to_u256(&transaction.commitments[expected])
+        {
+            return Err(TransactionVerificationError::UnshieldMismatch);
+        }
+
+        let inputs = public_inputs(transaction)?;
+        let proof = Proof::from(&transaction.proof);
+        ark_groth16::Proof::try_from(&proof)
+            .map_err(TransactionVerificationError::MalformedProof)?;
+
+        let circuit_name = format!("railgun/{:02}x{:02}", nullifiers, commitments);
+        if !self
+            .verifying_keys
+            .verify(&circuit_name, &proof, &inputs)
+            .await?
+        {
+            return Err(TransactionVerificationError::InvalidProof);
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for TransactionVerifier {
+    fn default() -> Self {
+        Self::new(ArtifactLoader::default())
+    }
+}
+
+/// Assembles the transact circuit's public signals in circom order:
+/// `merkleRoot`, `boundParamsHash`, `nullifiers` and `commitmentsOut`.
+fn public_inputs(transaction: &Transaction) -> Result<Vec<U256>, TransactionVerificationError> {
+    let mut signals = vec![
+        to_u256(&transaction.merkleRoot),
+        transaction.boundParams.hash(),
+    ];
+    signals.extend(transaction.nullifiers.iter().map(to_u256));
+    signals.extend(transaction.commitments.iter().map(to_u256));
+
+    //? Checked here so out-of-range inputs are reported rather than failing verification.
+    match signals
+        .iter()
+        .find(|&&s| Fr::from_bigint(BigInt::from(s)).is_none())
+    {
+        Some(&s) => Err(TransactionVerificationError::InputOutOfRange(s)),
+        None => Ok(signals),
+    }
+}
+
+fn to_u256(bytes: &FixedBytes<32>) -> U256 {
+    U256::from_be_bytes(bytes.0)
+}
+
+#[cfg(all(test, native))]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy::primitives::{Address, Bytes};
+
+    use super::*;
+    use crate::{
+        abis::railgun::{
+            BoundParams, CommitmentCiphertext, CommitmentPreimage, G1Point, G2Point, SnarkProof,
+        },
+        circuit::{artifact_source::EmbeddedArtifactSource, verifying_keys::simulated_proof},
+    };
+
+    fn transaction(chain_id: u64, unshield: UnshieldType) -> Transaction {
+        let g1 = || G1Point {
+            x: U256::from(1),
+            y: U256::from(2),
+        };
+        let proof = SnarkProof {
+            a: g1(),
+            b: G2Point {
+                x: [U256::ZERO; 2],
+                y: [U256::ZERO; 2],
+            },
+            c: g1(),
+        };
+        let bound_params =
+            BoundParams::new(0, 0, unshield, chain_id, Address::ZERO, &[0u8; 32], vec![]);
+
+        Transaction::new(
+            proof,
+            FixedBytes::ZERO,
+            vec![FixedBytes::ZERO],
+            vec![FixedBytes::ZERO],
+            bound_params,
+            CommitmentPreimage::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_verifies_valid_transaction() {
+        let ciphertext = CommitmentCiphertext {
+            ciphertext: [FixedBytes::ZERO; 4],
+            blindedSenderViewingKey: FixedBytes::ZERO,
+            blindedReceiverViewingKey: FixedBytes::ZERO,
+            annotationData: Bytes::new(),
+            memo: Bytes::new(),
+        };
+        let mut transaction = transaction(1, UnshieldType::NONE);
+        transaction.boundParams.commitmentCiphertext = vec![ciphertext];
+        transaction.nullifiers = vec![FixedBytes::from(U256::from(7).to_be_bytes::<32>())];
+        transaction.commitments = vec![FixedBytes::from(U256::from(9).to_be_bytes::<32>())];
+
+        let signals = vec![
+            U256::ZERO,
+            transaction.boundParams.hash(),
+            U256::from(7),
+            U256::from(9),
+        ];
+        let (loader, proof) = simulated_proof("railgun/01x01", &signals);
+        transaction.proof = SnarkProof::from(proof);
+
+        //? The simulated proof's B is the G2 generator, which the contract takes
+        //? with each coordinate's imaginary part first, as in EIP-197.
+        let g2_x: [U256; 2] = [
+            "11559732032986387107991004021392285783925812861821192530917403151452391805634",
+            "10857046999023057135944570762232829481370756359578518086990519993285655852781",
+        ]
+        .map(|s| s.parse().unwrap());
+        let g2_y: [U256; 2] = [
+            "4082367875863433681332203403145435568316851327593401208105741076214120093531",
+            "8495653923123431417604973247489272438418190587263600148770280649306958101930",
+        ]
+        .map(|s| s.parse().unwrap());
+        assert_eq!(transaction.proof.b.x, g2_x);
+        assert_eq!(transaction.proof.b.y, g2_y);
+
+        let verifier = TransactionVerifier::new(loader);
+        verifier.verify_transaction(&transaction, 1).await.unwrap();
+
+        transaction.nullifiers = vec![FixedBytes::from(U256::from(8).to_be_bytes::<32>())];
+        assert!(matches!(
+            verifier.verify_transaction(&transaction, 1).await,
+            Err(TransactionVerificationError::InvalidProof)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_transactions() {
+        let loader = ArtifactLoader::new(Arc::new(EmbeddedArtifactSource::new()));
+        let verifier = TransactionVerifier::new(loader);
+
+        let err = verifier
+            .verify_transaction(&transaction(1, UnshieldType::NORMAL), 137)
+            .await;
+        assert!(matches!(
+            err,
+            Err(TransactionVerificationError::WrongChain {
+                expected: 137,
+                actual: 1
+            })
+        ));
+
+        let err = verifier
+            .verify_transaction(&transaction(1, UnshieldType::NONE), 1)
+            .await;
+        assert!(matches!(
+            err,
+            Err(TransactionVerificationError::CiphertextCount {
+                expected: 1,
+                actual: 0
+            })
+        ));
+
+        let err = verifier
+            .verify_transaction(&transaction(1, UnshieldType::NORMAL), 1)
+            .await;
+        assert!(matches!(
+            err,
+            Err(TransactionVerificationError::UnshieldMismatch)
+        ));
+
+        let mut oversized = transaction(1, UnshieldType::NORMAL);
+        oversized.nullifiers = vec![FixedBytes::ZERO; 6];
+        let err = verifier.verify_transaction(&oversized, 1).await;
+        assert!(matches!(
+            err,
+            Err(TransactionVerificationError::UnsupportedCircuit {
+                nullifiers: 6,
+                commitments: 1
+            })
+        ));
+    }
+}
@@ -0,0 +1,362 @@
+// Generates the `toy` circuit fixture for the witness graph equivalence test:
+// a hand-assembled WASM witness generator implementing circom 2's witness
+// calculator interface, the equivalent witness graph, and satisfying inputs.
+//
+// The circuit computes `out = a * b[0] + b[1] + 7`, with the witness laid out
+// as `[1, out, a, b[0], b[1]]`.
+//
+// Usage: `node generate.mjs`, from any directory. Outputs are written to
+// `toy/` next to this script.
+
+import { mkdirSync, writeFileSync } from "node:fs";
+import { dirname, join } from "node:path";
+import { fileURLToPath } from "node:url";
+import { brotliCompressSync } from "node:zlib";
+
+const P = 21888242871839275222246405745257275088548364400416034343698204186575808495617n;
+const CONSTANT = 7n;
+
+// Memory layout, in bytes. Field elements are 8 little-endian u32 words.
+const SHARED = 0;
+const INPUT_A = 64;
+const INPUT_B = 96; // b[0] at 96, b[1] at 128
+const WITNESS = 256; // 5 elements
+const PRIME = 512;
+const ACC = 576;
+const ZERO = 640;
+const ONE = 704;
+const SEVEN = 768;
+
+// Opcodes
+const BLOCK = 0x02, LOOP = 0x03, IF = 0x04, END = 0x0b, BR = 0x0c, BR_IF = 0x0d;
+const RETURN = 0x0f, CALL = 0x10, UNREACHABLE = 0x00, VOID = 0x40;
+const GET = 0x20, SET = 0x21;
+const I32_LOAD = 0x28, I64_LOAD32_U = 0x35, I32_STORE = 0x36, I64_STORE32 = 0x3e;
+const I32_CONST = 0x41, I64_CONST = 0x42;
+const I32_EQZ = 0x45, I32_EQ = 0x46, I32_LT_S = 0x48, I32_LT_U = 0x49, I32_GT_U = 0x4b;
+const I32_GE_U = 0x4f, I64_EQZ = 0x50;
+const I32_ADD = 0x6a, I32_SUB = 0x6b, I32_AND = 0x71, I32_OR = 0x72, I32_SHL = 0x74;
+const I32_SHR_U = 0x76, I64_ADD = 0x7c, I64_SUB = 0x7d, I64_SHR_U = 0x88;
+const I32 = 0x7f, I64 = 0x7e;
+
+function uleb(n) {
+    const out = [];
+    do {
+        let byte = n & 0x7f;
+        n >>>= 7;
+        if (n !== 0) byte |= 0x80;
+        out.push(byte);
+    } while (n !== 0);
+    return out;
+}
+
+function sleb(n) {
+    let value = BigInt(n);
+    const out = [];
+    for (;;) {
+        const byte = Number(value & 0x7fn);
+        value >>= 7n;
+        const done = (value === 0n && (byte & 0x40) === 0) || (value === -1n && (byte & 0x40) !== 0);
+        out.push(done ? byte : byte | 0x80);
+        if (done) return out;
+    }
+}
+
+const vec = (items) => [...uleb(items.length), ...items.flat()];
+const str = (s) => vec([...Buffer.from(s)]);
+const section = (id, content) => [id, ...uleb(content.length), ...content];
+const i32 = (n) => [I32_CONST, ...sleb(n | 0)];
+const get = (l) => [GET, l];
+const set = (l) => [SET, l];
+const call = (f) => [CALL, f];
+const mem = (op) => [op, 2, 0];
+// `base + index * 4`, the address of word `index` of the element at `base`.
+const word = (base, index) => [...base, ...get(index), ...i32(2), I32_SHL, I32_ADD];
+// Loops `index` over `from..to` (exclusive), or down to `to` if `step` is negative.
+const loop = (index, from, to, step, body) => [
+    ...i32(from), ...set(index),
+    BLOCK, VOID, LOOP, VOID,
+    ...(step > 0
+        ? [...get(index), ...i32(to), I32_GE_U]
+        : [...get(index), ...i32(to), I32_LT_S]),
+    BR_IF, 1,
+    ...body,
+    ...get(index), ...i32(Math.abs(step)), step > 0 ? I32_ADD : I32_SUB, ...set(index),
+    BR, 0,
+    END, END,
+];
+
+const types = [
+    [[], [I32]], // 0: () -> i32
+    [[], []], // 1: () -> ()
+    [[I32], [I32]], // 2: (i32) -> i32
+    [[I32, I32], []], // 3: (i32, i32) -> ()
+    [[I32], []], // 4: (i32) -> ()
+    [[I32, I32, I32], []], // 5: (i32, i32, i32) -> ()
+    [[I32, I32], [I32]], // 6: (i32, i32) -> i32
+];
+
+// Function indices
+const COPY = 10, ADDMOD = 11, GEQ_P = 12, SUB_P = 13, MULMOD = 14, COMPUTE = 15;
+
+function fnv(name) {
+    let hash = 0xcbf29ce484222325n;
+    for (const byte of Buffer.from(name)) {
+        hash ^= BigInt(byte);
+        hash = (hash * 0x100000001b3n) & 0xffffffffffffffffn;
+    }
+    return [Number(hash >> 32n), Number(hash & 0xffffffffn)];
+}
+
+// Copies the input in shared memory to `base + pos * 32` if the signal hash matches.
+function setInput(name, base) {
+    const [msb, lsb] = fnv(name);
+    return [
+        ...get(0), ...i32(msb), I32_EQ, ...get(1), ...i32(lsb), I32_EQ, I32_AND,
+        IF, VOID,
+        ...i32(base), ...get(2), ...i32(5), I32_SHL, I32_ADD, ...i32(SHARED), ...call(COPY),
+        RETURN,
+        END,
+    ];
+}
+
+// [type, locals, body]
+const functions = [
+    // 0: getVersion
+    [0, [], [...i32(2)]],
+    // 1: getFieldNumLen32
+    [0, [], [...i32(8)]],
+    // 2: getRawPrime
+    [1, [], [...i32(SHARED), ...i32(PRIME), ...call(COPY)]],
+    // 3: readSharedRWMemory(i)
+    [2, [], [...word(i32(SHARED), 0), ...mem(I32_LOAD)]],
+    // 4: writeSharedRWMemory(i, v)
+    [3, [], [...word(i32(SHARED), 0), ...get(1), ...mem(I32_STORE)]],
+    // 5: init(sanityCheck)
+    [4, [], []],
+    // 6: setInputSignal(msb, lsb, pos)
+    [5, [], [...setInput("a", INPUT_A), ...setInput("b", INPUT_B), UNREACHABLE]],
+    // 7: getWitnessSize
+    [0, [], [...i32(5)]],
+    // 8: getWitness(i)
+    [4, [], [
+        ...call(COMPUTE),
+        ...i32(SHARED), ...i32(WITNESS), ...get(0), ...i32(5), I32_SHL, I32_ADD, ...call(COPY),
+    ]],
+    // 9: getInputSignalSize(msb, lsb)
+    [6, [], (() => {
+        const [msb, lsb] = fnv("b");
+        return [
+            ...get(0), ...i32(msb), I32_EQ, ...get(1), ...i32(lsb), I32_EQ, I32_AND,
+            IF, VOID, ...i32(2), RETURN, END,
+            ...i32(1),
+        ];
+    })()],
+    // 10: copy(dst, src); locals: i
+    [3, [[1, I32]], loop(2, 0, 8, 1, [
+        ...word(get(0), 2), ...word(get(1), 2), ...mem(I32_LOAD), ...mem(I32_STORE),
+    ])],
+    // 11: addmod(dst, x, y); locals: i, carry, sum
+    [5, [[1, I32], [2, I64]], [
+        ...[I64_CONST, 0], ...set(4),
+        ...loop(3, 0, 8, 1, [
+            ...word(get(1), 3), ...mem(I64_LOAD32_U),
+            ...word(get(2), 3), ...mem(I64_LOAD32_U),
+            I64_ADD, ...get(4), I64_ADD, ...set(5),
+            ...word(get(0), 3), ...get(5), ...mem(I64_STORE32),
+            ...get(5), I64_CONST, 32, I64_SHR_U, ...set(4),
+        ]),
+        ...get(4), I64_EQZ, I32_EQZ, ...get(0), ...call(GEQ_P), I32_OR,
+        IF, VOID, ...get(0), ...call(SUB_P), END,
+    ]],
+    // 12: geq_p(x); locals: i, xi, pi
+    [2, [[3, I32]], [
+        ...loop(1, 7, 0, -1, [
+            ...word(get(0), 1), ...mem(I32_LOAD), ...set(2),
+            ...word(i32(PRIME), 1), ...mem(I32_LOAD), ...set(3),
+            ...get(2), ...get(3), I32_GT_U, IF, VOID, ...i32(1), RETURN, END,
+            ...get(2), ...get(3), I32_LT_U, IF, VOID, ...i32(0), RETURN, END,
+        ]),
+        ...i32(1),
+    ]],
+    // 13: sub_p(x); locals: i, borrow, diff
+    [4, [[1, I32], [2, I64]], [
+        ...[I64_CONST, 0], ...set(2),
+        ...loop(1, 0, 8, 1, [
+            ...word(get(0), 1), ...mem(I64_LOAD32_U),
+            ...word(i32(PRIME), 1), ...mem(I64_LOAD32_U),
+            I64_SUB, ...get(2), I64_SUB, ...set(3),
+            ...word(get(0), 1), ...get(3), ...mem(I64_STORE32),
+            ...get(3), I64_CONST, 63, I64_SHR_U, ...set(2),
+        ]),
+    ]],
+    // 14: mulmod(dst, x, y), by double-and-add over y's bits; locals: k
+    [5, [[1, I32]], [
+        ...i32(ACC), ...i32(ZERO), ...call(COPY),
+        ...loop(3, 255, 0, -1, [
+            ...i32(ACC), ...i32(ACC), ...i32(ACC), ...call(ADDMOD),
+            ...get(2), ...get(3), ...i32(5), I32_SHR_U, ...i32(2), I32_SHL, I32_ADD,
+            ...mem(I32_LOAD),
+            ...get(3), ...i32(31), I32_AND, I32_SHR_U, ...i32(1), I32_AND,
+            IF, VOID, ...i32(ACC), ...i32(ACC), ...get(1), ...call(ADDMOD), END,
+        ]),
+        ...get(0), ...i32(ACC), ...call(COPY),
+    ]],
+    // 15: compute
+    [1, [], [
+        ...i32(WITNESS), ...i32(ONE), ...call(COPY),
+        ...i32(WITNESS + 32), ...i32(INPUT_A), ...i32(INPUT_B), ...call(MULMOD),
+        ...i32(WITNESS + 32), ...i32(WITNESS + 32), ...i32(INPUT_B + 32), ...call(ADDMOD),
+        ...i32(WITNESS + 32), ...i32(WITNESS + 32), ...i32(SEVEN), ...call(ADDMOD),
+        ...i32(WITNESS + 64), ...i32(INPUT_A), ...call(COPY),
+        ...i32(WITNESS + 96), ...i32(INPUT_B), ...call(COPY),
+        ...i32(WITNESS + 128), ...i32(INPUT_B + 32), ...call(COPY),
+    ]],
+];
+
+const exports = [
+    ["getVersion", 0],
+    ["getFieldNumLen32", 1],
+    ["getRawPrime", 2],
+    ["readSharedRWMemory", 3],
+    ["writeSharedRWMemory", 4],
+    ["init", 5],
+    ["setInputSignal", 6],
+    ["getWitnessSize", 7],
+    ["getWitness", 8],
+    ["getInputSignalSize", 9],
+];
+
+function element(value) {
+    const bytes = [];
+    for (let i = 0n; i < 32n; i++) bytes.push(Number((value >> (8n * i)) & 0xffn));
+    return bytes;
+}
+
+function data(offset, bytes) {
+    return [0, ...i32(offset), END, ...vec(bytes)];
+}
+
+function assemble() {
+    const body = ([, locals, code]) => {
+        const content = [...vec(locals.map(([n, t]) => [...uleb(n), t])), ...code, END];
+        return [...uleb(content.length), ...content];
+    };
+    return new Uint8Array([
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+        ...section(1, vec(types.map(([params, results]) => [0x60, ...vec(params), ...vec(results)]))),
+        ...section(3, vec(functions.map(([type]) => uleb(type)))),
+        ...section(5, vec([[0x00, 1]])),
+        ...section(7, vec([
+            ...exports.map(([name, index]) => [...str(name), 0x00, ...uleb(index)]),
+            [...str("memory"), 0x02, 0],
+        ])),
+        ...section(10, vec(functions.map(body))),
+        ...section(11, vec([
+            data(PRIME, element(P)),
+            data(ONE, element(1n)),
+            data(SEVEN, element(CONSTANT)),
+        ])),
+    ]);
+}
+
+// Serializes the witness graph as `ark_serialize` does `WitnessGraph`: vectors are
+// prefixed with their u64 length, and integers are little-endian.
+function graph() {
+    const out = [];
+    const u8 = (n) => out.push(n);
+    const u32 = (n) => out.push(...[0, 8, 16, 24].map((s) => (n >>> s) & 0xff));
+    const u64 = (n) => out.push(...element(BigInt(n)).slice(0, 8));
+
+    // Op indices, as in `witness_graph::OPS`.
+    const INPUT = 0, CONSTANT_OP = 1, ADD = 2, MUL = 4;
+    const nodes = [
+        [INPUT, 0], // 0: one
+        [INPUT, 1], // 1: a
+        [INPUT, 2], // 2: b[0]
+        [INPUT, 3], // 3: b[1]
+        [CONSTANT_OP, 0], // 4: 7
+        [MUL, 1, 2], // 5: a * b[0]
+        [ADD, 5, 3], // 6: + b[1]
+        [ADD, 6, 4], // 7: + 7
+    ];
+
+    u64(1);
+    element(CONSTANT).forEach(u8);
+    u64(nodes.length);
+    for (const [op, a = 0, b = 0, c = 0] of nodes) {
+        u8(op);
+        [a, b, c].forEach(u32);
+    }
+    u64(2);
+    for (const [name, offset, len] of [["a", 1, 1], ["b", 2, 2]]) {
+        u64(name.length);
+        out.push(...Buffer.from(name));
+        u32(offset);
+        u32(len);
+    }
+    const signals = [0, 7, 1, 2, 3];
+    u64(signals.length);
+    signals.forEach(u32);
+    return new Uint8Array(out);
+}
+
+// Runs the witness generator the way ark-circom's `WitnessCalculator` does.
+async function calculate(wasm, inputs) {
+    const { instance } = await WebAssembly.instantiate(wasm, {});
+    const e = instance.exports;
+    const n32 = e.getFieldNumLen32();
+    const read = () => {
+        let value = 0n;
+        for (let j = n32 - 1; j >= 0; j--) value = (value << 32n) | BigInt(e.readSharedRWMemory(j) >>> 0);
+        return value;
+    };
+
+    e.getRawPrime();
+    if (read() !== P) throw new Error("getRawPrime returned the wrong prime");
+
+    e.init(1);
+    for (const [name, values] of Object.entries(inputs)) {
+        const [msb, lsb] = fnv(name);
+        values.forEach((value, i) => {
+            for (let j = 0; j < n32; j++) {
+                e.writeSharedRWMemory(j, Number((value >> (32n * BigInt(j))) & 0xffffffffn));
+            }
+            e.setInputSignal(msb, lsb, i);
+        });
+    }
+
+    const witness = [];
+    for (let i = 0; i < e.getWitnessSize(); i++) {
+        e.getWitness(i);
+        witness.push(read());
+    }
+    return witness;
+}
+
+const inputs = {
+    a: [P - 2n],
+    b: [0x1234567890abcdef1234567890abcdef1234567890abcdefn, P - 5n],
+};
+
+const wasm = assemble();
+const witness = await calculate(wasm, inputs);
+const out = (((inputs.a[0] * inputs.b[0]) % P) + inputs.b[1] + CONSTANT) % P;
+const expected = [1n, out, inputs.a[0], ...inputs.b];
+if (witness.some((w, i) => w !== expected[i])) {
+    throw new Error(`Unexpected witness ${witness} (expected ${expected})`);
+}
+
+const dir = join(dirname(fileURLToPath(import.meta.url)), "toy");
+mkdirSync(dir, { recursive: true });
+writeFileSync(join(dir, "wasm.br"), brotliCompressSync(wasm));
+writeFileSync(join(dir, "graph.bin.br"), brotliCompressSync(graph()));
+writeFileSync(
+    join(dir, "inputs.json"),
+    JSON.stringify(
+        Object.fromEntries(Object.entries(inputs).map(([k, v]) => [k, v.map(String)])),
+        null,
+        4,
+    ) + "\n",
+);
+console.log(`Wrote toy circuit fixture to ${dir}`);
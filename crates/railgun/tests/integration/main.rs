@@ -6,5 +6,7 @@ mod sync_txid;
 mod sync_utxo;
 mod transact_poi;
 mod transact_utxo;
+#[cfg(wasm_witness)]
+mod witness_graph;
 
 mod utils;
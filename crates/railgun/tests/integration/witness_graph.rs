@@ -0,0 +1,77 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use alloy::primitives::U256;
+use railgun::{
+    DirectoryArtifactSource,
+    test_helpers::{ArtifactLoader, WitnessBackend, WitnessGraphs, WitnessModules},
+};
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+/// Tests that the native witness graph backend produces the same witness as the
+/// WASM witness generator.
+///
+/// Compares every circuit in `WITNESS_ARTIFACTS_DIR`, defaulting to the toy
+/// circuit in `tests/fixtures/witness_graph` (regenerated by its
+/// `generate.mjs`). Each circuit directory holds a `wasm.br`, a `graph.bin.br`,
+/// and an `inputs.json` of satisfying circuit inputs (signal name to decimal
+/// strings). Point it at converted artifacts to check the real circuits.
+#[tokio::test]
+async fn test_witness_graph_matches_wasm() {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_test_writer()
+        .try_init()
+        .ok();
+
+    let dir = std::env::var("WITNESS_ARTIFACTS_DIR").unwrap_or_else(|_| {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/witness_graph").to_string()
+    });
+    let loader = ArtifactLoader::new(Arc::new(DirectoryArtifactSource::new(&dir)));
+    let wasm = WitnessBackend::Wasm(WitnessModules::new());
+    let graph = WitnessBackend::Graph(WitnessGraphs::new());
+
+    let circuits = find_circuits(Path::new(&dir), Path::new(&dir));
+    assert!(!circuits.is_empty(), "No circuits found in {}", dir);
+
+    for circuit in circuits {
+        info!("Comparing witnesses for {}", circuit);
+        let inputs =
+            std::fs::read_to_string(Path::new(&dir).join(&circuit).join("inputs.json")).unwrap();
+        let inputs: HashMap<String, Vec<String>> = serde_json::from_str(&inputs).unwrap();
+        let inputs: HashMap<String, Vec<U256>> = inputs
+            .into_iter()
+            .map(|(k, v)| (k, v.iter().map(|x| x.parse().unwrap()).collect()))
+            .collect();
+
+        let expected = wasm
+            .generator(&loader, &circuit)
+            .await
+            .unwrap()
+            .calculate(inputs.clone())
+            .unwrap();
+        let actual = graph
+            .generator(&loader, &circuit)
+            .await
+            .unwrap()
+            .calculate(inputs)
+            .unwrap();
+        assert_eq!(expected, actual, "Witness mismatch for {}", circuit);
+    }
+}
+
+/// Returns the name of every circuit under `dir` with an `inputs.json` fixture.
+fn find_circuits(root: &Path, dir: &Path) -> Vec<String> {
+    let mut circuits = Vec::new();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            circuits.extend(find_circuits(root, &path));
+        } else if path.file_name().is_some_and(|f| f == "inputs.json") {
+            let circuit = path.parent().unwrap().strip_prefix(root).unwrap();
+            circuits.push(circuit.to_string_lossy().into_owned());
+        }
+    }
+    circuits.sort();
+    circuits
+}
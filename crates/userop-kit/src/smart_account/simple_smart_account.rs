@@ -1,11 +1,14 @@
 use std::sync::Arc;
 
 use alloy::{
+    eips::BlockNumberOrTag,
     primitives::{Address, Bytes, U256, address, aliases::U192, bytes},
     rpc::types::Authorization,
 };
 use alloy_sol_types::{Eip712Domain, SolCall};
-use eip_1193_provider::provider::{Eip1193Caller, Eip1193Provider, IntoEip1193Provider};
+use eip_1193_provider::provider::{
+    Eip1193Caller, Eip1193Provider, Eip1193Transactions, IntoEip1193Provider,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -130,7 +133,10 @@ impl SmartAccount for SimpleSmartAccount {
 impl SimpleSmartAccount {
     /// Gets the nonce for the owner address
     async fn owner_nonce(&self) -> Result<u64, SmartAccountError> {
-        let nonce = self.provider.transaction_count(self.owner, None).await?;
+        let nonce = self
+            .provider
+            .transaction_count(self.owner, BlockNumberOrTag::Latest)
+            .await?;
         Ok(nonce)
     }
 }